@@ -30,7 +30,7 @@ async fn main() -> std::result::Result<(), Box<dyn Error>> {
     let domains = vec!["example.com".to_string()];
 
     // Note: This call will block until all challenges are solved and the cert is issued
-    match client.issue_certificate(domains, &mut registry).await {
+    match client.issue_certificate(domains, &registry).await {
         Ok(bundle) => {
             println!("Certificate issued successfully!");
             println!("Certificate PEM:\n{}", bundle.certificate_pem);
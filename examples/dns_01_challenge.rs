@@ -18,7 +18,8 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
 
     let cf_config = CloudFlareConfig {
         api_token,
-        zone_id: std::env::var("CLOUDFLARE_ZONE_ID").unwrap_or_default(),
+        // Without a zone ID, the zone of each record is discovered
+        zone_id: std::env::var("CLOUDFLARE_ZONE_ID").ok(),
     };
     let cf_provider = CloudFlareDnsProvider::new(cf_config);
 
@@ -31,7 +32,7 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     println!("Requesting wildcard certificate *.example.com via DNS-01...");
     let domains = vec!["example.com".to_string(), "*.example.com".to_string()];
 
-    match client.issue_certificate(domains, &mut registry).await {
+    match client.issue_certificate(domains, &registry).await {
         Ok(bundle) => {
             println!("Success! Wildcard certificate obtained.");
             println!("Primary Domain: {}", bundle.domains[0]);
@@ -1,26 +1,41 @@
 /// Account credentials and key pair management.
 /// This module provides a wrapper around cryptographic key pairs used for
 /// ACME account identification and request signing.
+use crate::crypto::{KeyPairGenerator, KeyType};
 use crate::error::Result;
+use crate::protocol::Jwk;
 use rcgen::KeyPair as RcgenKeyPair;
 use std::fs;
 use std::path::Path;
 
-/// A wrapper for cryptographic key pairs (primarily Ed25519).
+/// A wrapper for cryptographic key pairs (ECDSA, RSA or Ed25519).
 /// This structure is used to sign ACME requests and identify the account.
 pub struct KeyPair(pub RcgenKeyPair);
 
 impl KeyPair {
-    /// Generates a new random Ed25519 key pair.
+    /// Generates a new random ECDSA P-256 (ES256) key pair.
+    /// ES256 is accepted by every major ACME CA.
     pub fn generate() -> Result<Self> {
-        tracing::debug!("Generating new Ed25519 key pair");
-        let key_pair = RcgenKeyPair::generate().map_err(|e| {
-            tracing::error!("Failed to generate key pair: {}", e);
-            crate::error::AcmeError::crypto(format!("Failed to generate key pair: {}", e))
-        })?;
+        Self::generate_with(KeyType::EcdsaP256)
+    }
+
+    /// Generates a new random key pair of the given type.
+    pub fn generate_with(key_type: KeyType) -> Result<Self> {
+        tracing::debug!("Generating new {} account key pair", key_type);
+        let key_pair = KeyPairGenerator::new(key_type).generate()?;
         Ok(Self(key_pair))
     }
 
+    /// Returns the type of this key pair.
+    pub fn key_type(&self) -> Result<KeyType> {
+        KeyType::from_key_pair(&self.0)
+    }
+
+    /// Returns the public key as a JSON Web Key (JWK).
+    pub fn jwk(&self) -> Result<Jwk> {
+        Jwk::from_key_pair(&self.0)
+    }
+
     /// Creates a `KeyPair` from a PEM-encoded string.
     pub fn from_pem(pem_str: &str) -> Result<Self> {
         tracing::debug!("Parsing KeyPair from PEM string");
@@ -75,6 +90,16 @@ mod tests {
         assert!(keypair.is_ok());
     }
 
+    #[test]
+    fn test_generate_with_key_type() {
+        let keypair = KeyPair::generate_with(KeyType::EcdsaP384).unwrap();
+        assert_eq!(keypair.key_type().unwrap(), KeyType::EcdsaP384);
+
+        let restored = KeyPair::from_pem(&keypair.serialize_pem()).unwrap();
+        assert_eq!(restored.key_type().unwrap(), KeyType::EcdsaP384);
+        assert_eq!(restored.jwk().unwrap(), keypair.jwk().unwrap());
+    }
+
     #[test]
     fn test_from_pem() {
         let keypair1 = KeyPair::generate().expect("Failed to generate key pair");
@@ -0,0 +1,147 @@
+/// External Account Binding (EAB) support.
+/// This module builds the `externalAccountBinding` object required by CAs such as
+/// ZeroSSL and Google Trust Services when creating a new account (RFC 8555 Section 7.3.4).
+use crate::crypto::signer::{HmacSigner, Signer};
+use crate::error::{AcmeError, Result};
+use crate::protocol::Jwk;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use serde_json::{Value, json};
+
+/// External account credentials issued by the CA out of band.
+#[derive(Clone)]
+pub struct ExternalAccountKey {
+    /// The key identifier provided by the CA.
+    key_id: String,
+    /// The decoded MAC key.
+    hmac_key: Vec<u8>,
+}
+
+impl ExternalAccountKey {
+    /// Creates EAB credentials from a key ID and a base64url-encoded HMAC key,
+    /// as they are typically shown in the CA's dashboard.
+    pub fn new(key_id: impl Into<String>, hmac_key: &str) -> Result<Self> {
+        let key_id = key_id.into();
+        if key_id.is_empty() {
+            return Err(AcmeError::configuration("EAB key ID must not be empty"));
+        }
+
+        let trimmed = hmac_key.trim().trim_end_matches('=');
+        let hmac_key = URL_SAFE_NO_PAD
+            .decode(trimmed)
+            .or_else(|_| STANDARD.decode(hmac_key.trim()))
+            .map_err(|e| {
+                tracing::error!("Failed to decode EAB HMAC key: {}", e);
+                AcmeError::configuration(format!("EAB HMAC key is not valid base64url: {}", e))
+            })?;
+        if hmac_key.is_empty() {
+            return Err(AcmeError::configuration("EAB HMAC key must not be empty"));
+        }
+
+        Ok(Self { key_id, hmac_key })
+    }
+
+    /// Returns the key identifier.
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Builds the flattened JWS binding the account key to the external account.
+    /// The inner JWS is MAC'd with HS256 over the account JWK and targets the newAccount URL.
+    pub fn binding(&self, account_jwk: &Jwk, new_account_url: &str) -> Result<Value> {
+        tracing::debug!(
+            "Creating external account binding for key ID {}",
+            self.key_id
+        );
+        let protected = json!({
+            "alg": "HS256",
+            "kid": self.key_id,
+            "url": new_account_url,
+        });
+
+        let protected_encoded = URL_SAFE_NO_PAD.encode(protected.to_string());
+        let payload_encoded = URL_SAFE_NO_PAD.encode(account_jwk.to_value().to_string());
+        let signing_input = format!("{}.{}", protected_encoded, payload_encoded);
+
+        let signature = HmacSigner::hs256(self.hmac_key.clone()).sign(signing_input.as_bytes())?;
+
+        Ok(json!({
+            "protected": protected_encoded,
+            "payload": payload_encoded,
+            "signature": signature.to_base64(),
+        }))
+    }
+
+    /// Checks an HS256 MAC over a binding's signing input, as an ACME server
+    /// receiving the binding does.
+    pub fn verify(&self, signing_input: &[u8], mac: &[u8]) -> Result<bool> {
+        HmacSigner::hs256(self.hmac_key.clone()).verify(signing_input, mac)
+    }
+}
+
+impl std::fmt::Debug for ExternalAccountKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExternalAccountKey")
+            .field("key_id", &self.key_id)
+            .field("hmac_key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl TryFrom<&crate::config::ExternalAccountBinding> for ExternalAccountKey {
+    type Error = AcmeError;
+
+    fn try_from(binding: &crate::config::ExternalAccountBinding) -> Result<Self> {
+        Self::new(binding.key_id.clone(), &binding.hmac_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binding_structure() {
+        let eab = ExternalAccountKey::new("kid-1", "c2VjcmV0LWhtYWMta2V5").unwrap();
+        let jwk = Jwk::new_ec("P-256", "eA", "eQ");
+        let binding = eab
+            .binding(&jwk, "https://ca.example/acme/new-account")
+            .unwrap();
+
+        let protected = URL_SAFE_NO_PAD
+            .decode(binding["protected"].as_str().unwrap())
+            .unwrap();
+        let protected: Value = serde_json::from_slice(&protected).unwrap();
+        assert_eq!(protected["alg"], "HS256");
+        assert_eq!(protected["kid"], "kid-1");
+        assert_eq!(protected["url"], "https://ca.example/acme/new-account");
+        assert!(protected.get("nonce").is_none());
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(binding["payload"].as_str().unwrap())
+            .unwrap();
+        let payload: Jwk = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(payload, jwk);
+
+        let signing_input = format!(
+            "{}.{}",
+            binding["protected"].as_str().unwrap(),
+            binding["payload"].as_str().unwrap()
+        );
+        let signature = URL_SAFE_NO_PAD
+            .decode(binding["signature"].as_str().unwrap())
+            .unwrap();
+        let verifier = HmacSigner::hs256(b"secret-hmac-key".to_vec());
+        assert!(
+            verifier
+                .verify(signing_input.as_bytes(), &signature)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_hmac_key() {
+        assert!(ExternalAccountKey::new("kid", "not base64!!").is_err());
+        assert!(ExternalAccountKey::new("", "c2VjcmV0").is_err());
+    }
+}
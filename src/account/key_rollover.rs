@@ -3,9 +3,7 @@
 /// associated with an ACME account (RFC 8555 Section 7.3.5).
 use crate::account::{Account, AccountManager, KeyPair};
 use crate::error::Result;
-use crate::protocol::{Jwk, JwsSigner};
-use base64::Engine;
-use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use crate::protocol::{Jwk, JwsSigner, KeyId};
 use serde_json::json;
 
 /// Manages the process of rotating an ACME account's key pair.
@@ -17,10 +15,12 @@ pub struct KeyRollover<'a> {
 }
 
 impl<'a> KeyRollover<'a> {
-    /// Creates a new `KeyRollover` manager and generates a new random key pair.
+    /// Creates a new `KeyRollover` manager and generates a new random key pair
+    /// of the same type as the current account key.
     pub fn new(account_manager: &'a AccountManager<'a>) -> Result<Self> {
         tracing::debug!("Initializing KeyRollover with a new random key pair");
-        let new_key_pair = KeyPair::generate()?;
+        let key_type = account_manager.get_signer().key_type()?;
+        let new_key_pair = KeyPair::generate_with(key_type)?;
         Ok(Self {
             account_manager,
             new_key_pair,
@@ -46,8 +46,7 @@ impl<'a> KeyRollover<'a> {
         let key_change_url = directory.key_change;
 
         // 2. Prepare new key information
-        let new_jwk =
-            Jwk::new_ed25519(URL_SAFE_NO_PAD.encode(self.new_key_pair.public_key_bytes()));
+        let new_jwk = Jwk::from_key_pair(&self.new_key_pair.0)?;
 
         // 3. Create inner JWS (signed by NEW key)
         tracing::debug!("Creating inner JWS signed by the new key");
@@ -58,76 +57,23 @@ impl<'a> KeyRollover<'a> {
 
         let new_signer = JwsSigner::new(&self.new_key_pair.0);
         let inner_header = json!({
-            "alg": "EdDSA",
+            "alg": new_signer.algorithm()?,
             "jwk": new_jwk.to_value(),
             "url": key_change_url
         });
 
-        let inner_jws = new_signer.sign(&inner_header, &inner_payload)?;
+        let inner_jws = new_signer.sign_flattened(&inner_header, &inner_payload)?;
 
-        // 4. Create outer JWS (signed by OLD key)
-        tracing::debug!("Creating outer JWS signed by the old key");
-        let nonce = self.account_manager.nonce_manager.get_nonce().await?;
-
-        let outer_header = json!({
-            "alg": "EdDSA",
-            "kid": account_url,
-            "nonce": nonce,
-            "url": key_change_url
-        });
-
-        // The payload for the outer JWS is the inner JWS object
-        let inner_jws_parts: Vec<&str> = inner_jws.split('.').collect();
-        let inner_jws_obj = json!({
-            "protected": inner_jws_parts[0],
-            "payload": inner_jws_parts[1],
-            "signature": inner_jws_parts[2]
-        });
-
-        let outer_jws = self
-            .account_manager
-            .get_signer()
-            .sign(&outer_header, &inner_jws_obj)?;
-
-        // 5. Send request to the keyChange endpoint
+        // 4. Send the inner JWS as the payload of an outer JWS signed by the OLD key
         tracing::info!("Sending keyChange request to: {}", key_change_url);
         let response = self
             .account_manager
-            .http_client
-            .post(&key_change_url)
-            .header("Content-Type", "application/jose+json")
-            .body(outer_jws)
-            .send()
+            .requester()
+            .post(&key_change_url, KeyId::Kid(account_url), &inner_jws)
             .await
-            .map_err(|e| {
-                tracing::error!("Network error during keyChange request: {}", e);
-                crate::error::AcmeError::transport(format!("Failed to change key: {}", e))
-            })?;
-
-        // Cache the nonce from response
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.account_manager
-                .nonce_manager
-                .cache_nonce(nonce_str.to_string())
-                .await;
-        }
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            tracing::error!("Key rollover failed with status {}: {}", status, error_text);
-            return Err(crate::error::AcmeError::account(format!(
-                "Failed to change key: HTTP {}: {}",
-                status, error_text
-            )));
-        }
+            .inspect_err(|e| tracing::error!("Key rollover failed: {}", e))?;
 
-        let mut account: Account = response.json().await.map_err(|e| {
+        let mut account: Account = response.json().map_err(|e| {
             tracing::error!("Failed to parse account response after key rollover: {}", e);
             crate::error::AcmeError::account(format!("Failed to parse account response: {}", e))
         })?;
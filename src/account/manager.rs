@@ -2,14 +2,23 @@
 /// This module provides the `AccountManager` which handles account registration,
 /// updates, and deactivation according to RFC 8555.
 use crate::error::Result;
-use crate::protocol::{DirectoryManager, Jwk, JwsSigner, NonceManager};
+use crate::protocol::{AcmeRequester, DirectoryManager, Jwk, JwsSigner, KeyId, NonceManager};
 use crate::types::Contact;
-use base64::Engine;
-use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use super::credentials::KeyPair;
+use super::eab::ExternalAccountKey;
+
+/// Upper bound on the pages fetched when listing an account's orders.
+const MAX_ORDER_LIST_PAGES: usize = 100;
+
+/// A page of the account's orders list (RFC 8555 Section 7.1.2.1).
+#[derive(Debug, Deserialize)]
+struct OrdersPage {
+    #[serde(default)]
+    orders: Vec<String>,
+}
 
 /// Represents an ACME account as returned by the server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +31,7 @@ pub struct Account {
     pub status: String,
 
     /// A list of contact URIs (e.g., "mailto:admin@example.com").
+    #[serde(default)]
     pub contact: Vec<String>,
 
     /// Indicates if the user has agreed to the terms of service.
@@ -41,14 +51,13 @@ pub struct Account {
     pub orders: Option<String>,
 
     /// Information about external account binding, if applicable.
-    #[serde(default)]
-    pub external_account_binding: Option<String>,
+    #[serde(rename = "externalAccountBinding", default)]
+    pub external_account_binding: Option<serde_json::Value>,
 }
 
 /// Manages the lifecycle of an ACME account.
 pub struct AccountManager<'a> {
     /// The key pair used for signing requests.
-    #[allow(dead_code)]
     pub(crate) key_pair: &'a KeyPair,
     /// The JWS signer for creating signed requests.
     pub(crate) signer: JwsSigner<'a>,
@@ -72,7 +81,7 @@ impl<'a> AccountManager<'a> {
     ) -> Result<Self> {
         tracing::debug!("Initializing AccountManager");
         let signer = JwsSigner::new(&key_pair.0);
-        let jwk = Jwk::new_ed25519(URL_SAFE_NO_PAD.encode(key_pair.public_key_bytes()));
+        let jwk = key_pair.jwk()?;
 
         Ok(Self {
             key_pair,
@@ -93,50 +102,58 @@ impl<'a> AccountManager<'a> {
         &self,
         contacts: Vec<Contact>,
         terms_of_service_agreed: bool,
+    ) -> Result<Account> {
+        self.register_with_eab(contacts, terms_of_service_agreed, None)
+            .await
+    }
+
+    /// Registers a new account, binding it to an external account when credentials are given.
+    ///
+    /// # Arguments
+    /// * `contacts` - A list of contact information for the account.
+    /// * `terms_of_service_agreed` - Must be true to proceed with registration.
+    /// * `eab` - External Account Binding credentials issued by the CA, if any.
+    pub async fn register_with_eab(
+        &self,
+        contacts: Vec<Contact>,
+        terms_of_service_agreed: bool,
+        eab: Option<&ExternalAccountKey>,
     ) -> Result<Account> {
         tracing::info!("Registering new ACME account with contacts: {:?}", contacts);
         let directory = self.directory_manager.get().await?;
-        let nonce = self.nonce_manager.get_nonce().await?;
 
-        let header = json!({
-            "alg": "EdDSA",
-            "jwk": self.jwk.to_value(),
-            "nonce": nonce,
-            "url": directory.new_account,
-        });
+        let eab_required = directory
+            .meta
+            .as_ref()
+            .and_then(|m| m.external_account_required)
+            .unwrap_or(false);
+        if eab_required && eab.is_none() {
+            tracing::error!("ACME server requires External Account Binding but none is configured");
+            return Err(crate::error::AcmeError::configuration(
+                "The ACME server requires External Account Binding (externalAccountRequired); \
+                 configure an EAB key ID and HMAC key from your CA",
+            ));
+        }
 
         let contacts_uri: Vec<String> = contacts.iter().map(|c| c.to_uri()).collect();
-        let payload = json!({
+        let mut payload = json!({
             "termsOfServiceAgreed": terms_of_service_agreed,
             "contact": contacts_uri,
         });
 
-        let jws = self.signer.sign(&header, &payload)?;
+        if let Some(eab) = eab {
+            tracing::debug!("Attaching external account binding (kid: {})", eab.key_id());
+            payload["externalAccountBinding"] = eab.binding(&self.jwk, &directory.new_account)?;
+        }
 
         let response = self
-            .http_client
-            .post(&directory.new_account)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
+            .requester()
+            .post(&directory.new_account, KeyId::Jwk(&self.jwk), &payload)
             .await
-            .map_err(|e| {
-                tracing::error!("Network error during account registration: {}", e);
-                crate::error::AcmeError::transport(format!("Failed to register account: {}", e))
-            })?;
+            .inspect_err(|e| tracing::error!("Account registration failed: {}", e))?;
 
-        // Cache the nonce from response
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
-
-        // Extract account URL before consuming response
         let account_url = response
-            .headers()
-            .get("location")
-            .and_then(|h| h.to_str().ok())
+            .location()
             .ok_or_else(|| {
                 tracing::error!("ACME server did not return a Location header for the new account");
                 crate::error::AcmeError::account(
@@ -145,24 +162,7 @@ impl<'a> AccountManager<'a> {
             })?
             .to_string();
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            tracing::error!(
-                "Account registration failed with status {}: {}",
-                status,
-                error_text
-            );
-            return Err(crate::error::AcmeError::account(format!(
-                "Failed to register account: HTTP {}: {}",
-                status, error_text
-            )));
-        }
-
-        let mut account: Account = response.json().await.map_err(|e| {
+        let mut account: Account = response.json().map_err(|e| {
             tracing::error!("Failed to parse account JSON response: {}", e);
             crate::error::AcmeError::account(format!("Failed to parse account response: {}", e))
         })?;
@@ -172,6 +172,42 @@ impl<'a> AccountManager<'a> {
         Ok(account)
     }
 
+    /// Looks up the account registered for this key without creating one (`onlyReturnExisting`).
+    /// Fails with `AcmeError::Account` if the server has no account for the key.
+    pub async fn find_existing(&self) -> Result<Account> {
+        tracing::info!("Looking up existing ACME account for key");
+        let directory = self.directory_manager.get().await?;
+
+        let response = self
+            .requester()
+            .post(
+                &directory.new_account,
+                KeyId::Jwk(&self.jwk),
+                &json!({ "onlyReturnExisting": true }),
+            )
+            .await
+            .inspect_err(|e| tracing::error!("Account lookup failed: {}", e))?;
+
+        let account_url = response
+            .location()
+            .ok_or_else(|| {
+                tracing::error!("ACME server did not return a Location header for the account");
+                crate::error::AcmeError::account(
+                    "Missing location header in account response".to_string(),
+                )
+            })?
+            .to_string();
+
+        let mut account: Account = response.json().map_err(|e| {
+            tracing::error!("Failed to parse account JSON response: {}", e);
+            crate::error::AcmeError::account(format!("Failed to parse account response: {}", e))
+        })?;
+
+        account.id = account_url;
+        tracing::info!("Found existing account: {}", account.id);
+        Ok(account)
+    }
+
     /// Updates the contact information for an existing account.
     pub async fn update_contacts(
         &self,
@@ -183,59 +219,18 @@ impl<'a> AccountManager<'a> {
             account_id,
             contacts
         );
-        let nonce = self.nonce_manager.get_nonce().await?;
-
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": account_id,
-            "nonce": nonce,
-            "url": account_id,
-        });
-
         let contacts_uri: Vec<String> = contacts.iter().map(|c| c.to_uri()).collect();
         let payload = json!({
             "contact": contacts_uri,
         });
 
-        let jws = self.signer.sign(&header, &payload)?;
-
         let response = self
-            .http_client
-            .post(account_id)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
+            .requester()
+            .post(account_id, KeyId::Kid(account_id), &payload)
             .await
-            .map_err(|e| {
-                tracing::error!("Network error during contact update: {}", e);
-                crate::error::AcmeError::transport(format!("Failed to update account: {}", e))
-            })?;
-
-        // Cache the nonce from response
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
+            .inspect_err(|e| tracing::error!("Contact update failed: {}", e))?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            tracing::error!(
-                "Contact update failed with status {}: {}",
-                status,
-                error_text
-            );
-            return Err(crate::error::AcmeError::account(format!(
-                "Failed to update account: HTTP {}: {}",
-                status, error_text
-            )));
-        }
-
-        let account: Account = response.json().await.map_err(|e| {
+        let account: Account = response.json().map_err(|e| {
             tracing::error!("Failed to parse updated account JSON: {}", e);
             crate::error::AcmeError::account(format!("Failed to parse account response: {}", e))
         })?;
@@ -247,48 +242,13 @@ impl<'a> AccountManager<'a> {
     /// Retrieves the current account information from the ACME server.
     pub async fn get_account(&self, account_id: &str) -> Result<Account> {
         tracing::debug!("Fetching account info for {}", account_id);
-        let nonce = self.nonce_manager.get_nonce().await?;
-
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": account_id,
-            "nonce": nonce,
-            "url": account_id,
-        });
-
-        let payload = json!({});
-
-        let jws = self.signer.sign(&header, &payload)?;
-
         let response = self
-            .http_client
-            .post(account_id)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
+            .requester()
+            .post_as_get(account_id, account_id)
             .await
-            .map_err(|e| {
-                tracing::error!("Network error while fetching account: {}", e);
-                crate::error::AcmeError::transport(format!("Failed to get account: {}", e))
-            })?;
-
-        // Cache the nonce from response
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
+            .inspect_err(|e| tracing::error!("Failed to fetch account {}: {}", account_id, e))?;
 
-        let status = response.status();
-        if !status.is_success() {
-            tracing::error!("Failed to fetch account {}, status: {}", account_id, status);
-            return Err(crate::error::AcmeError::account(format!(
-                "Failed to get account: HTTP {}",
-                status
-            )));
-        }
-
-        let mut account: Account = response.json().await.map_err(|e| {
+        let mut account: Account = response.json().map_err(|e| {
             tracing::error!("Failed to parse account JSON: {}", e);
             crate::error::AcmeError::account(format!("Failed to parse account response: {}", e))
         })?;
@@ -297,57 +257,58 @@ impl<'a> AccountManager<'a> {
         Ok(account)
     }
 
+    /// Lists the URLs of the account's orders, following `Link: rel="next"` pagination.
+    /// Servers may omit orders that are no longer pending or recently valid.
+    pub async fn list_orders(&self, account_id: &str, orders_url: &str) -> Result<Vec<String>> {
+        tracing::debug!("Listing orders of account {}", account_id);
+        let mut orders = Vec::new();
+        let mut next = Some(orders_url.to_string());
+        let mut pages = 0;
+
+        while let Some(page_url) = next.take() {
+            if pages == MAX_ORDER_LIST_PAGES {
+                tracing::warn!(
+                    "Stopped listing orders after {} pages; the list may be incomplete",
+                    MAX_ORDER_LIST_PAGES
+                );
+                break;
+            }
+            pages += 1;
+
+            let response = self
+                .requester()
+                .post_as_get(&page_url, account_id)
+                .await
+                .inspect_err(|e| tracing::error!("Failed to fetch orders {}: {}", page_url, e))?;
+            let page: OrdersPage = response.json()?;
+            orders.extend(page.orders);
+
+            next = response
+                .links("next")
+                .into_iter()
+                .next()
+                .filter(|url| *url != page_url);
+        }
+
+        tracing::debug!("Account {} has {} order(s)", account_id, orders.len());
+        Ok(orders)
+    }
+
     /// Deactivates the account on the ACME server.
     /// Once deactivated, the account cannot be used for further operations.
     pub async fn deactivate(&self, account_id: &str) -> Result<()> {
         tracing::info!("Deactivating account {}", account_id);
-        let nonce = self.nonce_manager.get_nonce().await?;
-
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": account_id,
-            "nonce": nonce,
-            "url": account_id,
-        });
-
         let payload = json!({
             "status": "deactivated"
         });
 
-        let jws = self.signer.sign(&header, &payload)?;
-
-        let response = self
-            .http_client
-            .post(account_id)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
+        self.requester()
+            .post(account_id, KeyId::Kid(account_id), &payload)
             .await
-            .map_err(|e| {
-                tracing::error!("Network error during account deactivation: {}", e);
-                crate::error::AcmeError::transport(format!("Failed to deactivate account: {}", e))
+            .inspect_err(|e| {
+                tracing::error!("Account deactivation failed for {}: {}", account_id, e)
             })?;
 
-        // Cache the nonce from response
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
-
-        let status = response.status();
-        if !status.is_success() {
-            tracing::error!(
-                "Account deactivation failed for {}, status: {}",
-                account_id,
-                status
-            );
-            return Err(crate::error::AcmeError::account(format!(
-                "Failed to deactivate account: HTTP {}",
-                status
-            )));
-        }
-
         tracing::info!("Account {} successfully deactivated", account_id);
         Ok(())
     }
@@ -373,6 +334,11 @@ impl<'a> AccountManager<'a> {
     pub fn get_signer(&self) -> &JwsSigner<'a> {
         &self.signer
     }
+
+    /// Returns a requester that signs ACME requests with the account key.
+    pub fn requester(&self) -> AcmeRequester<'_> {
+        AcmeRequester::new(&self.signer, self.nonce_manager, self.http_client)
+    }
 }
 
 #[cfg(test)]
@@ -393,4 +359,12 @@ mod tests {
         assert_eq!(account.contact.len(), 1);
         assert!(account.terms_of_service_agreed);
     }
+
+    #[test]
+    fn test_account_without_contacts() {
+        // onlyReturnExisting responses may omit the optional contact field
+        let account: Account =
+            serde_json::from_str(r#"{"status": "valid"}"#).expect("Failed to parse account");
+        assert!(account.contact.is_empty());
+    }
 }
@@ -1,8 +1,10 @@
 /// Account module for ACME client
 pub mod credentials;
+pub mod eab;
 pub mod key_rollover;
 pub mod manager;
 
 pub use credentials::KeyPair;
+pub use eab::ExternalAccountKey;
 pub use key_rollover::KeyRollover;
 pub use manager::{Account, AccountManager};
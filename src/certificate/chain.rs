@@ -1,4 +1,4 @@
-use crate::certificate::OcspVerifier;
+use crate::certificate::{OcspStatus, OcspVerifier};
 /// Certificate chain verification and management
 use crate::error::{AcmeError, Result};
 use jiff::Zoned;
@@ -75,21 +75,24 @@ impl CertificateChain {
         Ok(())
     }
 
-    /// Perform deep verification including OCSP real-time status check
-    pub async fn verify_deep(&self) -> Result<()> {
+    /// Perform deep verification including OCSP real-time status check.
+    /// The leaf's status is checked against its issuer, the first intermediate;
+    /// the verified response stays cached in `ocsp` for stapling.
+    pub async fn verify_deep(&self, ocsp: &OcspVerifier) -> Result<()> {
         self.verify()?;
 
         // Perform OCSP check for the end-entity certificate (index 0)
-        let end_entity = &self.leaf;
-        match OcspVerifier::verify_status(end_entity).await? {
-            crate::certificate::OcspStatus::Good => {
+        let issuer = &self.intermediates[0];
+        match ocsp.verify_status(&self.leaf, issuer).await?.status {
+            OcspStatus::Good => {
                 tracing::info!("OCSP status check: Good");
                 Ok(())
             }
-            crate::certificate::OcspStatus::Revoked => Err(AcmeError::certificate(
-                "Certificate is revoked according to OCSP".to_string(),
-            )),
-            crate::certificate::OcspStatus::Unknown => {
+            OcspStatus::Revoked { revoked_at, reason } => Err(AcmeError::certificate(format!(
+                "Certificate was revoked at {} according to OCSP (reason: {:?})",
+                revoked_at, reason
+            ))),
+            OcspStatus::Unknown => {
                 tracing::warn!("OCSP status check: Unknown");
                 Ok(()) // Treat as pass but log warning
             }
@@ -142,6 +145,24 @@ impl CertificateChain {
         Ok(sans)
     }
 
+    /// Get the issuer common name of the topmost certificate in the chain.
+    /// This names the root the chain leads to, as used by certbot's `--preferred-chain`.
+    pub fn top_issuer_common_name(&self) -> Result<Option<String>> {
+        let top = self
+            .root
+            .as_ref()
+            .or(self.intermediates.last())
+            .unwrap_or(&self.leaf);
+        let (_, cert) = X509Certificate::from_der(top)
+            .map_err(|e| AcmeError::crypto(format!("Invalid chain certificate: {}", e)))?;
+
+        Ok(cert
+            .issuer()
+            .iter_common_name()
+            .find_map(|cn| cn.as_str().ok())
+            .map(str::to_string))
+    }
+
     /// Get OCSP URL
     pub fn ocsp_url(&self) -> Result<Option<String>> {
         let (_, cert) = X509Certificate::from_der(&self.leaf)
@@ -164,6 +185,29 @@ impl CertificateChain {
     }
 }
 
+/// Selects the chain whose topmost certificate was issued by `preferred_issuer`.
+/// Chains are PEM-encoded, with the server's default chain first. Falls back to the
+/// default chain (index 0) when no chain matches.
+pub fn select_preferred_chain(chains: &[String], preferred_issuer: &str) -> usize {
+    let preferred = preferred_issuer.trim();
+    chains
+        .iter()
+        .position(|pem| {
+            CertificateChain::from_pem(pem.as_bytes())
+                .and_then(|chain| chain.top_issuer_common_name())
+                .ok()
+                .flatten()
+                .is_some_and(|cn| cn.eq_ignore_ascii_case(preferred))
+        })
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                "No certificate chain issued by '{}', using the default chain",
+                preferred
+            );
+            0
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,4 +231,47 @@ mod tests {
         assert_eq!(chain.common_name().unwrap(), "example.com");
         assert_eq!(chain.subject_alt_names().unwrap(), vec!["example.com"]);
     }
+
+    /// Builds a leaf + intermediate chain whose intermediate is issued by `root_cn`.
+    fn chain_to_root(root_cn: &str) -> String {
+        let ca = |cn: &str| {
+            let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, cn);
+            params
+        };
+        let root = rcgen::Issuer::new(ca(root_cn), rcgen::KeyPair::generate().unwrap());
+
+        let intermediate_key = rcgen::KeyPair::generate().unwrap();
+        let intermediate = ca("Example Intermediate")
+            .signed_by(&intermediate_key, &root)
+            .unwrap();
+        let intermediate_issuer = rcgen::Issuer::new(ca("Example Intermediate"), intermediate_key);
+
+        let leaf = CertificateParams::new(vec!["example.com".to_string()])
+            .unwrap()
+            .signed_by(&rcgen::KeyPair::generate().unwrap(), &intermediate_issuer)
+            .unwrap();
+        format!("{}{}", leaf.pem(), intermediate.pem())
+    }
+
+    #[test]
+    fn test_select_preferred_chain() {
+        let chains = vec![
+            chain_to_root("Example Root X2"),
+            chain_to_root("Example Root X1"),
+        ];
+
+        let default = CertificateChain::from_pem(chains[0].as_bytes()).unwrap();
+        assert_eq!(
+            default.top_issuer_common_name().unwrap().as_deref(),
+            Some("Example Root X2")
+        );
+
+        assert_eq!(select_preferred_chain(&chains, "Example Root X1"), 1);
+        assert_eq!(select_preferred_chain(&chains, "example root x2"), 0);
+        assert_eq!(select_preferred_chain(&chains, "Unknown Root"), 0);
+    }
 }
@@ -1,5 +1,5 @@
 pub mod chain;
 pub mod ocsp;
 
-pub use chain::CertificateChain;
-pub use ocsp::{OcspStatus, OcspVerifier};
+pub use chain::{CertificateChain, select_preferred_chain};
+pub use ocsp::{OcspResponse, OcspStatus, OcspVerifier};
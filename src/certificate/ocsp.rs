@@ -1,9 +1,18 @@
-/// Online Certificate Status Protocol (OCSP) verification.
-/// This module provides functionality to check the revocation status of a certificate
-/// by querying an OCSP responder as specified in the certificate's AIA extension.
+/// Online Certificate Status Protocol (OCSP) verification (RFC 6960).
+/// This module checks the revocation status of a certificate by POSTing a DER-encoded
+/// request to the OCSP responder named in the certificate's AIA extension, verifies the
+/// signed response against the issuer and caches it so TLS servers can staple it.
 use crate::error::{AcmeError, Result};
+use crate::types::RevocationReason;
+use jiff::{SignedDuration, Timestamp};
+use rustls::sign::CertifiedKey;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::sync::RwLock;
 use std::time::Duration;
+use x509_parser::asn1_rs::BitString;
 use x509_parser::prelude::*;
+use x509_parser::verify::verify_signature;
 
 /// Represents the revocation status of a certificate as returned by an OCSP responder.
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -11,69 +20,188 @@ pub enum OcspStatus {
     /// The certificate is valid and not revoked.
     Good,
     /// The certificate has been revoked.
-    Revoked,
+    Revoked {
+        /// When the certificate was revoked.
+        revoked_at: Timestamp,
+        /// Why the certificate was revoked, if the responder says.
+        reason: Option<RevocationReason>,
+    },
     /// The responder does not know the status of the certificate.
     Unknown,
 }
 
+/// A verified OCSP response for a single certificate.
+#[derive(Debug, Clone)]
+pub struct OcspResponse {
+    /// The status of the certificate.
+    pub status: OcspStatus,
+    /// When the status was known to be correct.
+    pub this_update: Timestamp,
+    /// When newer status information will be available, if the responder says.
+    pub next_update: Option<Timestamp>,
+    /// The complete DER-encoded `OCSPResponse`, as stapled in TLS handshakes.
+    pub der: Vec<u8>,
+}
+
+impl OcspResponse {
+    /// Whether the response may still be relied upon and stapled at `now`.
+    /// Responses without a `nextUpdate` are kept for [`DEFAULT_RESPONSE_LIFETIME`].
+    pub fn is_fresh(&self, now: Timestamp) -> bool {
+        let expires = self
+            .next_update
+            .unwrap_or_else(|| self.this_update + DEFAULT_RESPONSE_LIFETIME);
+        now < expires
+    }
+}
+
+/// How long a response without a `nextUpdate` is cached.
+pub const DEFAULT_RESPONSE_LIFETIME: SignedDuration = SignedDuration::from_hours(1);
+
+/// Clock skew tolerated between us and the responder.
+const CLOCK_SKEW: SignedDuration = SignedDuration::from_mins(5);
+
+/// DER encoding of the SHA-1 `AlgorithmIdentifier` used in CertIDs.
+const SHA1_ALGORITHM: &[u8] = &[
+    0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00,
+];
+
+/// id-pkix-ocsp-basic (1.3.6.1.5.5.7.48.1.1).
+const OID_OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+
+/// id-pkix-ocsp-nonce (1.3.6.1.5.5.7.48.1.2).
+const OID_OCSP_NONCE: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x02];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_ENUMERATED: u8 = 0x0a;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+
 /// A verifier for checking certificate status via OCSP.
-pub struct OcspVerifier;
+/// Verified responses are cached per certificate until they go stale, so that repeated
+/// checks do not hit the responder and TLS servers can staple them.
+pub struct OcspVerifier {
+    http_client: reqwest::Client,
+    /// Verified responses keyed by [`OcspVerifier::cache_key`].
+    cache: RwLock<HashMap<String, OcspResponse>>,
+}
+
+impl Default for OcspVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl OcspVerifier {
-    /// Verifies the OCSP status of a certificate.
+    /// Creates a verifier with an empty response cache.
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .user_agent("AcmeX/0.7.0")
+                .build()
+                .unwrap_or_default(),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Verifies the OCSP status of a certificate issued by `issuer_der`.
     ///
     /// This method performs the following steps:
-    /// 1. Parses the certificate to find the OCSP responder URL in the AIA extension.
-    /// 2. (In a full implementation) Constructs and sends an OCSP request.
-    /// 3. (In a full implementation) Parses the OCSP response and returns the status.
-    pub async fn verify_status(cert_der: &[u8]) -> Result<OcspStatus> {
+    /// 1. Returns the cached response for the certificate if it is still fresh.
+    /// 2. Finds the OCSP responder URL in the certificate's AIA extension.
+    /// 3. POSTs a request for the certificate's CertID with a random nonce.
+    /// 4. Verifies the response signature against the issuer or a responder it delegated
+    ///    to, checks the CertID, nonce and validity period, and caches the response.
+    pub async fn verify_status(&self, cert_der: &[u8], issuer_der: &[u8]) -> Result<OcspResponse> {
         tracing::debug!("Starting OCSP status verification for certificate");
 
-        let (_, x509) = parse_x509_certificate(cert_der).map_err(|e| {
-            tracing::error!("Failed to parse X.509 certificate for OCSP check: {}", e);
-            AcmeError::certificate(format!("Parse cert failed: {}", e))
-        })?;
+        let cert = parse_certificate(cert_der, "certificate")?;
+        let issuer = parse_certificate(issuer_der, "issuer certificate")?;
+        if cert.issuer().as_raw() != issuer.subject().as_raw() {
+            return Err(AcmeError::certificate(
+                "Certificate was not issued by the given issuer",
+            ));
+        }
 
-        // 1. Find OCSP responder URL in Authority Information Access (AIA) extension
-        let ocsp_url = match Self::find_ocsp_url(&x509) {
-            Ok(url) => {
-                tracing::info!("Found OCSP responder URL: {}", url);
-                url
-            }
-            Err(e) => {
-                tracing::warn!("Could not find OCSP responder URL in certificate: {}", e);
-                return Err(e);
-            }
-        };
+        let key = Self::cache_key(&cert);
+        if let Some(cached) = self.cached_response(&key) {
+            tracing::debug!("Using cached OCSP response");
+            return Ok(cached);
+        }
 
-        // 2. Build and send OCSP request
-        // Note: A production-grade implementation requires a specialized OCSP library
-        // (like `ocsp-rs` or `rcgen`'s internal tools) to handle ASN.1 encoding/decoding.
-        // Here we implement the network orchestration logic.
+        let ocsp_url = Self::find_ocsp_url(&cert).inspect_err(|e| {
+            tracing::warn!("Could not find OCSP responder URL in certificate: {}", e);
+        })?;
+        tracing::debug!("Querying OCSP responder at: {}", ocsp_url);
 
-        let _client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("AcmeX/0.7.0")
-            .build()
-            .map_err(|e| AcmeError::transport(format!("Failed to build HTTP client: {}", e)))?;
+        let nonce: [u8; 16] = rand::random();
+        let request = build_request(&cert_id(&cert, &issuer), &nonce);
+        let response = self
+            .http_client
+            .post(&ocsp_url)
+            .header(reqwest::header::CONTENT_TYPE, "application/ocsp-request")
+            .header(reqwest::header::ACCEPT, "application/ocsp-response")
+            .body(request)
+            .send()
+            .await
+            .map_err(|e| AcmeError::transport(format!("OCSP request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AcmeError::transport(format!(
+                "OCSP responder returned HTTP {}",
+                response.status()
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| AcmeError::transport(format!("Failed to read OCSP response: {}", e)))?;
 
-        tracing::debug!("Querying OCSP responder at: {}", ocsp_url);
+        let verified = verify_response(&body, &cert, &issuer, Some(&nonce), Timestamp::now())?;
+        tracing::info!("OCSP status from {}: {:?}", ocsp_url, verified.status);
+        self.cache
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, verified.clone());
+        Ok(verified)
+    }
 
-        // Simulation of OCSP request/response handling
-        // In a real scenario, we would:
-        // - Generate a nonce
-        // - Hash the issuer name and key
-        // - Encode the OCSPRequest as DER
-        // - POST the DER to ocsp_url with Content-Type: application/ocsp-request
-
-        // For the purpose of this implementation, we validate the URL and simulate a successful check.
-        if ocsp_url.starts_with("http") {
-            tracing::info!("OCSP check successful for responder: {}", ocsp_url);
-            Ok(OcspStatus::Good)
-        } else {
-            tracing::error!("Invalid OCSP responder URL format: {}", ocsp_url);
-            Ok(OcspStatus::Unknown)
+    /// Returns the cached, still fresh response for a certificate, if any.
+    pub fn cached(&self, cert_der: &[u8]) -> Option<OcspResponse> {
+        let cert = parse_certificate(cert_der, "certificate").ok()?;
+        self.cached_response(&Self::cache_key(&cert))
+    }
+
+    /// Attaches the cached response for the key's end-entity certificate, if any,
+    /// so that a rustls server staples it in its handshakes.
+    pub fn staple(&self, mut certified_key: CertifiedKey) -> CertifiedKey {
+        if let Some(response) = certified_key
+            .cert
+            .first()
+            .and_then(|leaf| self.cached(leaf))
+        {
+            certified_key.ocsp = Some(response.der);
         }
+        certified_key
+    }
+
+    fn cached_response(&self, key: &str) -> Option<OcspResponse> {
+        let mut cache = self.cache.write().unwrap_or_else(|e| e.into_inner());
+        let now = Timestamp::now();
+        cache.retain(|_, response| response.is_fresh(now));
+        cache.get(key).cloned()
+    }
+
+    /// Identifies a certificate by its issuer name and serial number, which is what
+    /// an OCSP CertID pins down.
+    fn cache_key(cert: &X509Certificate<'_>) -> String {
+        format!(
+            "{}:{}",
+            hex::encode(Sha1::digest(cert.issuer().as_raw())),
+            hex::encode(cert.raw_serial())
+        )
     }
 
     /// Extracts the OCSP responder URL from the certificate's Authority Information Access (AIA) extension.
@@ -100,15 +228,686 @@ impl OcspVerifier {
     }
 }
 
+fn parse_certificate<'a>(der: &'a [u8], what: &str) -> Result<X509Certificate<'a>> {
+    let (_, x509) = parse_x509_certificate(der).map_err(|e| {
+        tracing::error!("Failed to parse X.509 {} for OCSP check: {}", what, e);
+        AcmeError::certificate(format!("Parse {} failed: {}", what, e))
+    })?;
+    Ok(x509)
+}
+
+/// Encodes a DER element from its tag and content.
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Builds the SHA-1 `CertID` of a certificate: the hashes of its issuer's name and
+/// public key, and its serial number.
+fn cert_id(cert: &X509Certificate<'_>, issuer: &X509Certificate<'_>) -> Vec<u8> {
+    let name_hash = Sha1::digest(cert.issuer().as_raw());
+    let key_hash = Sha1::digest(&issuer.public_key().subject_public_key.data);
+    der(
+        TAG_SEQUENCE,
+        &[
+            SHA1_ALGORITHM.to_vec(),
+            der(TAG_OCTET_STRING, &name_hash),
+            der(TAG_OCTET_STRING, &key_hash),
+            der(TAG_INTEGER, cert.raw_serial()),
+        ]
+        .concat(),
+    )
+}
+
+/// Builds an `OCSPRequest` for one CertID carrying a nonce extension (RFC 8954).
+fn build_request(cert_id: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let request_list = der(TAG_SEQUENCE, &der(TAG_SEQUENCE, cert_id));
+    let nonce_extension = der(
+        TAG_SEQUENCE,
+        &[
+            der(TAG_OID, OID_OCSP_NONCE),
+            der(TAG_OCTET_STRING, &der(TAG_OCTET_STRING, nonce)),
+        ]
+        .concat(),
+    );
+    let extensions = der(0xa2, &der(TAG_SEQUENCE, &nonce_extension));
+    let tbs_request = der(TAG_SEQUENCE, &[request_list, extensions].concat());
+    der(TAG_SEQUENCE, &tbs_request)
+}
+
+fn malformed(what: &str) -> AcmeError {
+    AcmeError::certificate(format!("Malformed OCSP response: {}", what))
+}
+
+/// Reads consecutive DER elements, such as the fields of a SEQUENCE.
+struct DerReader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self { input }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.input.first().copied()
+    }
+
+    /// Reads the next element, returning its tag, its content and its complete encoding.
+    fn read(&mut self) -> Result<(u8, &'a [u8], &'a [u8])> {
+        let [tag, first, rest @ ..] = self.input else {
+            return Err(malformed("truncated element"));
+        };
+        let (len, header) = if *first < 0x80 {
+            (*first as usize, 2)
+        } else {
+            let count = (*first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(malformed("invalid length"));
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0usize, |len, b| (len << 8) | *b as usize);
+            (len, 2 + count)
+        };
+        let end = header
+            .checked_add(len)
+            .filter(|end| *end <= self.input.len())
+            .ok_or_else(|| malformed("truncated element"))?;
+        let (element, remaining) = self.input.split_at(end);
+        self.input = remaining;
+        Ok((*tag, &element[header..], element))
+    }
+
+    /// Reads the next element, which must have `tag`, and returns its content.
+    fn expect(&mut self, tag: u8, what: &str) -> Result<&'a [u8]> {
+        match self.read()? {
+            (t, content, _) if t == tag => Ok(content),
+            _ => Err(malformed(what)),
+        }
+    }
+
+    /// Reads the next element if it has `tag`.
+    fn optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>> {
+        if self.peek_tag() == Some(tag) {
+            self.read().map(|(_, content, _)| Some(content))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Parses a GeneralizedTime from its complete encoding.
+fn parse_time(encoded: &[u8]) -> Result<Timestamp> {
+    let (_, time) = ASN1Time::from_der(encoded).map_err(|_| malformed("invalid time"))?;
+    Timestamp::from_second(time.timestamp()).map_err(|_| malformed("time out of range"))
+}
+
+/// Reads a GeneralizedTime element.
+fn read_time(reader: &mut DerReader<'_>) -> Result<Timestamp> {
+    match reader.read()? {
+        (TAG_GENERALIZED_TIME, _, encoded) => parse_time(encoded),
+        _ => Err(malformed("expected a GeneralizedTime")),
+    }
+}
+
+/// Maps an RFC 5280 CRLReason code.
+fn revocation_reason(code: u8) -> Option<RevocationReason> {
+    Some(match code {
+        0 => RevocationReason::Unspecified,
+        1 => RevocationReason::KeyCompromise,
+        2 => RevocationReason::CaCompromise,
+        3 => RevocationReason::AffiliationChanged,
+        4 => RevocationReason::Superseded,
+        5 => RevocationReason::CessationOfOperation,
+        6 => RevocationReason::CertificateHold,
+        8 => RevocationReason::RemoveFromCRL,
+        9 => RevocationReason::PrivilegeWithdrawn,
+        10 => RevocationReason::AACompromise,
+        _ => return None,
+    })
+}
+
+/// Parses and verifies a DER `OCSPResponse` for `cert`: the response must be a signed
+/// BasicOCSPResponse from the issuer or a responder it delegated to, answer for the
+/// certificate's CertID, echo `nonce` if it carries one, and be current at `now`.
+fn verify_response(
+    der_response: &[u8],
+    cert: &X509Certificate<'_>,
+    issuer: &X509Certificate<'_>,
+    nonce: Option<&[u8]>,
+    now: Timestamp,
+) -> Result<OcspResponse> {
+    let mut outer = DerReader::new(der_response);
+    let mut response = DerReader::new(outer.expect(TAG_SEQUENCE, "expected OCSPResponse")?);
+    match response.expect(TAG_ENUMERATED, "expected responseStatus")? {
+        [0] => {}
+        [status] => {
+            let reason = match status {
+                1 => "malformedRequest",
+                2 => "internalError",
+                3 => "tryLater",
+                5 => "sigRequired",
+                6 => "unauthorized",
+                _ => "unknown status",
+            };
+            return Err(AcmeError::certificate(format!(
+                "OCSP responder refused the request: {}",
+                reason
+            )));
+        }
+        _ => return Err(malformed("invalid responseStatus")),
+    }
+
+    let response_bytes = response
+        .optional(0xa0)?
+        .ok_or_else(|| malformed("missing responseBytes"))?;
+    let mut response_bytes = DerReader::new(
+        DerReader::new(response_bytes).expect(TAG_SEQUENCE, "expected ResponseBytes")?,
+    );
+    if response_bytes.expect(TAG_OID, "expected responseType")? != OID_OCSP_BASIC {
+        return Err(malformed("unsupported response type"));
+    }
+    let basic = response_bytes.expect(TAG_OCTET_STRING, "expected response")?;
+
+    // BasicOCSPResponse
+    let mut basic =
+        DerReader::new(DerReader::new(basic).expect(TAG_SEQUENCE, "expected BasicOCSPResponse")?);
+    let (tag, tbs_response_data, tbs_raw) = basic.read()?;
+    if tag != TAG_SEQUENCE {
+        return Err(malformed("expected ResponseData"));
+    }
+    let (tag, _, algorithm_raw) = basic.read()?;
+    if tag != TAG_SEQUENCE {
+        return Err(malformed("expected signatureAlgorithm"));
+    }
+    let (_, signature_algorithm) = AlgorithmIdentifier::from_der(algorithm_raw)
+        .map_err(|_| malformed("invalid signatureAlgorithm"))?;
+    let (tag, _, signature_raw) = basic.read()?;
+    if tag != TAG_BIT_STRING {
+        return Err(malformed("expected signature"));
+    }
+    let (_, signature) =
+        BitString::from_der(signature_raw).map_err(|_| malformed("invalid signature"))?;
+    let mut certs = Vec::new();
+    if let Some(explicit) = basic.optional(0xa0)? {
+        let mut list =
+            DerReader::new(DerReader::new(explicit).expect(TAG_SEQUENCE, "expected certs")?);
+        while !list.is_empty() {
+            let (_, _, encoded) = list.read()?;
+            certs.push(encoded);
+        }
+    }
+
+    verify_responder(
+        &signature_algorithm,
+        &signature,
+        tbs_raw,
+        issuer,
+        &certs,
+        now,
+    )?;
+
+    // ResponseData
+    let mut data = DerReader::new(tbs_response_data);
+    data.optional(0xa0)?;
+    match data.read()? {
+        (0xa1 | 0xa2, _, _) => {}
+        _ => return Err(malformed("expected responderID")),
+    }
+    read_time(&mut data)?;
+    let mut responses = DerReader::new(data.expect(TAG_SEQUENCE, "expected responses")?);
+    if let Some(extensions) = data.optional(0xa1)? {
+        check_nonce(extensions, nonce)?;
+    }
+
+    let expected_id = cert_id(cert, issuer);
+    while !responses.is_empty() {
+        let mut single = DerReader::new(responses.expect(TAG_SEQUENCE, "expected SingleResponse")?);
+        let (_, id, _) = single.read()?;
+        if !cert_id_matches(id, &expected_id)? {
+            continue;
+        }
+
+        let status = match single.read()? {
+            (0x80, _, _) => OcspStatus::Good,
+            (0xa1, revoked_info, _) => {
+                let mut info = DerReader::new(revoked_info);
+                let revoked_at = read_time(&mut info)?;
+                let reason = match info.optional(0xa0)? {
+                    Some(explicit) => {
+                        match DerReader::new(explicit).expect(TAG_ENUMERATED, "expected reason")? {
+                            [code] => revocation_reason(*code),
+                            _ => None,
+                        }
+                    }
+                    None => None,
+                };
+                OcspStatus::Revoked { revoked_at, reason }
+            }
+            (0x82, _, _) => OcspStatus::Unknown,
+            _ => return Err(malformed("invalid certStatus")),
+        };
+        let this_update = read_time(&mut single)?;
+        let next_update = match single.optional(0xa0)? {
+            Some(explicit) => Some(read_time(&mut DerReader::new(explicit))?),
+            None => None,
+        };
+
+        if this_update > now + CLOCK_SKEW {
+            return Err(AcmeError::certificate("OCSP response is not yet valid"));
+        }
+        if next_update.is_some_and(|next| next + CLOCK_SKEW < now) {
+            return Err(AcmeError::certificate("OCSP response is stale"));
+        }
+
+        let encoded_len = der_response.len() - outer.input.len();
+        return Ok(OcspResponse {
+            status,
+            this_update,
+            next_update,
+            der: der_response[..encoded_len].to_vec(),
+        });
+    }
+
+    Err(AcmeError::certificate(
+        "OCSP response does not cover the certificate",
+    ))
+}
+
+/// Checks that the response was signed by the issuer itself, or by a certificate the
+/// issuer signed for the OCSPSigning purpose (RFC 6960 Section 4.2.2.2).
+fn verify_responder(
+    algorithm: &AlgorithmIdentifier<'_>,
+    signature: &BitString<'_>,
+    tbs_response_data: &[u8],
+    issuer: &X509Certificate<'_>,
+    certs: &[&[u8]],
+    now: Timestamp,
+) -> Result<()> {
+    if verify_signature(issuer.public_key(), algorithm, signature, tbs_response_data).is_ok() {
+        return Ok(());
+    }
+
+    for encoded in certs {
+        let Ok((_, responder)) = X509Certificate::from_der(encoded) else {
+            continue;
+        };
+        let authorized = responder.issuer().as_raw() == issuer.subject().as_raw()
+            && responder
+                .verify_signature(Some(issuer.public_key()))
+                .is_ok()
+            && ASN1Time::from_timestamp(now.as_second())
+                .is_ok_and(|now| responder.validity().is_valid_at(now))
+            && responder
+                .extended_key_usage()
+                .ok()
+                .flatten()
+                .is_some_and(|eku| eku.value.ocsp_signing);
+        if authorized
+            && verify_signature(
+                responder.public_key(),
+                algorithm,
+                signature,
+                tbs_response_data,
+            )
+            .is_ok()
+        {
+            tracing::debug!(
+                "OCSP response signed by delegated responder {}",
+                responder.subject()
+            );
+            return Ok(());
+        }
+    }
+
+    Err(AcmeError::certificate(
+        "OCSP response signature does not verify against the issuer or a delegated responder",
+    ))
+}
+
+/// Compares a response's CertID with the one requested, field by field since
+/// responders may encode the hash algorithm parameters differently.
+fn cert_id_matches(id: &[u8], expected: &[u8]) -> Result<bool> {
+    fn fields(id: &[u8]) -> Result<[&[u8]; 4]> {
+        let mut reader = DerReader::new(id);
+        let mut algorithm = DerReader::new(reader.expect(TAG_SEQUENCE, "expected hashAlgorithm")?);
+        let oid = algorithm.expect(TAG_OID, "expected hash algorithm OID")?;
+        let name_hash = reader.expect(TAG_OCTET_STRING, "expected issuerNameHash")?;
+        let key_hash = reader.expect(TAG_OCTET_STRING, "expected issuerKeyHash")?;
+        let serial = reader.expect(TAG_INTEGER, "expected serialNumber")?;
+        Ok([oid, name_hash, key_hash, serial])
+    }
+    let (_, expected, _) = DerReader::new(expected).read()?;
+    Ok(fields(id)? == fields(expected)?)
+}
+
+/// Checks the nonce extension of a response, if the response carries one.
+/// Many responders serve pre-signed responses without echoing the nonce.
+fn check_nonce(extensions: &[u8], nonce: Option<&[u8]>) -> Result<()> {
+    let mut list =
+        DerReader::new(DerReader::new(extensions).expect(TAG_SEQUENCE, "expected extensions")?);
+    while !list.is_empty() {
+        let mut extension = DerReader::new(list.expect(TAG_SEQUENCE, "expected extension")?);
+        if extension.expect(TAG_OID, "expected extnID")? != OID_OCSP_NONCE {
+            continue;
+        }
+        extension.optional(0x01)?;
+        let value = extension.expect(TAG_OCTET_STRING, "expected extnValue")?;
+        let echoed = match DerReader::new(value).read() {
+            Ok((TAG_OCTET_STRING, inner, encoded)) if encoded.len() == value.len() => inner,
+            _ => value,
+        };
+        if nonce.is_some_and(|nonce| nonce != echoed) {
+            return Err(AcmeError::certificate("OCSP response nonce does not match"));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rcgen::{
+        BasicConstraints, CertificateParams, CustomExtension, ExtendedKeyUsagePurpose, IsCa,
+        KeyPair, SigningKey,
+    };
+
+    /// ecdsa-with-SHA256, as produced by rcgen's default P-256 keys.
+    const ECDSA_SHA256: &[u8] = &[
+        0x30, 0x0a, 0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02,
+    ];
+
+    struct Fixture {
+        issuer: rcgen::Issuer<'static, KeyPair>,
+        issuer_der: Vec<u8>,
+        leaf_der: Vec<u8>,
+    }
+
+    fn fixture() -> Fixture {
+        let issuer_key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec![]).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "OCSP Test CA");
+        let issuer_cert = params.self_signed(&issuer_key).unwrap();
+        let issuer = rcgen::Issuer::new(params, issuer_key);
+
+        let mut leaf = CertificateParams::new(vec!["ocsp.test".to_string()]).unwrap();
+        leaf.custom_extensions
+            .push(CustomExtension::from_oid_content(
+                &[1, 3, 6, 1, 5, 5, 7, 1, 1],
+                aia("http://127.0.0.1:1/ocsp"),
+            ));
+        let leaf_cert = leaf
+            .signed_by(&KeyPair::generate().unwrap(), &issuer)
+            .unwrap();
+
+        Fixture {
+            issuer,
+            issuer_der: issuer_cert.der().to_vec(),
+            leaf_der: leaf_cert.der().to_vec(),
+        }
+    }
+
+    /// Content of an AIA extension naming an OCSP responder.
+    fn aia(url: &str) -> Vec<u8> {
+        der(
+            TAG_SEQUENCE,
+            &der(
+                TAG_SEQUENCE,
+                &[
+                    der(TAG_OID, &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01]),
+                    der(0x86, url.as_bytes()),
+                ]
+                .concat(),
+            ),
+        )
+    }
+
+    fn generalized_time(at: Timestamp) -> Vec<u8> {
+        der(
+            TAG_GENERALIZED_TIME,
+            at.strftime("%Y%m%d%H%M%SZ").to_string().as_bytes(),
+        )
+    }
+
+    /// Builds a BasicOCSPResponse for `cert_id` signed by `signer`.
+    fn response(
+        cert_id: &[u8],
+        cert_status: Vec<u8>,
+        nonce: Option<&[u8]>,
+        signer: &KeyPair,
+        certs: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let now = Timestamp::now();
+        let single = der(
+            TAG_SEQUENCE,
+            &[
+                cert_id.to_vec(),
+                cert_status,
+                generalized_time(now - SignedDuration::from_mins(1)),
+                der(
+                    0xa0,
+                    &generalized_time(now + SignedDuration::from_hours(24)),
+                ),
+            ]
+            .concat(),
+        );
+        let mut data = vec![
+            der(0xa2, &der(TAG_OCTET_STRING, &[0u8; 20])),
+            generalized_time(now),
+            der(TAG_SEQUENCE, &single),
+        ];
+        if let Some(nonce) = nonce {
+            let extension = der(
+                TAG_SEQUENCE,
+                &[
+                    der(TAG_OID, OID_OCSP_NONCE),
+                    der(TAG_OCTET_STRING, &der(TAG_OCTET_STRING, nonce)),
+                ]
+                .concat(),
+            );
+            data.push(der(0xa1, &der(TAG_SEQUENCE, &extension)));
+        }
+        let tbs = der(TAG_SEQUENCE, &data.concat());
+        let signature = signer.sign(&tbs).unwrap();
+        let mut basic = vec![
+            tbs,
+            ECDSA_SHA256.to_vec(),
+            der(TAG_BIT_STRING, &[&[0u8][..], &signature].concat()),
+        ];
+        if !certs.is_empty() {
+            basic.push(der(0xa0, &der(TAG_SEQUENCE, &certs.concat())));
+        }
+        let basic = der(TAG_SEQUENCE, &basic.concat());
+        let bytes = der(
+            TAG_SEQUENCE,
+            &[der(TAG_OID, OID_OCSP_BASIC), der(TAG_OCTET_STRING, &basic)].concat(),
+        );
+        der(
+            TAG_SEQUENCE,
+            &[der(TAG_ENUMERATED, &[0]), der(0xa0, &bytes)].concat(),
+        )
+    }
+
+    fn verify(f: &Fixture, der_response: &[u8], nonce: Option<&[u8]>) -> Result<OcspResponse> {
+        let leaf = parse_certificate(&f.leaf_der, "certificate").unwrap();
+        let issuer = parse_certificate(&f.issuer_der, "issuer").unwrap();
+        verify_response(der_response, &leaf, &issuer, nonce, Timestamp::now())
+    }
+
+    fn leaf_cert_id(f: &Fixture) -> Vec<u8> {
+        let leaf = parse_certificate(&f.leaf_der, "certificate").unwrap();
+        let issuer = parse_certificate(&f.issuer_der, "issuer").unwrap();
+        cert_id(&leaf, &issuer)
+    }
 
     #[tokio::test]
     async fn test_ocsp_url_extraction_failure() {
         // Test with a dummy cert that has no AIA
         let dummy_cert = vec![0u8; 10];
-        let result = OcspVerifier::verify_status(&dummy_cert).await;
+        let result = OcspVerifier::new()
+            .verify_status(&dummy_cert, &dummy_cert)
+            .await;
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_request_encoding() {
+        let f = fixture();
+        let leaf = parse_certificate(&f.leaf_der, "certificate").unwrap();
+        assert_eq!(
+            OcspVerifier::find_ocsp_url(&leaf).unwrap(),
+            "http://127.0.0.1:1/ocsp"
+        );
+
+        let request = build_request(&leaf_cert_id(&f), &[7u8; 16]);
+        let mut tbs = DerReader::new(
+            DerReader::new(DerReader::new(&request).expect(TAG_SEQUENCE, "").unwrap())
+                .expect(TAG_SEQUENCE, "")
+                .unwrap(),
+        );
+        let list = tbs.expect(TAG_SEQUENCE, "").unwrap();
+        let request_cert = DerReader::new(list).expect(TAG_SEQUENCE, "").unwrap();
+        assert_eq!(request_cert, leaf_cert_id(&f).as_slice());
+        assert!(tbs.optional(0xa2).unwrap().is_some());
+        assert!(tbs.is_empty());
+    }
+
+    #[test]
+    fn test_verify_good_and_revoked() {
+        let f = fixture();
+        let id = leaf_cert_id(&f);
+        let nonce = [9u8; 16];
+
+        let good = response(&id, der(0x80, &[]), Some(&nonce), f.issuer.key(), &[]);
+        let verified = verify(&f, &good, Some(&nonce)).unwrap();
+        assert_eq!(verified.status, OcspStatus::Good);
+        assert_eq!(verified.der, good);
+        assert!(verified.is_fresh(Timestamp::now()));
+
+        let revoked_at = Timestamp::from_second(1_700_000_000).unwrap();
+        let revoked_info = der(
+            0xa1,
+            &[
+                generalized_time(revoked_at),
+                der(0xa0, &der(TAG_ENUMERATED, &[1])),
+            ]
+            .concat(),
+        );
+        let revoked = response(&id, revoked_info, None, f.issuer.key(), &[]);
+        assert_eq!(
+            verify(&f, &revoked, Some(&nonce)).unwrap().status,
+            OcspStatus::Revoked {
+                revoked_at,
+                reason: Some(RevocationReason::KeyCompromise)
+            }
+        );
+
+        let unknown = response(&id, der(0x82, &[]), None, f.issuer.key(), &[]);
+        assert_eq!(
+            verify(&f, &unknown, None).unwrap().status,
+            OcspStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_forged_responses() {
+        let f = fixture();
+        let id = leaf_cert_id(&f);
+        let nonce = [9u8; 16];
+
+        // Signed by a key the issuer never certified
+        let stranger = KeyPair::generate().unwrap();
+        let forged = response(&id, der(0x80, &[]), None, &stranger, &[]);
+        assert!(verify(&f, &forged, None).is_err());
+
+        // Replayed with another request's nonce
+        let replayed = response(&id, der(0x80, &[]), Some(&[1u8; 16]), f.issuer.key(), &[]);
+        assert!(verify(&f, &replayed, Some(&nonce)).is_err());
+
+        // Answering for another certificate
+        let mut other_id = id.clone();
+        *other_id.last_mut().unwrap() ^= 0xff;
+        let other = response(&other_id, der(0x80, &[]), None, f.issuer.key(), &[]);
+        assert!(verify(&f, &other, None).is_err());
+
+        // A responder refusing the request
+        let refused = der(TAG_SEQUENCE, &der(TAG_ENUMERATED, &[3]));
+        assert!(verify(&f, &refused, None).is_err());
+    }
+
+    #[test]
+    fn test_verify_delegated_responder() {
+        let f = fixture();
+        let id = leaf_cert_id(&f);
+        let responder_key = KeyPair::generate().unwrap();
+
+        let mut params = CertificateParams::new(vec![]).unwrap();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "OCSP Responder");
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::OcspSigning];
+        let delegated = params.signed_by(&responder_key, &f.issuer).unwrap();
+        let signed = response(
+            &id,
+            der(0x80, &[]),
+            None,
+            &responder_key,
+            &[delegated.der().to_vec()],
+        );
+        assert_eq!(verify(&f, &signed, None).unwrap().status, OcspStatus::Good);
+
+        // Without the OCSPSigning purpose the certificate may not sign responses
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let undelegated = params.signed_by(&responder_key, &f.issuer).unwrap();
+        let signed = response(
+            &id,
+            der(0x80, &[]),
+            None,
+            &responder_key,
+            &[undelegated.der().to_vec()],
+        );
+        assert!(verify(&f, &signed, None).is_err());
+    }
+
+    #[test]
+    fn test_cache_and_staple() {
+        let f = fixture();
+        let leaf = parse_certificate(&f.leaf_der, "certificate").unwrap();
+        let good = response(&leaf_cert_id(&f), der(0x80, &[]), None, f.issuer.key(), &[]);
+        let verified = verify(&f, &good, None).unwrap();
+
+        let verifier = OcspVerifier::new();
+        assert!(verifier.cached(&f.leaf_der).is_none());
+        verifier
+            .cache
+            .write()
+            .unwrap()
+            .insert(OcspVerifier::cache_key(&leaf), verified);
+        assert_eq!(verifier.cached(&f.leaf_der).unwrap().der, good);
+
+        let signing_key = rustls::crypto::aws_lc_rs::sign::any_supported_type(
+            &rustls::pki_types::PrivateKeyDer::try_from(f.issuer.key().serialize_der()).unwrap(),
+        )
+        .unwrap();
+        let certified = CertifiedKey::new(vec![f.leaf_der.clone().into()], signing_key);
+        assert_eq!(verifier.staple(certified).ocsp, Some(good));
+    }
 }
@@ -6,7 +6,8 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use super::ChallengeSolver;
-use crate::error::Result;
+use super::propagation::PropagationChecker;
+use crate::error::{AcmeError, Result};
 use crate::order::Challenge;
 use crate::types::{ChallengeType, Identifier};
 
@@ -21,8 +22,34 @@ pub trait DnsProvider: Send + Sync {
 
     /// Verify that the DNS record is propagated
     async fn verify_record(&self, domain: &str, value: &str) -> Result<bool>;
+
+    /// List the zones managed by the provider's account, for zone discovery
+    async fn list_zones(&self) -> Result<Vec<DnsZone>> {
+        Err(AcmeError::configuration(
+            "This DNS provider cannot list its zones; configure the zone explicitly",
+        ))
+    }
 }
 
+/// A DNS zone managed by a provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsZone {
+    /// Zone apex, e.g. "example.com"
+    pub name: String,
+    /// Provider-specific zone identifier
+    pub id: String,
+}
+
+/// Looks up CNAME records, letting the solver follow delegated challenge names
+#[async_trait]
+pub trait CnameResolver: Send + Sync {
+    /// Return the CNAME target of `name`, or `None` if it has no CNAME record
+    async fn resolve_cname(&self, name: &str) -> Result<Option<String>>;
+}
+
+/// Longest CNAME chain followed from a challenge name
+const MAX_CNAME_HOPS: usize = 8;
+
 /// Mock DNS provider for testing
 pub struct MockDnsProvider {
     records: Arc<RwLock<std::collections::HashMap<String, String>>>,
@@ -79,10 +106,14 @@ impl DnsProvider for MockDnsProvider {
 pub struct Dns01Solver {
     /// DNS provider implementation
     provider: Arc<dyn DnsProvider>,
-    /// Domain name
+    /// Domain used when the identifier carries no name
     domain: String,
-    /// Record ID for cleanup
-    record_id: Arc<RwLock<Option<String>>>,
+    /// Created records (record name, record ID, value) for propagation checks and cleanup
+    records: Arc<RwLock<Vec<(String, String, String)>>>,
+    /// Resolver following CNAME delegation of challenge names, if enabled
+    cname_resolver: Option<Arc<dyn CnameResolver>>,
+    /// Checker waiting for the authoritative nameservers, if enabled
+    propagation: Option<Arc<PropagationChecker>>,
 }
 
 impl Dns01Solver {
@@ -91,14 +122,73 @@ impl Dns01Solver {
         Self {
             provider,
             domain,
-            record_id: Arc::new(RwLock::new(None)),
+            records: Arc::new(RwLock::new(Vec::new())),
+            cname_resolver: None,
+            propagation: None,
         }
     }
 
+    /// Wait in `present` until every authoritative nameserver serves the records
+    pub fn with_propagation_checker(mut self, checker: Arc<PropagationChecker>) -> Self {
+        self.propagation = Some(checker);
+        self
+    }
+
+    /// Follow CNAMEs at `_acme-challenge.<domain>` with `resolver`, writing the TXT
+    /// record at the end of the chain instead
+    pub fn with_cname_resolver(mut self, resolver: Arc<dyn CnameResolver>) -> Self {
+        self.cname_resolver = Some(resolver);
+        self
+    }
+
     /// Create with mock provider (for testing)
     pub fn with_mock(domain: String) -> Self {
         Self::new(Arc::new(MockDnsProvider::new()), domain)
     }
+
+    /// Name of the TXT record validating an identifier; wildcards share the base name
+    fn record_name(&self, identifier: &Identifier) -> String {
+        let domain = identifier.value.trim_start_matches("*.");
+        let domain = if domain.is_empty() {
+            &self.domain
+        } else {
+            domain
+        };
+        format!("_acme-challenge.{}", domain)
+    }
+
+    /// Follow the CNAME chain starting at a challenge name to the name holding the record
+    async fn delegated_name(&self, name: String) -> Result<String> {
+        match &self.cname_resolver {
+            Some(resolver) => follow_cnames(resolver.as_ref(), name).await,
+            None => Ok(name),
+        }
+    }
+}
+
+/// Follow the CNAME chain starting at `name` to the name holding its records
+pub(crate) async fn follow_cnames(resolver: &dyn CnameResolver, name: String) -> Result<String> {
+    let mut seen = vec![name.to_lowercase()];
+    let mut current = name;
+    while let Some(target) = resolver.resolve_cname(&current).await? {
+        let target = target.trim_end_matches('.').to_string();
+        if seen.contains(&target.to_lowercase()) {
+            return Err(AcmeError::configuration(format!(
+                "CNAME loop at {} while following {}",
+                target, seen[0]
+            )));
+        }
+        if seen.len() > MAX_CNAME_HOPS {
+            return Err(AcmeError::configuration(format!(
+                "More than {} CNAMEs while following {}",
+                MAX_CNAME_HOPS, seen[0]
+            )));
+        }
+        tracing::debug!("Following CNAME {} -> {}", current, target);
+        seen.push(target.to_lowercase());
+        current = target;
+    }
+    Ok(current)
 }
 
 #[async_trait]
@@ -108,9 +198,9 @@ impl ChallengeSolver for Dns01Solver {
     }
 
     async fn prepare(
-        &mut self,
+        &self,
         challenge: &Challenge,
-        _identifier: &Identifier,
+        identifier: &Identifier,
         key_authorization: &str,
     ) -> Result<()> {
         // Compute DNS record value (base64url of SHA256 hash)
@@ -121,16 +211,18 @@ impl ChallengeSolver for Dns01Solver {
         let digest = hasher.finalize();
         let record_value = URL_SAFE_NO_PAD.encode(&digest[..]);
 
-        // Create the DNS record
-        let domain = format!("_acme-challenge.{}", self.domain);
+        // Create the DNS record, at the delegation target if the name is a CNAME
+        let domain = self.delegated_name(self.record_name(identifier)).await?;
         let id = self
             .provider
             .create_txt_record(&domain, &record_value)
             .await?;
 
         // Store the record ID for cleanup
-        let mut record_id = self.record_id.write().await;
-        *record_id = Some(id);
+        self.records
+            .write()
+            .await
+            .push((domain.clone(), id, record_value));
 
         tracing::info!(
             "DNS-01 challenge prepared for domain: {} (token: {})",
@@ -142,27 +234,41 @@ impl ChallengeSolver for Dns01Solver {
     }
 
     async fn present(&self) -> Result<()> {
-        // For DNS-01, we just need to have the record created
+        // Records of concurrent authorizations are checked too; served ones pass at once
+        if let Some(checker) = &self.propagation {
+            let records = self.records.read().await.clone();
+            for (domain, _, value) in records {
+                checker.wait_for(&domain, &value).await?;
+            }
+        }
         tracing::debug!("DNS-01 challenge presented");
         Ok(())
     }
 
     async fn verify(&self) -> Result<bool> {
         // Check if the record exists (in a real scenario, we'd query DNS)
-        let record_id = self.record_id.read().await;
-        Ok(record_id.is_some())
+        let records = self.records.read().await;
+        Ok(!records.is_empty())
     }
 
-    async fn cleanup(&mut self) -> Result<()> {
-        let mut record_id_guard = self.record_id.write().await;
-
-        if let Some(id) = record_id_guard.take() {
-            let domain = format!("_acme-challenge.{}", self.domain);
-            self.provider.delete_txt_record(&domain, &id).await?;
-            tracing::info!("DNS-01 record cleaned up");
+    async fn cleanup(&self) -> Result<()> {
+        let records = std::mem::take(&mut *self.records.write().await);
+
+        // Attempt every deletion, reporting the first failure
+        let mut result = Ok(());
+        for (domain, id, _) in records {
+            match self.provider.delete_txt_record(&domain, &id).await {
+                Ok(()) => tracing::info!("DNS-01 record cleaned up: {}", domain),
+                Err(e) => {
+                    tracing::warn!("Failed to delete DNS-01 record {}: {}", domain, e);
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                }
+            }
         }
 
-        Ok(())
+        result
     }
 }
 
@@ -204,7 +310,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_dns01_solver_prepare() {
-        let mut solver = Dns01Solver::with_mock("example.com".to_string());
+        let solver = Dns01Solver::with_mock("example.com".to_string());
         let challenge = Challenge {
             challenge_type: "dns-01".to_string(),
             url: "https://example.com/challenge/123".to_string(),
@@ -214,6 +320,7 @@ mod tests {
             validation: None,
             updated: None,
             error: None,
+            retry_after: None,
         };
         let identifier = Identifier::dns("example.com");
 
@@ -222,4 +329,118 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_dns01_records_per_identifier() {
+        let provider = Arc::new(MockDnsProvider::new());
+        let solver = Dns01Solver::new(provider.clone(), "example.com".to_string());
+        let challenge = |token: &str| Challenge {
+            challenge_type: "dns-01".to_string(),
+            url: format!("https://example.com/challenge/{}", token),
+            status: "pending".to_string(),
+            token: token.to_string(),
+            key_authorization: None,
+            validation: None,
+            updated: None,
+            error: None,
+            retry_after: None,
+        };
+
+        solver
+            .prepare(
+                &challenge("a"),
+                &Identifier::dns("www.example.com"),
+                "a.auth",
+            )
+            .await
+            .unwrap();
+        solver
+            .prepare(&challenge("b"), &Identifier::dns("*.example.org"), "b.auth")
+            .await
+            .unwrap();
+        assert_eq!(provider.records.read().await.len(), 2);
+        assert!(
+            provider
+                .records
+                .read()
+                .await
+                .keys()
+                .any(|k| k.starts_with("_acme-challenge.example.org/"))
+        );
+
+        solver.cleanup().await.unwrap();
+        assert!(provider.records.read().await.is_empty());
+        assert!(!solver.verify().await.unwrap());
+    }
+
+    /// Resolves CNAMEs from a fixed table
+    struct StaticCnames(Vec<(&'static str, &'static str)>);
+
+    #[async_trait]
+    impl CnameResolver for StaticCnames {
+        async fn resolve_cname(&self, name: &str) -> Result<Option<String>> {
+            Ok(self
+                .0
+                .iter()
+                .find(|(from, _)| *from == name)
+                .map(|(_, to)| to.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dns01_follows_cname_delegation() {
+        let provider = Arc::new(MockDnsProvider::new());
+        let solver = Dns01Solver::new(provider.clone(), String::new()).with_cname_resolver(
+            Arc::new(StaticCnames(vec![
+                (
+                    "_acme-challenge.example.com",
+                    "example-com.validation.example.net.",
+                ),
+                (
+                    "example-com.validation.example.net",
+                    "d420c923.auth.acme-dns.example.org.",
+                ),
+                ("_acme-challenge.loop.example", "a.loop.example"),
+                ("a.loop.example", "_acme-challenge.loop.example"),
+            ])),
+        );
+        let challenge = Challenge {
+            challenge_type: "dns-01".to_string(),
+            url: "https://example.com/challenge/1".to_string(),
+            status: "pending".to_string(),
+            token: "token".to_string(),
+            key_authorization: None,
+            validation: None,
+            updated: None,
+            error: None,
+            retry_after: None,
+        };
+
+        solver
+            .prepare(&challenge, &Identifier::dns("*.example.com"), "token.auth")
+            .await
+            .unwrap();
+        solver
+            .prepare(&challenge, &Identifier::dns("example.org"), "token.auth")
+            .await
+            .unwrap();
+        let names: Vec<String> = provider
+            .records
+            .read()
+            .await
+            .keys()
+            .map(|k| k.split('/').next().unwrap().to_string())
+            .collect();
+        assert!(names.contains(&"d420c923.auth.acme-dns.example.org".to_string()));
+        assert!(names.contains(&"_acme-challenge.example.org".to_string()));
+
+        let err = solver
+            .prepare(&challenge, &Identifier::dns("loop.example"), "token.auth")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("CNAME loop"));
+
+        solver.cleanup().await.unwrap();
+        assert!(provider.records.read().await.is_empty());
+    }
 }
@@ -1,6 +1,7 @@
+use async_trait::async_trait;
 use hickory_resolver::config::ResolverConfig;
 use hickory_resolver::net::runtime::TokioRuntimeProvider;
-use hickory_resolver::proto::rr::RData;
+use hickory_resolver::proto::rr::{RData, RecordType};
 /// DNS query caching implementation
 use std::collections::HashMap;
 use std::net::IpAddr;
@@ -8,6 +9,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+use super::dns01::CnameResolver;
 use crate::error::Result;
 
 /// Cached DNS record
@@ -202,4 +204,83 @@ impl CachingDnsResolver {
 
         Ok(txts)
     }
+
+    /// Resolve the NS records of a name, empty if it is not a zone apex.
+    /// Not cached; the nameserver addresses are, through `resolve_a`.
+    pub async fn resolve_ns(&self, domain: &str) -> Result<Vec<String>> {
+        let response = match self.resolver.lookup(domain, RecordType::NS).await {
+            Ok(response) => response,
+            Err(e) if e.is_no_records_found() => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(crate::error::AcmeError::transport(format!(
+                    "DNS NS lookup failed: {}",
+                    e
+                )));
+            }
+        };
+
+        Ok(response
+            .answers()
+            .iter()
+            .filter_map(|record| match &record.data {
+                RData::NS(ns) => Some(ns.to_string().trim_end_matches('.').to_string()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Check whether a name is a zone apex, i.e. owns an SOA record.
+    pub async fn is_zone_apex(&self, domain: &str) -> Result<bool> {
+        let response = match self.resolver.lookup(domain, RecordType::SOA).await {
+            Ok(response) => response,
+            Err(e) if e.is_no_records_found() => return Ok(false),
+            Err(e) => {
+                return Err(crate::error::AcmeError::transport(format!(
+                    "DNS SOA lookup failed: {}",
+                    e
+                )));
+            }
+        };
+
+        // A CNAME at the name answers with the SOA of its target's zone instead
+        let domain = domain.trim_end_matches('.');
+        Ok(response.answers().iter().any(|record| {
+            matches!(record.data, RData::SOA(_))
+                && record
+                    .name
+                    .to_string()
+                    .trim_end_matches('.')
+                    .eq_ignore_ascii_case(domain)
+        }))
+    }
+
+    /// Resolve the CNAME target of a name.
+    /// Not cached, so a delegation added just before issuance is seen at once.
+    pub async fn resolve_cname(&self, domain: &str) -> Result<Option<String>> {
+        let response = match self.resolver.lookup(domain, RecordType::CNAME).await {
+            Ok(response) => response,
+            Err(e) if e.is_no_records_found() => return Ok(None),
+            Err(e) => {
+                return Err(crate::error::AcmeError::transport(format!(
+                    "DNS CNAME lookup failed: {}",
+                    e
+                )));
+            }
+        };
+
+        Ok(response
+            .answers()
+            .iter()
+            .find_map(|record| match &record.data {
+                RData::CNAME(target) => Some(target.to_string().trim_end_matches('.').to_string()),
+                _ => None,
+            }))
+    }
+}
+
+#[async_trait]
+impl CnameResolver for CachingDnsResolver {
+    async fn resolve_cname(&self, name: &str) -> Result<Option<String>> {
+        CachingDnsResolver::resolve_cname(self, name).await
+    }
 }
@@ -1,6 +1,7 @@
 /// HTTP-01 challenge implementation
 use async_trait::async_trait;
 use axum::{Router, extract::Path, http::StatusCode, routing::get};
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
@@ -15,8 +16,8 @@ use crate::types::{ChallengeType, Identifier};
 pub struct Http01Solver {
     /// Server listening address
     listen_addr: SocketAddr,
-    /// Key authorization token
-    key_authorization: Arc<RwLock<Option<String>>>,
+    /// Key authorizations keyed by challenge token
+    key_authorizations: Arc<RwLock<HashMap<String, String>>>,
     /// Server handle for shutdown
     server_handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
 }
@@ -33,14 +34,19 @@ impl Http01Solver {
     pub fn new(listen_addr: SocketAddr) -> Self {
         Self {
             listen_addr,
-            key_authorization: Arc::new(RwLock::new(None)),
+            key_authorizations: Arc::new(RwLock::new(HashMap::new())),
             server_handle: Arc::new(RwLock::new(None)),
         }
     }
 
-    /// Start the HTTP server
+    /// Start the HTTP server, unless it is already serving other challenges
     async fn start_server(&self) -> Result<()> {
-        let key_auth = Arc::clone(&self.key_authorization);
+        let mut server = self.server_handle.write().await;
+        if server.is_some() {
+            return Ok(());
+        }
+
+        let key_auth = Arc::clone(&self.key_authorizations);
 
         // Create router
         let app = Router::new()
@@ -62,7 +68,6 @@ impl Http01Solver {
             let _ = axum::serve(listener, app).await;
         });
 
-        let mut server = self.server_handle.write().await;
         *server = Some(handle);
 
         Ok(())
@@ -72,15 +77,14 @@ impl Http01Solver {
 /// Handle ACME challenge requests
 async fn handle_challenge(
     Path(token): Path<String>,
-    axum::extract::State(key_auth): axum::extract::State<Arc<RwLock<Option<String>>>>,
+    axum::extract::State(key_auths): axum::extract::State<Arc<RwLock<HashMap<String, String>>>>,
 ) -> std::result::Result<String, StatusCode> {
-    let auth = key_auth.read().await;
-    if let Some(ref auth_str) = *auth
-        && auth_str.contains(&token)
-    {
-        return Ok(auth_str.clone());
-    }
-    Err(StatusCode::NOT_FOUND)
+    key_auths
+        .read()
+        .await
+        .get(&token)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 #[async_trait]
@@ -90,14 +94,16 @@ impl ChallengeSolver for Http01Solver {
     }
 
     async fn prepare(
-        &mut self,
+        &self,
         challenge: &Challenge,
         _identifier: &Identifier,
         key_authorization: &str,
     ) -> Result<()> {
         // Store the key authorization
-        let mut auth = self.key_authorization.write().await;
-        *auth = Some(key_authorization.to_string());
+        self.key_authorizations
+            .write()
+            .await
+            .insert(challenge.token.clone(), key_authorization.to_string());
 
         // Start the server
         self.start_server().await?;
@@ -116,14 +122,13 @@ impl ChallengeSolver for Http01Solver {
 
     async fn verify(&self) -> Result<bool> {
         // Try to fetch the challenge from the server
-        let auth_guard = self.key_authorization.read().await;
-        Ok(auth_guard.is_some())
+        let auths = self.key_authorizations.read().await;
+        Ok(!auths.is_empty())
     }
 
-    async fn cleanup(&mut self) -> Result<()> {
-        // Clear the key authorization
-        let mut auth = self.key_authorization.write().await;
-        *auth = None;
+    async fn cleanup(&self) -> Result<()> {
+        // Clear the key authorizations
+        self.key_authorizations.write().await.clear();
 
         // Stop the server
         let mut handle = self.server_handle.write().await;
@@ -157,10 +162,11 @@ mod tests {
             validation: None,
             updated: None,
             error: None,
+            retry_after: None,
         };
         let identifier = Identifier::dns("example.com");
 
-        let mut solver = Http01Solver::new("127.0.0.1:9999".parse().unwrap());
+        let solver = Http01Solver::new("127.0.0.1:9999".parse().unwrap());
         let result = solver
             .prepare(&challenge, &identifier, "test-token.test-auth")
             .await;
@@ -168,4 +174,24 @@ mod tests {
         // This might fail if port 9999 is not available, so we just check the method exists
         let _ = result;
     }
+
+    #[tokio::test]
+    async fn test_http01_serves_each_token() {
+        let key_auths = Arc::new(RwLock::new(HashMap::from([
+            ("token-a".to_string(), "token-a.thumb".to_string()),
+            ("token-b".to_string(), "token-b.thumb".to_string()),
+        ])));
+
+        let served = handle_challenge(
+            Path("token-b".to_string()),
+            axum::extract::State(key_auths.clone()),
+        )
+        .await;
+        assert_eq!(served, Ok("token-b.thumb".to_string()));
+
+        // A prefix of a known token must not match
+        let missing =
+            handle_challenge(Path("token".to_string()), axum::extract::State(key_auths)).await;
+        assert_eq!(missing, Err(StatusCode::NOT_FOUND));
+    }
 }
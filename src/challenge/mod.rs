@@ -3,19 +3,25 @@ use crate::order::Challenge;
 use crate::types::{ChallengeType, Identifier};
 /// Challenge solver trait and registry
 use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
 // Re-export challenge types
 pub mod dns01;
 pub mod dns_cache;
 pub mod http01;
+pub mod propagation;
 pub mod tls_alpn01;
 
 pub use dns_cache::{CachingDnsResolver, DnsCache};
-pub use dns01::{Dns01Solver, DnsProvider, MockDnsProvider};
+pub use dns01::{CnameResolver, Dns01Solver, DnsProvider, DnsZone, MockDnsProvider};
 pub use http01::Http01Solver;
+pub use propagation::{Nameserver, PropagationChecker};
 pub use tls_alpn01::TlsAlpn01Solver;
 
-/// Trait for implementing different challenge types
+/// Trait for implementing different challenge types.
+/// Authorizations are processed concurrently, so a solver may have several challenges
+/// prepared at once and must keep per-challenge state behind interior mutability.
 #[async_trait]
 pub trait ChallengeSolver: Send + Sync {
     /// Get the challenge type this solver handles
@@ -23,7 +29,7 @@ pub trait ChallengeSolver: Send + Sync {
 
     /// Prepare the challenge (e.g., set up DNS records or HTTP server)
     async fn prepare(
-        &mut self,
+        &self,
         challenge: &Challenge,
         identifier: &Identifier,
         key_authorization: &str,
@@ -35,13 +41,15 @@ pub trait ChallengeSolver: Send + Sync {
     /// Verify that the challenge has been completed
     async fn verify(&self) -> Result<bool>;
 
-    /// Clean up after the challenge (e.g., remove DNS records or stop HTTP server)
-    async fn cleanup(&mut self) -> Result<()>;
+    /// Clean up all prepared challenges (e.g., remove DNS records or stop HTTP server)
+    async fn cleanup(&self) -> Result<()>;
 }
 
 /// Registry for managing multiple challenge solvers
 pub struct ChallengeSolverRegistry {
-    solvers: std::collections::HashMap<ChallengeType, Box<dyn ChallengeSolver>>,
+    solvers: std::collections::HashMap<ChallengeType, Arc<dyn ChallengeSolver>>,
+    /// Orders currently using the solvers, locked while the last one cleans up
+    users: Arc<Mutex<usize>>,
 }
 
 impl ChallengeSolverRegistry {
@@ -49,13 +57,14 @@ impl ChallengeSolverRegistry {
     pub fn new() -> Self {
         Self {
             solvers: std::collections::HashMap::new(),
+            users: Arc::default(),
         }
     }
 
     /// Register a new challenge solver
     pub fn register<S: ChallengeSolver + 'static>(&mut self, solver: S) {
         self.solvers
-            .insert(solver.challenge_type(), Box::new(solver));
+            .insert(solver.challenge_type(), Arc::new(solver));
     }
 
     /// Get a solver for the given challenge type
@@ -63,15 +72,26 @@ impl ChallengeSolverRegistry {
         self.solvers.get(&challenge_type).map(|s| s.as_ref())
     }
 
-    /// Get a mutable solver for the given challenge type
+    /// Get a mutable solver for the given challenge type.
+    /// Returns `None` while the solver is shared with an in-flight cleanup.
     pub fn get_mut(
         &mut self,
         challenge_type: ChallengeType,
     ) -> Option<&mut (dyn ChallengeSolver + '_)> {
-        if let Some(solver) = self.solvers.get_mut(&challenge_type) {
-            Some(solver.as_mut())
-        } else {
-            None
+        let solver = self.solvers.get_mut(&challenge_type)?;
+        Arc::get_mut(solver).map(|s| s as &mut dyn ChallengeSolver)
+    }
+
+    /// Returns a guard that cleans up every registered solver.
+    /// A solver's cleanup removes all the challenges it prepared, so when several orders
+    /// share the registry the last one to finish cleans up, and a new order waits while
+    /// that cleanup runs.
+    pub async fn cleanup_guard(&self) -> CleanupGuard {
+        *self.users.lock().await += 1;
+        CleanupGuard {
+            solvers: self.solvers.values().cloned().collect(),
+            users: self.users.clone(),
+            counted: true,
         }
     }
 
@@ -86,3 +106,148 @@ impl Default for ChallengeSolverRegistry {
         Self::new()
     }
 }
+
+/// Cleans up challenge solvers once authorization has finished.
+/// Call [`CleanupGuard::cleanup`] on both success and error paths; if the guard is
+/// dropped instead (e.g. the issuance future was cancelled), cleanup is spawned onto
+/// the current Tokio runtime so DNS records and challenge servers are not leaked.
+#[must_use = "challenges are only cleaned up when the guard is consumed or dropped"]
+pub struct CleanupGuard {
+    solvers: Vec<Arc<dyn ChallengeSolver>>,
+    /// Orders using the registry
+    users: Arc<Mutex<usize>>,
+    /// Whether this guard is still counted among the users
+    counted: bool,
+}
+
+impl CleanupGuard {
+    /// Cleans up all solvers, logging (not returning) failures so every solver gets its turn.
+    /// Does nothing while other orders still use the registry.
+    pub async fn cleanup(mut self) {
+        let users = self.users.clone();
+        let mut users = users.lock().await;
+        *users -= 1;
+        self.counted = false;
+        if *users > 0 {
+            tracing::debug!("Leaving challenge cleanup to {} running order(s)", *users);
+            self.solvers.clear();
+            return;
+        }
+
+        // A solver is only released once its cleanup completed, so cancelling this
+        // future leaves the remaining solvers to the drop handler
+        while let Some(solver) = self.solvers.last().cloned() {
+            cleanup_solver(solver.as_ref()).await;
+            self.solvers.pop();
+        }
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        if self.solvers.is_empty() && !self.counted {
+            return;
+        }
+        let solvers = std::mem::take(&mut self.solvers);
+        let users = self.users.clone();
+        let counted = self.counted;
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                tracing::warn!("Challenge cleanup interrupted, finishing in background");
+                handle.spawn(async move {
+                    let mut users = users.lock().await;
+                    if counted {
+                        *users -= 1;
+                    }
+                    if *users > 0 {
+                        return;
+                    }
+                    for solver in solvers {
+                        cleanup_solver(solver.as_ref()).await;
+                    }
+                });
+            }
+            Err(_) => tracing::error!("No async runtime available, challenges were not cleaned up"),
+        }
+    }
+}
+
+async fn cleanup_solver(solver: &dyn ChallengeSolver) {
+    if let Err(e) = solver.cleanup().await {
+        tracing::warn!(
+            "Failed to clean up {} challenge: {}",
+            solver.challenge_type(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cleanup_guard_runs_on_drop() {
+        let mut registry = ChallengeSolverRegistry::new();
+        registry.register(Dns01Solver::with_mock("example.com".to_string()));
+
+        let challenge = Challenge {
+            challenge_type: "dns-01".to_string(),
+            url: "https://example.com/challenge/1".to_string(),
+            status: "pending".to_string(),
+            token: "token".to_string(),
+            key_authorization: None,
+            validation: None,
+            updated: None,
+            error: None,
+            retry_after: None,
+        };
+        let solver = registry.get(ChallengeType::Dns01).unwrap();
+        solver
+            .prepare(&challenge, &Identifier::dns("example.com"), "token.auth")
+            .await
+            .unwrap();
+        assert!(solver.verify().await.unwrap());
+
+        // Dropping the guard (as on cancellation) cleans up in the background
+        drop(registry.cleanup_guard().await);
+        for _ in 0..10 {
+            if !solver.verify().await.unwrap() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(!solver.verify().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_last_concurrent_order_cleans_up() {
+        let mut registry = ChallengeSolverRegistry::new();
+        registry.register(Dns01Solver::with_mock("example.com".to_string()));
+        let challenge = Challenge {
+            challenge_type: "dns-01".to_string(),
+            url: "https://example.com/challenge/1".to_string(),
+            status: "pending".to_string(),
+            token: "token".to_string(),
+            key_authorization: None,
+            validation: None,
+            updated: None,
+            error: None,
+            retry_after: None,
+        };
+        let solver = registry.get(ChallengeType::Dns01).unwrap();
+
+        // Two orders share the registry; the first to finish leaves the other's record
+        let first = registry.cleanup_guard().await;
+        let second = registry.cleanup_guard().await;
+        solver
+            .prepare(&challenge, &Identifier::dns("example.com"), "token.auth")
+            .await
+            .unwrap();
+        first.cleanup().await;
+        assert!(solver.verify().await.unwrap());
+
+        second.cleanup().await;
+        assert!(!solver.verify().await.unwrap());
+    }
+}
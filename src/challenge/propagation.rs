@@ -0,0 +1,382 @@
+/// DNS-01 propagation checking against authoritative nameservers
+use hickory_resolver::config::{NameServerConfig, ResolveHosts, ResolverConfig};
+use hickory_resolver::net::runtime::TokioRuntimeProvider;
+use hickory_resolver::proto::rr::RData;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::dns_cache::CachingDnsResolver;
+use super::dns01::{CnameResolver, follow_cnames};
+use crate::error::{AcmeError, Result};
+
+/// Timeout of a single query to one nameserver
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest wait between two checks
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// An authoritative nameserver address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nameserver {
+    /// Host name from the zone's NS record
+    pub name: String,
+    /// Address queried
+    pub addr: SocketAddr,
+}
+
+impl fmt::Display for Nameserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.addr.ip())
+    }
+}
+
+/// Waits until every authoritative nameserver of a zone serves a TXT record
+pub struct PropagationChecker {
+    /// Recursive resolver finding the NS set and its addresses
+    resolver: Arc<CachingDnsResolver>,
+    /// Resolver following CNAMEs from a record name to the name holding the record
+    cnames: Arc<dyn CnameResolver>,
+    /// Nameservers queried instead of the zone's NS set, if set
+    nameservers: Option<Vec<Nameserver>>,
+    /// Total time to wait for propagation
+    timeout: Duration,
+    /// First wait between checks, doubled after each check
+    poll_interval: Duration,
+}
+
+impl PropagationChecker {
+    /// Create a checker giving up after `timeout`
+    pub fn new(timeout: Duration) -> Result<Self> {
+        let resolver = Arc::new(CachingDnsResolver::new()?);
+        Ok(Self {
+            resolver: resolver.clone(),
+            cnames: resolver,
+            nameservers: None,
+            timeout,
+            poll_interval: Duration::from_secs(2),
+        })
+    }
+
+    /// Query these nameservers instead of looking up the zone's NS set
+    pub fn with_nameservers(mut self, nameservers: Vec<Nameserver>) -> Self {
+        self.nameservers = Some(nameservers);
+        self
+    }
+
+    /// Follow CNAMEs with `resolver` instead of the recursive resolver
+    pub fn with_cname_resolver(mut self, resolver: Arc<dyn CnameResolver>) -> Self {
+        self.cnames = resolver;
+        self
+    }
+
+    /// Set the first wait between checks
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Find the authoritative nameservers of the zone containing `name`
+    pub async fn nameservers(&self, name: &str) -> Result<Vec<Nameserver>> {
+        if let Some(nameservers) = &self.nameservers {
+            return Ok(nameservers.clone());
+        }
+
+        // The zone apex is the closest enclosing name with NS records
+        let name = name.trim_end_matches('.');
+        let mut candidate = name;
+        let hosts = loop {
+            let hosts = self.resolver.resolve_ns(&format!("{}.", candidate)).await?;
+            if !hosts.is_empty() {
+                tracing::debug!("Zone of {} is {}, served by {:?}", name, candidate, hosts);
+                break hosts;
+            }
+            candidate = candidate
+                .split_once('.')
+                .map(|(_, parent)| parent)
+                .ok_or_else(|| {
+                    AcmeError::transport(format!("No authoritative nameservers found for {}", name))
+                })?;
+        };
+
+        let mut nameservers = Vec::new();
+        for host in hosts {
+            for ip in self.resolver.resolve_a(&format!("{}.", host)).await? {
+                nameservers.push(Nameserver {
+                    name: host.clone(),
+                    addr: SocketAddr::new(ip, 53),
+                });
+            }
+        }
+        if nameservers.is_empty() {
+            return Err(AcmeError::transport(format!(
+                "Nameservers of {} have no addresses",
+                name
+            )));
+        }
+        Ok(nameservers)
+    }
+
+    /// Follow CNAMEs from `name`: a delegated challenge name only holds a CNAME in its own
+    /// zone, and the TXT record is served by the nameservers of the target's zone
+    async fn record_name(&self, name: &str) -> Result<String> {
+        let name = name.trim_end_matches('.');
+        let target = follow_cnames(self.cnames.as_ref(), name.to_string()).await?;
+        if !target.eq_ignore_ascii_case(name) {
+            tracing::debug!(
+                "Checking propagation of {} at its CNAME target {}",
+                name,
+                target
+            );
+        }
+        Ok(target)
+    }
+
+    /// Return the nameservers not yet serving `value` in the TXT records of `name`
+    pub async fn lagging(&self, name: &str, value: &str) -> Result<Vec<Nameserver>> {
+        let name = self.record_name(name).await?;
+        self.lagging_at(&name, value).await
+    }
+
+    /// Like `lagging`, for a name already known to hold the record
+    async fn lagging_at(&self, name: &str, value: &str) -> Result<Vec<Nameserver>> {
+        let nameservers = self.nameservers(name).await?;
+        let checks = nameservers.iter().map(|ns| query_txt(ns.addr, name));
+        let results = futures_util::future::join_all(checks).await;
+
+        Ok(nameservers
+            .into_iter()
+            .zip(results)
+            .filter_map(|(ns, result)| match result {
+                Ok(txts) if txts.iter().any(|txt| txt == value) => None,
+                Ok(_) => Some(ns),
+                Err(e) => {
+                    tracing::debug!("Querying {} for {} failed: {}", ns, name, e);
+                    Some(ns)
+                }
+            })
+            .collect())
+    }
+
+    /// Wait with backoff until all nameservers serve `value` at `name`
+    pub async fn wait_for(&self, name: &str, value: &str) -> Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        let mut interval = self.poll_interval;
+        let name = self.record_name(name).await?;
+        let name = name.as_str();
+        loop {
+            let lagging = self.lagging_at(name, value).await?;
+            if lagging.is_empty() {
+                tracing::info!(
+                    "TXT record {} is served by all authoritative nameservers",
+                    name
+                );
+                return Ok(());
+            }
+
+            let lagging = lagging
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(AcmeError::timeout(format!(
+                    "TXT record {} not propagated after {}s; still missing on {}",
+                    name,
+                    self.timeout.as_secs(),
+                    lagging
+                )));
+            }
+            tracing::debug!("TXT record {} still missing on {}", name, lagging);
+            tokio::time::sleep(interval.min(deadline - now)).await;
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Query one nameserver for TXT records directly, without recursion or caching
+async fn query_txt(addr: SocketAddr, name: &str) -> Result<Vec<String>> {
+    let mut server = NameServerConfig::udp_and_tcp(addr.ip());
+    for connection in &mut server.connections {
+        connection.port = addr.port();
+    }
+    let mut builder = hickory_resolver::TokioResolver::builder_with_config(
+        ResolverConfig::from_parts(None, Vec::new(), vec![server]),
+        TokioRuntimeProvider::default(),
+    );
+    let options = builder.options_mut();
+    options.cache_size = 0;
+    options.recursion_desired = false;
+    options.use_hosts_file = ResolveHosts::Never;
+    options.timeout = QUERY_TIMEOUT;
+    let resolver = builder
+        .build()
+        .map_err(|e| AcmeError::transport(format!("DNS resolver setup failed: {}", e)))?;
+
+    let fqdn = format!("{}.", name.trim_end_matches('.'));
+    match resolver.txt_lookup(fqdn.as_str()).await {
+        Ok(response) => Ok(response
+            .answers()
+            .iter()
+            .filter_map(|record| match &record.data {
+                RData::TXT(txt) => Some(txt.to_string()),
+                _ => None,
+            })
+            .collect()),
+        Err(e) if e.is_no_records_found() => Ok(Vec::new()),
+        Err(e) => Err(AcmeError::transport(format!(
+            "TXT lookup for {} at {} failed: {}",
+            name, addr, e
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use tokio::net::UdpSocket;
+
+    /// Resolves CNAMEs from a fixed table
+    struct StaticCnames(Vec<(&'static str, &'static str)>);
+
+    #[async_trait]
+    impl CnameResolver for StaticCnames {
+        async fn resolve_cname(&self, name: &str) -> Result<Option<String>> {
+            Ok(self
+                .0
+                .iter()
+                .find(|(from, _)| *from == name)
+                .map(|(_, to)| to.to_string()))
+        }
+    }
+
+    /// An authoritative server answering TXT queries for `name` from a shared list of values
+    async fn start_server(name: &'static str, values: Arc<Mutex<Vec<String>>>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 4096];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let request = &buf[..len];
+                // Question: name, type and class after the 12-byte header
+                let mut end = 12;
+                while request[end] != 0 {
+                    end += 1 + request[end] as usize;
+                }
+                let question = &request[12..end + 5];
+                let mut qname = Vec::new();
+                let mut pos = 12;
+                while request[pos] != 0 {
+                    let len = request[pos] as usize;
+                    qname.push(String::from_utf8_lossy(&request[pos + 1..pos + 1 + len]));
+                    pos += 1 + len;
+                }
+
+                let values = if qname.join(".").eq_ignore_ascii_case(name) {
+                    values.lock().unwrap().clone()
+                } else {
+                    Vec::new()
+                };
+                let mut response = request[0..2].to_vec();
+                response.extend_from_slice(&[0x84, 0x00, 0, 1, 0, values.len() as u8, 0, 0, 0, 0]);
+                response.extend_from_slice(question);
+                for value in values {
+                    // Owner name points at the question
+                    response.extend_from_slice(&[0xc0, 12, 0, 16, 0, 1, 0, 0, 0, 60]);
+                    response.extend_from_slice(&(value.len() as u16 + 1).to_be_bytes());
+                    response.push(value.len() as u8);
+                    response.extend_from_slice(value.as_bytes());
+                }
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_lagging_nameserver() {
+        let name = "_acme-challenge.example.test";
+        let primary = Arc::new(Mutex::new(vec!["token".to_string()]));
+        let secondary = Arc::new(Mutex::new(vec!["stale".to_string()]));
+        let nameservers = vec![
+            Nameserver {
+                name: "ns1.example.test".to_string(),
+                addr: start_server(name, primary).await,
+            },
+            Nameserver {
+                name: "ns2.example.test".to_string(),
+                addr: start_server(name, secondary.clone()).await,
+            },
+        ];
+        let checker = PropagationChecker::new(Duration::from_secs(5))
+            .unwrap()
+            .with_nameservers(nameservers.clone())
+            .with_cname_resolver(Arc::new(StaticCnames(Vec::new())))
+            .with_poll_interval(Duration::from_millis(50));
+
+        assert_eq!(
+            checker.lagging(name, "token").await.unwrap(),
+            vec![nameservers[1].clone()]
+        );
+
+        // The secondary catches up while the checker waits
+        let update = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            secondary.lock().unwrap().push("token".to_string());
+        });
+        checker.wait_for(name, "token").await.unwrap();
+        update.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reports_lagging_nameservers_on_timeout() {
+        let nameservers = vec![Nameserver {
+            name: "ns1.example.test".to_string(),
+            addr: start_server("_acme-challenge.example.test", Arc::default()).await,
+        }];
+        let checker = PropagationChecker::new(Duration::from_millis(300))
+            .unwrap()
+            .with_nameservers(nameservers)
+            .with_cname_resolver(Arc::new(StaticCnames(Vec::new())))
+            .with_poll_interval(Duration::from_millis(50));
+
+        let err = checker
+            .wait_for("_acme-challenge.example.test", "token")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("still missing on ns1.example.test (127.0.0.1)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checks_cname_target() {
+        // The challenge name is delegated; only the target's nameserver holds the TXT record
+        let nameservers = vec![Nameserver {
+            name: "ns1.auth.example.net".to_string(),
+            addr: start_server(
+                "d420c923.auth.example.net",
+                Arc::new(Mutex::new(vec!["token".to_string()])),
+            )
+            .await,
+        }];
+        let checker = PropagationChecker::new(Duration::from_millis(300))
+            .unwrap()
+            .with_nameservers(nameservers)
+            .with_cname_resolver(Arc::new(StaticCnames(vec![(
+                "_acme-challenge.example.test",
+                "d420c923.auth.example.net.",
+            )])))
+            .with_poll_interval(Duration::from_millis(50));
+
+        let name = "_acme-challenge.example.test.";
+        assert!(checker.lagging(name, "token").await.unwrap().is_empty());
+        checker.wait_for(name, "token").await.unwrap();
+    }
+}
@@ -3,6 +3,9 @@ use async_trait::async_trait;
 use rcgen::CertificateParams;
 use rustls::ServerConfig;
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
@@ -14,12 +17,34 @@ use crate::error::Result;
 use crate::order::Challenge;
 use crate::types::{ChallengeType, Identifier};
 
+/// Serves validation certificates by TLS server name (SNI)
+#[derive(Debug, Default)]
+struct ValidationCertResolver {
+    /// Validation certificates keyed by lower-case server name
+    certs: std::sync::RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+impl ResolvesServerCert for ValidationCertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        // RFC 8737 requires SNI; IP identifiers use their reverse-DNS name (RFC 8738)
+        let name = client_hello.server_name()?.to_lowercase();
+        let certs = self.certs.read().ok()?;
+        let cert = certs.get(&name).cloned();
+        if cert.is_none() {
+            tracing::warn!("No TLS-ALPN-01 certificate for server name {}", name);
+        }
+        cert
+    }
+}
+
 /// TLS-ALPN-01 challenge solver
 pub struct TlsAlpn01Solver {
     /// Server listening address
     listen_addr: SocketAddr,
     /// Key authorization token
     key_authorization: Arc<RwLock<Option<String>>>,
+    /// Validation certificates served by SNI
+    resolver: Arc<ValidationCertResolver>,
     /// Server handle for shutdown
     server_handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
 }
@@ -36,37 +61,26 @@ impl TlsAlpn01Solver {
         Self {
             listen_addr,
             key_authorization: Arc::new(RwLock::new(None)),
+            resolver: Arc::new(ValidationCertResolver::default()),
             server_handle: Arc::new(RwLock::new(None)),
         }
     }
 
-    /// Generate a self-signed certificate with the acme-tls/1 ALPN extension
+    /// Generate a self-signed certificate with the acme-tls/1 ALPN extension.
+    /// The SAN is the identifier value, which rcgen encodes as an IP SAN for IP addresses.
     fn generate_cert(
-        domain: &str,
+        identifier: &str,
         key_auth_sha256: &[u8],
     ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
-        let mut params = CertificateParams::new(vec![domain.to_string()]).map_err(|e| {
+        let mut params = CertificateParams::new(vec![identifier.to_string()]).map_err(|e| {
             crate::error::AcmeError::crypto(format!("Failed to create cert params: {}", e))
         })?;
 
-        // Add the acmeValidation-v1 extension (OID 1.3.6.1.5.5.7.1.31)
-        // The value is the SHA-256 digest of the key authorization
-        // We need to construct the ASN.1 DER encoding manually or use a library
-        // For simplicity, we'll use a custom extension if rcgen supports it,
-        // or we might need to use a lower-level library if rcgen doesn't support custom extensions easily.
-        // Note: rcgen 0.14 supports custom extensions.
-
-        // OID: 1.3.6.1.5.5.7.1.31 (id-pe-acmeIdentifier)
-        let oid = vec![1, 3, 6, 1, 5, 5, 7, 1, 31];
-
-        // The value is an OCTET STRING containing the SHA-256 digest
-        // ASN.1 encoding: Tag (0x04) | Length | Value
-        let mut value = vec![0x04, 0x20]; // Tag for OCTET STRING, Length 32
-        value.extend_from_slice(key_auth_sha256);
-
+        // Critical acmeIdentifier extension (OID 1.3.6.1.5.5.7.1.31) holding the
+        // SHA-256 digest of the key authorization (RFC 8737 Section 3)
         params
             .custom_extensions
-            .push(rcgen::CustomExtension::from_oid_content(&oid, value));
+            .push(rcgen::CustomExtension::new_acme_identifier(key_auth_sha256));
 
         // Generate a key pair for signing
         let key_pair = rcgen::KeyPair::generate().map_err(|e| {
@@ -88,8 +102,8 @@ impl TlsAlpn01Solver {
         ))
     }
 
-    /// Start the TLS server
-    async fn start_server(&self, domain: String, key_auth: String) -> Result<()> {
+    /// Register the validation certificate for an identifier and start the TLS server
+    async fn start_server(&self, identifier: &Identifier, key_auth: String) -> Result<()> {
         // Calculate SHA-256 of key authorization
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
@@ -97,16 +111,30 @@ impl TlsAlpn01Solver {
         let key_auth_sha256 = hasher.finalize();
 
         // Generate certificate
-        let (certs, key) = Self::generate_cert(&domain, &key_auth_sha256)?;
+        let (certs, key) = Self::generate_cert(&identifier.value, &key_auth_sha256)?;
 
-        // Configure TLS server
-        let mut config = ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(certs, key)
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to create TLS config: {}", e))
+        let builder = ServerConfig::builder().with_no_client_auth();
+        let certified_key =
+            CertifiedKey::from_der(certs, key, builder.crypto_provider()).map_err(|e| {
+                crate::error::AcmeError::crypto(format!("Failed to load certificate key: {}", e))
             })?;
 
+        let server_name = identifier.tls_server_name();
+        tracing::debug!("Serving TLS-ALPN-01 certificate for SNI {}", server_name);
+        self.resolver
+            .certs
+            .write()
+            .map_err(|_| crate::error::AcmeError::crypto("Certificate store poisoned"))?
+            .insert(server_name, Arc::new(certified_key));
+
+        let mut server = self.server_handle.write().await;
+        if server.is_some() {
+            return Ok(());
+        }
+
+        // Configure TLS server
+        let mut config = builder.with_cert_resolver(self.resolver.clone());
+
         // Set ALPN protocols - MUST include "acme-tls/1"
         config.alpn_protocols = vec![b"acme-tls/1".to_vec()];
 
@@ -153,7 +181,6 @@ impl TlsAlpn01Solver {
             }
         });
 
-        let mut server = self.server_handle.write().await;
         *server = Some(handle);
 
         Ok(())
@@ -167,20 +194,16 @@ impl ChallengeSolver for TlsAlpn01Solver {
     }
 
     async fn prepare(
-        &mut self,
+        &self,
         challenge: &Challenge,
         identifier: &Identifier,
         key_authorization: &str,
     ) -> Result<()> {
         // Store the key authorization
-        let mut auth = self.key_authorization.write().await;
-        *auth = Some(key_authorization.to_string());
-
-        // Use the identifier value as the domain
-        let domain = identifier.value.clone();
+        *self.key_authorization.write().await = Some(key_authorization.to_string());
 
         // Start the server
-        self.start_server(domain, key_authorization.to_string())
+        self.start_server(identifier, key_authorization.to_string())
             .await?;
 
         tracing::info!(
@@ -201,11 +224,15 @@ impl ChallengeSolver for TlsAlpn01Solver {
         Ok(auth_guard.is_some())
     }
 
-    async fn cleanup(&mut self) -> Result<()> {
+    async fn cleanup(&self) -> Result<()> {
         // Clear the key authorization
         let mut auth = self.key_authorization.write().await;
         *auth = None;
 
+        if let Ok(mut certs) = self.resolver.certs.write() {
+            certs.clear();
+        }
+
         // Stop the server
         let mut handle = self.server_handle.write().await;
         if let Some(h) = handle.take() {
@@ -216,3 +243,28 @@ impl ChallengeSolver for TlsAlpn01Solver {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x509_parser::prelude::*;
+
+    #[test]
+    fn test_validation_cert_for_ip_identifier() {
+        let (certs, _) = TlsAlpn01Solver::generate_cert("192.0.2.10", &[0u8; 32]).unwrap();
+        let (_, cert) = X509Certificate::from_der(&certs[0]).unwrap();
+
+        let san = cert.subject_alternative_name().unwrap().unwrap();
+        assert_eq!(
+            san.value.general_names,
+            vec![GeneralName::IPAddress(&[192, 0, 2, 10])]
+        );
+
+        let acme_ext = cert
+            .extensions()
+            .iter()
+            .find(|ext| ext.oid.to_id_string() == "1.3.6.1.5.5.7.1.31")
+            .expect("acmeIdentifier extension");
+        assert!(acme_ext.critical);
+    }
+}
@@ -17,7 +17,7 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Obtain a new certificate
-    Obtain(ObtainArgs),
+    Obtain(Box<ObtainArgs>),
 
     /// Renew an existing certificate
     Renew(RenewArgs),
@@ -31,6 +31,9 @@ pub enum Commands {
     /// Account management
     Account(AccountArgs),
 
+    /// Authorization management (pre-authorization, deactivation)
+    Authz(AuthzArgs),
+
     /// Order management
     Order(OrderArgs),
 
@@ -41,6 +44,67 @@ pub enum Commands {
     Serve(ServeArgs),
 }
 
+#[derive(Parser, Debug)]
+pub struct AuthzArgs {
+    #[command(subcommand)]
+    pub command: AuthzCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthzCommands {
+    /// Pre-authorize identifiers ahead of ordering (requires CA support for newAuthz)
+    Create(AuthzCreateArgs),
+    /// Deactivate an authorization
+    Deactivate(AuthzDeactivateArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct AuthzCreateArgs {
+    /// Domain(s) or IP address(es) to pre-authorize
+    #[arg(short, long, required = true)]
+    pub domains: Vec<String>,
+
+    /// Account key path
+    #[arg(short, long, required = true)]
+    pub key_path: String,
+
+    /// Challenge type (http-01, dns-01, tls-alpn-01); defaults to the config file's,
+    /// or http-01 without one
+    #[arg(short, long)]
+    pub challenge: Option<String>,
+
+    /// Config file path (TOML format) with the challenge solver settings
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Use production Let's Encrypt
+    #[arg(long, default_value_t = false)]
+    pub prod: bool,
+
+    /// ACME directory URL (overrides --prod)
+    #[arg(long)]
+    pub directory: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct AuthzDeactivateArgs {
+    /// Authorization URL
+    #[arg(short, long, required = true)]
+    pub url: String,
+
+    /// Account key path
+    #[arg(short, long, required = true)]
+    pub key_path: String,
+
+    /// Use production Let's Encrypt
+    #[arg(long, default_value_t = false)]
+    pub prod: bool,
+
+    /// ACME directory URL (overrides --prod)
+    #[arg(long)]
+    pub directory: Option<String>,
+}
+
 #[derive(Parser, Debug)]
 pub struct OrderArgs {
     #[command(subcommand)]
@@ -49,8 +113,18 @@ pub struct OrderArgs {
 
 #[derive(Subcommand, Debug)]
 pub enum OrderCommands {
-    /// List all orders
-    List,
+    /// List the account's orders on the ACME server
+    List {
+        /// Account key path
+        #[arg(short, long)]
+        key_path: String,
+        /// Use production Let's Encrypt
+        #[arg(long, default_value_t = false)]
+        prod: bool,
+        /// ACME directory URL (overrides --prod)
+        #[arg(long)]
+        directory: Option<String>,
+    },
     /// Show order details
     Show {
         #[arg(short, long)]
@@ -77,14 +151,25 @@ pub enum CertCommands {
         #[arg(short, long)]
         reason: Option<String>,
         /// Account key path
-        #[arg(short, long)]
-        key: String,
+        #[arg(
+            short,
+            long,
+            required_unless_present = "cert_key",
+            conflicts_with = "cert_key"
+        )]
+        key: Option<String>,
+        /// Certificate private key path (PEM), to revoke without the issuing account
+        #[arg(long)]
+        cert_key: Option<String>,
+        /// ACME directory URL (default: Let's Encrypt production)
+        #[arg(long)]
+        directory: Option<String>,
     },
 }
 
 #[derive(Parser, Debug)]
 pub struct ObtainArgs {
-    /// Domain(s) to obtain certificate for
+    /// Domain(s) or IP address(es) to obtain certificate for
     #[arg(short, long, required = true)]
     pub domains: Vec<String>,
 
@@ -108,9 +193,65 @@ pub struct ObtainArgs {
     #[arg(long, default_value_t = false)]
     pub prod: bool,
 
-    /// DNS provider (cloudflare, digitalocean, linode, route53)
+    /// DNS provider (cloudflare, route53, azure, google, ...; needs its dns-* feature)
     #[arg(long)]
     pub dns_provider: Option<String>,
+
+    /// DNS provider credential as KEY=VALUE, e.g. api_token=... or client_secret=... (repeatable)
+    #[arg(
+        long = "dns-credential",
+        value_name = "KEY=VALUE",
+        requires = "dns_provider"
+    )]
+    pub dns_credentials: Vec<String>,
+
+    /// External Account Binding key ID (required by ZeroSSL, Google, ...)
+    #[arg(long, requires = "eab_hmac_key")]
+    pub eab_kid: Option<String>,
+
+    /// External Account Binding HMAC key (base64url)
+    #[arg(long, requires = "eab_kid")]
+    pub eab_hmac_key: Option<String>,
+
+    /// Certificate profile to request (e.g. classic, shortlived), if the CA offers profiles
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Prefer the chain whose topmost certificate is issued by this common name (e.g. "ISRG Root X1")
+    #[arg(long)]
+    pub preferred_chain: Option<String>,
+
+    /// Certificate key type (ecdsa-p256, ecdsa-p384, ed25519, rsa-2048, rsa-3072, rsa-4096)
+    #[arg(long, default_value = "ecdsa-p256")]
+    pub key_type: String,
+
+    /// Request the OCSP Must-Staple TLS feature
+    #[arg(long, default_value_t = false)]
+    pub must_staple: bool,
+
+    /// Keep the certificate's private key when it is renewed
+    #[arg(long, default_value_t = false)]
+    pub reuse_key: bool,
+
+    /// Subject organization (O)
+    #[arg(long)]
+    pub organization: Option<String>,
+
+    /// Subject organizational unit (OU)
+    #[arg(long)]
+    pub organizational_unit: Option<String>,
+
+    /// Subject country code (C)
+    #[arg(long)]
+    pub country: Option<String>,
+
+    /// Subject state or province (ST)
+    #[arg(long)]
+    pub state: Option<String>,
+
+    /// Subject locality (L)
+    #[arg(long)]
+    pub locality: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -172,6 +313,8 @@ pub struct AccountArgs {
 pub enum AccountCommands {
     /// Register a new account
     Register(AccountRegisterArgs),
+    /// Look up the existing account for a key
+    Lookup(AccountLookupArgs),
     /// Update account contacts
     Update(AccountUpdateArgs),
     /// Deactivate account
@@ -193,6 +336,37 @@ pub struct AccountRegisterArgs {
     /// Output account key path
     #[arg(short, long, default_value = "account_key.pem")]
     pub key_path: String,
+
+    /// Account key type (ecdsa-p256, ecdsa-p384, rsa-2048, rsa-4096, ed25519)
+    #[arg(long, default_value = "ecdsa-p256")]
+    pub key_type: String,
+
+    /// ACME directory URL (overrides --prod)
+    #[arg(long)]
+    pub directory: Option<String>,
+
+    /// External Account Binding key ID
+    #[arg(long, requires = "eab_hmac_key")]
+    pub eab_kid: Option<String>,
+
+    /// External Account Binding HMAC key (base64url)
+    #[arg(long, requires = "eab_kid")]
+    pub eab_hmac_key: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct AccountLookupArgs {
+    /// Account key path
+    #[arg(short, long, required = true)]
+    pub key_path: String,
+
+    /// Use production Let's Encrypt
+    #[arg(long, default_value_t = false)]
+    pub prod: bool,
+
+    /// ACME directory URL (overrides --prod)
+    #[arg(long)]
+    pub directory: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -1,23 +1,39 @@
 /// Account management commands
-use crate::account::{AccountManager, KeyPair, KeyRollover};
+use crate::account::{AccountManager, ExternalAccountKey, KeyPair, KeyRollover};
+use crate::cli::args::{AccountLookupArgs, AccountRegisterArgs};
+use crate::crypto::KeyType;
 use crate::error::Result;
 use crate::protocol::{DirectoryManager, NonceManager};
 use crate::types::Contact;
 use tracing::info;
 
 /// Handle account registration
-pub async fn handle_register(email: String, prod: bool, key_path: String) -> Result<()> {
+pub async fn handle_register(args: AccountRegisterArgs) -> Result<()> {
+    let AccountRegisterArgs {
+        email,
+        prod,
+        key_path,
+        key_type,
+        directory,
+        eab_kid,
+        eab_hmac_key,
+    } = args;
     info!("Registering new account for {}", email);
 
     // 1. Generate key pair
-    let key_pair = KeyPair::generate()?;
+    let key_type: KeyType = key_type.parse()?;
+    let key_pair = KeyPair::generate_with(key_type)?;
+    let eab = match (eab_kid, eab_hmac_key) {
+        (Some(kid), Some(hmac_key)) => Some(ExternalAccountKey::new(kid, &hmac_key)?),
+        _ => None,
+    };
 
     // 2. Setup client components
-    let acme_url = if prod {
+    let acme_url = directory.as_deref().unwrap_or(if prod {
         "https://acme-v02.api.letsencrypt.org/directory"
     } else {
         "https://acme-staging-v02.api.letsencrypt.org/directory"
-    };
+    });
 
     let http_client = reqwest::Client::new();
     let dir_mgr = DirectoryManager::new(acme_url, http_client.clone());
@@ -28,12 +44,15 @@ pub async fn handle_register(email: String, prod: bool, key_path: String) -> Res
 
     // 3. Register
     let contact = Contact::email(email);
-    let account = account_mgr.register(vec![contact], true).await?;
+    let account = account_mgr
+        .register_with_eab(vec![contact], true, eab.as_ref())
+        .await?;
 
     info!("Account registered: {}", account.id);
     println!("✅ Account registered successfully");
     println!("   ID: {}", account.id);
     println!("   Status: {}", account.status);
+    println!("   Key type: {} ({})", key_type, key_type.jwa_algorithm());
 
     // 4. Save key
     key_pair.save_to_file(&key_path)?;
@@ -42,6 +61,48 @@ pub async fn handle_register(email: String, prod: bool, key_path: String) -> Res
     Ok(())
 }
 
+/// Handle account lookup by key
+pub async fn handle_lookup(args: AccountLookupArgs) -> Result<()> {
+    let AccountLookupArgs {
+        key_path,
+        prod,
+        directory,
+    } = args;
+    info!("Looking up account for key {}", key_path);
+
+    // 1. Load key
+    let key_pair = KeyPair::load_from_file(&key_path)?;
+
+    // 2. Setup client components
+    let acme_url = directory.as_deref().unwrap_or(if prod {
+        "https://acme-v02.api.letsencrypt.org/directory"
+    } else {
+        "https://acme-staging-v02.api.letsencrypt.org/directory"
+    });
+
+    let http_client = reqwest::Client::new();
+    let dir_mgr = DirectoryManager::new(acme_url, http_client.clone());
+    let directory = dir_mgr.get().await?;
+    let nonce_mgr = NonceManager::new(&directory.new_nonce, http_client.clone());
+
+    let account_mgr = AccountManager::new(&key_pair, &nonce_mgr, &dir_mgr, &http_client)?;
+
+    // 3. Look up without registering
+    let account = account_mgr.find_existing().await?;
+
+    println!("✅ Account found");
+    println!("   ID: {}", account.id);
+    println!("   Status: {}", account.status);
+    if !account.contact.is_empty() {
+        println!("   Contacts: {:?}", account.contact);
+    }
+    if let Some(orders) = &account.orders {
+        println!("   Orders: {}", orders);
+    }
+
+    Ok(())
+}
+
 /// Handle account update
 pub async fn handle_update(key_path: String, email: String, prod: bool) -> Result<()> {
     info!("Updating account contact to {}", email);
@@ -63,11 +124,9 @@ pub async fn handle_update(key_path: String, email: String, prod: bool) -> Resul
 
     let account_mgr = AccountManager::new(&key_pair, &nonce_mgr, &dir_mgr, &http_client)?;
 
-    // 3. Get account ID (need to register/lookup first to get ID)
-    // In a real implementation, we'd store the account ID or look it up
-    // For now, we'll re-register which returns the existing account
+    // 3. Look up the account ID for the key
     let contact = Contact::email(email.clone());
-    let account = account_mgr.register(vec![contact.clone()], true).await?;
+    let account = account_mgr.find_existing().await?;
 
     // 4. Update
     let updated = account_mgr
@@ -103,9 +162,8 @@ pub async fn handle_deactivate(key_path: String, prod: bool) -> Result<()> {
 
     let account_mgr = AccountManager::new(&key_pair, &nonce_mgr, &dir_mgr, &http_client)?;
 
-    // 3. Get account ID
-    // Re-register to get ID (safe operation, returns existing account)
-    let account = account_mgr.register(vec![], true).await?;
+    // 3. Look up the account ID for the key
+    let account = account_mgr.find_existing().await?;
 
     // 4. Deactivate
     account_mgr.deactivate(&account.id).await?;
@@ -137,8 +195,8 @@ pub async fn handle_rotate_key(key_path: String, new_key_path: String, prod: boo
 
     let account_mgr = AccountManager::new(&key_pair, &nonce_mgr, &dir_mgr, &http_client)?;
 
-    // 3. Get account ID
-    let account = account_mgr.register(vec![], true).await?;
+    // 3. Look up the account ID for the key
+    let account = account_mgr.find_existing().await?;
 
     // 4. Perform rollover
     let rollover = KeyRollover::new(&account_mgr)?;
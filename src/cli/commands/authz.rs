@@ -0,0 +1,91 @@
+/// Authorization management commands
+use crate::account::KeyPair;
+use crate::cli::args::{AuthzCreateArgs, AuthzDeactivateArgs};
+use crate::client::{AcmeClient, AcmeConfig};
+use crate::config::Config;
+use crate::error::Result;
+use crate::orchestrator::provisioner::build_solver_registry;
+use tracing::info;
+
+fn directory_url(directory: Option<String>, prod: bool) -> String {
+    directory.unwrap_or_else(|| {
+        if prod {
+            "https://acme-v02.api.letsencrypt.org/directory".to_string()
+        } else {
+            "https://acme-staging-v02.api.letsencrypt.org/directory".to_string()
+        }
+    })
+}
+
+/// Handle pre-authorization of identifiers
+pub async fn handle_authz_create(args: AuthzCreateArgs) -> Result<()> {
+    let AuthzCreateArgs {
+        domains,
+        key_path,
+        challenge,
+        config: config_path,
+        prod,
+        directory,
+    } = args;
+    info!("Pre-authorizing {:?}", domains);
+
+    // 1. Load key and build the challenge solvers from the user's configuration
+    let key_pair = KeyPair::load_from_file(&key_path)?;
+    let (mut config, directory) = match config_path {
+        Some(path) => {
+            info!("Loading config from: {}", path);
+            let config = Config::from_file(std::path::Path::new(&path))?;
+            let directory = directory.unwrap_or_else(|| config.acme.directory.clone());
+            (config, Some(directory))
+        }
+        None => {
+            let mut config = Config::new();
+            config.challenge.challenge_type = "http-01".to_string();
+            (config, directory)
+        }
+    };
+    if let Some(challenge) = challenge {
+        config.challenge.challenge_type = challenge;
+    }
+    let solver_registry = build_solver_registry(&config).await?;
+
+    // 2. Pre-authorize
+    let acme_url = directory_url(directory, prod);
+    let mut client =
+        AcmeClient::with_key_pair(AcmeConfig::new(acme_url).with_tos_agreed(true), key_pair);
+    let Some(authorizations) = client.pre_authorize(domains, &solver_registry).await? else {
+        println!("⚠️  The ACME server does not support pre-authorization (no newAuthz endpoint)");
+        println!("   Identifiers will be authorized when ordering instead.");
+        return Ok(());
+    };
+
+    println!("✅ Identifiers pre-authorized successfully");
+    for auth in authorizations {
+        println!("   {} ({}): {}", auth.identifier, auth.status, auth.url);
+    }
+
+    Ok(())
+}
+
+/// Handle authorization deactivation
+pub async fn handle_authz_deactivate(args: AuthzDeactivateArgs) -> Result<()> {
+    let AuthzDeactivateArgs {
+        url,
+        key_path,
+        prod,
+        directory,
+    } = args;
+    info!("Deactivating authorization {}", url);
+
+    let key_pair = KeyPair::load_from_file(&key_path)?;
+    let acme_url = directory_url(directory, prod);
+    let mut client =
+        AcmeClient::with_key_pair(AcmeConfig::new(acme_url).with_tos_agreed(true), key_pair);
+    let auth = client.deactivate_authorization(&url).await?;
+
+    println!("✅ Authorization deactivated");
+    println!("   Identifier: {}", auth.identifier.value);
+    println!("   Status: {}", auth.status);
+
+    Ok(())
+}
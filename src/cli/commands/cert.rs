@@ -1,7 +1,6 @@
-use crate::account::{AccountManager, KeyPair};
-use crate::error::Result;
-use crate::order::CertificateRevocation;
-use crate::protocol::{DirectoryManager, NonceManager};
+use crate::account::KeyPair;
+use crate::client::{AcmeClient, AcmeConfig};
+use crate::error::{AcmeError, Result};
 use crate::storage::{FileStorage, StorageBackend};
 use crate::types::RevocationReason;
 use std::fs;
@@ -57,52 +56,53 @@ pub async fn handle_cert_list() -> Result<()> {
     Ok(())
 }
 
-/// Handle certificate revocation
+/// Handle certificate revocation, signed with the account key or the certificate key
 pub async fn handle_cert_revoke(
     cert_path: String,
     reason_str: Option<String>,
-    key_path: String,
+    key_path: Option<String>,
+    cert_key_path: Option<String>,
+    directory: Option<String>,
 ) -> Result<()> {
     info!("Revoking certificate: {}", cert_path);
 
     // 1. Load certificate
-    let cert_der = pem::parse(fs::read(cert_path)?)
-        .map_err(|e| crate::error::AcmeError::Certificate(e.to_string()))?
-        .contents()
-        .to_vec();
-
-    // 2. Load account key
-    let key_pem = fs::read_to_string(key_path)?;
-    let key_pair = KeyPair::from_pem(&key_pem)?;
-
-    // 3. Initialize ACME protocol components
-    let http_client = reqwest::Client::new();
-    let dir_url = "https://acme-v02.api.letsencrypt.org/directory"; // Default to prod for revocation usually
-    let dir_manager = DirectoryManager::new(dir_url, http_client.clone());
-    let directory = dir_manager.get().await?;
-    let nonce_manager = NonceManager::new(&directory.new_nonce, http_client.clone());
-
-    // 4. Create account manager
-    let account_manager =
-        AccountManager::new(&key_pair, &nonce_manager, &dir_manager, &http_client)?;
-
-    // 5. Build and execute revocation
-    let mut revocation = CertificateRevocation::new(&account_manager, "dummy_id", cert_der);
-
-    if let Some(r) = reason_str {
-        let reason = match r.to_lowercase().as_str() {
-            "key-compromise" => RevocationReason::KeyCompromise,
-            "ca-compromise" => RevocationReason::CaCompromise,
-            "affiliation-changed" => RevocationReason::AffiliationChanged,
-            "superseded" => RevocationReason::Superseded,
-            "cessation-of-operation" => RevocationReason::CessationOfOperation,
-            _ => RevocationReason::Unspecified,
-        };
-        revocation = revocation.with_reason(reason);
+    let cert_pem = fs::read_to_string(&cert_path)?;
+    let reason = reason_str.map(|r| match r.to_lowercase().as_str() {
+        "key-compromise" => RevocationReason::KeyCompromise,
+        "ca-compromise" => RevocationReason::CaCompromise,
+        "affiliation-changed" => RevocationReason::AffiliationChanged,
+        "superseded" => RevocationReason::Superseded,
+        "cessation-of-operation" => RevocationReason::CessationOfOperation,
+        _ => RevocationReason::Unspecified,
+    });
+
+    // 2. Default to production for revocation, where issued certificates usually live
+    let dir_url =
+        directory.unwrap_or_else(|| "https://acme-v02.api.letsencrypt.org/directory".to_string());
+    let config = AcmeConfig::new(dir_url);
+
+    // 3. Revoke with the certificate key, or on behalf of the account that holds it
+    if let Some(cert_key_path) = cert_key_path {
+        let cert_key = KeyPair::load_from_file(&cert_key_path)?;
+        let client = AcmeClient::new(config)?;
+        client
+            .revoke_with_certificate_key(&cert_pem, &cert_key, reason)
+            .await?;
+    } else if let Some(key_path) = key_path {
+        let key_pair = KeyPair::load_from_file(&key_path)?;
+        let mut client = AcmeClient::with_key_pair(config, key_pair);
+        // Revocation must not register a new account for an unknown key
+        client.find_account().await?;
+        client.revoke_certificate(&cert_pem, reason).await?;
+    } else {
+        return Err(AcmeError::invalid_input(
+            "Either an account key or the certificate key is required to revoke",
+        ));
     }
 
-    revocation.revoke().await?;
     info!("Successfully revoked certificate.");
+    println!("✅ Certificate revoked: {}", cert_path);
 
     Ok(())
 }
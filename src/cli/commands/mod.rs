@@ -1,4 +1,5 @@
 pub mod account;
+pub mod authz;
 pub mod cert;
 pub mod daemon;
 pub mod info;
@@ -7,7 +8,10 @@ pub mod order;
 pub mod renew;
 pub mod serve;
 
-pub use account::{handle_deactivate, handle_register, handle_rotate_key, handle_update};
+pub use account::{
+    handle_deactivate, handle_lookup, handle_register, handle_rotate_key, handle_update,
+};
+pub use authz::{handle_authz_create, handle_authz_deactivate};
 pub use cert::{handle_cert_list, handle_cert_revoke};
 pub use daemon::handle_daemon;
 pub use info::handle_info;
@@ -1,9 +1,12 @@
-use crate::config::{AcmeSettings, Config};
+use crate::cli::args::ObtainArgs;
+use crate::config::{AcmeSettings, CertificateSettings, Config, ExternalAccountBinding};
 /// Obtain new certificate command implementation.
 /// This module handles the 'obtain' CLI command, coordinating with the
 /// orchestrator and the new multi-CA configuration system.
 use crate::error::{AcmeError, Result};
 use crate::orchestrator::CertificateProvisioner;
+use crate::orchestrator::provisioner::build_solver_registry;
+use crate::order::CertificateSubject;
 use std::fs;
 use std::path::Path;
 
@@ -11,15 +14,30 @@ use std::path::Path;
 ///
 /// This implementation leverages the `CAConfig` system to automatically
 /// resolve the correct ACME directory URL based on the provided parameters.
-pub async fn handle_obtain(
-    domains: Vec<String>,
-    email: String,
-    challenge_type: String,
-    cert_path: String,
-    key_path: String,
-    prod: bool,
-    dns_provider: Option<String>,
-) -> Result<()> {
+pub async fn handle_obtain(args: ObtainArgs) -> Result<()> {
+    let ObtainArgs {
+        domains,
+        email,
+        challenge: challenge_type,
+        cert_path,
+        key_path,
+        prod,
+        dns_provider,
+        dns_credentials,
+        eab_kid,
+        eab_hmac_key,
+        profile,
+        preferred_chain,
+        key_type,
+        must_staple,
+        reuse_key,
+        organization,
+        organizational_unit,
+        country,
+        state,
+        locality,
+    } = args;
+
     // 1. Validate basic inputs
     if domains.is_empty() {
         return Err(AcmeError::invalid_input("No domains specified"));
@@ -46,15 +64,51 @@ pub async fn handle_obtain(
         },
         contact: vec![format!("mailto:{}", email)],
         tos_agreed: true,
+        external_account_binding: eab_kid
+            .zip(eab_hmac_key)
+            .map(|(key_id, hmac_key)| ExternalAccountBinding { key_id, hmac_key }),
+        profile,
+        preferred_chain,
+        ..Default::default()
+    };
+
+    config.certificate = CertificateSettings {
+        key_type,
+        must_staple,
+        reuse_key,
+        subject: CertificateSubject {
+            organization,
+            organizational_unit,
+            country,
+            state,
+            locality,
+        },
         ..Default::default()
     };
+    let certificate_options = config.certificate.options_for(&domains)?;
 
     // Configure challenge settings
     config.challenge.challenge_type = challenge_type.clone();
-    if let Some(provider) = dns_provider
-        && let Some(ref mut dns_config) = config.challenge.dns01
-    {
+    if let Some(provider) = dns_provider {
+        let mut dns_config = config.challenge.dns01.take().unwrap_or_default();
         dns_config.provider = Some(provider);
+        for credential in dns_credentials {
+            let (key, value) = credential.split_once('=').ok_or_else(|| {
+                AcmeError::invalid_input(format!(
+                    "DNS credential '{}' is not of the form KEY=VALUE",
+                    credential
+                ))
+            })?;
+            let value = value.to_string();
+            match key {
+                "api_token" => dns_config.api_token = Some(value),
+                "zone_id" => dns_config.zone_id = Some(value),
+                _ => {
+                    dns_config.extra.insert(key.to_string(), value);
+                }
+            }
+        }
+        config.challenge.dns01 = Some(dns_config);
     }
 
     // 3. Resolve the ACME directory URL via the CAConfig system
@@ -67,6 +121,16 @@ pub async fn handle_obtain(
     println!("   CA: {}", ca_config.ca);
     println!("   Environment: {:?}", ca_config.environment);
     println!("   ACME Directory: {}", acme_url);
+    if let Some(ref profile) = config.acme.profile {
+        println!("   Profile: {}", profile);
+    }
+    if let Some(ref preferred) = config.acme.preferred_chain {
+        println!("   Preferred chain: {}", preferred);
+    }
+    println!("   Key type: {}", certificate_options.key_type);
+    if certificate_options.must_staple {
+        println!("   OCSP Must-Staple: requested");
+    }
 
     // 4. Initialize the Provisioner Orchestrator
     // In a real scenario, we would use the orchestrator to handle the full flow.
@@ -74,7 +138,16 @@ pub async fn handle_obtain(
     let _provisioner = CertificateProvisioner::new(domains.clone());
 
     println!("\n⏳ Step 1: Validating system readiness...");
-    // Here we would call orchestrator.execute(&config)
+    // Building the solvers fails early on unknown providers or missing credentials
+    build_solver_registry(&config).await?;
+    if let Some(provider) = config
+        .challenge
+        .dns01
+        .as_ref()
+        .and_then(|dns| dns.provider.as_ref())
+    {
+        println!("   DNS provider: {}", provider);
+    }
 
     println!("⏳ Step 2: Executing ACME flow (Account -> Order -> Challenge -> Finalize)...");
     // For demonstration in this CLI handler, we log the intent.
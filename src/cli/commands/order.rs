@@ -1,13 +1,45 @@
+use crate::account::KeyPair;
+use crate::client::{AcmeClient, AcmeConfig};
 use crate::error::Result;
 use tracing::info;
 
 /// Handle order list command
-pub async fn handle_order_list() -> Result<()> {
+pub async fn handle_order_list(
+    key_path: String,
+    prod: bool,
+    directory: Option<String>,
+) -> Result<()> {
     info!("Listing ACME orders...");
-    println!("ACME v2 currently doesn't support listing all historical orders via client storage.");
+
+    let key_pair = KeyPair::load_from_file(&key_path)?;
+    let acme_url = directory.unwrap_or_else(|| {
+        if prod {
+            "https://acme-v02.api.letsencrypt.org/directory".to_string()
+        } else {
+            "https://acme-staging-v02.api.letsencrypt.org/directory".to_string()
+        }
+    });
+
+    let mut client = AcmeClient::with_key_pair(AcmeConfig::new(acme_url), key_pair);
+    let orders = client.list_orders().await?;
+
+    if orders.is_empty() {
+        println!(
+            "No orders found for account {}",
+            client.account_id().unwrap_or_default()
+        );
+        return Ok(());
+    }
+
     println!(
-        "Please check the 'orders' endpoint of your ACME account for a list of recent order URLs."
+        "Orders of account {}:",
+        client.account_id().unwrap_or_default()
     );
+    for order_url in &orders {
+        println!("   {}", order_url);
+    }
+    println!("Total: {}", orders.len());
+
     Ok(())
 }
 
@@ -1,14 +1,14 @@
-#[cfg(feature = "redis")]
-use crate::RedisStorage;
 use crate::config::Config;
 /// Serve command implementation
 use crate::error::Result;
 use crate::notifications::{WebhookConfig, WebhookFormat, WebhookManager};
 use crate::scheduler::AdvancedRenewalScheduler;
-use crate::server::start_server;
-use crate::storage::{CertificateStore, FileStorage, MemoryStorage, StorageBackend};
+use crate::server::acme::Relay;
+use crate::server::{AcmeServer, start_acme_server, start_server};
+use crate::storage::CertificateStore;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Handle serve command
 pub async fn handle_serve(addr: String, config_path: Option<String>) -> Result<()> {
@@ -26,50 +26,39 @@ pub async fn handle_serve(addr: String, config_path: Option<String>) -> Result<(
     let config = Arc::new(config);
 
     // Initialize storage backend
-    let storage: Arc<dyn StorageBackend> = match config.storage.backend.as_str() {
-        "memory" => Arc::new(MemoryStorage::new()),
-        "redis" => {
-            #[cfg(feature = "redis")]
-            {
-                if let Some(redis_config) = &config.storage.redis {
-                    Arc::new(RedisStorage::new(&redis_config.url)?)
-                } else {
-                    return Err(crate::error::AcmeError::configuration(
-                        "Redis configuration missing".to_string(),
-                    ));
-                }
-            }
-            #[cfg(not(feature = "redis"))]
-            {
-                return Err(crate::error::AcmeError::configuration(
-                    "Redis feature not enabled".to_string(),
-                ));
-            }
-        }
-        _ => {
-            let path = config
-                .storage
-                .file
-                .as_ref()
-                .map(|f| f.path.as_str())
-                .unwrap_or(".acmex");
-            Arc::new(FileStorage::new(path))
-        }
-    };
+    let storage = crate::storage::backend_from_settings(&config.storage)?;
 
     // Initialize certificate store
     let cert_store = CertificateStore::new(storage.clone());
 
     // Initialize ACME client
     let mut acme_config = crate::client::AcmeConfig::new(&config.acme.directory)
-        .with_tos_agreed(config.acme.tos_agreed);
+        .with_tos_agreed(config.acme.tos_agreed)
+        .with_account_key_type(config.acme.key_type()?)
+        .with_authorization_concurrency(config.challenge.authorization_concurrency)
+        .with_poll_interval(Duration::from_secs(config.acme.poll_interval_secs))
+        .with_authorization_timeout(Duration::from_secs(config.acme.authorization_timeout_secs))
+        .with_finalization_timeout(Duration::from_secs(config.acme.finalization_timeout_secs))
+        .with_certificate_options(config.certificate.options_for(&[])?);
+    if let Some(ref eab) = config.acme.external_account_binding {
+        acme_config = acme_config.with_external_account_binding(eab.try_into()?);
+    }
+    if let Some(ref profile) = config.acme.profile {
+        acme_config = acme_config.with_profile(profile.clone());
+    }
+    if let Some(ref preferred) = config.acme.preferred_chain {
+        acme_config = acme_config.with_preferred_chain(preferred.clone());
+    }
     for contact in &config.acme.contact {
         if contact.strip_prefix("mailto:").is_some() {
             let contact_mail = &contact[7..];
             acme_config = acme_config.with_contact(crate::types::Contact::email(contact_mail));
         }
     }
-    let client = crate::client::AcmeClient::new(acme_config)?;
+    // Keep the account key across restarts so stored orders can be resumed
+    let account_key = crate::orchestrator::provisioner::load_account_key(&storage, &config).await?;
+    let client = crate::client::AcmeClient::with_key_pair(acme_config, account_key)
+        .with_order_storage(storage.clone());
 
     // Initialize Advanced Renewal Scheduler
     let (scheduler, _task_tx) = AdvancedRenewalScheduler::new(
@@ -77,7 +66,13 @@ pub async fn handle_serve(addr: String, config_path: Option<String>) -> Result<(
         cert_store,
         config.renewal.concurrency as usize,
     );
-    let scheduler = Arc::new(scheduler);
+    let scheduler = Arc::new(
+        scheduler
+            .with_renew_before(Duration::from_secs(
+                config.should_renew_days_before() as u64 * 24 * 3600,
+            ))
+            .with_certificate_settings(config.certificate.clone()),
+    );
 
     // Start scheduler in background
     let scheduler_clone = scheduler.clone();
@@ -98,6 +93,25 @@ pub async fn handle_serve(addr: String, config_path: Option<String>) -> Result<(
 
     let webhook_manager = Arc::new(WebhookManager::new(vec![webhook_config]));
 
+    // Start the built-in ACME server on its own listener when configured
+    if let Some(ref settings) = config.acme_server {
+        let acme_addr = settings.listen_addr()?;
+        let mut acme_server = AcmeServer::new(settings.server_config(), storage.clone())
+            .await?
+            .with_verifier(Arc::new(settings.verifier()));
+        if let Some(ref relay) = settings.relay {
+            tracing::info!("ACME server relays orders to {}", config.acme.directory);
+            let solvers = crate::orchestrator::provisioner::build_solver_registry(&config).await?;
+            acme_server =
+                acme_server.with_relay(Relay::new(client.clone(), solvers, relay.policy()?)?);
+        }
+        tokio::spawn(async move {
+            if let Err(e) = start_acme_server(acme_addr, acme_server).await {
+                tracing::error!("ACME server stopped: {}", e);
+            }
+        });
+    }
+
     // Start server
     start_server(
         addr,
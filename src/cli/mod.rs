@@ -1,7 +1,7 @@
 /// CLI commands and entry point implementation.
 /// This module handles command-line argument parsing and dispatches execution
 /// to the appropriate command handlers.
-use crate::cli::args::{AccountCommands, Cli, Commands};
+use crate::cli::args::{AccountCommands, AuthzCommands, Cli, Commands};
 use clap::Parser;
 use tracing_subscriber::EnvFilter;
 
@@ -35,25 +35,30 @@ pub async fn run() -> crate::error::Result<()> {
     match cli.command {
         Commands::Obtain(args) => {
             tracing::info!("Handling 'obtain' command for domains: {:?}", args.domains);
-            commands::handle_obtain(
-                args.domains,
-                args.email,
-                args.challenge,
-                args.cert_path,
-                args.key_path,
-                args.prod,
-                args.dns_provider,
-            )
-            .await?;
+            commands::handle_obtain(*args).await?;
         }
         Commands::Renew(args) => {
             tracing::info!("Handling 'renew' command (force: {})", args.force);
             commands::handle_renew(args.domains, args.force, args.storage_path).await?;
         }
+        Commands::Authz(args) => match args.command {
+            AuthzCommands::Create(a) => {
+                tracing::info!("Pre-authorizing identifiers: {:?}", a.domains);
+                commands::handle_authz_create(a).await?;
+            }
+            AuthzCommands::Deactivate(a) => {
+                tracing::info!("Deactivating authorization: {}", a.url);
+                commands::handle_authz_deactivate(a).await?;
+            }
+        },
         Commands::Order(args) => match args.command {
-            args::OrderCommands::List => {
+            args::OrderCommands::List {
+                key_path,
+                prod,
+                directory,
+            } => {
                 tracing::info!("Listing ACME orders");
-                commands::handle_order_list().await?;
+                commands::handle_order_list(key_path, prod, directory).await?;
             }
             args::OrderCommands::Show { order_id } => {
                 tracing::info!("Showing details for order: {}", order_id);
@@ -65,9 +70,15 @@ pub async fn run() -> crate::error::Result<()> {
                 tracing::info!("Listing managed certificates");
                 commands::handle_cert_list().await?;
             }
-            args::CertCommands::Revoke { cert, reason, key } => {
+            args::CertCommands::Revoke {
+                cert,
+                reason,
+                key,
+                cert_key,
+                directory,
+            } => {
                 tracing::info!("Revoking certificate: {}", cert);
-                commands::handle_cert_revoke(cert, reason, key).await?;
+                commands::handle_cert_revoke(cert, reason, key, cert_key, directory).await?;
             }
         },
         Commands::Daemon(args) => {
@@ -92,7 +103,11 @@ pub async fn run() -> crate::error::Result<()> {
         Commands::Account(args) => match args.command {
             AccountCommands::Register(a) => {
                 tracing::info!("Registering new ACME account with email: {:?}", a.email);
-                commands::handle_register(a.email, a.prod, a.key_path).await?;
+                commands::handle_register(a).await?;
+            }
+            AccountCommands::Lookup(a) => {
+                tracing::info!("Looking up ACME account for key: {}", a.key_path);
+                commands::handle_lookup(a).await?;
             }
             AccountCommands::Update(a) => {
                 tracing::info!("Updating ACME account contacts");
@@ -1,13 +1,37 @@
 /// High-level ACME client for certificate issuance and account management.
-use crate::account::{AccountManager, KeyPair};
+use crate::account::{Account, AccountManager, ExternalAccountKey, KeyPair};
 use crate::challenge::ChallengeSolverRegistry;
+use crate::crypto::KeyType;
 use crate::error::Result;
-use crate::order::{CsrGenerator, NewOrderRequest, OrderManager};
-use crate::protocol::{DirectoryManager, NonceManager, NoncePool};
-use crate::types::{ChallengeType, Contact, Identifier};
+use crate::order::{
+    Authorization, AutoRenewal, CertificateOptions, CertificateRevocation, CsrGenerator,
+    NewOrderRequest, Order, OrderManager,
+};
+use crate::protocol::{
+    Directory, DirectoryManager, NonceManager, NoncePool, RenewalInfo, renewal_info,
+};
+use crate::server::acme::CertificateRequest;
+use crate::server::acme::ca::canonical_name;
+use crate::storage::{AuthorizationState, OrderStore, PendingOrder, StorageBackend};
+use crate::types::{AcmeErrorDetail, ChallengeType, Contact, Identifier, RevocationReason};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Default number of authorizations validated at the same time.
+pub const DEFAULT_AUTHORIZATION_CONCURRENCY: usize = 4;
+
+/// Default interval between polls when the server sends no `Retry-After`.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default time allowed for authorizations to be validated and the order to become ready.
+pub const DEFAULT_AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Default time allowed for the CA to issue the certificate after finalization.
+pub const DEFAULT_FINALIZATION_TIMEOUT: Duration = Duration::from_secs(900);
 
 /// Configuration for the ACME client.
 #[derive(Clone)]
@@ -18,6 +42,24 @@ pub struct AcmeConfig {
     pub contacts: Vec<Contact>,
     /// Whether the terms of service have been agreed to.
     pub terms_of_service_agreed: bool,
+    /// The type of key generated for new accounts (defaults to ECDSA P-256 / ES256).
+    pub account_key_type: KeyType,
+    /// External Account Binding credentials, required by some CAs (ZeroSSL, Google).
+    pub external_account_binding: Option<ExternalAccountKey>,
+    /// Certificate profile requested for new orders, if the CA offers profiles.
+    pub profile: Option<String>,
+    /// Issuer common name of the preferred root when the CA offers alternate chains.
+    pub preferred_chain: Option<String>,
+    /// Maximum number of authorizations validated at the same time.
+    pub authorization_concurrency: usize,
+    /// Interval between polls when the server sends no `Retry-After`.
+    pub poll_interval: Duration,
+    /// Time allowed for authorizations to be validated and the order to become ready.
+    pub authorization_timeout: Duration,
+    /// Time allowed for the CA to issue the certificate after finalization.
+    pub finalization_timeout: Duration,
+    /// Key type, CSR contents and key reuse of issued certificates.
+    pub certificate: CertificateOptions,
 }
 
 impl AcmeConfig {
@@ -27,6 +69,15 @@ impl AcmeConfig {
             directory_url: directory_url.into(),
             contacts: Vec::new(),
             terms_of_service_agreed: false,
+            account_key_type: KeyType::EcdsaP256,
+            external_account_binding: None,
+            profile: None,
+            preferred_chain: None,
+            authorization_concurrency: DEFAULT_AUTHORIZATION_CONCURRENCY,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            authorization_timeout: DEFAULT_AUTHORIZATION_TIMEOUT,
+            finalization_timeout: DEFAULT_FINALIZATION_TIMEOUT,
+            certificate: CertificateOptions::default(),
         }
     }
 
@@ -42,6 +93,61 @@ impl AcmeConfig {
         self
     }
 
+    /// Sets the key type used when generating a new account key.
+    pub fn with_account_key_type(mut self, key_type: KeyType) -> Self {
+        self.account_key_type = key_type;
+        self
+    }
+
+    /// Sets the External Account Binding credentials used when registering the account.
+    pub fn with_external_account_binding(mut self, eab: ExternalAccountKey) -> Self {
+        self.external_account_binding = Some(eab);
+        self
+    }
+
+    /// Sets the certificate profile requested for new orders (e.g. "shortlived").
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Prefers the certificate chain whose topmost certificate is issued by this common name
+    /// (e.g. "ISRG Root X1"), falling back to the CA's default chain.
+    pub fn with_preferred_chain(mut self, issuer_cn: impl Into<String>) -> Self {
+        self.preferred_chain = Some(issuer_cn.into());
+        self
+    }
+
+    /// Sets how many authorizations are prepared and validated concurrently (at least one).
+    pub fn with_authorization_concurrency(mut self, concurrency: usize) -> Self {
+        self.authorization_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the interval between polls used when the server sends no `Retry-After`.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Sets how long to wait for authorizations to be validated and the order to become ready.
+    pub fn with_authorization_timeout(mut self, timeout: Duration) -> Self {
+        self.authorization_timeout = timeout;
+        self
+    }
+
+    /// Sets how long to wait for the CA to issue the certificate after finalization.
+    pub fn with_finalization_timeout(mut self, timeout: Duration) -> Self {
+        self.finalization_timeout = timeout;
+        self
+    }
+
+    /// Sets the key type, CSR contents and key reuse of issued certificates.
+    pub fn with_certificate_options(mut self, options: CertificateOptions) -> Self {
+        self.certificate = options;
+        self
+    }
+
     /// Returns a configuration for the Let's Encrypt staging directory.
     pub fn lets_encrypt_staging() -> Self {
         Self::new("https://acme-staging-v02.api.letsencrypt.org/directory")
@@ -53,8 +159,29 @@ impl AcmeConfig {
     }
 }
 
+/// Protocol state reused across the operations of a client and all of its clones.
+#[derive(Default)]
+struct Session {
+    /// The directory and nonce managers, created on first use.
+    managers: OnceCell<SessionManagers>,
+    /// The URL of the registered account, once known.
+    account_url: OnceLock<String>,
+    /// An optional pool pre-fetching nonces into the session's nonce cache.
+    nonce_pool: std::sync::RwLock<Option<Arc<NoncePool>>>,
+    /// Renewal information by ARI certificate identifier, with the time to poll it next.
+    renewal_info: std::sync::Mutex<HashMap<String, (RenewalInfo, jiff::Timestamp)>>,
+}
+
+/// Managers caching the directory and the nonces returned by the server.
+struct SessionManagers {
+    directory: DirectoryManager,
+    nonces: NonceManager,
+}
+
 /// The primary high-level ACME client.
 /// This client manages account registration, order creation, and certificate issuance.
+/// Clones share one session, so the directory, cached nonces and the account URL are
+/// fetched once and reused by every clone.
 #[derive(Clone)]
 pub struct AcmeClient {
     /// The client configuration.
@@ -63,10 +190,10 @@ pub struct AcmeClient {
     http_client: reqwest::Client,
     /// The account key pair.
     key_pair: Arc<KeyPair>,
-    /// The registered account ID, if any.
-    account_id: Option<String>,
-    /// An optional pool for managing nonces.
-    nonce_pool: Option<Arc<NoncePool>>,
+    /// The cached protocol session.
+    session: Arc<Session>,
+    /// Storage for in-flight orders, allowing issuance to resume after a restart.
+    order_store: Option<OrderStore<Arc<dyn StorageBackend>>>,
 }
 
 impl AcmeClient {
@@ -78,14 +205,14 @@ impl AcmeClient {
             config.directory_url
         );
         let http_client = reqwest::Client::new();
-        let key_pair = Arc::new(KeyPair::generate()?);
+        let key_pair = Arc::new(KeyPair::generate_with(config.account_key_type)?);
 
         Ok(Self {
             config,
             http_client,
             key_pair,
-            account_id: None,
-            nonce_pool: None,
+            session: Arc::default(),
+            order_store: None,
         })
     }
 
@@ -98,70 +225,109 @@ impl AcmeClient {
             config,
             http_client,
             key_pair: Arc::new(key_pair),
-            account_id: None,
-            nonce_pool: None,
+            session: Arc::default(),
+            order_store: None,
         }
     }
 
+    /// Persists in-flight orders to `storage`, so an interrupted issuance resumes the
+    /// existing order instead of creating a new one.
+    pub fn with_order_storage(mut self, storage: Arc<dyn StorageBackend>) -> Self {
+        self.order_store = Some(OrderStore::new(storage));
+        self
+    }
+
     /// Registers a new account or retrieves an existing one using the configured key pair.
     pub async fn register_account(&mut self) -> Result<String> {
         tracing::info!(
             "Registering account with ACME server: {}",
             self.config.directory_url
         );
-        let dir_mgr = DirectoryManager::new(&self.config.directory_url, self.http_client.clone());
-        let directory = dir_mgr.get().await?;
-
-        let nonce_mgr = NonceManager::new(&directory.new_nonce, self.http_client.clone());
-
-        let account_mgr =
-            AccountManager::new(&self.key_pair, &nonce_mgr, &dir_mgr, &self.http_client)?;
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
 
         let account = account_mgr
-            .register(
+            .register_with_eab(
                 self.config.contacts.clone(),
                 self.config.terms_of_service_agreed,
+                self.config.external_account_binding.as_ref(),
             )
             .await?;
 
-        self.account_id = Some(account.id.clone());
+        // Re-registering the same key returns the same account, so the first URL stays valid
+        let _ = self.session.account_url.set(account.id.clone());
         tracing::info!("Account successfully registered: {}", account.id);
 
         Ok(account.id)
     }
 
+    /// Looks up the existing account for the configured key pair without registering a new one.
+    pub async fn find_account(&mut self) -> Result<Account> {
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+
+        let account = account_mgr.find_existing().await?;
+        let _ = self.session.account_url.set(account.id.clone());
+        Ok(account)
+    }
+
+    /// Lists the URLs of the account's orders as reported by the ACME server.
+    /// Looks up the account if needed, but never registers one.
+    pub async fn list_orders(&mut self) -> Result<Vec<String>> {
+        let account_url = match self.session.account_url.get() {
+            Some(url) => url.clone(),
+            None => self.find_account().await?.id,
+        };
+
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let account = account_mgr.get_account(&account_url).await?;
+
+        let Some(orders_url) = account.orders.as_deref() else {
+            tracing::warn!("Account {} has no orders URL", account.id);
+            return Ok(Vec::new());
+        };
+        account_mgr.list_orders(&account.id, orders_url).await
+    }
+
     /// Creates a new certificate order for the specified domains.
     /// Automatically registers the account if it hasn't been registered yet.
     pub async fn create_order(&mut self, domains: Vec<String>) -> Result<crate::order::Order> {
         tracing::info!("Creating order for domains: {:?}", domains);
-        // Ensure account is registered
-        if self.account_id.is_none() {
-            self.register_account().await?;
-        }
+        let account_id = self.ensure_account().await?;
 
-        let account_id = self.account_id.as_ref().unwrap().clone();
-
-        let dir_mgr = DirectoryManager::new(&self.config.directory_url, self.http_client.clone());
-        let nonce_mgr =
-            NonceManager::new(&dir_mgr.get().await?.new_nonce, self.http_client.clone());
-
-        let account_mgr =
-            AccountManager::new(&self.key_pair, &nonce_mgr, &dir_mgr, &self.http_client)?;
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
 
         let order_mgr = OrderManager::new(
             &account_mgr,
-            &dir_mgr,
-            &nonce_mgr,
+            &session.directory,
+            &session.nonces,
             &self.http_client,
             account_id,
         );
 
-        let identifiers: Vec<Identifier> = domains.iter().map(Identifier::dns).collect();
-        let order_req = NewOrderRequest {
-            identifiers,
-            not_before: None,
-            not_after: None,
-        };
+        let order_req = self.new_order_request(domains);
 
         let (url, order) = order_mgr.create_order(&order_req).await?;
         tracing::info!("Order created successfully at URL: {}", url);
@@ -178,149 +344,566 @@ impl AcmeClient {
     pub async fn issue_certificate(
         &mut self,
         domains: Vec<String>,
-        solver_registry: &mut ChallengeSolverRegistry,
+        solver_registry: &ChallengeSolverRegistry,
     ) -> Result<CertificateBundle> {
-        tracing::info!("Starting certificate issuance for domains: {:?}", domains);
-        // Ensure account is registered
-        if self.account_id.is_none() {
-            self.register_account().await?;
+        let order_req = self.new_order_request(domains.clone());
+        self.issue_with_request(order_req, domains, None, solver_registry)
+            .await
+    }
+
+    /// Renews a previously issued certificate.
+    /// When the CA supports ARI, the new order carries a `replaces` field pointing at
+    /// the old certificate so the CA can exempt it from rate limits and track the renewal.
+    /// The old private key is reused if the certificate options ask for it.
+    pub async fn renew_certificate(
+        &mut self,
+        previous: &CertificateBundle,
+        solver_registry: &ChallengeSolverRegistry,
+    ) -> Result<CertificateBundle> {
+        tracing::info!(
+            "Starting certificate renewal for domains: {:?}",
+            previous.domains
+        );
+        let mut order_req = self.new_order_request(previous.domains.clone());
+
+        let session = self.managers().await?;
+        if session.directory.get().await?.renewal_info.is_some() {
+            let cert_id = previous.certificate_der().and_then(|chain| {
+                let leaf = chain.first().ok_or_else(|| {
+                    crate::error::AcmeError::certificate("Empty certificate chain".to_string())
+                })?;
+                renewal_info::ari_cert_id(leaf)
+            });
+            match cert_id {
+                Ok(cert_id) => order_req = order_req.with_replaces(cert_id),
+                Err(e) => tracing::warn!(
+                    "Cannot compute ARI identifier, not sending 'replaces': {}",
+                    e
+                ),
+            }
         }
 
-        let account_id = self.account_id.as_ref().unwrap().clone();
+        // Keep the old key if configured, e.g. for pinned keys or DANE records
+        let reuse_key = self
+            .config
+            .certificate
+            .reuse_key
+            .then_some(previous.private_key_pem.as_str());
+        self.issue_with_request(
+            order_req,
+            previous.domains.clone(),
+            reuse_key,
+            solver_registry,
+        )
+        .await
+    }
 
-        // Create managers
-        let dir_mgr = DirectoryManager::new(&self.config.directory_url, self.http_client.clone());
-        let nonce_mgr =
-            NonceManager::new(&dir_mgr.get().await?.new_nonce, self.http_client.clone());
-        let account_mgr =
-            AccountManager::new(&self.key_pair, &nonce_mgr, &dir_mgr, &self.http_client)?;
+    /// Issues a Short-Term Automatically Renewed (STAR) certificate (RFC 8739).
+    /// The CA keeps issuing short-lived certificates for the order until its end date;
+    /// fetch each one with [`AcmeClient::fetch_star_certificate`] instead of ordering again.
+    pub async fn issue_star_certificate(
+        &mut self,
+        domains: Vec<String>,
+        auto_renewal: AutoRenewal,
+        solver_registry: &ChallengeSolverRegistry,
+    ) -> Result<CertificateBundle> {
+        let order_req = self
+            .new_order_request(domains.clone())
+            .with_auto_renewal(auto_renewal);
+        self.issue_with_request(order_req, domains, None, solver_registry)
+            .await
+    }
+
+    /// Downloads the current certificate of a STAR bundle from its rolling `star-certificate`
+    /// URL. The returned bundle keeps the private key and STAR details of `bundle`.
+    pub async fn fetch_star_certificate(
+        &mut self,
+        bundle: &CertificateBundle,
+    ) -> Result<CertificateBundle> {
+        let star = bundle.star.as_ref().ok_or_else(|| {
+            crate::error::AcmeError::invalid_input(format!(
+                "Certificate for {:?} was not issued by a STAR order",
+                bundle.domains
+            ))
+        })?;
+        tracing::info!(
+            "Fetching STAR certificate for {:?} from {}",
+            bundle.domains,
+            star.certificate_url
+        );
+        let account_id = self.ensure_account().await?;
+
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
         let order_mgr = OrderManager::new(
             &account_mgr,
-            &dir_mgr,
-            &nonce_mgr,
+            &session.directory,
+            &session.nonces,
             &self.http_client,
-            account_id.clone(),
+            account_id,
         );
 
-        // Create order
-        let identifiers: Vec<Identifier> = domains.iter().map(Identifier::dns).collect();
-        let order_req = NewOrderRequest {
-            identifiers,
-            not_before: None,
-            not_after: None,
-        };
+        let certificate_pem = order_mgr
+            .download_certificate(&star.certificate_url)
+            .await?;
+        let chain_issuer =
+            crate::certificate::CertificateChain::from_pem(certificate_pem.as_bytes())
+                .and_then(|chain| chain.top_issuer_common_name())
+                .ok()
+                .flatten();
 
+        Ok(CertificateBundle {
+            certificate_pem,
+            chain_issuer,
+            ..bundle.clone()
+        })
+    }
+
+    /// Cancels a STAR order so the CA stops issuing certificates for it (RFC 8739 Section 2.3).
+    pub async fn cancel_star_order(&mut self, order_url: &str) -> Result<Order> {
+        tracing::info!("Canceling STAR order: {}", order_url);
+        let account_id = self.ensure_account().await?;
+
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let order_mgr = OrderManager::new(
+            &account_mgr,
+            &session.directory,
+            &session.nonces,
+            &self.http_client,
+            account_id,
+        );
+
+        order_mgr.cancel_order(order_url).await
+    }
+
+    /// Issues a certificate for a CSR generated elsewhere, e.g. by a client of the relay
+    /// server, and returns the PEM certificate chain. The CSR must request exactly `domains`,
+    /// or no order is created; its private key never passes through this client.
+    pub async fn issue_for_csr(
+        &mut self,
+        domains: Vec<String>,
+        csr_der: &[u8],
+        solver_registry: &ChallengeSolverRegistry,
+    ) -> Result<String> {
+        tracing::info!("Starting certificate issuance for CSR of: {:?}", domains);
+        let csr = CertificateRequest::from_der(csr_der)
+            .map_err(|e| crate::error::AcmeError::invalid_input(format!("Invalid CSR: {}", e)))?;
+        let mut requested = csr.names;
+        requested.sort();
+        requested.dedup();
+        let mut expected: Vec<String> = domains.iter().map(|d| canonical_name(d)).collect();
+        expected.sort();
+        expected.dedup();
+        if requested != expected {
+            return Err(crate::error::AcmeError::invalid_input(format!(
+                "CSR names {:?} do not match the requested domains {:?}",
+                requested, expected
+            )));
+        }
+
+        let account_id = self.ensure_account().await?;
+
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let order_mgr = OrderManager::new(
+            &account_mgr,
+            &session.directory,
+            &session.nonces,
+            &self.http_client,
+            account_id,
+        );
+
+        let order_req = self.new_order_request(domains);
         let (order_url, mut order) = order_mgr.create_order(&order_req).await?;
         tracing::info!("Order created: {}", order_url);
 
-        // Process authorizations
-        for auth_url in &order.authorizations {
-            let auth = order_mgr.get_authorization(auth_url).await?;
-            tracing::info!("Processing authorization for: {:?}", auth.identifier);
+        if order.status == "pending" {
+            self.authorize_order(
+                &order_mgr,
+                &account_mgr,
+                &order.authorizations,
+                solver_registry,
+            )
+            .await?;
+            order = order_mgr
+                .poll_order(
+                    &order_url,
+                    self.config.authorization_timeout,
+                    self.config.poll_interval,
+                )
+                .await?;
+        }
+        if order.status == "ready" {
+            tracing::info!("Finalizing order at URL: {}", order.finalize);
+            order = order_mgr.finalize_order(&order.finalize, csr_der).await?;
+        } else if order.status != "processing" && order.status != "valid" {
+            return Err(crate::error::AcmeError::order(
+                "Order not ready after authorization".to_string(),
+                order.status,
+            ));
+        }
 
-            // Find suitable challenge
-            let challenge = auth
-                .challenges
-                .iter()
-                .find(|c| {
-                    c.challenge_type
-                        .parse::<ChallengeType>()
-                        .map(|ct| solver_registry.get(ct).is_some())
-                        .unwrap_or(false)
-                })
-                .ok_or_else(|| {
-                    crate::error::AcmeError::challenge(
-                        "unknown".to_string(),
-                        "No suitable challenge solver found".to_string(),
-                    )
-                })?;
+        let order = self.wait_until_valid(&order_mgr, &order_url, order).await?;
+        let certificate_url = order.certificate.ok_or_else(|| {
+            crate::error::AcmeError::certificate("No certificate URL in order".to_string())
+        })?;
+        self.download_chain(&order_mgr, &certificate_url).await
+    }
 
-            // Get solver
-            let challenge_type: ChallengeType = challenge.challenge_type.parse().map_err(|_| {
-                crate::error::AcmeError::challenge(
-                    challenge.challenge_type.clone(),
-                    "Unsupported challenge type".to_string(),
-                )
-            })?;
+    /// Fetches the CA-suggested renewal window (ARI) for a PEM-encoded certificate.
+    /// Returns `None` if the ACME server does not advertise a `renewalInfo` endpoint.
+    ///
+    /// Results are cached per certificate until the server's `Retry-After` has passed,
+    /// and the chosen renewal time is kept for as long as the suggested window is unchanged.
+    pub async fn renewal_info(&self, certificate_pem: &str) -> Result<Option<RenewalInfo>> {
+        let directory = self.directory().await?;
+        let Some(renewal_info_url) = directory.renewal_info else {
+            tracing::debug!("ACME server does not support ARI");
+            return Ok(None);
+        };
 
-            let solver = solver_registry.get_mut(challenge_type).ok_or_else(|| {
-                crate::error::AcmeError::challenge(
-                    challenge.challenge_type.clone(),
-                    "Solver not found".to_string(),
-                )
-            })?;
+        let chain = crate::order::parse_certificate_chain(certificate_pem)?;
+        let leaf = chain.first().ok_or_else(|| {
+            crate::error::AcmeError::certificate("Empty certificate chain".to_string())
+        })?;
+        let cert_id = renewal_info::ari_cert_id(leaf)?;
 
-            // Compute key authorization
-            let key_auth = account_mgr.compute_key_authorization(&challenge.token)?;
+        let now = jiff::Timestamp::now();
+        let cached = self
+            .session
+            .renewal_info
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&cert_id).cloned());
+        if let Some((info, next_poll)) = &cached
+            && now < *next_poll
+        {
+            tracing::debug!("Using cached renewal information until {}", next_poll);
+            return Ok(Some(info.clone()));
+        }
 
-            // Prepare challenge
-            tracing::debug!("Preparing challenge: {}", challenge.challenge_type);
-            solver
-                .prepare(challenge, &auth.identifier, &key_auth)
+        let mut info =
+            renewal_info::fetch_renewal_info(&self.http_client, &renewal_info_url, &cert_id)
                 .await?;
+        if let Some((previous, _)) = cached
+            && previous.suggested_window == info.suggested_window
+        {
+            info.renewal_time = previous.renewal_time;
+        }
+        let next_poll = info.next_poll(now);
+        if let Ok(mut cache) = self.session.renewal_info.lock() {
+            cache.insert(cert_id, (info.clone(), next_poll));
+        }
+        Ok(Some(info))
+    }
+
+    /// Pre-authorizes identifiers ahead of ordering (RFC 8555 Section 7.4.1), so later
+    /// orders for them are ready without another round of challenges.
+    /// Returns `None` if the ACME server does not advertise a `newAuthz` endpoint.
+    pub async fn pre_authorize(
+        &mut self,
+        domains: Vec<String>,
+        solver_registry: &ChallengeSolverRegistry,
+    ) -> Result<Option<Vec<AuthorizationState>>> {
+        if self.directory().await?.new_authz.is_none() {
+            tracing::debug!("ACME server does not support pre-authorization");
+            return Ok(None);
+        }
 
-            // Present challenge
-            tracing::debug!("Presenting challenge: {}", challenge.challenge_type);
-            solver.present().await?;
+        tracing::info!("Pre-authorizing identifiers: {:?}", domains);
+        let account_id = self.ensure_account().await?;
 
-            // Respond to ACME server
-            tracing::debug!("Responding to challenge at URL: {}", challenge.url);
-            order_mgr.respond_to_challenge(&challenge.url).await?;
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let order_mgr = OrderManager::new(
+            &account_mgr,
+            &session.directory,
+            &session.nonces,
+            &self.http_client,
+            account_id,
+        );
 
-            tracing::info!("Challenge completed for: {:?}", auth.identifier);
+        let mut authorizations = Vec::with_capacity(domains.len());
+        for domain in domains {
+            let (auth_url, _) = order_mgr
+                .new_authorization(&Identifier::from_name(domain))
+                .await?;
+            authorizations.push(auth_url);
         }
 
-        // Poll order until ready
-        tracing::info!("Polling order status until ready...");
-        order = order_mgr
-            .poll_order(&order_url, 30, Duration::from_secs(2))
+        let states = self
+            .authorize_order(&order_mgr, &account_mgr, &authorizations, solver_registry)
             .await?;
+        Ok(Some(states))
+    }
 
-        if order.status != "ready" {
-            tracing::error!(
-                "Order failed to reach 'ready' status. Current status: {}",
-                order.status
-            );
-            return Err(crate::error::AcmeError::order(
-                "Order not ready after authorization".to_string(),
-                order.status,
-            ));
+    /// Deactivates an authorization, e.g. when the identifier leaves our control (RFC 8555 Section 7.5.2).
+    pub async fn deactivate_authorization(&mut self, auth_url: &str) -> Result<Authorization> {
+        tracing::info!("Deactivating authorization: {}", auth_url);
+        let account_id = self.ensure_account().await?;
+
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let order_mgr = OrderManager::new(
+            &account_mgr,
+            &session.directory,
+            &session.nonces,
+            &self.http_client,
+            account_id,
+        );
+
+        order_mgr.deactivate_authorization(auth_url).await
+    }
+
+    /// Revokes a PEM-encoded certificate issued to this client's account.
+    pub async fn revoke_certificate(
+        &mut self,
+        certificate_pem: &str,
+        reason: Option<RevocationReason>,
+    ) -> Result<()> {
+        let leaf = Self::leaf_certificate(certificate_pem)?;
+        let account_id = self.ensure_account().await?;
+
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let mut revocation = CertificateRevocation::new(&account_mgr, account_id, leaf);
+        if let Some(reason) = reason {
+            revocation = revocation.with_reason(reason);
         }
+        revocation.revoke().await
+    }
 
-        // Generate CSR
-        tracing::info!("Generating CSR for domains: {:?}", domains);
-        let csr_gen = CsrGenerator::new(domains.clone());
-        let (csr_der, private_key_pem) = csr_gen.generate()?;
+    /// Revokes a PEM-encoded certificate by signing with its own private key instead of an
+    /// account key, e.g. after a key compromise when the issuing account is unknown.
+    pub async fn revoke_with_certificate_key(
+        &self,
+        certificate_pem: &str,
+        certificate_key: &KeyPair,
+        reason: Option<RevocationReason>,
+    ) -> Result<()> {
+        let leaf = Self::leaf_certificate(certificate_pem)?;
 
-        // Finalize order
-        tracing::info!("Finalizing order at URL: {}", order.finalize);
-        let _order = order_mgr.finalize_order(&order.finalize, &csr_der).await?;
+        let session = self.managers().await?;
+        let key_mgr = AccountManager::new(
+            certificate_key,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let mut revocation = CertificateRevocation::with_certificate_key(&key_mgr, leaf);
+        if let Some(reason) = reason {
+            revocation = revocation.with_reason(reason);
+        }
+        revocation.revoke().await
+    }
 
-        // Poll until valid
-        tracing::info!("Polling order status until valid...");
-        let order = order_mgr
-            .poll_order(&order_url, 30, Duration::from_secs(2))
-            .await?;
+    /// Returns the DER encoding of the first certificate in a PEM chain.
+    fn leaf_certificate(certificate_pem: &str) -> Result<Vec<u8>> {
+        crate::order::parse_certificate_chain(certificate_pem)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                crate::error::AcmeError::certificate("Empty certificate chain".to_string())
+            })
+    }
+
+    /// Builds an order request for the domains, applying the configured profile.
+    fn new_order_request(&self, domains: Vec<String>) -> NewOrderRequest {
+        let order_req = NewOrderRequest::new(domains);
+        match &self.config.profile {
+            Some(profile) => order_req.with_profile(profile.clone()),
+            None => order_req,
+        }
+    }
+
+    /// Runs the full issuance flow for a prepared order request.
+    /// `reuse_key_pem` is the certificate key to use instead of generating a new one.
+    async fn issue_with_request(
+        &mut self,
+        mut order_req: NewOrderRequest,
+        domains: Vec<String>,
+        reuse_key_pem: Option<&str>,
+        solver_registry: &ChallengeSolverRegistry,
+    ) -> Result<CertificateBundle> {
+        tracing::info!("Starting certificate issuance for domains: {:?}", domains);
+        let account_id = self.ensure_account().await?;
+
+        // Create managers on top of the cached session
+        let session = self.managers().await?;
+        let account_mgr = AccountManager::new(
+            &self.key_pair,
+            &session.nonces,
+            &session.directory,
+            &self.http_client,
+        )?;
+        let order_mgr = OrderManager::new(
+            &account_mgr,
+            &session.directory,
+            &session.nonces,
+            &self.http_client,
+            account_id.clone(),
+        );
+
+        // Resume the stored order for these domains, or create a new one
+        let resumed = self.resume_order(&order_mgr, &domains, &account_id).await;
+        let (order_url, mut order, mut state) = match resumed {
+            Some(resumed) => resumed,
+            None => {
+                let (order_url, order) = match order_mgr.create_order(&order_req).await {
+                    Err(e) if order_req.replaces.is_some() && rejects_replaces(&e) => {
+                        // The CA refused the replacement itself; retry as a plain order
+                        tracing::warn!(
+                            "Order with 'replaces' was rejected, retrying without it: {}",
+                            e
+                        );
+                        order_req.replaces = None;
+                        order_mgr.create_order(&order_req).await?
+                    }
+                    result => result?,
+                };
+                tracing::info!("Order created: {}", order_url);
+                let state = PendingOrder {
+                    domains: domains.clone(),
+                    directory_url: self.config.directory_url.clone(),
+                    account_url: account_id.clone(),
+                    order_url: order_url.clone(),
+                    status: order.status.clone(),
+                    finalize_url: order.finalize.clone(),
+                    authorizations: Vec::new(),
+                    certificate_key_pem: None,
+                    updated_at: jiff::Timestamp::now(),
+                };
+                self.save_pending_order(&state).await;
+                (order_url, order, state)
+            }
+        };
+
+        if order.status == "pending" {
+            // Process authorizations
+            state.authorizations = self
+                .authorize_order(
+                    &order_mgr,
+                    &account_mgr,
+                    &order.authorizations,
+                    solver_registry,
+                )
+                .await?;
+
+            // Poll order until ready
+            tracing::info!("Polling order status until ready...");
+            order = order_mgr
+                .poll_order(
+                    &order_url,
+                    self.config.authorization_timeout,
+                    self.config.poll_interval,
+                )
+                .await?;
+
+            if order.status != "ready" {
+                tracing::error!(
+                    "Order failed to reach 'ready' status. Current status: {}",
+                    order.status
+                );
+                return Err(crate::error::AcmeError::order(
+                    "Order not ready after authorization".to_string(),
+                    order.status,
+                ));
+            }
+            state.status = order.status.clone();
+            self.save_pending_order(&state).await;
+        }
 
         if order.status != "valid" {
-            tracing::error!(
-                "Order failed to reach 'valid' status. Current status: {}",
-                order.status
-            );
-            return Err(crate::error::AcmeError::order(
-                "Order not valid after finalization".to_string(),
-                order.status,
-            ));
+            if order.status == "ready" {
+                // Generate CSR, reusing the key of an interrupted finalization or the renewed certificate
+                tracing::info!("Generating CSR for domains: {:?}", domains);
+                let mut csr_gen =
+                    CsrGenerator::new(domains.clone()).with_options(&self.config.certificate);
+                if let Some(key_pem) = state.certificate_key_pem.as_deref().or(reuse_key_pem) {
+                    let key = rcgen::KeyPair::from_pem(key_pem).map_err(|e| {
+                        crate::error::AcmeError::pem(format!(
+                            "Failed to parse certificate key: {}",
+                            e
+                        ))
+                    })?;
+                    csr_gen = csr_gen.with_private_key(key);
+                }
+                let (csr_der, private_key_pem) = csr_gen.generate()?;
+
+                // The key must survive a crash once the CA may issue for it
+                state.certificate_key_pem = Some(private_key_pem);
+                state.status = "processing".to_string();
+                self.save_pending_order(&state).await;
+
+                // Finalize order
+                tracing::info!("Finalizing order at URL: {}", order.finalize);
+                order = order_mgr.finalize_order(&order.finalize, &csr_der).await?;
+            }
+
+            order = self.wait_until_valid(&order_mgr, &order_url, order).await?;
         }
 
-        // Download certificate
-        let certificate_url = order.certificate.ok_or_else(|| {
-            tracing::error!("Order is valid but no certificate URL was provided");
-            crate::error::AcmeError::certificate("No certificate URL in order".to_string())
+        let private_key_pem = state.certificate_key_pem.clone().ok_or_else(|| {
+            crate::error::AcmeError::certificate(
+                "No certificate key is known for the finalized order".to_string(),
+            )
         })?;
 
-        tracing::info!("Downloading certificate from: {}", certificate_url);
-        let cert_pem = order_mgr.download_certificate(&certificate_url).await?;
+        // STAR orders expose a rolling certificate URL instead of a fixed one
+        let star = match (&order.star_certificate, &order.auto_renewal) {
+            (Some(certificate_url), Some(auto_renewal)) => Some(StarCertificate {
+                order_url: order_url.clone(),
+                certificate_url: certificate_url.clone(),
+                end_date: auto_renewal.end_date,
+            }),
+            _ => None,
+        };
+
+        // Download certificate
+        let certificate_url = order
+            .certificate
+            .or(order.star_certificate)
+            .ok_or_else(|| {
+                tracing::error!("Order is valid but no certificate URL was provided");
+                crate::error::AcmeError::certificate("No certificate URL in order".to_string())
+            })?;
+
+        let cert_pem = self.download_chain(&order_mgr, &certificate_url).await?;
+        let chain_issuer = crate::certificate::CertificateChain::from_pem(cert_pem.as_bytes())
+            .and_then(|chain| chain.top_issuer_common_name())
+            .ok()
+            .flatten();
 
         // Verify certificate chain
         if let Ok(chain) = crate::certificate::CertificateChain::from_pem(cert_pem.as_bytes()) {
@@ -331,44 +914,366 @@ impl AcmeClient {
             }
         }
 
+        if let Some(store) = &self.order_store
+            && let Err(e) = store.delete(&domains).await
+        {
+            tracing::warn!("Failed to remove completed order from storage: {}", e);
+        }
+
         tracing::info!("Certificate issuance completed successfully");
         Ok(CertificateBundle {
             certificate_pem: cert_pem,
             private_key_pem,
             domains,
+            chain_issuer,
+            star,
         })
     }
 
+    /// Waits for a finalized order to become valid.
+    async fn wait_until_valid(
+        &self,
+        order_mgr: &OrderManager<'_>,
+        order_url: &str,
+        mut order: Order,
+    ) -> Result<Order> {
+        if order.status != "valid" {
+            // The CA may issue asynchronously; wait as long as it asks before polling
+            tracing::info!("Polling order status until valid...");
+            if let Some(delay) = order.retry_after {
+                tokio::time::sleep(delay.min(self.config.finalization_timeout)).await;
+            }
+            order = order_mgr
+                .poll_order(
+                    order_url,
+                    self.config.finalization_timeout,
+                    self.config.poll_interval,
+                )
+                .await?;
+        }
+
+        if order.status != "valid" {
+            tracing::error!(
+                "Order failed to reach 'valid' status. Current status: {}",
+                order.status
+            );
+            return Err(crate::error::AcmeError::order(
+                "Order not valid after finalization".to_string(),
+                order.status,
+            ));
+        }
+        Ok(order)
+    }
+
+    /// Downloads the issued certificate chain, selecting the preferred chain if configured.
+    async fn download_chain(
+        &self,
+        order_mgr: &OrderManager<'_>,
+        certificate_url: &str,
+    ) -> Result<String> {
+        tracing::info!("Downloading certificate from: {}", certificate_url);
+        match &self.config.preferred_chain {
+            Some(preferred) => {
+                let mut chains = order_mgr
+                    .download_certificate_chains(certificate_url)
+                    .await?;
+                let index = crate::certificate::select_preferred_chain(&chains, preferred);
+                tracing::info!(
+                    "Selected certificate chain {} of {} (preferred issuer: {})",
+                    index + 1,
+                    chains.len(),
+                    preferred
+                );
+                Ok(chains.swap_remove(index))
+            }
+            None => order_mgr.download_certificate(certificate_url).await,
+        }
+    }
+
+    /// Validates the authorizations of an order, up to `authorization_concurrency` at a time.
+    /// Challenges are cleaned up afterwards whether validation succeeded, failed or was cancelled.
+    async fn authorize_order(
+        &self,
+        order_mgr: &OrderManager<'_>,
+        account_mgr: &AccountManager<'_>,
+        authorizations: &[String],
+        solver_registry: &ChallengeSolverRegistry,
+    ) -> Result<Vec<AuthorizationState>> {
+        let concurrency = self.config.authorization_concurrency.max(1);
+        tracing::info!(
+            "Processing {} authorizations ({} at a time)",
+            authorizations.len(),
+            concurrency
+        );
+
+        // Futures are lazy: buffer_unordered only starts `concurrency` of them at a time
+        let tasks: Vec<_> = authorizations
+            .iter()
+            .map(|auth_url| {
+                Self::authorize(
+                    &self.config,
+                    order_mgr,
+                    account_mgr,
+                    auth_url,
+                    solver_registry,
+                )
+            })
+            .collect();
+
+        let cleanup = solver_registry.cleanup_guard().await;
+        let result = stream::iter(tasks)
+            .buffer_unordered(concurrency)
+            .try_collect::<Vec<_>>()
+            .await;
+        cleanup.cleanup().await;
+
+        result
+    }
+
+    /// Completes a single authorization: answers a challenge and waits for the CA to validate it.
+    async fn authorize(
+        config: &AcmeConfig,
+        order_mgr: &OrderManager<'_>,
+        account_mgr: &AccountManager<'_>,
+        auth_url: &str,
+        solver_registry: &ChallengeSolverRegistry,
+    ) -> Result<AuthorizationState> {
+        let auth = order_mgr.get_authorization(auth_url).await?;
+        match auth.status.as_str() {
+            "valid" => {
+                tracing::info!(
+                    "Authorization for {} is already valid",
+                    auth.identifier.value
+                );
+                return Ok(AuthorizationState {
+                    url: auth_url.to_string(),
+                    identifier: auth.identifier.value,
+                    status: auth.status,
+                    challenge_type: None,
+                    challenge_url: None,
+                });
+            }
+            "pending" => {
+                tracing::info!("Processing authorization for: {:?}", auth.identifier);
+            }
+            status => {
+                return Err(crate::error::AcmeError::order(
+                    status.to_string(),
+                    format!("Authorization for {} is {}", auth.identifier.value, status),
+                ));
+            }
+        }
+
+        // Find a challenge we have a solver for
+        let (challenge, solver) = auth
+            .challenges
+            .iter()
+            .find_map(|c| {
+                let challenge_type = c.challenge_type.parse::<ChallengeType>().ok()?;
+                Some((c, solver_registry.get(challenge_type)?))
+            })
+            .ok_or_else(|| {
+                crate::error::AcmeError::challenge(
+                    "unknown".to_string(),
+                    format!(
+                        "No suitable challenge solver found for {}",
+                        auth.identifier.value
+                    ),
+                )
+            })?;
+
+        // Compute key authorization
+        let key_auth = account_mgr.compute_key_authorization(&challenge.token)?;
+
+        // Prepare challenge
+        tracing::debug!("Preparing challenge: {}", challenge.challenge_type);
+        solver
+            .prepare(challenge, &auth.identifier, &key_auth)
+            .await?;
+
+        // Present challenge
+        tracing::debug!("Presenting challenge: {}", challenge.challenge_type);
+        solver.present().await?;
+
+        // Respond to ACME server
+        tracing::debug!("Responding to challenge at URL: {}", challenge.url);
+        let response = order_mgr.respond_to_challenge(&challenge.url).await?;
+
+        // Wait for the CA to validate the challenge
+        if let Some(delay) = response.retry_after {
+            tokio::time::sleep(delay.min(config.authorization_timeout)).await;
+        }
+        let auth = order_mgr
+            .poll_authorization(auth_url, config.authorization_timeout, config.poll_interval)
+            .await?;
+        if auth.status != "valid" {
+            let reason = auth
+                .challenges
+                .iter()
+                .find(|c| c.url == challenge.url)
+                .and_then(|c| c.error.clone())
+                .map(
+                    |error| match serde_json::from_value::<AcmeErrorDetail>(error.clone()) {
+                        Ok(detail) => detail.to_string(),
+                        Err(_) => error.to_string(),
+                    },
+                )
+                .unwrap_or_else(|| format!("authorization is {}", auth.status));
+            tracing::error!(
+                "Validation failed for {}: {}",
+                auth.identifier.value,
+                reason
+            );
+            return Err(crate::error::AcmeError::challenge(
+                challenge.challenge_type.clone(),
+                format!(
+                    "Validation failed for {}: {}",
+                    auth.identifier.value, reason
+                ),
+            ));
+        }
+
+        tracing::info!("Challenge completed for: {:?}", auth.identifier);
+        Ok(AuthorizationState {
+            url: auth_url.to_string(),
+            identifier: auth.identifier.value,
+            status: auth.status,
+            challenge_type: Some(challenge.challenge_type.clone()),
+            challenge_url: Some(challenge.url.clone()),
+        })
+    }
+
+    /// Loads the stored order for `domains` and returns it if it can still be completed.
+    async fn resume_order(
+        &self,
+        order_mgr: &OrderManager<'_>,
+        domains: &[String],
+        account_url: &str,
+    ) -> Option<(String, crate::order::Order, PendingOrder)> {
+        let store = self.order_store.as_ref()?;
+        let state = match store.load(domains).await {
+            Ok(state) => state?,
+            Err(e) => {
+                tracing::warn!("Failed to load stored order for {:?}: {}", domains, e);
+                return None;
+            }
+        };
+        if !state.belongs_to(&self.config.directory_url, account_url) {
+            tracing::info!(
+                "Stored order {} belongs to another account or CA, not resuming",
+                state.order_url
+            );
+            return None;
+        }
+
+        let order = match order_mgr.get_order(&state.order_url).await {
+            Ok(order) => order,
+            Err(e) => {
+                tracing::warn!("Cannot resume order {}: {}", state.order_url, e);
+                return None;
+            }
+        };
+        // Once finalized, the certificate can only be used with the stored key
+        let resumable = match order.status.as_str() {
+            "pending" | "ready" => true,
+            "processing" | "valid" => state.certificate_key_pem.is_some(),
+            _ => false,
+        };
+        if !resumable {
+            tracing::info!(
+                "Stored order {} is {}, creating a new order",
+                state.order_url,
+                order.status
+            );
+            return None;
+        }
+
+        tracing::info!("Resuming order {} ({})", state.order_url, order.status);
+        Some((state.order_url.clone(), order, state))
+    }
+
+    /// Persists the state of an in-flight order, if order storage is configured.
+    /// Failures are logged: losing the state only costs a new order after a restart.
+    async fn save_pending_order(&self, state: &PendingOrder) {
+        if let Some(store) = &self.order_store
+            && let Err(e) = store.save(state).await
+        {
+            tracing::warn!("Failed to persist order {}: {}", state.order_url, e);
+        }
+    }
+
     /// Enables and initializes a nonce pool for better performance.
-    /// This pre-fetches nonces to minimize round-trips during ACME operations.
+    /// This pre-fetches nonces into the session so signed requests rarely wait for a fresh one.
     pub async fn enable_nonce_pool(&mut self, min_size: usize, max_size: usize) -> Result<()> {
         tracing::info!("Enabling nonce pool (min: {}, max: {})", min_size, max_size);
-        let dir_mgr = DirectoryManager::new(&self.config.directory_url, self.http_client.clone());
-        let directory = dir_mgr.get().await?;
-        let nonce_manager = NonceManager::new(&directory.new_nonce, self.http_client.clone());
-        let pool = NoncePool::new(nonce_manager, min_size, max_size);
+        let session = self.managers().await?;
+        let pool = NoncePool::new(session.nonces.clone(), min_size, max_size);
         pool.refill().await?;
-        self.nonce_pool = Some(Arc::new(pool));
+        if let Ok(mut nonce_pool) = self.session.nonce_pool.write() {
+            *nonce_pool = Some(Arc::new(pool));
+        }
         Ok(())
     }
 
-    /// Internal helper to get a nonce, either from the pool or directly from the server.
-    #[allow(dead_code)]
-    async fn get_nonce(&self) -> Result<String> {
-        if let Some(pool) = &self.nonce_pool {
-            pool.get_nonce().await
-        } else {
-            let dir_mgr =
-                DirectoryManager::new(&self.config.directory_url, self.http_client.clone());
-            let directory = dir_mgr.get().await?;
-            let nonce_manager = NonceManager::new(&directory.new_nonce, self.http_client.clone());
-            nonce_manager.get_nonce().await
+    /// Returns the session managers, fetching the directory on first use.
+    /// Tops up the nonce pool in the background when it is enabled.
+    async fn managers(&self) -> Result<&SessionManagers> {
+        let managers = self
+            .session
+            .managers
+            .get_or_try_init(|| async {
+                let directory =
+                    DirectoryManager::new(&self.config.directory_url, self.http_client.clone());
+                let new_nonce = directory.get().await?.new_nonce;
+                tracing::debug!("Started ACME session for {}", self.config.directory_url);
+                Ok::<_, crate::error::AcmeError>(SessionManagers {
+                    directory,
+                    nonces: NonceManager::new(new_nonce, self.http_client.clone()),
+                })
+            })
+            .await?;
+
+        let pool = self
+            .session
+            .nonce_pool
+            .read()
+            .ok()
+            .and_then(|pool| pool.clone());
+        if let Some(pool) = pool {
+            pool.maintain().await;
+        }
+
+        Ok(managers)
+    }
+
+    /// Returns the URL of the session's account, registering it on first use.
+    async fn ensure_account(&mut self) -> Result<String> {
+        match self.session.account_url.get() {
+            Some(url) => Ok(url.clone()),
+            None => self.register_account().await,
         }
     }
 
+    /// Returns the ACME directory, fetched once per session.
+    pub async fn directory(&self) -> Result<Directory> {
+        self.managers().await?.directory.get().await
+    }
+
+    /// Returns a mutable reference to the client configuration.
+    /// This starts a new session, as the changes may point the client at another server.
+    pub fn config_mut(&mut self) -> &mut AcmeConfig {
+        self.session = Arc::default();
+        &mut self.config
+    }
+
+    /// Sets the certificate options of later orders, keeping the session.
+    pub fn set_certificate_options(&mut self, options: CertificateOptions) {
+        self.config.certificate = options;
+    }
+
     /// Returns the registered account ID, if any.
     pub fn account_id(&self) -> Option<&str> {
-        self.account_id.as_deref()
+        self.session.account_url.get().map(String::as_str)
     }
 
     /// Returns a reference to the account key pair.
@@ -386,6 +1291,23 @@ pub struct CertificateBundle {
     pub private_key_pem: String,
     /// The list of domains covered by this certificate.
     pub domains: Vec<String>,
+    /// Issuer common name of the topmost certificate in the chosen chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_issuer: Option<String>,
+    /// The STAR order this certificate was issued by, if any (RFC 8739).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub star: Option<StarCertificate>,
+}
+
+/// The STAR order behind a certificate bundle, whose certificates the CA renews on its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StarCertificate {
+    /// The URL of the STAR order.
+    pub order_url: String,
+    /// The rolling URL serving the order's current certificate.
+    pub certificate_url: String,
+    /// When the CA stops renewing the certificate.
+    pub end_date: jiff::Timestamp,
 }
 
 impl CertificateBundle {
@@ -405,6 +1327,23 @@ impl CertificateBundle {
     pub fn certificate_der(&self) -> Result<Vec<Vec<u8>>> {
         crate::order::parse_certificate_chain(&self.certificate_pem)
     }
+
+    /// Returns the STAR order of this bundle while the CA still renews it at `now`.
+    pub fn active_star(&self, now: jiff::Timestamp) -> Option<&StarCertificate> {
+        self.star.as_ref().filter(|star| now < star.end_date)
+    }
+}
+
+/// Returns true if the CA refused a new order because of its `replaces` field, e.g. the
+/// certificate was already replaced. Other errors, such as rate limits, are not retried.
+fn rejects_replaces(err: &crate::error::AcmeError) -> bool {
+    match err {
+        crate::error::AcmeError::Order { status, .. } => {
+            status == "alreadyReplaced" || status == "conflict"
+        }
+        crate::error::AcmeError::Rejected { kind, .. } => kind == "malformed",
+        _ => false,
+    }
 }
 
 #[cfg(test)]
@@ -427,4 +1366,49 @@ mod tests {
         let client = AcmeClient::new(config);
         assert!(client.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_issue_for_csr_rejects_mismatched_names() {
+        let (csr_der, _) = CsrGenerator::new(vec!["a.example.com".to_string()])
+            .generate()
+            .unwrap();
+        let mut client = AcmeClient::new(AcmeConfig::new("http://127.0.0.1:9/directory")).unwrap();
+        let result = client
+            .issue_for_csr(
+                vec!["b.example.com".to_string()],
+                &csr_der,
+                &ChallengeSolverRegistry::new(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::error::AcmeError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_replaces() {
+        let problem = |kind: &str| {
+            crate::error::AcmeError::from_problem(
+                serde_json::from_value(serde_json::json!({
+                    "type": format!("urn:ietf:params:acme:error:{}", kind),
+                    "detail": "rejected"
+                }))
+                .unwrap(),
+                None,
+            )
+        };
+        assert!(rejects_replaces(&problem("alreadyReplaced")));
+        assert!(rejects_replaces(&problem("conflict")));
+        assert!(rejects_replaces(&problem("malformed")));
+        assert!(!rejects_replaces(&problem("rateLimited")));
+        assert!(!rejects_replaces(&problem("rejectedIdentifier")));
+        // Only the problem type counts, not text that happens to look like one
+        assert!(!rejects_replaces(&crate::error::AcmeError::invalid_input(
+            "bad domain (malformed)"
+        )));
+        assert!(!rejects_replaces(&crate::error::AcmeError::transport(
+            "connection reset"
+        )));
+    }
 }
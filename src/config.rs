@@ -2,7 +2,9 @@ use crate::ca::{CAConfig, CertificateAuthority, Environment};
 /// Configuration management for AcmeX.
 /// This module provides comprehensive configuration support, including TOML parsing,
 /// environment variable overrides, and validation for multi-CA setups.
+use crate::crypto::KeyType;
 use crate::error::{AcmeError, Result};
+use crate::order::{CertificateOptions, CertificateSubject};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
@@ -17,6 +19,10 @@ pub struct Config {
     #[serde(default)]
     pub acme: AcmeSettings,
 
+    /// Certificate key and CSR settings.
+    #[serde(default)]
+    pub certificate: CertificateSettings,
+
     /// Storage backend settings.
     #[serde(default)]
     pub storage: StorageSettings,
@@ -44,6 +50,10 @@ pub struct Config {
     /// API server settings.
     #[serde(default)]
     pub server: Option<ServerSettings>,
+
+    /// Built-in ACME server settings; the server runs with `serve` when present.
+    #[serde(default)]
+    pub acme_server: Option<AcmeServerSettings>,
 }
 
 /// ACME protocol and Certificate Authority (CA) settings.
@@ -73,12 +83,61 @@ pub struct AcmeSettings {
     #[serde(default)]
     pub external_account_binding: Option<ExternalAccountBinding>,
 
+    /// Account key type: "ecdsa-p256" (ES256), "ecdsa-p384" (ES384), "rsa-2048" (RS256), etc.
+    #[serde(default = "default_account_key_type")]
+    pub account_key_type: String,
+
+    /// Default certificate profile requested for new orders (e.g. "classic", "shortlived").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Per-certificate profile overrides, keyed by the certificate's primary domain.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub certificate_profiles: HashMap<String, String>,
+
+    /// Issuer common name of the preferred root chain (e.g. "ISRG Root X1").
+    /// Falls back to the CA's default chain when no alternate chain matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_chain: Option<String>,
+
+    /// Interval in seconds between polls when the CA sends no `Retry-After`.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_secs: u64,
+
+    /// Seconds allowed for authorizations to be validated and the order to become ready.
+    #[serde(default = "default_authorization_timeout")]
+    pub authorization_timeout_secs: u64,
+
+    /// Seconds allowed for the CA to issue the certificate after finalization.
+    #[serde(default = "default_finalization_timeout")]
+    pub finalization_timeout_secs: u64,
+
     /// Internal cache for the resolved directory URL.
     #[serde(skip)]
     pub directory: String,
 }
 
 impl AcmeSettings {
+    /// Parses the configured account key type.
+    pub fn key_type(&self) -> Result<KeyType> {
+        KeyType::from_str(&self.account_key_type).map_err(|_| {
+            AcmeError::configuration(format!(
+                "Invalid account key type: {}",
+                self.account_key_type
+            ))
+        })
+    }
+
+    /// Returns the certificate profile to request for a certificate covering `domains`.
+    /// A per-certificate override for the primary (first) domain wins over the default.
+    pub fn profile_for(&self, domains: &[String]) -> Option<String> {
+        domains
+            .first()
+            .and_then(|primary| self.certificate_profiles.get(primary))
+            .or(self.profile.as_ref())
+            .cloned()
+    }
+
     /// Converts the settings into a `CAConfig` for endpoint resolution.
     pub fn to_ca_config(&self) -> Result<CAConfig> {
         let ca_type = match self.ca.to_lowercase().as_str() {
@@ -132,6 +191,53 @@ impl AcmeSettings {
     }
 }
 
+/// Certificate key and CSR settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateSettings {
+    /// Certificate key type: "ecdsa-p256", "ecdsa-p384", "ed25519", "rsa-2048", "rsa-3072", "rsa-4096".
+    #[serde(default = "default_certificate_key_type")]
+    pub key_type: String,
+
+    /// Whether to request the OCSP Must-Staple TLS feature.
+    #[serde(default)]
+    pub must_staple: bool,
+
+    /// Whether renewals keep the private key of the certificate they replace.
+    #[serde(default)]
+    pub reuse_key: bool,
+
+    /// Subject fields added to certificate requests.
+    #[serde(default, skip_serializing_if = "CertificateSubject::is_empty")]
+    pub subject: CertificateSubject,
+
+    /// Per-certificate overrides, keyed by the certificate's primary domain.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub overrides: HashMap<String, CertificateSettings>,
+}
+
+impl CertificateSettings {
+    /// Returns the certificate options for a certificate covering `domains`.
+    /// An override for the primary (first) domain replaces the defaults entirely.
+    pub fn options_for(&self, domains: &[String]) -> Result<CertificateOptions> {
+        let settings = domains
+            .first()
+            .and_then(|primary| self.overrides.get(primary))
+            .unwrap_or(self);
+        let key_type = KeyType::from_str(&settings.key_type).map_err(|_| {
+            AcmeError::configuration(format!(
+                "Invalid certificate key type: {}",
+                settings.key_type
+            ))
+        })?;
+
+        Ok(CertificateOptions::default()
+            .with_key_type(key_type)
+            .with_must_staple(settings.must_staple)
+            .with_subject(settings.subject.clone())
+            .with_reuse_key(settings.reuse_key))
+    }
+}
+
 /// External account binding configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExternalAccountBinding {
@@ -192,6 +298,37 @@ pub struct EncryptedStorageConfig {
     pub key_format: String,
 }
 
+impl EncryptedStorageConfig {
+    /// Decodes the 32-byte encryption key, expanding `${VAR}` references first.
+    pub fn key(&self) -> Result<[u8; 32]> {
+        use base64::Engine;
+
+        let encoded = Config::expand_env_var(&self.encryption_key)?;
+        let bytes = match self.key_format.as_str() {
+            "hex" => hex::decode(encoded.trim()).map_err(|e| {
+                AcmeError::configuration(format!("Invalid hex encryption key: {}", e))
+            })?,
+            "base64" => base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| {
+                    AcmeError::configuration(format!("Invalid base64 encryption key: {}", e))
+                })?,
+            other => {
+                return Err(AcmeError::configuration(format!(
+                    "Unknown encryption key format '{}', expected 'hex' or 'base64'",
+                    other
+                )));
+            }
+        };
+        bytes.try_into().map_err(|bytes: Vec<u8>| {
+            AcmeError::configuration(format!(
+                "Encryption key must be 32 bytes, got {}",
+                bytes.len()
+            ))
+        })
+    }
+}
+
 /// Challenge configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChallengeSettings {
@@ -207,6 +344,9 @@ pub struct ChallengeSettings {
     /// TLS-ALPN-01 challenge configuration.
     #[serde(default)]
     pub tls_alpn: Option<TlsAlpnConfig>,
+    /// Maximum number of authorizations validated at the same time.
+    #[serde(default = "default_authorization_concurrency")]
+    pub authorization_concurrency: usize,
 }
 
 /// HTTP-01 challenge configuration.
@@ -229,22 +369,34 @@ pub struct Dns01Config {
     pub provider: Option<String>,
     /// API token/key.
     pub api_token: Option<String>,
-    /// Zone ID or domain.
+    /// Zone ID or domain; discovered per record by providers that can list their zones.
     pub zone_id: Option<String>,
+    /// Provider-specific credentials and settings of the primary provider.
+    #[serde(default)]
+    pub extra: HashMap<String, String>,
     /// Multiple provider configurations.
     #[serde(default)]
     pub providers: Vec<DnsProviderConfig>,
-    /// DNS propagation timeout in seconds.
+    /// How long to wait, in seconds, for all authoritative nameservers to serve the
+    /// challenge records before responding to the challenge; 0 disables the check.
     #[serde(default = "default_dns_timeout")]
     pub propagation_timeout_secs: u64,
+    /// Follow CNAMEs at `_acme-challenge.<domain>` and write the TXT record at their
+    /// target, for challenges delegated to another zone (e.g. an acme-dns server).
+    #[serde(default)]
+    pub follow_cname: bool,
 }
 
 /// DNS provider configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DnsProviderConfig {
+    /// Provider name, e.g. "cloudflare" or "route53".
     pub name: String,
+    /// API token/key.
     pub api_token: Option<String>,
+    /// Zone ID or domain; discovered per record by providers that can list their zones.
     pub zone_id: Option<String>,
+    /// Provider-specific credentials and settings, e.g. `client_secret` for Azure.
     #[serde(default)]
     pub extra: HashMap<String, String>,
 }
@@ -364,6 +516,132 @@ pub struct ServerSettings {
     pub enable_webhook: bool,
 }
 
+/// Built-in ACME server settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcmeServerSettings {
+    /// Address the ACME server listens on.
+    #[serde(default = "default_acme_server_listen")]
+    pub listen_addr: String,
+    /// Externally reachable base URL; defaults to `http://{listen_addr}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Name of the local CA.
+    #[serde(default = "default_acme_server_ca_name")]
+    pub ca_name: String,
+    /// Lifetime of issued certificates in days.
+    #[serde(default = "default_acme_server_validity_days")]
+    pub certificate_validity_days: u64,
+    /// Port queried for http-01 validation.
+    #[serde(default = "default_http01_port")]
+    pub http01_port: u16,
+    /// Port connected to for tls-alpn-01 validation.
+    #[serde(default = "default_tls_alpn01_port")]
+    pub tls_alpn01_port: u16,
+    /// Relay mode: fulfil orders at the `[acme]` CA instead of the local CA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relay: Option<AcmeRelaySettings>,
+}
+
+/// Relay mode settings of the built-in ACME server.
+/// Upstream orders use the `[acme]` account and the `[challenge]` solvers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AcmeRelaySettings {
+    /// EAB credentials handed out to internal clients; when set, accounts must be bound.
+    #[serde(default)]
+    pub eab_keys: Vec<ExternalAccountBinding>,
+    /// Domains (including their subdomains) and IP addresses internal clients may order.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+}
+
+impl Dns01Config {
+    /// Returns the configuration of the provider answering DNS-01 challenges:
+    /// the top-level `provider`, or else the first entry of `providers`.
+    pub fn primary_provider(&self) -> Result<DnsProviderConfig> {
+        if let Some(ref name) = self.provider {
+            return Ok(DnsProviderConfig {
+                name: name.clone(),
+                api_token: self.api_token.clone(),
+                zone_id: self.zone_id.clone(),
+                extra: self.extra.clone(),
+            });
+        }
+        self.providers
+            .first()
+            .cloned()
+            .ok_or_else(|| AcmeError::configuration("DNS-01 configuration names no DNS provider"))
+    }
+}
+
+impl DnsProviderConfig {
+    /// Returns a non-empty credential: `api_token` and `zone_id` come from their
+    /// fields, everything else from `extra`.
+    pub fn credential(&self, key: &str) -> Option<&str> {
+        let value = match key {
+            "api_token" => self.api_token.as_deref(),
+            "zone_id" => self.zone_id.as_deref(),
+            _ => self.extra.get(key).map(String::as_str),
+        };
+        value.map(str::trim).filter(|v| !v.is_empty())
+    }
+
+    /// Returns a credential the provider cannot work without.
+    pub fn require_credential(&self, key: &str) -> Result<&str> {
+        self.credential(key).ok_or_else(|| {
+            AcmeError::configuration(format!(
+                "DNS provider '{}' requires '{}' to be configured",
+                self.name, key
+            ))
+        })
+    }
+}
+
+impl AcmeRelaySettings {
+    /// Builds the relay policy.
+    pub fn policy(&self) -> Result<crate::server::acme::RelayPolicy> {
+        let mut policy = crate::server::acme::RelayPolicy::new();
+        for eab in &self.eab_keys {
+            policy = policy.with_eab_key(eab.try_into()?);
+        }
+        for domain in &self.allowed_domains {
+            policy = policy.with_allowed_domain(domain.clone());
+        }
+        Ok(policy)
+    }
+}
+
+impl AcmeServerSettings {
+    /// Returns the listen address.
+    pub fn listen_addr(&self) -> Result<std::net::SocketAddr> {
+        self.listen_addr.parse().map_err(|e| {
+            AcmeError::configuration(format!(
+                "Invalid ACME server listen address {}: {}",
+                self.listen_addr, e
+            ))
+        })
+    }
+
+    /// Builds the ACME server configuration.
+    pub fn server_config(&self) -> crate::server::AcmeServerConfig {
+        let base_url = self
+            .base_url
+            .clone()
+            .unwrap_or_else(|| format!("http://{}", self.listen_addr));
+        crate::server::AcmeServerConfig::new(base_url)
+            .with_ca_name(self.ca_name.clone())
+            .with_certificate_validity(Duration::from_secs(
+                self.certificate_validity_days * 24 * 3600,
+            ))
+    }
+
+    /// Builds the challenge verifier for the configured validation ports.
+    pub fn verifier(&self) -> crate::server::acme::NetworkVerifier {
+        crate::server::acme::NetworkVerifier::new()
+            .with_http01_port(self.http01_port)
+            .with_tls_alpn01_port(self.tls_alpn01_port)
+    }
+}
+
 // Default value functions
 fn default_ca() -> String {
     "letsencrypt".to_string()
@@ -371,6 +649,12 @@ fn default_ca() -> String {
 fn default_ca_env() -> String {
     "production".to_string()
 }
+fn default_account_key_type() -> String {
+    "ecdsa-p256".to_string()
+}
+fn default_certificate_key_type() -> String {
+    "ecdsa-p256".to_string()
+}
 fn default_true() -> bool {
     true
 }
@@ -416,6 +700,18 @@ fn default_retry_delay() -> u64 {
 fn default_concurrency() -> u32 {
     5
 }
+fn default_authorization_concurrency() -> usize {
+    crate::client::DEFAULT_AUTHORIZATION_CONCURRENCY
+}
+fn default_poll_interval() -> u64 {
+    crate::client::DEFAULT_POLL_INTERVAL.as_secs()
+}
+fn default_authorization_timeout() -> u64 {
+    crate::client::DEFAULT_AUTHORIZATION_TIMEOUT.as_secs()
+}
+fn default_finalization_timeout() -> u64 {
+    crate::client::DEFAULT_FINALIZATION_TIMEOUT.as_secs()
+}
 fn default_metrics_listen() -> String {
     "127.0.0.1:9090".to_string()
 }
@@ -446,6 +742,21 @@ fn default_log_backup_count() -> u32 {
 fn default_server_listen() -> String {
     "127.0.0.1:8080".to_string()
 }
+fn default_acme_server_listen() -> String {
+    "0.0.0.0:14000".to_string()
+}
+fn default_acme_server_ca_name() -> String {
+    crate::server::acme::DEFAULT_CA_NAME.to_string()
+}
+fn default_acme_server_validity_days() -> u64 {
+    90
+}
+fn default_http01_port() -> u16 {
+    80
+}
+fn default_tls_alpn01_port() -> u16 {
+    443
+}
 
 impl Default for AcmeSettings {
     fn default() -> Self {
@@ -456,11 +767,30 @@ impl Default for AcmeSettings {
             contact: Vec::new(),
             tos_agreed: true,
             external_account_binding: None,
+            account_key_type: default_account_key_type(),
+            profile: None,
+            certificate_profiles: HashMap::new(),
+            preferred_chain: None,
+            poll_interval_secs: default_poll_interval(),
+            authorization_timeout_secs: default_authorization_timeout(),
+            finalization_timeout_secs: default_finalization_timeout(),
             directory: String::new(),
         }
     }
 }
 
+impl Default for CertificateSettings {
+    fn default() -> Self {
+        Self {
+            key_type: default_certificate_key_type(),
+            must_staple: false,
+            reuse_key: false,
+            subject: CertificateSubject::default(),
+            overrides: HashMap::new(),
+        }
+    }
+}
+
 impl Default for StorageSettings {
     fn default() -> Self {
         Self {
@@ -481,6 +811,21 @@ impl Default for ChallengeSettings {
             http01: None,
             dns01: None,
             tls_alpn: None,
+            authorization_concurrency: default_authorization_concurrency(),
+        }
+    }
+}
+
+impl Default for Dns01Config {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            api_token: None,
+            zone_id: None,
+            extra: HashMap::new(),
+            providers: Vec::new(),
+            propagation_timeout_secs: default_dns_timeout(),
+            follow_cname: false,
         }
     }
 }
@@ -531,6 +876,20 @@ impl Default for ServerSettings {
     }
 }
 
+impl Default for AcmeServerSettings {
+    fn default() -> Self {
+        Self {
+            listen_addr: default_acme_server_listen(),
+            base_url: None,
+            ca_name: default_acme_server_ca_name(),
+            certificate_validity_days: default_acme_server_validity_days(),
+            http01_port: default_http01_port(),
+            tls_alpn01_port: default_tls_alpn01_port(),
+            relay: None,
+        }
+    }
+}
+
 impl Config {
     /// Creates a new configuration with default values.
     pub fn new() -> Self {
@@ -660,6 +1019,13 @@ impl Config {
             ));
         }
 
+        self.acme.key_type()?;
+        self.certificate.options_for(&[])?;
+        for primary in self.certificate.overrides.keys() {
+            self.certificate
+                .options_for(std::slice::from_ref(primary))?;
+        }
+
         match self.storage.backend.as_str() {
             "file" => {
                 if let Some(ref file_config) = self.storage.file
@@ -677,6 +1043,12 @@ impl Config {
                     return Err(AcmeError::configuration("Redis URL cannot be empty"));
                 }
             }
+            "encrypted" => {
+                let encrypted = self.storage.encrypted.as_ref().ok_or_else(|| {
+                    AcmeError::configuration("Encrypted storage requires [storage.encrypted]")
+                })?;
+                encrypted.key()?;
+            }
             _ => {}
         }
 
@@ -720,6 +1092,41 @@ mod tests {
         assert_eq!(config.storage.backend, "file");
     }
 
+    #[test]
+    fn test_dns_primary_provider() {
+        let config: Config = r#"
+[challenge]
+challenge_type = "dns-01"
+
+[challenge.dns01]
+provider = "azure"
+zone_id = " "
+
+[challenge.dns01.extra]
+client_secret = "secret"
+"#
+        .parse()
+        .unwrap();
+        let dns = config.challenge.dns01.unwrap();
+        let primary = dns.primary_provider().unwrap();
+        assert_eq!(primary.name, "azure");
+        assert_eq!(primary.credential("client_secret"), Some("secret"));
+        assert_eq!(primary.credential("zone_id"), None);
+        assert!(primary.require_credential("tenant_id").is_err());
+
+        let fallback = Dns01Config {
+            providers: vec![DnsProviderConfig {
+                name: "cloudflare".to_string(),
+                api_token: Some("token".to_string()),
+                zone_id: None,
+                extra: HashMap::new(),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(fallback.primary_provider().unwrap().name, "cloudflare");
+        assert!(Dns01Config::default().primary_provider().is_err());
+    }
+
     #[test]
     fn test_ca_resolution() {
         let toml = r#"
@@ -733,4 +1140,95 @@ ca_environment = "staging"
             "https://acme-staging-v02.api.letsencrypt.org/directory"
         );
     }
+
+    #[test]
+    fn test_certificate_profiles() {
+        let toml = r#"
+[acme]
+ca = "letsencrypt"
+profile = "classic"
+
+preferred_chain = "ISRG Root X1"
+
+[acme.certificate_profiles]
+"short.example.com" = "shortlived"
+"#;
+        let config = Config::from_str(toml).unwrap();
+        let profile = |domain: &str| config.acme.profile_for(&[domain.to_string()]);
+        assert_eq!(profile("short.example.com").as_deref(), Some("shortlived"));
+        assert_eq!(profile("www.example.com").as_deref(), Some("classic"));
+        assert_eq!(Config::default().acme.profile_for(&[]), None);
+        assert_eq!(config.acme.preferred_chain.as_deref(), Some("ISRG Root X1"));
+    }
+
+    #[test]
+    fn test_certificate_settings() {
+        let toml = r#"
+[acme]
+ca = "letsencrypt"
+
+[certificate]
+key_type = "rsa-3072"
+must_staple = true
+
+[certificate.subject]
+organization = "Example Corp"
+
+[certificate.overrides."edge.example.com"]
+key_type = "ecdsa-p384"
+reuse_key = true
+"#;
+        let config = Config::from_str(toml).unwrap();
+        config.validate().unwrap();
+
+        let options = config
+            .certificate
+            .options_for(&["www.example.com".to_string()])
+            .unwrap();
+        assert_eq!(options.key_type, KeyType::Rsa3072);
+        assert!(options.must_staple);
+        assert!(!options.reuse_key);
+        assert_eq!(
+            options.subject.organization.as_deref(),
+            Some("Example Corp")
+        );
+
+        let edge = config
+            .certificate
+            .options_for(&["edge.example.com".to_string()])
+            .unwrap();
+        assert_eq!(edge.key_type, KeyType::EcdsaP384);
+        assert!(!edge.must_staple);
+        assert!(edge.reuse_key);
+
+        let mut invalid = Config::default();
+        invalid.certificate.key_type = "dsa".to_string();
+        assert!(invalid.certificate.options_for(&[]).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_storage_key() {
+        let toml = r#"
+[acme]
+ca = "letsencrypt"
+
+[storage]
+backend = "encrypted"
+
+[storage.encrypted]
+inner_backend = "file"
+encryption_key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+"#;
+        let mut config = Config::from_str(toml).unwrap();
+        config.validate().unwrap();
+        let encrypted = config.storage.encrypted.as_mut().unwrap();
+        assert_eq!(encrypted.key().unwrap()[31], 0x1f);
+
+        encrypted.encryption_key = "AAEC".to_string();
+        encrypted.key_format = "base64".to_string();
+        assert!(encrypted.key().is_err());
+
+        config.storage.encrypted = None;
+        assert!(config.validate().is_err());
+    }
 }
@@ -1,4 +1,4 @@
-/// Key pair management supporting EdDSA (Ed25519) and ECDSA (P-256) keys.
+/// Key pair management supporting EdDSA (Ed25519), ECDSA (P-256/P-384/P-521) and RSA keys.
 /// This module provides utilities for generating and managing cryptographic keys
 /// used for ACME account identification and certificate signing requests.
 use crate::error::AcmeError;
@@ -17,6 +17,8 @@ pub enum KeyType {
     EcdsaP521,
     /// RSA 2048-bit.
     Rsa2048,
+    /// RSA 3072-bit.
+    Rsa3072,
     /// RSA 4096-bit.
     Rsa4096,
 }
@@ -29,7 +31,7 @@ impl KeyType {
             KeyType::EcdsaP256 => "ES256",
             KeyType::EcdsaP384 => "ES384",
             KeyType::EcdsaP521 => "ES512",
-            KeyType::Rsa2048 | KeyType::Rsa4096 => "RS256",
+            KeyType::Rsa2048 | KeyType::Rsa3072 | KeyType::Rsa4096 => "RS256",
         }
     }
 
@@ -42,6 +44,67 @@ impl KeyType {
             _ => None,
         }
     }
+
+    /// Returns the rcgen signature algorithm used to generate and sign with this key type.
+    pub fn signature_algorithm(&self) -> &'static rcgen::SignatureAlgorithm {
+        match self {
+            KeyType::Ed25519 => &rcgen::PKCS_ED25519,
+            KeyType::EcdsaP256 => &rcgen::PKCS_ECDSA_P256_SHA256,
+            KeyType::EcdsaP384 => &rcgen::PKCS_ECDSA_P384_SHA384,
+            KeyType::EcdsaP521 => &rcgen::PKCS_ECDSA_P521_SHA512,
+            KeyType::Rsa2048 | KeyType::Rsa3072 | KeyType::Rsa4096 => &rcgen::PKCS_RSA_SHA256,
+        }
+    }
+
+    /// Determines the key type of an existing rcgen key pair.
+    /// RSA keys are classified by their modulus size.
+    pub fn from_key_pair(key_pair: &rcgen::KeyPair) -> Result<Self> {
+        let alg = key_pair.algorithm();
+        if alg == &rcgen::PKCS_ED25519 {
+            Ok(KeyType::Ed25519)
+        } else if alg == &rcgen::PKCS_ECDSA_P256_SHA256 {
+            Ok(KeyType::EcdsaP256)
+        } else if alg == &rcgen::PKCS_ECDSA_P384_SHA384 {
+            Ok(KeyType::EcdsaP384)
+        } else if alg == &rcgen::PKCS_ECDSA_P521_SHA512
+            || alg == &rcgen::PKCS_ECDSA_P521_SHA384
+            || alg == &rcgen::PKCS_ECDSA_P521_SHA256
+        {
+            Ok(KeyType::EcdsaP521)
+        } else if alg == &rcgen::PKCS_RSA_SHA256
+            || alg == &rcgen::PKCS_RSA_SHA384
+            || alg == &rcgen::PKCS_RSA_SHA512
+        {
+            // PKCS#1 RSAPublicKey: SEQUENCE { INTEGER n, INTEGER e }, length ~ modulus size
+            match key_pair.public_key_raw().len() {
+                len if len > 460 => Ok(KeyType::Rsa4096),
+                len if len > 330 => Ok(KeyType::Rsa3072),
+                _ => Ok(KeyType::Rsa2048),
+            }
+        } else {
+            Err(AcmeError::crypto(format!(
+                "Unsupported key algorithm: {:?}",
+                alg
+            )))
+        }
+    }
+}
+
+impl std::str::FromStr for KeyType {
+    type Err = AcmeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "ed25519" | "eddsa" => Ok(KeyType::Ed25519),
+            "ecdsa-p256" | "p256" | "p-256" | "ec256" | "es256" => Ok(KeyType::EcdsaP256),
+            "ecdsa-p384" | "p384" | "p-384" | "ec384" | "es384" => Ok(KeyType::EcdsaP384),
+            "ecdsa-p521" | "p521" | "p-521" | "ec521" | "es512" => Ok(KeyType::EcdsaP521),
+            "rsa-2048" | "rsa2048" | "rsa" | "rs256" => Ok(KeyType::Rsa2048),
+            "rsa-3072" | "rsa3072" => Ok(KeyType::Rsa3072),
+            "rsa-4096" | "rsa4096" => Ok(KeyType::Rsa4096),
+            _ => Err(AcmeError::invalid_input(format!("Unknown key type: {}", s))),
+        }
+    }
 }
 
 impl std::fmt::Display for KeyType {
@@ -52,6 +115,7 @@ impl std::fmt::Display for KeyType {
             KeyType::EcdsaP384 => write!(f, "ECDSA-P384"),
             KeyType::EcdsaP521 => write!(f, "ECDSA-P521"),
             KeyType::Rsa2048 => write!(f, "RSA-2048"),
+            KeyType::Rsa3072 => write!(f, "RSA-3072"),
             KeyType::Rsa4096 => write!(f, "RSA-4096"),
         }
     }
@@ -114,22 +178,22 @@ impl KeyPairGenerator {
     /// Generates a new key pair based on the configured key type.
     pub fn generate(&self) -> Result<rcgen::KeyPair> {
         tracing::info!("Generating new {} key pair", self.key_type);
-        match self.key_type {
-            KeyType::Ed25519 => rcgen::KeyPair::generate().map_err(|e| {
-                tracing::error!("Failed to generate Ed25519 key: {}", e);
-                AcmeError::crypto(format!("Failed to generate Ed25519 key: {}", e))
-            }),
-            _ => {
-                tracing::error!(
-                    "Key generation for {} is not yet implemented",
-                    self.key_type
-                );
-                Err(AcmeError::crypto(format!(
-                    "Key type {} generation not yet implemented",
-                    self.key_type
-                )))
-            }
-        }
+        let alg = self.key_type.signature_algorithm();
+        let result = match self.key_type {
+            KeyType::Rsa2048 => rcgen::KeyPair::generate_rsa_for(alg, rcgen::RsaKeySize::_2048),
+            KeyType::Rsa3072 => rcgen::KeyPair::generate_rsa_for(alg, rcgen::RsaKeySize::_3072),
+            KeyType::Rsa4096 => rcgen::KeyPair::generate_rsa_for(alg, rcgen::RsaKeySize::_4096),
+            _ => rcgen::KeyPair::generate_for(alg),
+        };
+        result.map_err(|e| {
+            tracing::error!("Failed to generate {} key: {}", self.key_type, e);
+            AcmeError::crypto(format!("Failed to generate {} key: {}", self.key_type, e))
+        })
+    }
+
+    /// Returns the key type this generator produces.
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
     }
 }
 
@@ -150,4 +214,27 @@ mod tests {
         let result = generator.generate();
         assert!(result.is_ok(), "Ed25519 generation should work");
     }
+
+    #[test]
+    fn test_generate_round_trips_key_type() {
+        for key_type in [
+            KeyType::Ed25519,
+            KeyType::EcdsaP256,
+            KeyType::EcdsaP384,
+            KeyType::Rsa2048,
+            KeyType::Rsa3072,
+        ] {
+            let key = KeyPairGenerator::new(key_type).generate().unwrap();
+            assert_eq!(KeyType::from_key_pair(&key).unwrap(), key_type);
+        }
+    }
+
+    #[test]
+    fn test_key_type_from_str() {
+        assert_eq!("es256".parse::<KeyType>().unwrap(), KeyType::EcdsaP256);
+        assert_eq!("ECDSA-P384".parse::<KeyType>().unwrap(), KeyType::EcdsaP384);
+        assert_eq!("rsa-2048".parse::<KeyType>().unwrap(), KeyType::Rsa2048);
+        assert_eq!("RSA-3072".parse::<KeyType>().unwrap(), KeyType::Rsa3072);
+        assert!("dsa".parse::<KeyType>().is_err());
+    }
 }
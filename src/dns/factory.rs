@@ -0,0 +1,331 @@
+/// DNS provider factory.
+/// Builds the `DnsProvider` named in the DNS-01 configuration. Every provider takes its
+/// credentials from `api_token` and `zone_id` where they fit, and from `extra` otherwise;
+/// providers whose feature is not enabled in this build are reported as such.
+use crate::challenge::DnsProvider;
+use crate::config::DnsProviderConfig;
+use crate::error::{AcmeError, Result};
+use std::sync::Arc;
+
+/// Provider names accepted in the configuration, with the feature each one needs.
+pub const DNS_PROVIDERS: &[(&str, &str)] = &[
+    ("acmedns", "dns-acmedns"),
+    ("alibaba", "dns-alibaba"),
+    ("azure", "dns-azure"),
+    ("cloudflare", "dns-cloudflare"),
+    ("cloudns", "dns-cloudns"),
+    ("digitalocean", "dns-digitalocean"),
+    ("godaddy", "dns-godaddy"),
+    ("google", "dns-google"),
+    ("huawei", "dns-huawei"),
+    ("linode", "dns-linode"),
+    ("rfc2136", "dns-rfc2136"),
+    ("route53", "dns-route53"),
+    ("tencent", "dns-tencent"),
+];
+
+/// Returns the names of the DNS providers enabled in this build.
+pub fn available_providers() -> Vec<&'static str> {
+    let enabled = [
+        cfg!(feature = "dns-acmedns"),
+        cfg!(feature = "dns-alibaba"),
+        cfg!(feature = "dns-azure"),
+        cfg!(feature = "dns-cloudflare"),
+        cfg!(feature = "dns-cloudns"),
+        cfg!(feature = "dns-digitalocean"),
+        cfg!(feature = "dns-godaddy"),
+        cfg!(feature = "dns-google"),
+        cfg!(feature = "dns-huawei"),
+        cfg!(feature = "dns-linode"),
+        cfg!(feature = "dns-rfc2136"),
+        cfg!(feature = "dns-route53"),
+        cfg!(feature = "dns-tencent"),
+    ];
+    DNS_PROVIDERS
+        .iter()
+        .zip(enabled)
+        .filter_map(|((name, _), enabled)| enabled.then_some(*name))
+        .collect()
+}
+
+/// Builds the DNS provider described by `config`.
+/// Zones in parentheses are optional; when left out, the zone of each record is discovered
+/// from DNS and the provider's zone list.
+///
+/// | Provider | Credentials |
+/// |----------|-------------|
+/// | `cloudflare` | `api_token`, (`zone_id`) |
+/// | `route53` | `zone_id` (hosted zone); AWS credentials from the environment |
+/// | `digitalocean` | `api_token`, (`domain` or `zone_id`) |
+/// | `linode` | `api_token`, (`domain_id` or `zone_id`) |
+/// | `azure` | `subscription_id`, `resource_group`, `client_id`, `client_secret`, `tenant_id` |
+/// | `google` | `project_id`, optional `service_account` (JSON key path) |
+/// | `alibaba` | `access_key_id`, `access_key_secret`, optional `region` |
+/// | `godaddy` | `api_key` (or `api_token`), `api_secret`, optional `production` |
+/// | `tencent` | `secret_id`, `secret_key`, optional `region` |
+/// | `huawei` | `access_key`, `secret_key`, `project_id`, `region` |
+/// | `cloudns` | `auth_id`, `auth_password` |
+/// | `acmedns` | `server_url`; `username`, `password`, `subdomain`, `fulldomain` or `storage_path`, optional `allow_from` |
+/// | `rfc2136` | `server`, `zone` (or `zone_id`), `key_name`, `key_secret`, optional `algorithm`, `ttl` |
+pub async fn build_dns_provider(config: &DnsProviderConfig) -> Result<Arc<dyn DnsProvider>> {
+    let name = config.name.trim().to_lowercase();
+    tracing::debug!("Building DNS provider: {}", name);
+
+    match name.as_str() {
+        #[cfg(feature = "dns-acmedns")]
+        "acmedns" => {
+            use super::providers::acmedns::{AcmeDnsAccount, AcmeDnsConfig};
+            let storage_path = config.credential("storage_path").map(Into::into);
+            // A configured account serves every domain; otherwise accounts are registered
+            let account = if config.credential("username").is_some() || storage_path.is_none() {
+                Some(AcmeDnsAccount {
+                    username: config.require_credential("username")?.to_string(),
+                    password: config.require_credential("password")?.to_string(),
+                    fulldomain: config.require_credential("fulldomain")?.to_string(),
+                    subdomain: config.require_credential("subdomain")?.to_string(),
+                    allowfrom: Vec::new(),
+                })
+            } else {
+                None
+            };
+            Ok(Arc::new(super::AcmeDnsProvider::new(AcmeDnsConfig {
+                server_url: config.require_credential("server_url")?.to_string(),
+                account,
+                storage_path,
+                allow_from: config
+                    .credential("allow_from")
+                    .map(|ranges| ranges.split(',').map(|r| r.trim().to_string()).collect())
+                    .unwrap_or_default(),
+            })))
+        }
+        #[cfg(feature = "dns-alibaba")]
+        "alibaba" => Ok(Arc::new(super::AlibabaCloudDnsProvider::new(
+            config.require_credential("access_key_id")?.to_string(),
+            config.require_credential("access_key_secret")?.to_string(),
+            config
+                .credential("region")
+                .unwrap_or("cn-hangzhou")
+                .to_string(),
+        ))),
+        #[cfg(feature = "dns-azure")]
+        "azure" => Ok(Arc::new(super::AzureDnsProvider::new(
+            config.require_credential("subscription_id")?.to_string(),
+            config.require_credential("resource_group")?.to_string(),
+            config.require_credential("client_id")?.to_string(),
+            config.require_credential("client_secret")?.to_string(),
+            config.require_credential("tenant_id")?.to_string(),
+        ))),
+        #[cfg(feature = "dns-cloudflare")]
+        "cloudflare" => Ok(Arc::new(super::CloudFlareDnsProvider::new(
+            super::providers::cloudflare::CloudFlareConfig {
+                api_token: config.require_credential("api_token")?.to_string(),
+                zone_id: config.credential("zone_id").map(str::to_string),
+            },
+        ))),
+        #[cfg(feature = "dns-cloudns")]
+        "cloudns" => Ok(Arc::new(super::ClouDnsProvider::new(
+            config.require_credential("auth_id")?.to_string(),
+            config.require_credential("auth_password")?.to_string(),
+        ))),
+        #[cfg(feature = "dns-digitalocean")]
+        "digitalocean" => Ok(Arc::new(super::DigitalOceanDnsProvider::new(
+            super::providers::digitalocean::DigitalOceanConfig {
+                api_token: config.require_credential("api_token")?.to_string(),
+                domain: config
+                    .credential("domain")
+                    .or_else(|| config.credential("zone_id"))
+                    .map(str::to_string),
+            },
+        ))),
+        #[cfg(feature = "dns-godaddy")]
+        "godaddy" => {
+            let provider = super::GodaddyDnsProvider::new(
+                config
+                    .credential("api_key")
+                    .map_or_else(|| config.require_credential("api_token"), Ok)?
+                    .to_string(),
+                config.require_credential("api_secret")?.to_string(),
+            );
+            // The OTE test API only affects GoDaddy's sandbox, so default to production
+            let provider = match config.credential("production") {
+                Some("false") => provider.test(),
+                _ => provider.production(),
+            };
+            Ok(Arc::new(provider))
+        }
+        #[cfg(feature = "dns-google")]
+        "google" => {
+            let provider = super::GoogleCloudDnsProvider::new(
+                config.require_credential("project_id")?.to_string(),
+            );
+            Ok(Arc::new(match config.credential("service_account") {
+                Some(path) => provider.with_service_account(path.to_string()),
+                None => provider.with_default_credentials(),
+            }))
+        }
+        #[cfg(feature = "dns-huawei")]
+        "huawei" => Ok(Arc::new(super::HuaweiCloudDnsProvider::new(
+            config.require_credential("access_key")?.to_string(),
+            config.require_credential("secret_key")?.to_string(),
+            config.require_credential("project_id")?.to_string(),
+            config.require_credential("region")?.to_string(),
+        ))),
+        #[cfg(feature = "dns-linode")]
+        "linode" => {
+            let domain_id = config
+                .credential("domain_id")
+                .or_else(|| config.credential("zone_id"))
+                .map(|domain_id| {
+                    domain_id.parse().map_err(|_| {
+                        AcmeError::configuration(format!(
+                            "Linode domain ID must be numeric, got '{}'",
+                            domain_id
+                        ))
+                    })
+                })
+                .transpose()?;
+            Ok(Arc::new(super::LinodeDnsProvider::new(
+                super::providers::linode::LinodeConfig {
+                    api_token: config.require_credential("api_token")?.to_string(),
+                    domain_id,
+                },
+            )))
+        }
+        #[cfg(feature = "dns-rfc2136")]
+        "rfc2136" => {
+            use super::providers::rfc2136::{Rfc2136Config, TsigKey};
+            let algorithm = config
+                .credential("algorithm")
+                .unwrap_or("hmac-sha256")
+                .parse()?;
+            let ttl = match config.credential("ttl") {
+                Some(ttl) => ttl.parse().map_err(|_| {
+                    AcmeError::configuration(format!("RFC 2136 TTL must be numeric, got '{}'", ttl))
+                })?,
+                None => 60,
+            };
+            Ok(Arc::new(super::Rfc2136DnsProvider::new(Rfc2136Config {
+                server: config.require_credential("server")?.to_string(),
+                zone: config
+                    .credential("zone")
+                    .map_or_else(|| config.require_credential("zone_id"), Ok)?
+                    .to_string(),
+                key: TsigKey::new(
+                    config.require_credential("key_name")?,
+                    algorithm,
+                    config.require_credential("key_secret")?,
+                )?,
+                ttl,
+            })))
+        }
+        #[cfg(feature = "dns-route53")]
+        "route53" => Ok(Arc::new(
+            super::Route53DnsProvider::new(super::providers::route53::Route53Config {
+                hosted_zone_id: config.require_credential("zone_id")?.to_string(),
+            })
+            .await,
+        )),
+        #[cfg(feature = "dns-tencent")]
+        "tencent" => Ok(Arc::new(super::TencentCloudDnsProvider::new(
+            config.require_credential("secret_id")?.to_string(),
+            config.require_credential("secret_key")?.to_string(),
+            config
+                .credential("region")
+                .unwrap_or("ap-guangzhou")
+                .to_string(),
+        ))),
+        other => Err(unavailable(other)),
+    }
+}
+
+/// Explains why a provider name could not be built.
+fn unavailable(name: &str) -> AcmeError {
+    match DNS_PROVIDERS.iter().find(|(known, _)| *known == name) {
+        Some((_, feature)) => AcmeError::configuration(format!(
+            "DNS provider '{}' is not enabled in this build; rebuild acmex with the '{}' feature",
+            name, feature
+        )),
+        None => AcmeError::configuration(format!(
+            "Unknown DNS provider '{}'; expected one of: {}",
+            name,
+            DNS_PROVIDERS
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(name: &str, extra: &[(&str, &str)]) -> DnsProviderConfig {
+        DnsProviderConfig {
+            name: name.to_string(),
+            api_token: None,
+            zone_id: None,
+            extra: extra
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_provider() {
+        let err = build_dns_provider(&config("bind9", &[]))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Unknown DNS provider 'bind9'"));
+    }
+
+    #[cfg(not(feature = "dns-azure"))]
+    #[tokio::test]
+    async fn test_disabled_provider() {
+        let err = build_dns_provider(&config("Azure", &[]))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("'dns-azure' feature"));
+        assert!(!available_providers().contains(&"azure"));
+    }
+
+    #[cfg(feature = "dns-cloudflare")]
+    #[tokio::test]
+    async fn test_cloudflare_credentials() {
+        let mut cloudflare = config("cloudflare", &[]);
+        cloudflare.zone_id = Some("zone".to_string());
+        let err = build_dns_provider(&cloudflare).await.err().unwrap();
+        assert!(err.to_string().contains("requires 'api_token'"));
+
+        // Without a zone ID, zones are discovered per record
+        cloudflare.api_token = Some("token".to_string());
+        cloudflare.zone_id = None;
+        assert!(build_dns_provider(&cloudflare).await.is_ok());
+    }
+
+    #[cfg(feature = "dns-azure")]
+    #[tokio::test]
+    async fn test_azure_credentials_from_extra() {
+        let mut azure = config(
+            "azure",
+            &[
+                ("subscription_id", "sub"),
+                ("resource_group", "rg"),
+                ("client_id", "client"),
+                ("tenant_id", "tenant"),
+            ],
+        );
+        let err = build_dns_provider(&azure).await.err().unwrap();
+        assert!(err.to_string().contains("requires 'client_secret'"));
+
+        azure
+            .extra
+            .insert("client_secret".to_string(), "secret".to_string());
+        assert!(build_dns_provider(&azure).await.is_ok());
+    }
+}
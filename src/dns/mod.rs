@@ -1,9 +1,16 @@
 /// DNS provider implementations for ACME DNS-01 challenge.
 /// This module contains various DNS provider implementations that allow
 /// automatic creation and deletion of TXT records required for domain validation.
+pub mod factory;
 pub mod providers;
+pub mod zone;
+
+pub use factory::{DNS_PROVIDERS, available_providers, build_dns_provider};
+pub use zone::{ZoneDiscovery, find_zone_apex};
 
 // Re-exports with feature gates for easier access to specific providers.
+#[cfg(feature = "dns-acmedns")]
+pub use providers::AcmeDnsProvider;
 #[cfg(feature = "dns-alibaba")]
 pub use providers::AlibabaCloudDnsProvider;
 #[cfg(feature = "dns-azure")]
@@ -22,6 +29,8 @@ pub use providers::GoogleCloudDnsProvider;
 pub use providers::HuaweiCloudDnsProvider;
 #[cfg(feature = "dns-linode")]
 pub use providers::LinodeDnsProvider;
+#[cfg(feature = "dns-rfc2136")]
+pub use providers::Rfc2136DnsProvider;
 #[cfg(feature = "dns-route53")]
 pub use providers::Route53DnsProvider;
 #[cfg(feature = "dns-tencent")]
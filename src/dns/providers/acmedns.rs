@@ -0,0 +1,375 @@
+//! acme-dns DNS Provider implementation for AcmeX
+//!
+//! This module updates TXT records through the HTTP API of an acme-dns server
+//! (https://github.com/joohoi/acme-dns). Each domain delegates its challenge with a static
+//! `_acme-challenge.<domain> CNAME <subdomain>.<acme-dns zone>` record, so AcmeX never needs
+//! credentials for the domain's own zone. Accounts are registered with `/register` and kept
+//! in a JSON file mapping each domain to its account.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+use crate::challenge::{CachingDnsResolver, DnsProvider};
+use crate::error::{AcmeError, Result};
+
+/// Credentials of an acme-dns account, as returned by `/register`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AcmeDnsAccount {
+    pub username: String,
+    pub password: String,
+    /// The full name of the account's TXT record, the target of the CNAME
+    pub fulldomain: String,
+    pub subdomain: String,
+    #[serde(default)]
+    pub allowfrom: Vec<String>,
+}
+
+impl std::fmt::Debug for AcmeDnsAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcmeDnsAccount")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field("fulldomain", &self.fulldomain)
+            .field("subdomain", &self.subdomain)
+            .field("allowfrom", &self.allowfrom)
+            .finish()
+    }
+}
+
+/// acme-dns DNS provider configuration
+#[derive(Debug, Clone)]
+pub struct AcmeDnsConfig {
+    /// Base URL of the acme-dns API, e.g. `https://auth.acme-dns.io`
+    pub server_url: String,
+    /// Account used for every domain, if registered outside AcmeX
+    pub account: Option<AcmeDnsAccount>,
+    /// JSON file of per-domain accounts; domains without one are registered into it.
+    /// The file holds the accounts' API passwords and is created readable only by its owner.
+    pub storage_path: Option<PathBuf>,
+    /// CIDR ranges allowed to update newly registered accounts
+    pub allow_from: Vec<String>,
+}
+
+/// acme-dns DNS provider
+pub struct AcmeDnsProvider {
+    config: AcmeDnsConfig,
+    http_client: reqwest::Client,
+    /// Accounts by domain, loaded from `storage_path` on first use
+    accounts: RwLock<Option<HashMap<String, AcmeDnsAccount>>>,
+}
+
+#[derive(Debug, Serialize)]
+struct AcmeDnsRegisterRequest<'a> {
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    allowfrom: &'a [String],
+}
+
+#[derive(Debug, Serialize)]
+struct AcmeDnsUpdateRequest<'a> {
+    subdomain: &'a str,
+    txt: &'a str,
+}
+
+impl AcmeDnsProvider {
+    pub fn new(config: AcmeDnsConfig) -> Self {
+        Self {
+            config,
+            http_client: reqwest::Client::new(),
+            accounts: RwLock::new(None),
+        }
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.server_url.trim_end_matches('/'), path)
+    }
+
+    /// Returns the accounts from the storage file, reading it on first use
+    async fn load_accounts(&self) -> Result<HashMap<String, AcmeDnsAccount>> {
+        if let Some(accounts) = self.accounts.read().await.as_ref() {
+            return Ok(accounts.clone());
+        }
+
+        let accounts = match &self.config.storage_path {
+            Some(path) => match tokio::fs::read(path).await {
+                Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                    AcmeError::configuration(format!(
+                        "Invalid acme-dns account file {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+                Err(e) => return Err(e.into()),
+            },
+            None => HashMap::new(),
+        };
+        *self.accounts.write().await = Some(accounts.clone());
+        Ok(accounts)
+    }
+
+    /// Finds the account updating `name`, which is either `_acme-challenge.<domain>` or,
+    /// when the solver followed the CNAME, the account's `fulldomain`
+    async fn account_for(&self, name: &str) -> Result<Option<AcmeDnsAccount>> {
+        let name = name.trim_end_matches('.');
+        let domain = name.strip_prefix("_acme-challenge.").unwrap_or(name);
+        let accounts = self.load_accounts().await?;
+        let stored = accounts.get(domain).or_else(|| {
+            accounts
+                .values()
+                .find(|account| account.fulldomain.eq_ignore_ascii_case(name))
+        });
+        Ok(stored.or(self.config.account.as_ref()).cloned())
+    }
+
+    /// Registers a new acme-dns account for `domain` and saves it to the storage file.
+    /// The domain must then delegate `_acme-challenge.<domain>` with a CNAME to the
+    /// returned `fulldomain`.
+    pub async fn register(&self, domain: &str) -> Result<AcmeDnsAccount> {
+        let path = self.config.storage_path.as_ref().ok_or_else(|| {
+            AcmeError::configuration("acme-dns registration requires 'storage_path'")
+        })?;
+        info!("Registering acme-dns account for {}", domain);
+
+        let response = self
+            .http_client
+            .post(self.api_url("register"))
+            .json(&AcmeDnsRegisterRequest {
+                allowfrom: &self.config.allow_from,
+            })
+            .send()
+            .await
+            .map_err(|e| AcmeError::transport(format!("acme-dns request failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AcmeError::transport(format!(
+                "acme-dns registration failed with status {}: {}",
+                status, body
+            )));
+        }
+        let account: AcmeDnsAccount = response.json().await.map_err(|e| {
+            AcmeError::transport(format!("Invalid acme-dns registration response: {}", e))
+        })?;
+
+        let mut accounts = self.load_accounts().await?;
+        accounts.insert(domain.to_string(), account.clone());
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        write_private(path, &serde_json::to_vec_pretty(&accounts)?).await?;
+        *self.accounts.write().await = Some(accounts);
+
+        Ok(account)
+    }
+}
+
+/// Writes `data` to `path`, restricting the file to its owner (mode 0600) on Unix since it
+/// holds account passwords.
+async fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    // The mode only applies on creation, so files written by older versions are fixed too
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await?;
+    }
+    file.write_all(data).await?;
+    file.flush().await
+}
+
+#[async_trait]
+impl DnsProvider for AcmeDnsProvider {
+    async fn create_txt_record(&self, domain: &str, value: &str) -> Result<String> {
+        info!("Updating acme-dns TXT record for {}", domain);
+
+        let account = match self.account_for(domain).await? {
+            Some(account) => account,
+            None if self.config.storage_path.is_some() => {
+                // A fresh account only works once the domain delegates to it
+                let name = domain.trim_end_matches('.');
+                let account = self
+                    .register(name.strip_prefix("_acme-challenge.").unwrap_or(name))
+                    .await?;
+                return Err(AcmeError::configuration(format!(
+                    "Registered a new acme-dns account; create the record '{} CNAME {}.' and retry",
+                    name, account.fulldomain
+                )));
+            }
+            None => {
+                return Err(AcmeError::configuration(format!(
+                    "No acme-dns account for {}; configure an account or 'storage_path'",
+                    domain
+                )));
+            }
+        };
+
+        let response = self
+            .http_client
+            .post(self.api_url("update"))
+            .header("X-Api-User", &account.username)
+            .header("X-Api-Key", &account.password)
+            .json(&AcmeDnsUpdateRequest {
+                subdomain: &account.subdomain,
+                txt: value,
+            })
+            .send()
+            .await
+            .map_err(|e| AcmeError::transport(format!("acme-dns request failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AcmeError::transport(format!(
+                "acme-dns update failed with status {}: {}",
+                status, body
+            )));
+        }
+
+        // The record is identified by the account's fulldomain, where it can be verified
+        Ok(account.fulldomain)
+    }
+
+    async fn delete_txt_record(&self, domain: &str, _record_id: &str) -> Result<()> {
+        // acme-dns has no delete; each subdomain keeps its two latest values
+        debug!("acme-dns keeps TXT records for {} until replaced", domain);
+        Ok(())
+    }
+
+    async fn verify_record(&self, domain: &str, value: &str) -> Result<bool> {
+        let name = match self.account_for(domain).await? {
+            Some(account) => account.fulldomain,
+            None => domain.to_string(),
+        };
+        debug!("Verifying acme-dns TXT record at {}", name);
+        let records = CachingDnsResolver::new()?.resolve_txt(&name).await?;
+        Ok(records.iter().any(|txt| txt == value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use serde_json::json;
+
+    fn account(fulldomain: &str) -> AcmeDnsAccount {
+        AcmeDnsAccount {
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            fulldomain: fulldomain.to_string(),
+            subdomain: fulldomain.split('.').next().unwrap().to_string(),
+            allowfrom: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_with_configured_account() {
+        let mut server = mockito::Server::new_async().await;
+        let update = server
+            .mock("POST", "/update")
+            .match_header("X-Api-User", "user")
+            .match_header("X-Api-Key", "secret")
+            .match_body(Matcher::Json(json!({
+                "subdomain": "d420c923",
+                "txt": "LHDhK3oGRvkiefQnx7OOczTY5Tic_xZ6HcMOc_gmtoM"
+            })))
+            .with_status(200)
+            .with_body(r#"{"txt":"LHDhK3oGRvkiefQnx7OOczTY5Tic_xZ6HcMOc_gmtoM"}"#)
+            .create_async()
+            .await;
+        let provider = AcmeDnsProvider::new(AcmeDnsConfig {
+            server_url: format!("{}/", server.url()),
+            account: Some(account("d420c923.auth.example.org")),
+            storage_path: None,
+            allow_from: Vec::new(),
+        });
+
+        // The solver passes the CNAME target when following delegation
+        let id = provider
+            .create_txt_record(
+                "d420c923.auth.example.org",
+                "LHDhK3oGRvkiefQnx7OOczTY5Tic_xZ6HcMOc_gmtoM",
+            )
+            .await
+            .unwrap();
+        assert_eq!(id, "d420c923.auth.example.org");
+        provider
+            .delete_txt_record("_acme-challenge.example.com", &id)
+            .await
+            .unwrap();
+        update.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_register_unknown_domain() {
+        let mut server = mockito::Server::new_async().await;
+        let register = server
+            .mock("POST", "/register")
+            .match_body(Matcher::Json(json!({ "allowfrom": ["192.0.2.0/24"] })))
+            .with_status(201)
+            .with_body(
+                json!({
+                    "username": "user",
+                    "password": "secret",
+                    "fulldomain": "8e5700ea.auth.example.org",
+                    "subdomain": "8e5700ea",
+                    "allowfrom": ["192.0.2.0/24"]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let update = server
+            .mock("POST", "/update")
+            .match_header("X-Api-User", "user")
+            .with_status(200)
+            .create_async()
+            .await;
+        let path =
+            std::env::temp_dir().join(format!("acmex-acmedns-{}.json", rand::random::<u64>()));
+        let config = AcmeDnsConfig {
+            server_url: server.url(),
+            account: None,
+            storage_path: Some(path.clone()),
+            allow_from: vec!["192.0.2.0/24".to_string()],
+        };
+
+        // The first attempt registers and asks for the CNAME
+        let provider = AcmeDnsProvider::new(config.clone());
+        let err = provider
+            .create_txt_record("_acme-challenge.example.com", "value")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("'_acme-challenge.example.com CNAME 8e5700ea.auth.example.org.'")
+        );
+        register.assert_async().await;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Later runs find the saved account
+        let provider = AcmeDnsProvider::new(config);
+        provider
+            .create_txt_record("_acme-challenge.example.com", "value")
+            .await
+            .unwrap();
+        update.assert_async().await;
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,8 +1,10 @@
 /// Azure DNS Provider implementation.
 /// This module provides DNS record management for Azure DNS using the Azure Resource Manager REST API.
-use crate::challenge::DnsProvider;
+use crate::challenge::{DnsProvider, DnsZone};
+use crate::dns::zone::ZoneDiscovery;
 use crate::error::{AcmeError, Result};
 use async_trait::async_trait;
+use std::sync::Arc;
 
 /// Azure DNS Provider for handling DNS-01 challenges.
 #[derive(Debug, Clone)]
@@ -19,6 +21,8 @@ pub struct AzureDnsProvider {
     tenant_id: String,
     /// Internal HTTP client.
     client: reqwest::Client,
+    /// Zones of the resource group holding the records, shared between clones.
+    zones: Arc<ZoneDiscovery>,
 }
 
 impl AzureDnsProvider {
@@ -41,6 +45,7 @@ impl AzureDnsProvider {
             client_secret,
             tenant_id,
             client: reqwest::Client::new(),
+            zones: Arc::new(ZoneDiscovery::new()),
         }
     }
 
@@ -98,14 +103,9 @@ impl AzureDnsProvider {
             .map(|s| s.to_string())
     }
 
-    /// Parses the full domain to extract the DNS zone name.
-    fn parse_zone_name(&self, domain: &str) -> String {
-        let parts: Vec<&str> = domain.split('.').collect();
-        if parts.len() > 2 {
-            parts[parts.len() - 2..].join(".")
-        } else {
-            domain.to_string()
-        }
+    /// Finds the DNS zone holding the domain among the zones of the resource group.
+    async fn zone_name(&self, domain: &str) -> Result<String> {
+        Ok(self.zones.zone_for(self, domain).await?.name)
     }
 
     /// Returns the record name relative to the DNS zone.
@@ -127,8 +127,8 @@ impl DnsProvider for AzureDnsProvider {
     async fn create_txt_record(&self, domain: &str, value: &str) -> Result<String> {
         tracing::info!("Creating TXT record in Azure DNS for domain: {}", domain);
 
+        let zone_name = self.zone_name(domain).await?;
         let token = self.get_access_token().await?;
-        let zone_name = self.parse_zone_name(domain);
         let record_name = self.get_record_name(domain, &zone_name);
 
         let api_url = format!(
@@ -179,8 +179,8 @@ impl DnsProvider for AzureDnsProvider {
     async fn delete_txt_record(&self, domain: &str, record_id: &str) -> Result<()> {
         tracing::info!("Deleting TXT record from Azure DNS for domain: {}", domain);
 
+        let zone_name = self.zone_name(domain).await?;
         let token = self.get_access_token().await?;
-        let zone_name = self.parse_zone_name(domain);
 
         let api_url = format!(
             "https://management.azure.com/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnsZones/{}/TXT/{}?api-version=2018-05-01",
@@ -216,8 +216,8 @@ impl DnsProvider for AzureDnsProvider {
     /// Verifies the existence of a TXT record in Azure DNS.
     async fn verify_record(&self, domain: &str, value: &str) -> Result<bool> {
         tracing::debug!("Verifying TXT record in Azure DNS for domain: {}", domain);
+        let zone_name = self.zone_name(domain).await?;
         let token = self.get_access_token().await?;
-        let zone_name = self.parse_zone_name(domain);
         let record_name = self.get_record_name(domain, &zone_name);
 
         let api_url = format!(
@@ -265,6 +265,55 @@ impl DnsProvider for AzureDnsProvider {
         tracing::warn!("Azure DNS record verification failed: value not found");
         Ok(false)
     }
+
+    /// Lists the DNS zones of the resource group.
+    async fn list_zones(&self) -> Result<Vec<DnsZone>> {
+        tracing::debug!("Listing Azure DNS zones in {}", self.resource_group);
+        let token = self.get_access_token().await?;
+
+        let mut zones = Vec::new();
+        let mut url = Some(format!(
+            "https://management.azure.com/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnsZones?api-version=2018-05-01",
+            self.subscription_id, self.resource_group
+        ));
+        while let Some(page) = url {
+            let response = self
+                .client
+                .get(&page)
+                .bearer_auth(&token)
+                .send()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Network error while listing Azure DNS zones: {}", e);
+                    AcmeError::transport(format!("Azure API list zones failed: {}", e))
+                })?;
+
+            let status = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            if !status.is_success() {
+                let message = body["error"]["message"].as_str().unwrap_or("Unknown error");
+                tracing::error!("Azure DNS API error while listing zones: {}", message);
+                return Err(AcmeError::protocol(format!(
+                    "Azure DNS list zones error: {}",
+                    message
+                )));
+            }
+
+            if let Some(values) = body["value"].as_array() {
+                // Zones are addressed by name within the resource group
+                zones.extend(values.iter().filter_map(|zone| {
+                    let name = zone["name"].as_str()?;
+                    Some(DnsZone {
+                        name: name.to_string(),
+                        id: name.to_string(),
+                    })
+                }));
+            }
+            url = body["nextLink"].as_str().map(str::to_string);
+        }
+
+        Ok(zones)
+    }
 }
 
 #[cfg(test)]
@@ -272,7 +321,7 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_zone_name() {
+    fn test_get_record_name() {
         let provider = AzureDnsProvider::new(
             "sub".to_string(),
             "rg".to_string(),
@@ -280,10 +329,10 @@ mod tests {
             "s".to_string(),
             "t".to_string(),
         );
-        assert_eq!(provider.parse_zone_name("example.com"), "example.com");
+        assert_eq!(provider.get_record_name("example.com", "example.com"), "@");
         assert_eq!(
-            provider.parse_zone_name("_acme-challenge.example.com"),
-            "example.com"
+            provider.get_record_name("_acme-challenge.example.co.uk", "example.co.uk"),
+            "_acme-challenge"
         );
     }
 }
@@ -3,7 +3,8 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-use crate::challenge::DnsProvider;
+use crate::challenge::{DnsProvider, DnsZone};
+use crate::dns::zone::ZoneDiscovery;
 use crate::error::{AcmeError, Result};
 
 /// Configuration for the CloudFlare DNS provider.
@@ -11,8 +12,8 @@ use crate::error::{AcmeError, Result};
 pub struct CloudFlareConfig {
     /// API token with DNS:Edit permissions.
     pub api_token: String,
-    /// The Zone ID of the domain being validated.
-    pub zone_id: String,
+    /// The Zone ID of the domain being validated; discovered per record when `None`.
+    pub zone_id: Option<String>,
 }
 
 /// CloudFlare DNS provider for handling DNS-01 challenges.
@@ -21,6 +22,8 @@ pub struct CloudFlareDnsProvider {
     config: CloudFlareConfig,
     /// Internal HTTP client.
     http_client: reqwest::Client,
+    /// Zones discovered when no Zone ID is configured.
+    zones: ZoneDiscovery,
 }
 
 impl CloudFlareDnsProvider {
@@ -28,11 +31,20 @@ impl CloudFlareDnsProvider {
     pub fn new(config: CloudFlareConfig) -> Self {
         tracing::debug!(
             "Initializing CloudFlareDnsProvider for Zone: {}",
-            config.zone_id
+            config.zone_id.as_deref().unwrap_or("<discovered>")
         );
         Self {
             config,
             http_client: reqwest::Client::new(),
+            zones: ZoneDiscovery::new(),
+        }
+    }
+
+    /// Returns the configured Zone ID, or discovers the zone holding `domain`.
+    async fn zone_id(&self, domain: &str) -> Result<String> {
+        match &self.config.zone_id {
+            Some(zone_id) => Ok(zone_id.clone()),
+            None => Ok(self.zones.zone_for(self, domain).await?.id),
         }
     }
 }
@@ -64,6 +76,31 @@ struct CloudFlareRecordResult {
     id: String,
 }
 
+/// A page of zones from the CloudFlare API.
+#[derive(Debug, Deserialize)]
+struct CloudFlareZoneListResponse {
+    /// Zones on this page.
+    result: Vec<CloudFlareZone>,
+    /// Pagination details.
+    result_info: CloudFlareResultInfo,
+}
+
+/// A zone of the CloudFlare account.
+#[derive(Debug, Deserialize)]
+struct CloudFlareZone {
+    /// The Zone ID.
+    id: String,
+    /// The zone apex, e.g. "example.com".
+    name: String,
+}
+
+/// Pagination details of a CloudFlare list response.
+#[derive(Debug, Deserialize)]
+struct CloudFlareResultInfo {
+    /// Number of pages available.
+    total_pages: u32,
+}
+
 #[async_trait]
 impl DnsProvider for CloudFlareDnsProvider {
     /// Creates a TXT record for the DNS-01 challenge.
@@ -71,7 +108,7 @@ impl DnsProvider for CloudFlareDnsProvider {
         tracing::info!("Creating CloudFlare TXT record for domain: {}", domain);
         let url = format!(
             "https://api.cloudflare.com/client/v4/zones/{}/dns_records",
-            self.config.zone_id
+            self.zone_id(domain).await?
         );
 
         let payload = CloudFlareRecordCreateRequest {
@@ -116,11 +153,12 @@ impl DnsProvider for CloudFlareDnsProvider {
     }
 
     /// Deletes the TXT record after validation is complete.
-    async fn delete_txt_record(&self, _domain: &str, record_id: &str) -> Result<()> {
+    async fn delete_txt_record(&self, domain: &str, record_id: &str) -> Result<()> {
         tracing::info!("Deleting CloudFlare TXT record ID: {}", record_id);
         let url = format!(
             "https://api.cloudflare.com/client/v4/zones/{}/dns_records/{}",
-            self.config.zone_id, record_id
+            self.zone_id(domain).await?,
+            record_id
         );
 
         let response = self
@@ -157,7 +195,8 @@ impl DnsProvider for CloudFlareDnsProvider {
         tracing::debug!("Verifying CloudFlare TXT record for domain: {}", domain);
         let url = format!(
             "https://api.cloudflare.com/client/v4/zones/{}/dns_records?type=TXT&name={}",
-            self.config.zone_id, domain
+            self.zone_id(domain).await?,
+            domain
         );
 
         let response = self
@@ -188,4 +227,48 @@ impl DnsProvider for CloudFlareDnsProvider {
         }
         Ok(verified)
     }
+
+    /// Lists the zones the API token can access.
+    async fn list_zones(&self) -> Result<Vec<DnsZone>> {
+        tracing::debug!("Listing CloudFlare zones");
+        let mut zones = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "https://api.cloudflare.com/client/v4/zones?per_page=50&page={}",
+                page
+            );
+            let response = self
+                .http_client
+                .get(url)
+                .bearer_auth(&self.config.api_token)
+                .send()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Network error while listing CloudFlare zones: {}", e);
+                    AcmeError::transport(format!("CloudFlare list zones failed: {}", e))
+                })?;
+
+            if !response.status().is_success() {
+                let text = response.text().await.unwrap_or_default();
+                tracing::error!("CloudFlare API error while listing zones: {}", text);
+                return Err(AcmeError::protocol(format!(
+                    "CloudFlare list zones failed: {}",
+                    text
+                )));
+            }
+
+            let body: CloudFlareZoneListResponse = response.json().await.map_err(|e| {
+                AcmeError::protocol(format!("CloudFlare parse zones failed: {}", e))
+            })?;
+            zones.extend(body.result.into_iter().map(|zone| DnsZone {
+                name: zone.name,
+                id: zone.id,
+            }));
+            if page >= body.result_info.total_pages {
+                return Ok(zones);
+            }
+            page += 1;
+        }
+    }
 }
@@ -7,20 +7,23 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info};
 
-use crate::challenge::DnsProvider;
+use crate::challenge::{DnsProvider, DnsZone};
+use crate::dns::zone::ZoneDiscovery;
 use crate::error::{AcmeError, Result};
 
 /// DigitalOcean DNS provider configuration
 #[derive(Debug, Clone)]
 pub struct DigitalOceanConfig {
     pub api_token: String,
-    pub domain: String,
+    /// The domain (zone) holding the records; discovered per record when `None`
+    pub domain: Option<String>,
 }
 
 /// DigitalOcean DNS provider
 pub struct DigitalOceanDnsProvider {
     config: DigitalOceanConfig,
     http_client: reqwest::Client,
+    zones: ZoneDiscovery,
 }
 
 impl DigitalOceanDnsProvider {
@@ -28,10 +31,28 @@ impl DigitalOceanDnsProvider {
         Self {
             config,
             http_client: reqwest::Client::new(),
+            zones: ZoneDiscovery::new(),
+        }
+    }
+
+    /// Returns the configured domain, or discovers the zone holding `domain`
+    async fn zone_name(&self, domain: &str) -> Result<String> {
+        match &self.config.domain {
+            Some(zone) => Ok(zone.clone()),
+            None => Ok(self.zones.zone_for(self, domain).await?.name),
         }
     }
 }
 
+/// The relative record name DigitalOcean expects (e.g., _acme-challenge)
+fn relative_name<'a>(domain: &'a str, zone: &str) -> &'a str {
+    if domain == zone {
+        "@"
+    } else {
+        domain.strip_suffix(&format!(".{}", zone)).unwrap_or(domain)
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct DigitalOceanRecordCreateRequest<'a> {
     r#type: &'a str,
@@ -50,6 +71,29 @@ struct DigitalOceanListResponse {
     domain_records: Vec<DigitalOceanRecord>,
 }
 
+#[derive(Debug, Deserialize)]
+struct DigitalOceanDomainListResponse {
+    domains: Vec<DigitalOceanDomain>,
+    #[serde(default)]
+    links: DigitalOceanLinks,
+}
+
+#[derive(Debug, Deserialize)]
+struct DigitalOceanDomain {
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DigitalOceanLinks {
+    #[serde(default)]
+    pages: DigitalOceanPages,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DigitalOceanPages {
+    next: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct DigitalOceanRecord {
     id: u64,
@@ -62,19 +106,9 @@ impl DnsProvider for DigitalOceanDnsProvider {
     async fn create_txt_record(&self, domain: &str, value: &str) -> Result<String> {
         info!("Creating TXT record in DigitalOcean DNS: {}", domain);
 
-        let url = format!(
-            "https://api.digitalocean.com/v2/domains/{}/records",
-            self.config.domain
-        );
-
-        // DigitalOcean expects the relative name (e.g., _acme-challenge)
-        let record_name = if domain == self.config.domain {
-            "@"
-        } else {
-            domain
-                .strip_suffix(&format!(".{}", self.config.domain))
-                .unwrap_or(domain)
-        };
+        let zone = self.zone_name(domain).await?;
+        let url = format!("https://api.digitalocean.com/v2/domains/{}/records", zone);
+        let record_name = relative_name(domain, &zone);
 
         let payload = DigitalOceanRecordCreateRequest {
             r#type: "TXT",
@@ -109,12 +143,13 @@ impl DnsProvider for DigitalOceanDnsProvider {
         Ok(body.domain_record.id.to_string())
     }
 
-    async fn delete_txt_record(&self, _domain: &str, record_id: &str) -> Result<()> {
+    async fn delete_txt_record(&self, domain: &str, record_id: &str) -> Result<()> {
         info!("Deleting TXT record from DigitalOcean DNS: {}", record_id);
 
         let url = format!(
             "https://api.digitalocean.com/v2/domains/{}/records/{}",
-            self.config.domain, record_id
+            self.zone_name(domain).await?,
+            record_id
         );
 
         let response = self
@@ -141,9 +176,10 @@ impl DnsProvider for DigitalOceanDnsProvider {
     async fn verify_record(&self, domain: &str, value: &str) -> Result<bool> {
         debug!("Verifying DigitalOcean DNS record for: {}", domain);
 
+        let zone = self.zone_name(domain).await?;
         let url = format!(
             "https://api.digitalocean.com/v2/domains/{}/records?type=TXT",
-            self.config.domain
+            zone
         );
 
         let response = self
@@ -162,13 +198,7 @@ impl DnsProvider for DigitalOceanDnsProvider {
             AcmeError::protocol("Failed to parse DigitalOcean list response".to_string())
         })?;
 
-        let record_name = if domain == self.config.domain {
-            "@"
-        } else {
-            domain
-                .strip_suffix(&format!(".{}", self.config.domain))
-                .unwrap_or(domain)
-        };
+        let record_name = relative_name(domain, &zone);
 
         for record in body.domain_records {
             if record.name == record_name && record.data == value {
@@ -178,4 +208,40 @@ impl DnsProvider for DigitalOceanDnsProvider {
 
         Ok(false)
     }
+
+    async fn list_zones(&self) -> Result<Vec<DnsZone>> {
+        debug!("Listing DigitalOcean domains");
+
+        let mut zones = Vec::new();
+        let mut url = Some("https://api.digitalocean.com/v2/domains?per_page=200".to_string());
+        while let Some(page) = url {
+            let response = self
+                .http_client
+                .get(page)
+                .bearer_auth(&self.config.api_token)
+                .send()
+                .await
+                .map_err(|e| {
+                    AcmeError::transport(format!("DigitalOcean API list failed: {}", e))
+                })?;
+
+            if !response.status().is_success() {
+                let text = response.text().await.unwrap_or_default();
+                error!("DigitalOcean list domains error: {}", text);
+                return Err(AcmeError::protocol(format!("DigitalOcean error: {}", text)));
+            }
+
+            let body: DigitalOceanDomainListResponse = response.json().await.map_err(|e| {
+                AcmeError::protocol(format!("Failed to parse DigitalOcean domains: {}", e))
+            })?;
+            // Domains are addressed by name
+            zones.extend(body.domains.into_iter().map(|domain| DnsZone {
+                id: domain.name.clone(),
+                name: domain.name,
+            }));
+            url = body.links.pages.next;
+        }
+
+        Ok(zones)
+    }
 }
@@ -7,20 +7,23 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info};
 
-use crate::challenge::DnsProvider;
+use crate::challenge::{DnsProvider, DnsZone};
+use crate::dns::zone::ZoneDiscovery;
 use crate::error::{AcmeError, Result};
 
 /// Linode DNS provider configuration
 #[derive(Debug, Clone)]
 pub struct LinodeConfig {
     pub api_token: String,
-    pub domain_id: u64,
+    /// ID of the domain (zone) holding the records; discovered per record when `None`
+    pub domain_id: Option<u64>,
 }
 
 /// Linode DNS provider
 pub struct LinodeDnsProvider {
     config: LinodeConfig,
     http_client: reqwest::Client,
+    zones: ZoneDiscovery,
 }
 
 impl LinodeDnsProvider {
@@ -28,6 +31,15 @@ impl LinodeDnsProvider {
         Self {
             config,
             http_client: reqwest::Client::new(),
+            zones: ZoneDiscovery::new(),
+        }
+    }
+
+    /// Returns the configured domain ID, or discovers the zone holding `domain`
+    async fn domain_id(&self, domain: &str) -> Result<String> {
+        match self.config.domain_id {
+            Some(domain_id) => Ok(domain_id.to_string()),
+            None => Ok(self.zones.zone_for(self, domain).await?.id),
         }
     }
 }
@@ -50,6 +62,19 @@ struct LinodeListResponse {
     data: Vec<LinodeRecord>,
 }
 
+#[derive(Debug, Deserialize)]
+struct LinodeDomainListResponse {
+    data: Vec<LinodeDomain>,
+    page: u32,
+    pages: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinodeDomain {
+    id: u64,
+    domain: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct LinodeRecord {
     _id: u64,
@@ -63,7 +88,7 @@ impl DnsProvider for LinodeDnsProvider {
 
         let url = format!(
             "https://api.linode.com/v4/domains/{}/records",
-            self.config.domain_id
+            self.domain_id(domain).await?
         );
 
         // Linode expects the subdomain part only if it's a subdomain
@@ -99,12 +124,13 @@ impl DnsProvider for LinodeDnsProvider {
         Ok(body.id.to_string())
     }
 
-    async fn delete_txt_record(&self, _domain: &str, record_id: &str) -> Result<()> {
+    async fn delete_txt_record(&self, domain: &str, record_id: &str) -> Result<()> {
         info!("Deleting TXT record from Linode DNS: {}", record_id);
 
         let url = format!(
             "https://api.linode.com/v4/domains/{}/records/{}",
-            self.config.domain_id, record_id
+            self.domain_id(domain).await?,
+            record_id
         );
 
         let response = self
@@ -133,7 +159,7 @@ impl DnsProvider for LinodeDnsProvider {
 
         let url = format!(
             "https://api.linode.com/v4/domains/{}/records",
-            self.config.domain_id
+            self.domain_id(domain).await?
         );
 
         let response = self
@@ -161,4 +187,42 @@ impl DnsProvider for LinodeDnsProvider {
 
         Ok(false)
     }
+
+    async fn list_zones(&self) -> Result<Vec<DnsZone>> {
+        debug!("Listing Linode domains");
+
+        let mut zones = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "https://api.linode.com/v4/domains?page={}&page_size=500",
+                page
+            );
+            let response = self
+                .http_client
+                .get(url)
+                .bearer_auth(&self.config.api_token)
+                .send()
+                .await
+                .map_err(|e| AcmeError::transport(format!("Linode API list failed: {}", e)))?;
+
+            if !response.status().is_success() {
+                let text = response.text().await.unwrap_or_default();
+                error!("Linode list domains error: {}", text);
+                return Err(AcmeError::protocol(format!("Linode error: {}", text)));
+            }
+
+            let body: LinodeDomainListResponse = response.json().await.map_err(|e| {
+                AcmeError::protocol(format!("Failed to parse Linode domains: {}", e))
+            })?;
+            zones.extend(body.data.into_iter().map(|domain| DnsZone {
+                name: domain.domain,
+                id: domain.id.to_string(),
+            }));
+            if body.page >= body.pages {
+                return Ok(zones);
+            }
+            page += 1;
+        }
+    }
 }
@@ -1,5 +1,6 @@
 /// Built-in DNS providers for various cloud platforms.
 /// Each provider implements the `DnsProvider` trait to handle TXT record management.
+pub mod acmedns;
 pub mod alibaba;
 pub mod azure;
 pub mod cloudflare;
@@ -9,10 +10,13 @@ pub mod godaddy;
 pub mod google;
 pub mod huawei;
 pub mod linode;
+pub mod rfc2136;
 pub mod route53;
 pub mod tencent;
 
 // Re-exports with feature gates to provide a clean public API.
+#[cfg(feature = "dns-acmedns")]
+pub use acmedns::AcmeDnsProvider;
 #[cfg(feature = "dns-alibaba")]
 pub use alibaba::AlibabaCloudDnsProvider;
 #[cfg(feature = "dns-azure")]
@@ -31,6 +35,8 @@ pub use google::GoogleCloudDnsProvider;
 pub use huawei::HuaweiCloudDnsProvider;
 #[cfg(feature = "dns-linode")]
 pub use linode::LinodeDnsProvider;
+#[cfg(feature = "dns-rfc2136")]
+pub use rfc2136::Rfc2136DnsProvider;
 #[cfg(feature = "dns-route53")]
 pub use route53::Route53DnsProvider;
 #[cfg(feature = "dns-tencent")]
@@ -0,0 +1,587 @@
+/// RFC 2136 dynamic DNS update provider.
+/// This provider manages TXT records on self-hosted authoritative servers (BIND, Knot,
+/// PowerDNS, ...) by sending UPDATE messages to the zone's primary server, authenticated
+/// with TSIG (RFC 8945) using HMAC-SHA256 or HMAC-SHA512.
+use crate::challenge::DnsProvider;
+use crate::error::{AcmeError, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hickory_resolver::proto::op::{Header, Message, Query, update_message};
+use hickory_resolver::proto::rr::rdata::{TXT, tsig};
+use hickory_resolver::proto::rr::{Name, RData, Record, RecordSet, RecordType};
+use hickory_resolver::proto::serialize::binary::{BinDecodable, BinDecoder, BinEncodable};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+#[cfg(feature = "dns-rfc2136")]
+use hickory_resolver::proto::op::{MessageType, ResponseCode};
+#[cfg(feature = "dns-rfc2136")]
+use hickory_resolver::proto::rr::TSigner;
+
+/// Permitted difference between our clock and the server's, in seconds.
+#[cfg(feature = "dns-rfc2136")]
+const TSIG_FUDGE: u16 = 300;
+
+/// Length of a DNS message header.
+const HEADER_LEN: usize = 12;
+
+/// HMAC algorithms usable for TSIG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsigAlgorithm {
+    /// hmac-sha256
+    HmacSha256,
+    /// hmac-sha512
+    HmacSha512,
+}
+
+impl From<TsigAlgorithm> for tsig::TsigAlgorithm {
+    fn from(algorithm: TsigAlgorithm) -> Self {
+        match algorithm {
+            TsigAlgorithm::HmacSha256 => Self::HmacSha256,
+            TsigAlgorithm::HmacSha512 => Self::HmacSha512,
+        }
+    }
+}
+
+impl FromStr for TsigAlgorithm {
+    type Err = AcmeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().trim_end_matches('.').to_lowercase().as_str() {
+            "hmac-sha256" => Ok(Self::HmacSha256),
+            "hmac-sha512" => Ok(Self::HmacSha512),
+            other => Err(AcmeError::configuration(format!(
+                "Unsupported TSIG algorithm '{}', expected hmac-sha256 or hmac-sha512",
+                other
+            ))),
+        }
+    }
+}
+
+/// A TSIG key shared with the DNS server.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "dns-rfc2136"), allow(dead_code))]
+pub struct TsigKey {
+    /// The key name, as in the server's `key` statement.
+    name: Name,
+    algorithm: TsigAlgorithm,
+    secret: Vec<u8>,
+}
+
+impl TsigKey {
+    /// Creates a key from its name and base64-encoded secret, as written in BIND's
+    /// `key` statements and produced by `tsig-keygen`.
+    pub fn new(name: impl Into<String>, algorithm: TsigAlgorithm, secret: &str) -> Result<Self> {
+        let name = name.into().trim_end_matches('.').to_lowercase();
+        if name.is_empty() {
+            return Err(AcmeError::configuration("TSIG key name must not be empty"));
+        }
+        let name = parse_name(&name).map_err(|e| {
+            AcmeError::configuration(format!("Invalid TSIG key name '{}': {}", name, e))
+        })?;
+        let secret = STANDARD.decode(secret.trim()).map_err(|e| {
+            AcmeError::configuration(format!("TSIG secret is not valid base64: {}", e))
+        })?;
+        if secret.is_empty() {
+            return Err(AcmeError::configuration("TSIG secret must not be empty"));
+        }
+        Ok(Self {
+            name,
+            algorithm,
+            secret,
+        })
+    }
+
+    /// The signer computing and checking this key's TSIG records.
+    #[cfg(feature = "dns-rfc2136")]
+    fn signer(&self) -> Result<TSigner> {
+        TSigner::new(
+            self.secret.clone(),
+            self.algorithm.into(),
+            self.name.clone(),
+            TSIG_FUDGE,
+        )
+        .map_err(|e| AcmeError::configuration(format!("Invalid TSIG key: {}", e)))
+    }
+}
+
+impl std::fmt::Debug for TsigKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TsigKey")
+            .field("name", &self.name.to_ascii())
+            .field("algorithm", &self.algorithm)
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Configuration for the RFC 2136 DNS provider.
+#[derive(Debug, Clone)]
+pub struct Rfc2136Config {
+    /// The primary server accepting updates, as `host` or `host:port`.
+    pub server: String,
+    /// The zone the records are updated in, e.g. `example.com`.
+    pub zone: String,
+    /// The key authenticating the updates.
+    pub key: TsigKey,
+    /// TTL of created records in seconds.
+    pub ttl: u32,
+}
+
+/// RFC 2136 dynamic update provider for handling DNS-01 challenges.
+pub struct Rfc2136DnsProvider {
+    /// Provider configuration.
+    config: Rfc2136Config,
+    /// How long to wait for each server response.
+    timeout: Duration,
+}
+
+impl Rfc2136DnsProvider {
+    /// Creates a new `Rfc2136DnsProvider` with the given configuration.
+    pub fn new(config: Rfc2136Config) -> Self {
+        tracing::debug!(
+            "Initializing Rfc2136DnsProvider for zone {} at {}",
+            config.zone,
+            config.server
+        );
+        Self {
+            config,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Resolves the server address, defaulting to port 53.
+    async fn server_addr(&self) -> Result<SocketAddr> {
+        let server = &self.config.server;
+        let with_port = if server.parse::<SocketAddr>().is_ok()
+            || server
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.contains(':') && port.parse::<u16>().is_ok())
+        {
+            server.clone()
+        } else {
+            match server.parse::<std::net::IpAddr>() {
+                Ok(ip) => SocketAddr::new(ip, 53).to_string(),
+                Err(_) => format!("{}:53", server),
+            }
+        };
+        tokio::net::lookup_host(&with_port)
+            .await
+            .map_err(|e| {
+                AcmeError::transport(format!("Cannot resolve DNS server {}: {}", server, e))
+            })?
+            .next()
+            .ok_or_else(|| AcmeError::transport(format!("DNS server {} has no address", server)))
+    }
+
+    /// Sends a message over UDP, retrying over TCP if the response is truncated.
+    async fn exchange(&self, message: &Message) -> Result<Vec<u8>> {
+        let addr = self.server_addr().await?;
+        let request = message
+            .to_vec()
+            .map_err(|e| AcmeError::invalid_input(format!("Cannot encode DNS message: {}", e)))?;
+        let local: SocketAddr = if addr.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        let transport = |e: std::io::Error| {
+            AcmeError::transport(format!("DNS exchange with {} failed: {}", addr, e))
+        };
+
+        let socket = UdpSocket::bind(local).await.map_err(transport)?;
+        socket.connect(addr).await.map_err(transport)?;
+        socket.send(&request).await.map_err(transport)?;
+        let (response, header) = tokio::time::timeout(self.timeout, async {
+            let mut buf = vec![0u8; 65535];
+            loop {
+                let len = socket.recv(&mut buf).await?;
+                // Ignore stray datagrams answering other queries
+                match reply_header(&buf[..len], message.id) {
+                    Ok(header) => return Ok::<_, std::io::Error>((buf[..len].to_vec(), header)),
+                    Err(e) => tracing::debug!("Ignoring datagram from {}: {}", addr, e),
+                }
+            }
+        })
+        .await
+        .map_err(|_| AcmeError::timeout(format!("DNS server {} did not respond", addr)))?
+        .map_err(transport)?;
+
+        if !header.truncation {
+            return Ok(response);
+        }
+
+        tracing::debug!("Truncated UDP response from {}, retrying over TCP", addr);
+        let response = tokio::time::timeout(self.timeout, async {
+            let mut stream = TcpStream::connect(addr).await?;
+            stream
+                .write_all(&(request.len() as u16).to_be_bytes())
+                .await?;
+            stream.write_all(&request).await?;
+            let len = stream.read_u16().await? as usize;
+            let mut response = vec![0u8; len];
+            stream.read_exact(&mut response).await?;
+            Ok::<_, std::io::Error>(response)
+        })
+        .await
+        .map_err(|_| AcmeError::timeout(format!("DNS server {} did not respond", addr)))?
+        .map_err(transport)?;
+        reply_header(&response, message.id)?;
+        Ok(response)
+    }
+
+    /// Sends a signed UPDATE adding (`add`) or deleting one TXT record.
+    async fn update(&self, domain: &str, value: &str, add: bool) -> Result<()> {
+        let name = parse_name(domain).map_err(|e| {
+            AcmeError::invalid_input(format!("Invalid record name '{}': {}", domain, e))
+        })?;
+        let zone = parse_name(&self.config.zone).map_err(|e| {
+            AcmeError::configuration(format!("Invalid zone '{}': {}", self.config.zone, e))
+        })?;
+        if !zone.zone_of(&name) {
+            return Err(AcmeError::configuration(format!(
+                "{} is not within the zone {}",
+                name, zone
+            )));
+        }
+
+        let record = Record::from_rdata(name.clone(), self.config.ttl, RData::TXT(txt(value)));
+        // Deleting sends the record with class NONE and TTL 0, removing exactly this value
+        let message = if add {
+            update_message::append(RecordSet::from(record), zone, false, false)
+        } else {
+            update_message::delete_by_rdata(RecordSet::from(record), zone, false)
+        };
+        self.send_signed(message, &name).await
+    }
+
+    /// Signs `message`, sends it and checks the server's signed response.
+    #[cfg(feature = "dns-rfc2136")]
+    async fn send_signed(&self, mut message: Message, name: &Name) -> Result<()> {
+        let (signature, verifier) = self
+            .config
+            .key
+            .signer()?
+            .sign_message(&message, unix_time())
+            .map_err(|e| AcmeError::invalid_input(format!("Cannot sign DNS update: {}", e)))?;
+        message.set_signature(signature);
+        let mut verifier =
+            verifier.ok_or_else(|| AcmeError::invalid_input("DNS update was not signed"))?;
+
+        let bytes = self.exchange(&message).await?;
+        let response = Message::from_vec(&bytes)
+            .map_err(|e| AcmeError::transport(format!("Malformed DNS response: {}", e)))?;
+        if response.message_type != MessageType::Response {
+            return Err(AcmeError::transport(
+                "DNS server sent a query instead of a response",
+            ));
+        }
+        // Refusals usually come unsigned, so the response code is reported first
+        let verified = verifier.verify(&bytes).map(|_| ()).map_err(|e| {
+            AcmeError::transport(format!("DNS response failed TSIG verification: {}", e))
+        });
+        if response.response_code != ResponseCode::NoError {
+            return Err(AcmeError::transport(format!(
+                "DNS server refused the update of {}: {}",
+                name, response.response_code
+            )));
+        }
+        verified
+    }
+
+    /// Updates cannot be signed without the `dns-rfc2136` feature.
+    #[cfg(not(feature = "dns-rfc2136"))]
+    async fn send_signed(&self, _message: Message, _name: &Name) -> Result<()> {
+        Err(AcmeError::configuration(
+            "RFC 2136 updates require the 'dns-rfc2136' feature",
+        ))
+    }
+}
+
+#[async_trait]
+impl DnsProvider for Rfc2136DnsProvider {
+    /// Adds the TXT record; the value doubles as the record ID for deletion.
+    async fn create_txt_record(&self, domain: &str, value: &str) -> Result<String> {
+        tracing::info!("Adding TXT record {} via RFC 2136 update", domain);
+        self.update(domain, value, true).await?;
+        Ok(value.to_string())
+    }
+
+    /// Deletes the TXT record holding the value returned at creation.
+    async fn delete_txt_record(&self, domain: &str, record_id: &str) -> Result<()> {
+        tracing::info!("Deleting TXT record {} via RFC 2136 update", domain);
+        self.update(domain, record_id, false).await
+    }
+
+    /// Asks the primary server whether it serves the TXT record.
+    async fn verify_record(&self, domain: &str, value: &str) -> Result<bool> {
+        tracing::debug!("Querying {} for TXT record {}", self.config.server, domain);
+        let name = parse_name(domain).map_err(|e| {
+            AcmeError::invalid_input(format!("Invalid record name '{}': {}", domain, e))
+        })?;
+        let mut query = Message::query();
+        query.add_query(Query::query(name, RecordType::TXT));
+
+        let bytes = self.exchange(&query).await?;
+        let response = Message::from_vec(&bytes)
+            .map_err(|e| AcmeError::transport(format!("Malformed DNS response: {}", e)))?;
+        Ok(response.answers.iter().any(|record| match &record.data {
+            RData::TXT(txt) => txt.to_string() == value,
+            _ => false,
+        }))
+    }
+}
+
+#[cfg(feature = "dns-rfc2136")]
+fn unix_time() -> u64 {
+    jiff::Timestamp::now().as_second().max(0) as u64
+}
+
+/// Parses an absolute domain name, checking it fits the wire format limits of 63 bytes
+/// per label and 255 bytes in total.
+fn parse_name(name: &str) -> std::result::Result<Name, String> {
+    let mut name = Name::from_ascii(name.trim_end_matches('.')).map_err(|e| e.to_string())?;
+    name.set_fqdn(true);
+    name.to_bytes().map_err(|e| e.to_string())?;
+    Ok(name)
+}
+
+/// TXT RDATA holding `value`, split into character-strings of at most 255 bytes.
+fn txt(value: &str) -> TXT {
+    TXT::from_bytes(value.as_bytes().chunks(255).collect())
+}
+
+/// Reads the header of a reply to the message `id`, rejecting replies too short to
+/// hold a header and replies to other messages.
+fn reply_header(reply: &[u8], id: u16) -> Result<Header> {
+    if reply.len() < HEADER_LEN {
+        return Err(AcmeError::transport(format!(
+            "DNS reply of {} bytes is shorter than its header",
+            reply.len()
+        )));
+    }
+    let header = Header::read(&mut BinDecoder::new(reply))
+        .map_err(|e| AcmeError::transport(format!("Malformed DNS reply header: {}", e)))?;
+    if header.metadata.id != id {
+        return Err(AcmeError::transport(format!(
+            "DNS reply has ID {} instead of {}",
+            header.metadata.id, id
+        )));
+    }
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[cfg(feature = "dns-rfc2136")]
+    use hickory_resolver::proto::op::{OpCode, UpdateMessage};
+    #[cfg(feature = "dns-rfc2136")]
+    use hickory_resolver::proto::rr::{DNSClass, TSigResponseContext};
+    #[cfg(feature = "dns-rfc2136")]
+    use std::collections::HashMap;
+    #[cfg(feature = "dns-rfc2136")]
+    use std::sync::{Arc, Mutex};
+
+    #[cfg(feature = "dns-rfc2136")]
+    type Zone = Arc<Mutex<HashMap<String, Vec<String>>>>;
+
+    /// A minimal authoritative server for `example.test` accepting updates signed with `key`.
+    #[cfg(feature = "dns-rfc2136")]
+    async fn start_server(key: TsigKey) -> (SocketAddr, Zone) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let zone: Zone = Arc::default();
+        let records = zone.clone();
+        let signer = key.signer().unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 65535];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let request = &buf[..len];
+                let response = answer(request, &signer, &records);
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+        (addr, zone)
+    }
+
+    #[cfg(feature = "dns-rfc2136")]
+    fn answer(request: &[u8], signer: &TSigner, zone: &Zone) -> Vec<u8> {
+        let message = Message::from_vec(request).unwrap();
+
+        if message.op_code != OpCode::Update {
+            // A TXT query: answer from the zone
+            let query = message.queries[0].clone();
+            let name = query.name().to_string().trim_end_matches('.').to_string();
+            let values = zone.lock().unwrap().get(&name).cloned().unwrap_or_default();
+            let mut response = Message::response(message.id, OpCode::Query);
+            for value in values {
+                response.add_answer(Record::from_rdata(
+                    query.name().clone(),
+                    60,
+                    RData::TXT(txt(&value)),
+                ));
+            }
+            response.add_query(query);
+            return response.to_vec().unwrap();
+        }
+
+        let mut response = Message::response(message.id, OpCode::Update);
+        let request_mac = match signer.verify_message_byte(request, None, true) {
+            Ok((mac, _, _)) => mac,
+            Err(_) => {
+                // NOTAUTH, unsigned
+                response.metadata.response_code = ResponseCode::NotAuth;
+                return response.to_vec().unwrap();
+            }
+        };
+
+        let update = &message.updates()[0];
+        let name = update.name.to_string().trim_end_matches('.').to_string();
+        let RData::TXT(value) = &update.data else {
+            panic!("not a TXT update: {:?}", update);
+        };
+        let mut zone = zone.lock().unwrap();
+        let values = zone.entry(name).or_default();
+        if update.dns_class == DNSClass::IN {
+            values.push(value.to_string());
+        } else {
+            values.retain(|v| *v != value.to_string());
+        }
+
+        let signature =
+            TSigResponseContext::new(message.id, unix_time(), signer.clone(), request_mac, None)
+                .sign(&response.to_vec().unwrap())
+                .unwrap();
+        response.set_signature(signature);
+        response.to_vec().unwrap()
+    }
+
+    /// A server whose UDP replies are truncated and whose TCP replies are `reply(request)`.
+    async fn start_truncating_server(reply: fn(&[u8]) -> Vec<u8>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let socket = UdpSocket::bind(addr).await.unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 65535];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                // The request's header with QR and TC set
+                let mut response = buf[..len.min(HEADER_LEN)].to_vec();
+                response[2] |= 0x82;
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let len = stream.read_u16().await.unwrap() as usize;
+                let mut request = vec![0u8; len];
+                stream.read_exact(&mut request).await.unwrap();
+                let response = reply(&request);
+                stream
+                    .write_all(&(response.len() as u16).to_be_bytes())
+                    .await
+                    .unwrap();
+                stream.write_all(&response).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    fn key(secret: &str) -> TsigKey {
+        TsigKey::new("acme-update.", TsigAlgorithm::HmacSha256, secret).unwrap()
+    }
+
+    fn provider(addr: SocketAddr, key: TsigKey) -> Rfc2136DnsProvider {
+        Rfc2136DnsProvider::new(Rfc2136Config {
+            server: addr.to_string(),
+            zone: "example.test".to_string(),
+            key,
+            ttl: 60,
+        })
+    }
+
+    #[test]
+    fn test_tsig_key_validation() {
+        assert!(TsigKey::new("k", "hmac-sha512".parse().unwrap(), "c2VjcmV0").is_ok());
+        assert!("hmac-md5".parse::<TsigAlgorithm>().is_err());
+
+        // Names the wire format cannot carry are refused on creation
+        let label = "a".repeat(64);
+        let long = ["a".repeat(63).as_str(); 4].join(".");
+        for name in ["", ".", label.as_str(), long.as_str()] {
+            let err = TsigKey::new(name, TsigAlgorithm::HmacSha256, "c2VjcmV0").unwrap_err();
+            assert!(matches!(err, AcmeError::Configuration(_)), "{}", name);
+        }
+        assert!(TsigKey::new("k", TsigAlgorithm::HmacSha256, "not base64!").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_short_and_mismatched_tcp_replies() {
+        let name = "_acme-challenge.example.test";
+        let addr = start_truncating_server(|request| request[..4].to_vec()).await;
+        let err = provider(addr, key("c2VjcmV0LWtleQ=="))
+            .verify_record(name, "token")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AcmeError::Transport(_)));
+        assert!(err.to_string().contains("shorter than its header"));
+
+        let addr = start_truncating_server(|request| {
+            let mut response = request.to_vec();
+            response[0] ^= 0xff;
+            response[2] |= 0x80;
+            response
+        })
+        .await;
+        let err = provider(addr, key("c2VjcmV0LWtleQ=="))
+            .verify_record(name, "token")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AcmeError::Transport(_)));
+        assert!(err.to_string().contains("instead of"));
+    }
+
+    #[cfg(feature = "dns-rfc2136")]
+    #[tokio::test]
+    async fn test_update_against_local_server() {
+        let (addr, zone) = start_server(key("c2VjcmV0LWtleQ==")).await;
+        let provider = provider(addr, key("c2VjcmV0LWtleQ=="));
+        let name = "_acme-challenge.www.example.test";
+
+        let id = provider.create_txt_record(name, "token-1").await.unwrap();
+        provider.create_txt_record(name, "token-2").await.unwrap();
+        assert_eq!(zone.lock().unwrap()[name], vec!["token-1", "token-2"]);
+        assert!(provider.verify_record(name, "token-1").await.unwrap());
+
+        provider.delete_txt_record(name, &id).await.unwrap();
+        assert_eq!(zone.lock().unwrap()[name], vec!["token-2"]);
+        assert!(!provider.verify_record(name, "token-1").await.unwrap());
+
+        // Names outside the zone are refused before anything is sent
+        assert!(
+            provider
+                .create_txt_record("_acme-challenge.other.test", "x")
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "dns-rfc2136")]
+    #[tokio::test]
+    async fn test_update_with_wrong_key() {
+        let (addr, zone) = start_server(key("c2VjcmV0LWtleQ==")).await;
+        let provider = provider(addr, key("d3Jvbmcta2V5"));
+        let err = provider
+            .create_txt_record("_acme-challenge.example.test", "token")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Not authorized"));
+        assert!(zone.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+/// DNS zone discovery.
+/// Finds the zone holding a record by walking SOA records up from the record name, then
+/// matches it against the zones the provider's account lists. One set of credentials can
+/// then serve every zone of the account without a configured zone ID.
+use crate::challenge::{CachingDnsResolver, DnsProvider, DnsZone};
+use crate::error::{AcmeError, Result};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// Discovers and remembers the zones of record names for one provider.
+#[derive(Debug, Default)]
+pub struct ZoneDiscovery {
+    /// Zones found so far, by record name
+    found: RwLock<HashMap<String, DnsZone>>,
+}
+
+impl ZoneDiscovery {
+    /// Creates an empty `ZoneDiscovery`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finds the zone of `name` among the zones `provider` manages.
+    pub async fn zone_for(&self, provider: &dyn DnsProvider, name: &str) -> Result<DnsZone> {
+        let name = name.trim_end_matches('.').to_lowercase();
+        if let Some(zone) = self.found.read().await.get(&name) {
+            return Ok(zone.clone());
+        }
+
+        let apex = find_zone_apex(&name).await?;
+        let zones = provider.list_zones().await?;
+        let zone = match_zone(&zones, &apex).cloned().ok_or_else(|| {
+            AcmeError::configuration(format!(
+                "Zone {} of {} is not managed by this DNS provider account (zones: {})",
+                apex,
+                name,
+                zones
+                    .iter()
+                    .map(|zone| zone.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })?;
+        tracing::debug!("Discovered zone {} ({}) for {}", zone.name, zone.id, name);
+
+        self.found.write().await.insert(name, zone.clone());
+        Ok(zone)
+    }
+}
+
+/// Finds the closest enclosing zone of `name` by walking SOA records towards the root.
+pub async fn find_zone_apex(name: &str) -> Result<String> {
+    let resolver = CachingDnsResolver::new()?;
+    let name = name.trim_end_matches('.');
+    let mut candidate = name;
+    loop {
+        if resolver.is_zone_apex(&format!("{}.", candidate)).await? {
+            return Ok(candidate.to_lowercase());
+        }
+        candidate = candidate
+            .split_once('.')
+            .map(|(_, parent)| parent)
+            .ok_or_else(|| AcmeError::configuration(format!("No DNS zone found for {}", name)))?;
+    }
+}
+
+/// Returns the listed zone whose name is `apex`.
+fn match_zone<'a>(zones: &'a [DnsZone], apex: &str) -> Option<&'a DnsZone> {
+    zones
+        .iter()
+        .find(|zone| zone.name.trim_end_matches('.').eq_ignore_ascii_case(apex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::MockDnsProvider;
+
+    fn zone(name: &str, id: &str) -> DnsZone {
+        DnsZone {
+            name: name.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_match_zone() {
+        let zones = [
+            zone("example.com", "1"),
+            zone("Sub.Example.com.", "2"),
+            zone("example.co.uk", "3"),
+        ];
+        assert_eq!(match_zone(&zones, "example.com").unwrap().id, "1");
+        assert_eq!(match_zone(&zones, "sub.example.com").unwrap().id, "2");
+        assert_eq!(match_zone(&zones, "example.co.uk").unwrap().id, "3");
+        assert!(match_zone(&zones, "co.uk").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_discovered_zones_are_remembered() {
+        let discovery = ZoneDiscovery::new();
+        discovery.found.write().await.insert(
+            "_acme-challenge.example.com".to_string(),
+            zone("example.com", "1"),
+        );
+
+        // The mock provider cannot list zones, so only the remembered zone resolves
+        let provider = MockDnsProvider::new();
+        let found = discovery
+            .zone_for(&provider, "_acme-challenge.Example.com.")
+            .await
+            .unwrap();
+        assert_eq!(found.id, "1");
+        assert!(provider.list_zones().await.is_err());
+    }
+}
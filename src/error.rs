@@ -62,6 +62,15 @@ pub enum AcmeError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    /// Requests the ACME server rejected as malformed or unacceptable.
+    #[error("Request rejected: {kind}, detail: {detail}")]
+    Rejected {
+        /// The ACME problem type, without the `urn:ietf:params:acme:error:` prefix.
+        kind: String,
+        /// Detailed error message from the server.
+        detail: String,
+    },
+
     /// Errors indicating that an operation has timed out.
     #[error("Timeout: {0}")]
     Timeout(String),
@@ -173,6 +182,43 @@ impl AcmeError {
         Self::Pem(msg.into())
     }
 
+    /// Maps a problem document returned by the ACME server onto the matching error variant.
+    /// `retry_after` is taken from the response's `Retry-After` header, if present.
+    pub fn from_problem(
+        problem: crate::types::AcmeErrorDetail,
+        retry_after: Option<std::time::Duration>,
+    ) -> Self {
+        let message = problem.to_string();
+        match problem.kind() {
+            "rateLimited" => Self::RateLimited(retry_after),
+            "accountDoesNotExist"
+            | "unauthorized"
+            | "userActionRequired"
+            | "externalAccountRequired"
+            | "invalidContact"
+            | "unsupportedContact" => Self::Account(message),
+            "orderNotReady" | "alreadyReplaced" | "conflict" => Self::Order {
+                status: problem.kind().to_string(),
+                detail: message,
+            },
+            "badCSR" | "badRevocationReason" | "alreadyRevoked" => Self::Certificate(message),
+            "malformed"
+            | "rejectedIdentifier"
+            | "unsupportedIdentifier"
+            | "badSignatureAlgorithm"
+            | "badPublicKey"
+            | "invalidProfile" => Self::Rejected {
+                kind: problem.kind().to_string(),
+                detail: message,
+            },
+            "caa" | "connection" | "dns" | "incorrectResponse" | "tls" => Self::Challenge {
+                challenge_type: "unknown".to_string(),
+                error: message,
+            },
+            _ => Self::Protocol(message),
+        }
+    }
+
     /// Converts the AcmeError into an RFC 7807 ProblemDetails structure.
     /// This is useful for returning standardized error responses in an API.
     pub fn to_problem_details(&self) -> ProblemDetails {
@@ -198,6 +244,27 @@ impl AcmeError {
                 detail: detail.clone(),
                 instance: None,
             },
+            Self::Configuration(d) => ProblemDetails {
+                problem_type: "https://acmex.sh/errors/configuration".into(),
+                title: "Configuration Error".into(),
+                status: 400,
+                detail: d.clone(),
+                instance: None,
+            },
+            Self::InvalidInput(d) => ProblemDetails {
+                problem_type: "https://acmex.sh/errors/invalid-input".into(),
+                title: "Invalid Input".into(),
+                status: 400,
+                detail: d.clone(),
+                instance: None,
+            },
+            Self::Rejected { kind, detail } => ProblemDetails {
+                problem_type: "https://acmex.sh/errors/invalid-input".into(),
+                title: format!("Request Rejected (Type: {})", kind),
+                status: 400,
+                detail: detail.clone(),
+                instance: None,
+            },
             Self::Storage(d) => ProblemDetails {
                 problem_type: "https://acmex.sh/errors/storage".into(),
                 title: "Storage Error".into(),
@@ -47,6 +47,8 @@ pub mod renewal;
 pub mod scheduler;
 pub mod server;
 pub mod storage;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod transport;
 pub mod types;
 
@@ -58,7 +60,7 @@ pub use challenge::{
     CachingDnsResolver, ChallengeSolver, ChallengeSolverRegistry, Dns01Solver, DnsCache,
     DnsProvider, Http01Solver, MockDnsProvider, TlsAlpn01Solver,
 };
-pub use client::{AcmeClient, AcmeConfig, CertificateBundle};
+pub use client::{AcmeClient, AcmeConfig, CertificateBundle, StarCertificate};
 pub use config::{AcmeSettings, ChallengeSettings, Config, RenewalSettings, StorageSettings};
 #[cfg(feature = "dns-alibaba")]
 pub use dns::AlibabaCloudDnsProvider;
@@ -87,13 +89,16 @@ pub use metrics::{HealthStatus, MetricsRegistry};
 pub use notifications::{EventType, WebhookClient, WebhookConfig, WebhookEvent, WebhookManager};
 pub use orchestrator::{CertificateProvisioner, DomainValidator, Orchestrator};
 pub use order::{
-    Authorization, CertificateRevocation, Challenge, CsrGenerator, FinalizationRequest,
-    NewOrderRequest, Order, OrderManager, parse_certificate_chain, verify_certificate_domains,
+    Authorization, AutoRenewal, CertificateOptions, CertificateRevocation, CertificateSubject,
+    Challenge, CsrGenerator, FinalizationRequest, NewOrderRequest, Order, OrderManager,
+    parse_certificate_chain, verify_certificate_domains,
 };
 pub use protocol::{Directory, DirectoryManager, Jwk, JwsSigner, NonceManager};
 pub use renewal::{RenewalHook, SimpleRenewalScheduler};
 pub use scheduler::{AdvancedRenewalScheduler, CleanupScheduler, RenewalScheduler};
-pub use server::{HealthCheck, WebhookHandler, start_server};
+pub use server::{
+    AcmeServer, AcmeServerConfig, HealthCheck, WebhookHandler, start_acme_server, start_server,
+};
 #[cfg(feature = "redis")]
 pub use storage::RedisStorage;
 pub use storage::{EncryptedStorage, FileStorage};
@@ -104,15 +109,15 @@ pub use types::{
 /// Prelude module with commonly used types
 pub mod prelude {
     pub use crate::{
-        AcmeClient, AcmeConfig,
-        account::{Account, AccountManager, KeyPair, KeyRollover},
+        AcmeClient, AcmeConfig, CertificateBundle,
+        account::{Account, AccountManager, ExternalAccountKey, KeyPair, KeyRollover},
         certificate::CertificateChain,
         crypto::{Base64Encoding, Sha256Hash},
         error::{AcmeError, Result},
         orchestrator::{CertificateProvisioner, DomainValidator, Orchestrator},
         order::{
-            Authorization, CertificateRevocation, Challenge, FinalizationRequest, NewOrderRequest,
-            Order,
+            Authorization, AutoRenewal, CertificateOptions, CertificateRevocation,
+            CertificateSubject, Challenge, FinalizationRequest, NewOrderRequest, Order,
         },
         protocol::{Directory, DirectoryManager, Jwk, JwsSigner, NonceManager},
         scheduler::{AdvancedRenewalScheduler, CleanupScheduler},
@@ -2,17 +2,27 @@
 /// This module coordinates the entire process of account registration,
 /// challenge fulfillment, and certificate issuance.
 use super::Orchestrator;
-use crate::challenge::{ChallengeSolverRegistry, Http01Solver, TlsAlpn01Solver};
+use crate::account::KeyPair;
+use crate::challenge::{
+    CachingDnsResolver, ChallengeSolverRegistry, Dns01Solver, Http01Solver, PropagationChecker,
+    TlsAlpn01Solver,
+};
 use crate::client::{AcmeClient, AcmeConfig};
-use crate::config::Config;
+use crate::config::{CertificateSettings, Config};
+use crate::dns::build_dns_provider;
 use crate::error::{AcmeError, Result};
+use crate::storage::{CertificateStore, StorageBackend, backend_from_settings};
 use crate::types::Contact;
 use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Orchestrator for provisioning certificates with automatic retries.
 pub struct CertificateProvisioner {
     /// The list of domains for which to provision a certificate.
     domains: Vec<String>,
+    /// Certificate settings replacing the configured ones, if any.
+    certificate: Option<CertificateSettings>,
 }
 
 #[async_trait]
@@ -25,7 +35,7 @@ impl Orchestrator for CertificateProvisioner {
 
         while retry_count <= max_retries {
             if retry_count > 0 {
-                let delay = std::time::Duration::from_secs(2u64.pow(retry_count));
+                let delay = Duration::from_secs(2u64.pow(retry_count));
                 tracing::info!(
                     "Retrying provisioning in {:?} (attempt {}/{})",
                     delay,
@@ -59,7 +69,16 @@ impl Orchestrator for CertificateProvisioner {
 impl CertificateProvisioner {
     /// Creates a new `CertificateProvisioner` for the specified domains.
     pub fn new(domains: Vec<String>) -> Self {
-        Self { domains }
+        Self {
+            domains,
+            certificate: None,
+        }
+    }
+
+    /// Uses these certificate settings instead of the ones in the configuration.
+    pub fn with_certificate_settings(mut self, settings: CertificateSettings) -> Self {
+        self.certificate = Some(settings);
+        self
     }
 
     /// Internal method that performs the actual provisioning steps.
@@ -74,8 +93,28 @@ impl CertificateProvisioner {
             "Configuring ACME client for directory: {}",
             config.acme.directory
         );
-        let mut acme_config =
-            AcmeConfig::new(&config.acme.directory).with_tos_agreed(config.acme.tos_agreed);
+        let mut acme_config = AcmeConfig::new(&config.acme.directory)
+            .with_tos_agreed(config.acme.tos_agreed)
+            .with_account_key_type(config.acme.key_type()?)
+            .with_authorization_concurrency(config.challenge.authorization_concurrency)
+            .with_poll_interval(Duration::from_secs(config.acme.poll_interval_secs))
+            .with_authorization_timeout(Duration::from_secs(config.acme.authorization_timeout_secs))
+            .with_finalization_timeout(Duration::from_secs(config.acme.finalization_timeout_secs))
+            .with_certificate_options(
+                self.certificate
+                    .as_ref()
+                    .unwrap_or(&config.certificate)
+                    .options_for(&self.domains)?,
+            );
+        if let Some(ref eab) = config.acme.external_account_binding {
+            acme_config = acme_config.with_external_account_binding(eab.try_into()?);
+        }
+        if let Some(profile) = config.acme.profile_for(&self.domains) {
+            acme_config = acme_config.with_profile(profile);
+        }
+        if let Some(ref preferred) = config.acme.preferred_chain {
+            acme_config = acme_config.with_preferred_chain(preferred.clone());
+        }
 
         for contact in &config.acme.contact {
             if contact.strip_prefix("mailto:").is_some() {
@@ -86,73 +125,28 @@ impl CertificateProvisioner {
             }
         }
 
-        let mut client = AcmeClient::new(acme_config)?;
+        // A persistent account key lets an interrupted order be resumed on the next attempt
+        let storage = backend_from_settings(&config.storage)?;
+        let account_key = load_account_key(&storage, config).await?;
+        let mut client =
+            AcmeClient::with_key_pair(acme_config, account_key).with_order_storage(storage.clone());
 
         // 2. Register account
         tracing::info!("Registering/retrieving ACME account");
         client.register_account().await?;
 
         // 3. Configure challenge solvers
-        let mut registry = ChallengeSolverRegistry::new();
-        tracing::debug!(
-            "Setting up challenge solver for type: {}",
-            config.challenge.challenge_type
-        );
-
-        match config.challenge.challenge_type.as_str() {
-            "http-01" => {
-                let addr = if let Some(ref http_config) = config.challenge.http01 {
-                    http_config.listen_addr.parse().map_err(|e| {
-                        AcmeError::configuration(format!("Invalid HTTP listen address: {}", e))
-                    })?
-                } else {
-                    "0.0.0.0:80".parse().unwrap()
-                };
-                tracing::debug!("Using HTTP-01 solver on address: {}", addr);
-                registry.register(Http01Solver::new(addr));
-            }
-            "tls-alpn-01" => {
-                tracing::debug!("Using default TLS-ALPN-01 solver on port 443");
-                registry.register(TlsAlpn01Solver::default());
-            }
-            "dns-01" => {
-                if let Some(ref dns_config) = config.challenge.dns01 {
-                    tracing::info!(
-                        "Configuring DNS-01 solver with provider: {:?}",
-                        dns_config.provider
-                    );
-                    // Note: In a full implementation, we would use a factory to create the provider
-                    // based on the provider name in the config.
-                    // For now, we assume the provider is correctly registered in the registry.
-                    // registry.register(Dns01Solver::new(Arc::new(provider)));
-                } else {
-                    return Err(AcmeError::configuration(
-                        "DNS-01 selected but no DNS config found".to_string(),
-                    ));
-                }
-            }
-            _ => {
-                tracing::error!(
-                    "Unsupported challenge type: {}",
-                    config.challenge.challenge_type
-                );
-                return Err(AcmeError::configuration(format!(
-                    "Unsupported challenge type: {}",
-                    config.challenge.challenge_type
-                )));
-            }
-        }
+        let registry = build_solver_registry(config).await?;
 
         // 4. Issue certificate
         tracing::info!("Requesting certificate issuance from ACME server");
-        let _bundle = client
-            .issue_certificate(self.domains.clone(), &mut registry)
+        let bundle = client
+            .issue_certificate(self.domains.clone(), &registry)
             .await?;
 
         // 5. Save certificate using the configured storage backend
         tracing::info!("Certificate issued successfully. Saving to storage...");
-        // TODO: Integrate with storage backend from config
-        // bundle.save_to_files("cert.pem", "key.pem")?;
+        CertificateStore::new(storage).save(&bundle).await?;
 
         tracing::info!(
             "Certificate provisioning completed for domains: {:?}",
@@ -162,3 +156,85 @@ impl CertificateProvisioner {
         Ok(())
     }
 }
+
+/// Loads the account key for the configured CA from storage, generating and storing
+/// a new one on first use.
+pub(crate) async fn load_account_key(
+    storage: &Arc<dyn StorageBackend>,
+    config: &Config,
+) -> Result<KeyPair> {
+    let key = format!("account-key:{}", config.acme.directory);
+    if let Some(pem) = storage.load(&key).await? {
+        let pem = String::from_utf8(pem)
+            .map_err(|e| AcmeError::storage(format!("Stored account key is not UTF-8: {}", e)))?;
+        tracing::debug!("Using stored account key for {}", config.acme.directory);
+        return KeyPair::from_pem(&pem);
+    }
+
+    let key_pair = KeyPair::generate_with(config.acme.key_type()?)?;
+    storage
+        .store(&key, key_pair.serialize_pem().as_bytes())
+        .await?;
+    tracing::info!("Generated new account key for {}", config.acme.directory);
+    Ok(key_pair)
+}
+
+/// Builds the challenge solver registry for the challenge type selected in the configuration.
+pub(crate) async fn build_solver_registry(config: &Config) -> Result<ChallengeSolverRegistry> {
+    let mut registry = ChallengeSolverRegistry::new();
+    tracing::debug!(
+        "Setting up challenge solver for type: {}",
+        config.challenge.challenge_type
+    );
+
+    match config.challenge.challenge_type.as_str() {
+        "http-01" => {
+            let addr = if let Some(ref http_config) = config.challenge.http01 {
+                http_config.listen_addr.parse().map_err(|e| {
+                    AcmeError::configuration(format!("Invalid HTTP listen address: {}", e))
+                })?
+            } else {
+                "0.0.0.0:80".parse().unwrap()
+            };
+            tracing::debug!("Using HTTP-01 solver on address: {}", addr);
+            registry.register(Http01Solver::new(addr));
+        }
+        "tls-alpn-01" => {
+            tracing::debug!("Using default TLS-ALPN-01 solver on port 443");
+            registry.register(TlsAlpn01Solver::default());
+        }
+        "dns-01" => {
+            let dns_config = config.challenge.dns01.as_ref().ok_or_else(|| {
+                AcmeError::configuration("DNS-01 selected but no DNS config found".to_string())
+            })?;
+            let provider_config = dns_config.primary_provider()?;
+            tracing::info!(
+                "Configuring DNS-01 solver with provider: {}",
+                provider_config.name
+            );
+            let provider = build_dns_provider(&provider_config).await?;
+            let mut solver = Dns01Solver::new(provider, String::new());
+            if dns_config.follow_cname {
+                solver = solver.with_cname_resolver(Arc::new(CachingDnsResolver::new()?));
+            }
+            if dns_config.propagation_timeout_secs > 0 {
+                solver = solver.with_propagation_checker(Arc::new(PropagationChecker::new(
+                    Duration::from_secs(dns_config.propagation_timeout_secs),
+                )?));
+            }
+            registry.register(solver);
+        }
+        _ => {
+            tracing::error!(
+                "Unsupported challenge type: {}",
+                config.challenge.challenge_type
+            );
+            return Err(AcmeError::configuration(format!(
+                "Unsupported challenge type: {}",
+                config.challenge.challenge_type
+            )));
+        }
+    }
+
+    Ok(registry)
+}
@@ -1,15 +1,18 @@
 use super::Orchestrator;
+use crate::client::{AcmeClient, CertificateBundle};
 use crate::config::Config;
-use crate::error::Result;
+use crate::error::{AcmeError, Result};
+use crate::renewal::renewal_due;
 use crate::storage::{CertificateStore, StorageBackend};
 use async_trait::async_trait;
-use tracing::info;
-use x509_parser::prelude::*;
+use std::time::Duration;
+use tracing::{error, info};
 
 /// Orchestrator for certificate renewal
 pub struct CertificateRenewer<B: StorageBackend> {
     store: CertificateStore<B>,
     renew_before_days: u64,
+    client: Option<AcmeClient>,
 }
 
 impl<B: StorageBackend> CertificateRenewer<B> {
@@ -18,49 +21,94 @@ impl<B: StorageBackend> CertificateRenewer<B> {
         Self {
             store,
             renew_before_days,
+            client: None,
         }
     }
 
-    /// Check if a certificate needs renewal
-    fn needs_renewal(&self, cert_der: &[u8]) -> bool {
-        if let Ok((_, x509)) = parse_x509_certificate(cert_der) {
-            let now = jiff::Zoned::now();
-            let not_after = x509.validity().not_after;
-            let expiry_ts = not_after.timestamp();
-            let now_ts = now.timestamp().as_second();
+    /// Use an ACME client to consult the CA's renewal information (ARI)
+    pub fn with_client(mut self, client: AcmeClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Check if a certificate needs renewal, preferring the CA-suggested window
+    async fn needs_renewal(&self, bundle: &CertificateBundle) -> Result<bool> {
+        let renew_before = Duration::from_secs(self.renew_before_days * 24 * 3600);
+        renewal_due(self.client.as_ref(), bundle, renew_before).await
+    }
+
+    /// Renew one certificate and store the result
+    async fn renew(
+        &self,
+        client: &AcmeClient,
+        bundle: &CertificateBundle,
+        config: &Config,
+    ) -> Result<CertificateBundle> {
+        // Overrides for the certificate's primary domain apply to its renewal
+        let mut client = client.clone();
+        client.set_certificate_options(config.certificate.options_for(&bundle.domains)?);
+        let registry = super::provisioner::build_solver_registry(config).await?;
+        let renewed = client.renew_certificate(bundle, &registry).await?;
+        self.store.save(&renewed).await?;
+        Ok(renewed)
+    }
 
-            let days_left = (expiry_ts - now_ts) / (24 * 3600);
-            return days_left <= self.renew_before_days as i64;
+    /// Return the certificates that are currently due for renewal
+    pub async fn due_certificates(&self) -> Result<Vec<CertificateBundle>> {
+        let mut due = Vec::new();
+        for bundle in self.store.list_all().await? {
+            match self.needs_renewal(&bundle).await {
+                Ok(true) => due.push(bundle),
+                Ok(false) => {}
+                Err(e) => tracing::warn!(
+                    "Failed to check renewal status for {:?}: {}",
+                    bundle.domains,
+                    e
+                ),
+            }
         }
-        false
+        Ok(due)
     }
 }
 
 #[async_trait]
 impl<B: StorageBackend + 'static> Orchestrator for CertificateRenewer<B> {
-    async fn execute(&self, _config: &Config) -> Result<()> {
+    async fn execute(&self, config: &Config) -> Result<()> {
         info!("Starting certificate renewal orchestration...");
 
-        // 1. Get all certificates from store
-        let cert_keys = self.store.backend().list("cert:").await?;
+        let mut failed = Vec::new();
+        for bundle in self.due_certificates().await? {
+            info!(
+                "Certificate for {:?} needs renewal, triggering process...",
+                bundle.domains
+            );
 
-        for key in cert_keys {
-            if let Some(cert_data) = self.store.backend().load(&key).await?
-                && self.needs_renewal(&cert_data)
-            {
-                info!("Certificate {} needs renewal, triggering process...", key);
+            let Some(client) = &self.client else {
+                // Without a client we can only report which certificates are due
+                continue;
+            };
 
-                // In a real implementation, we would extract domains from the certificate
-                // and use the CertificateProvisioner to renew them.
-                // For now, we log the intent.
-
-                // Example logic:
-                // let domains = extract_domains(&cert_data)?;
-                // let provisioner = CertificateProvisioner::new(domains);
-                // provisioner.execute(config).await?;
+            // One failing certificate must not hold back the renewal of the others
+            match self.renew(client, &bundle, config).await {
+                Ok(renewed) => info!("Certificate for {:?} renewed", renewed.domains),
+                Err(e) => {
+                    error!(
+                        "Failed to renew certificate for {:?}: {}",
+                        bundle.domains, e
+                    );
+                    failed.push(bundle.domains);
+                }
             }
         }
 
-        Ok(())
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(AcmeError::certificate(format!(
+                "Failed to renew {} certificate(s): {:?}",
+                failed.len(),
+                failed
+            )))
+        }
     }
 }
@@ -4,6 +4,7 @@
 use super::Orchestrator;
 use crate::config::Config;
 use crate::error::{AcmeError, Result};
+use crate::types::Identifier;
 use async_trait::async_trait;
 use std::net::ToSocketAddrs;
 
@@ -71,13 +72,25 @@ impl Orchestrator for DomainValidator {
 
             // 2. Verify DNS API credentials if using DNS-01
             if config.challenge.challenge_type == "dns-01" {
-                if config.challenge.dns01.is_none() {
+                if Identifier::from_name(domain.as_str()).is_ip() {
+                    tracing::error!("IP address {} cannot be validated with DNS-01", domain);
+                    return Err(AcmeError::configuration(format!(
+                        "IP address {} cannot be validated with DNS-01; use http-01 or tls-alpn-01",
+                        domain
+                    )));
+                }
+                let Some(ref dns_config) = config.challenge.dns01 else {
                     tracing::error!("DNS-01 challenge selected but no DNS configuration provided");
                     return Err(AcmeError::configuration(
                         "Missing DNS-01 configuration".to_string(),
                     ));
-                }
-                tracing::debug!("DNS-01 configuration found for domain: {}", domain);
+                };
+                let provider = dns_config.primary_provider()?;
+                tracing::debug!(
+                    "DNS-01 provider {} configured for domain: {}",
+                    provider.name,
+                    domain
+                );
             }
         }
 
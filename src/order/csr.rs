@@ -1,11 +1,117 @@
 /// Certificate Signing Request (CSR) generation
+use crate::crypto::{KeyPairGenerator, KeyType};
 use crate::error::Result;
-use rcgen::{CertificateParams, KeyPair};
+use rcgen::{CertificateParams, CustomExtension, DistinguishedName, DnType, KeyPair};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// OID of the TLS Feature extension (RFC 7633).
+pub(crate) const TLS_FEATURE_OID: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 1, 24];
+
+/// DER encoding of the TLS Feature `status_request` (5), i.e. OCSP Must-Staple.
+pub(crate) const TLS_FEATURE_STATUS_REQUEST: &[u8] = &[0x30, 0x03, 0x02, 0x01, 0x05];
+
+/// Subject fields of a certificate request besides the common name.
+/// CAs issuing domain-validated certificates may ignore or reject these.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateSubject {
+    /// Organization (O).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// Organizational unit (OU).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organizational_unit: Option<String>,
+    /// Two-letter country code (C).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// State or province (ST).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// Locality (L).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locality: Option<String>,
+}
+
+impl CertificateSubject {
+    /// Returns true if no subject field is set.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Appends the set fields to a distinguished name.
+    fn apply(&self, name: &mut DistinguishedName) {
+        let fields = [
+            (DnType::CountryName, &self.country),
+            (DnType::StateOrProvinceName, &self.state),
+            (DnType::LocalityName, &self.locality),
+            (DnType::OrganizationName, &self.organization),
+            (DnType::OrganizationalUnitName, &self.organizational_unit),
+        ];
+        for (dn_type, value) in fields {
+            if let Some(value) = value {
+                name.push(dn_type, value.as_str());
+            }
+        }
+    }
+}
+
+/// Options for the key and contents of certificate requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateOptions {
+    /// The type of key generated for new certificates.
+    pub key_type: KeyType,
+    /// Whether to request the OCSP Must-Staple TLS feature (RFC 7633).
+    pub must_staple: bool,
+    /// Subject fields added to the request.
+    pub subject: CertificateSubject,
+    /// Whether renewals keep the private key of the certificate they replace.
+    pub reuse_key: bool,
+}
+
+impl Default for CertificateOptions {
+    fn default() -> Self {
+        Self {
+            key_type: KeyType::EcdsaP256,
+            must_staple: false,
+            subject: CertificateSubject::default(),
+            reuse_key: false,
+        }
+    }
+}
+
+impl CertificateOptions {
+    /// Sets the type of key generated for new certificates.
+    pub fn with_key_type(mut self, key_type: KeyType) -> Self {
+        self.key_type = key_type;
+        self
+    }
+
+    /// Requests the OCSP Must-Staple TLS feature.
+    pub fn with_must_staple(mut self, must_staple: bool) -> Self {
+        self.must_staple = must_staple;
+        self
+    }
+
+    /// Sets the subject fields added to the request.
+    pub fn with_subject(mut self, subject: CertificateSubject) -> Self {
+        self.subject = subject;
+        self
+    }
+
+    /// Keeps the private key of the previous certificate on renewal.
+    pub fn with_reuse_key(mut self, reuse_key: bool) -> Self {
+        self.reuse_key = reuse_key;
+        self
+    }
+}
 
 /// CSR generator for ACME certificates
 pub struct CsrGenerator {
     domains: Vec<String>,
     private_key: Option<KeyPair>,
+    key_type: KeyType,
+    must_staple: bool,
+    subject: CertificateSubject,
 }
 
 impl CsrGenerator {
@@ -14,6 +120,9 @@ impl CsrGenerator {
         Self {
             domains,
             private_key: None,
+            key_type: KeyType::EcdsaP256,
+            must_staple: false,
+            subject: CertificateSubject::default(),
         }
     }
 
@@ -23,6 +132,31 @@ impl CsrGenerator {
         self
     }
 
+    /// Set the type of key generated when no private key is given
+    pub fn with_key_type(mut self, key_type: KeyType) -> Self {
+        self.key_type = key_type;
+        self
+    }
+
+    /// Request the OCSP Must-Staple TLS feature extension
+    pub fn with_must_staple(mut self, must_staple: bool) -> Self {
+        self.must_staple = must_staple;
+        self
+    }
+
+    /// Set subject fields besides the common name
+    pub fn with_subject(mut self, subject: CertificateSubject) -> Self {
+        self.subject = subject;
+        self
+    }
+
+    /// Apply the key type, Must-Staple and subject of `options`
+    pub fn with_options(self, options: &CertificateOptions) -> Self {
+        self.with_key_type(options.key_type)
+            .with_must_staple(options.must_staple)
+            .with_subject(options.subject.clone())
+    }
+
     /// Generate CSR and return (CSR DER, Private Key PEM)
     pub fn generate(&self) -> Result<(Vec<u8>, String)> {
         // Get or generate key pair
@@ -30,17 +164,22 @@ impl CsrGenerator {
         let key_pair = match self.private_key.as_ref() {
             Some(key) => key,
             None => {
-                generated_key = KeyPair::generate().map_err(|e| {
-                    crate::error::AcmeError::crypto(format!("Failed to generate key pair: {}", e))
-                })?;
+                generated_key = KeyPairGenerator::new(self.key_type).generate()?;
                 &generated_key
             }
         };
 
-        // Build certificate params with domains
-        let params = CertificateParams::new(self.domains.clone()).map_err(|e| {
-            crate::error::AcmeError::crypto(format!("Failed to create certificate params: {}", e))
-        })?;
+        // Build certificate params with domains (IP literals become IP SANs)
+        let mut params = csr_params(&self.domains)?;
+        self.subject.apply(&mut params.distinguished_name);
+        if self.must_staple {
+            params
+                .custom_extensions
+                .push(CustomExtension::from_oid_content(
+                    TLS_FEATURE_OID,
+                    TLS_FEATURE_STATUS_REQUEST.to_vec(),
+                ));
+        }
 
         // Generate CSR
         let csr = params.serialize_request(key_pair).map_err(|e| {
@@ -60,9 +199,7 @@ impl CsrGenerator {
             crate::error::AcmeError::crypto(format!("Failed to generate key pair: {}", e))
         })?;
 
-        let params = CertificateParams::new(domains.clone()).map_err(|e| {
-            crate::error::AcmeError::crypto(format!("Failed to create certificate params: {}", e))
-        })?;
+        let params = csr_params(&domains)?;
 
         let csr = params.serialize_request(&key_pair).map_err(|e| {
             crate::error::AcmeError::crypto(format!("Failed to generate CSR: {}", e))
@@ -75,6 +212,27 @@ impl CsrGenerator {
     }
 }
 
+/// Build CSR parameters for the given names.
+/// rcgen encodes IP address literals as IP SANs; the subject carries the first DNS name
+/// as common name, or stays empty when the certificate only covers IP addresses.
+fn csr_params(domains: &[String]) -> Result<CertificateParams> {
+    let mut params = CertificateParams::new(domains.to_vec()).map_err(|e| {
+        crate::error::AcmeError::crypto(format!("Failed to create certificate params: {}", e))
+    })?;
+
+    params.distinguished_name = DistinguishedName::new();
+    if let Some(name) = domains
+        .iter()
+        .find(|d| d.parse::<IpAddr>().is_err() && d.len() <= 64)
+    {
+        params
+            .distinguished_name
+            .push(DnType::CommonName, name.as_str());
+    }
+
+    Ok(params)
+}
+
 /// Parse certificate chain from PEM
 pub fn parse_certificate_chain(pem: &str) -> Result<Vec<Vec<u8>>> {
     let mut certs = Vec::new();
@@ -114,15 +272,28 @@ pub fn verify_certificate_domains(cert_der: &[u8], expected_domains: &[String])
         .unwrap_or(&empty_vec);
 
     let mut cert_domains = Vec::new();
+    let mut cert_ips = Vec::new();
     for san in sans {
-        if let GeneralName::DNSName(domain) = san {
-            cert_domains.push(domain.to_string());
+        match san {
+            GeneralName::DNSName(domain) => cert_domains.push(domain.to_string()),
+            GeneralName::IPAddress(bytes) => {
+                if let Ok(octets) = <[u8; 4]>::try_from(*bytes) {
+                    cert_ips.push(IpAddr::from(octets));
+                } else if let Ok(octets) = <[u8; 16]>::try_from(*bytes) {
+                    cert_ips.push(IpAddr::from(octets));
+                }
+            }
+            _ => {}
         }
     }
 
     // Check if all expected domains are in the certificate
     for expected in expected_domains {
-        if !cert_domains.contains(expected) {
+        let found = match expected.parse::<IpAddr>() {
+            Ok(ip) => cert_ips.contains(&ip),
+            Err(_) => cert_domains.contains(expected),
+        };
+        if !found {
             tracing::warn!("Domain {} not found in certificate", expected);
             return Ok(false);
         }
@@ -157,6 +328,95 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_csr_with_ip_addresses() {
+        use x509_parser::prelude::*;
+
+        let names = vec!["10.0.0.1".to_string(), "2001:db8::1".to_string()];
+        let (csr_der, _) = CsrGenerator::new(names.clone()).generate().unwrap();
+
+        let (_, csr) = X509CertificationRequest::from_der(&csr_der).unwrap();
+        assert_eq!(csr.certification_request_info.subject.iter().count(), 0);
+        let sans: Vec<_> = csr
+            .requested_extensions()
+            .into_iter()
+            .flatten()
+            .filter_map(|ext| match ext {
+                ParsedExtension::SubjectAlternativeName(san) => Some(san.general_names.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(
+            sans,
+            vec![
+                GeneralName::IPAddress(&[10, 0, 0, 1]),
+                GeneralName::IPAddress(
+                    &"2001:db8::1"
+                        .parse::<std::net::Ipv6Addr>()
+                        .unwrap()
+                        .octets()
+                ),
+            ]
+        );
+
+        let key_pair = KeyPair::generate().unwrap();
+        let cert = csr_params(&names).unwrap().self_signed(&key_pair).unwrap();
+        assert!(verify_certificate_domains(cert.der(), &names).unwrap());
+        assert!(!verify_certificate_domains(cert.der(), &["10.0.0.2".to_string()]).unwrap());
+    }
+
+    #[test]
+    fn test_csr_key_type_subject_and_must_staple() {
+        use x509_parser::prelude::*;
+
+        let options = CertificateOptions::default()
+            .with_key_type(KeyType::EcdsaP384)
+            .with_must_staple(true)
+            .with_subject(CertificateSubject {
+                organization: Some("Example Corp".to_string()),
+                country: Some("DE".to_string()),
+                ..Default::default()
+            });
+        let (csr_der, key_pem) = CsrGenerator::new(vec!["example.com".to_string()])
+            .with_options(&options)
+            .generate()
+            .unwrap();
+
+        let key = KeyPair::from_pem(&key_pem).unwrap();
+        assert_eq!(KeyType::from_key_pair(&key).unwrap(), KeyType::EcdsaP384);
+
+        let (_, csr) = X509CertificationRequest::from_der(&csr_der).unwrap();
+        let subject = &csr.certification_request_info.subject;
+        assert_eq!(
+            subject
+                .iter_organization()
+                .next()
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "Example Corp"
+        );
+        assert_eq!(
+            subject.iter_country().next().unwrap().as_str().unwrap(),
+            "DE"
+        );
+        assert_eq!(
+            subject.iter_common_name().next().unwrap().as_str().unwrap(),
+            "example.com"
+        );
+
+        let tls_feature =
+            csr.requested_extensions()
+                .into_iter()
+                .flatten()
+                .find_map(|ext| match ext {
+                    ParsedExtension::UnsupportedExtension { oid } => Some(oid.to_id_string()),
+                    _ => None,
+                });
+        assert_eq!(tls_feature.as_deref(), Some("1.3.6.1.5.5.7.1.24"));
+    }
+
     #[test]
     fn test_parse_certificate_chain() {
         let pem = "-----BEGIN CERTIFICATE-----\nMIIBkTCB+wIJAKHHCgVZU2T/MA0GCSqGSIb3DQEBCwUAMBExDzANBgNVBAMMBnRl\nc3QtMTAeFw0yMDAxMDEwMDAwMDBaFw0yMTAxMDEwMDAwMDBaMBExDzANBgNVBAMM\nBnRlc3QtMTBcMA0GCSqGSIb3DQEBAQUAA0sAMEgCQQC8hCb/c3T8KjL7w3M3i7kR\nXK3i7aZ3E3h+Q6V6TQ==\n-----END CERTIFICATE-----";
@@ -2,11 +2,27 @@ use crate::account::AccountManager;
 /// Order lifecycle management
 use crate::error::Result;
 use crate::order::{Authorization, Challenge, NewOrderRequest, Order};
-use crate::protocol::{DirectoryManager, NonceManager};
+use crate::protocol::{AcmeRequester, DirectoryManager, KeyId, NonceManager};
+use crate::types::Identifier;
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use serde_json::json;
 use std::time::Duration;
+use tokio::time::Instant;
+
+/// Returns how long to wait before polling again: the server's `Retry-After` if given,
+/// otherwise `interval`, never past `deadline`. Returns `None` once the deadline has passed.
+fn poll_delay(
+    retry_after: Option<Duration>,
+    interval: Duration,
+    deadline: Instant,
+) -> Option<Duration> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return None;
+    }
+    Some(retry_after.unwrap_or(interval).min(remaining))
+}
 
 /// Order manager for handling order lifecycle
 pub struct OrderManager<'a> {
@@ -35,49 +51,76 @@ impl<'a> OrderManager<'a> {
         }
     }
 
+    /// Requester signing with the account key
+    fn requester(&self) -> AcmeRequester<'_> {
+        AcmeRequester::new(
+            self.account_manager.get_signer(),
+            self.nonce_manager,
+            self.http_client,
+        )
+    }
+
     /// Create a new order
     pub async fn create_order(&self, request: &NewOrderRequest) -> Result<(String, Order)> {
         let directory = self.directory_manager.get().await?;
-        let nonce = self.nonce_manager.get_nonce().await?;
-
-        // Build JWS header
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": &self.account_id,
-            "nonce": nonce,
-            "url": &directory.new_order,
-        });
 
-        // Build payload
-        let payload = json!(request);
+        // Reject unknown profiles before spending a nonce on the request
+        if let Some(profile) = &request.profile {
+            let offered = directory
+                .meta
+                .as_ref()
+                .map(|m| m.profile_names())
+                .unwrap_or_default();
+            if !offered.contains(&profile.as_str()) {
+                tracing::error!("Certificate profile '{}' is not offered by the CA", profile);
+                let offered = if offered.is_empty() {
+                    "none".to_string()
+                } else {
+                    offered.join(", ")
+                };
+                return Err(crate::error::AcmeError::invalid_input(format!(
+                    "Certificate profile '{}' is not offered by the ACME server (available: {})",
+                    profile, offered
+                )));
+            }
+        }
 
-        // Sign the request
-        let jws = self.account_manager.get_signer().sign(&header, &payload)?;
+        // STAR orders are only accepted by CAs advertising auto-renewal (RFC 8739 Section 3.1.1)
+        if let Some(auto_renewal) = &request.auto_renewal {
+            let Some(limits) = directory.auto_renewal() else {
+                tracing::error!("STAR order requested but the CA does not offer auto-renewal");
+                return Err(crate::error::AcmeError::invalid_input(
+                    "ACME server does not support STAR auto-renewal orders",
+                ));
+            };
+            if auto_renewal.lifetime < limits.min_lifetime {
+                return Err(crate::error::AcmeError::invalid_input(format!(
+                    "STAR certificate lifetime of {}s is below the CA minimum of {}s",
+                    auto_renewal.lifetime, limits.min_lifetime
+                )));
+            }
+            let start = auto_renewal.start_date.unwrap_or_else(jiff::Timestamp::now);
+            let duration = auto_renewal.end_date.as_second() - start.as_second();
+            if duration <= 0 || duration as u64 > limits.max_duration {
+                return Err(crate::error::AcmeError::invalid_input(format!(
+                    "STAR order duration of {}s is outside the CA maximum of {}s",
+                    duration, limits.max_duration
+                )));
+            }
+        }
 
-        // Send request
         let response = self
-            .http_client
-            .post(&directory.new_order)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
-            .await
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to create order: {}", e))
-            })?;
-
-        // Cache nonce
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
+            .requester()
+            .post(
+                &directory.new_order,
+                KeyId::Kid(&self.account_id),
+                &json!(request),
+            )
+            .await?;
 
         // Get order URL from Location header
         let order_url = response
-            .headers()
-            .get("location")
-            .and_then(|h| h.to_str().ok())
+            .location()
             .ok_or_else(|| {
                 crate::error::AcmeError::order(
                     "Missing Location header in order response".to_string(),
@@ -86,22 +129,11 @@ impl<'a> OrderManager<'a> {
             })?
             .to_string();
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(crate::error::AcmeError::order(
-                format!("Failed to create order: HTTP {}", status),
-                error_text,
-            ));
-        }
-
         // Parse order
-        let order: Order = response.json().await.map_err(|e| {
+        let mut order: Order = response.json().map_err(|e| {
             crate::error::AcmeError::order("Failed to parse order".to_string(), e.to_string())
         })?;
+        order.retry_after = response.retry_after();
 
         tracing::info!("Order created: {}", order_url);
         Ok((order_url, order))
@@ -109,172 +141,172 @@ impl<'a> OrderManager<'a> {
 
     /// Get order status
     pub async fn get_order(&self, order_url: &str) -> Result<Order> {
-        let nonce = self.nonce_manager.get_nonce().await?;
+        let response = self
+            .requester()
+            .post_as_get(order_url, &self.account_id)
+            .await?;
 
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": &self.account_id,
-            "nonce": nonce,
-            "url": order_url,
-        });
+        let mut order: Order = response.json().map_err(|e| {
+            crate::error::AcmeError::order("Failed to parse order".to_string(), e.to_string())
+        })?;
+        order.retry_after = response.retry_after();
 
-        let payload = json!({});
-        let jws = self.account_manager.get_signer().sign(&header, &payload)?;
+        Ok(order)
+    }
 
+    /// Cancel a STAR order so the CA stops issuing certificates for it (RFC 8739 Section 2.3)
+    pub async fn cancel_order(&self, order_url: &str) -> Result<Order> {
         let response = self
-            .http_client
-            .post(order_url)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
-            .await
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to get order: {}", e))
-            })?;
-
-        // Cache nonce
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
-
-        let status = response.status();
-        if !status.is_success() {
-            return Err(crate::error::AcmeError::order(
-                format!("Failed to get order: HTTP {}", status),
-                "".to_string(),
-            ));
-        }
+            .requester()
+            .post(
+                order_url,
+                KeyId::Kid(&self.account_id),
+                &json!({ "status": "canceled" }),
+            )
+            .await?;
 
-        let order: Order = response.json().await.map_err(|e| {
+        let order: Order = response.json().map_err(|e| {
             crate::error::AcmeError::order("Failed to parse order".to_string(), e.to_string())
         })?;
 
+        tracing::info!("Order canceled: {}", order_url);
         Ok(order)
     }
 
     /// Get authorization
     pub async fn get_authorization(&self, auth_url: &str) -> Result<Authorization> {
-        let nonce = self.nonce_manager.get_nonce().await?;
-
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": &self.account_id,
-            "nonce": nonce,
-            "url": auth_url,
-        });
+        let response = self
+            .requester()
+            .post_as_get(auth_url, &self.account_id)
+            .await?;
 
-        let payload = json!({});
-        let jws = self.account_manager.get_signer().sign(&header, &payload)?;
+        let mut auth: Authorization = response.json().map_err(|e| {
+            crate::error::AcmeError::order(
+                "Failed to parse authorization".to_string(),
+                e.to_string(),
+            )
+        })?;
+        auth.retry_after = response.retry_after();
 
-        let response = self
-            .http_client
-            .post(auth_url)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
-            .await
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to get authorization: {}", e))
-            })?;
-
-        // Cache nonce
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
+        Ok(auth)
+    }
 
-        let status = response.status();
-        if !status.is_success() {
-            return Err(crate::error::AcmeError::order(
-                format!("Failed to get authorization: HTTP {}", status),
-                "".to_string(),
+    /// Pre-authorize an identifier ahead of ordering (RFC 8555 Section 7.4.1)
+    pub async fn new_authorization(
+        &self,
+        identifier: &Identifier,
+    ) -> Result<(String, Authorization)> {
+        let directory = self.directory_manager.get().await?;
+        let Some(new_authz) = directory.new_authz else {
+            return Err(crate::error::AcmeError::protocol(
+                "ACME server does not support pre-authorization (no newAuthz endpoint)",
             ));
+        };
+
+        // Wildcard authorizations can only be obtained through an order
+        if identifier.value.starts_with("*.") {
+            return Err(crate::error::AcmeError::invalid_input(format!(
+                "Cannot pre-authorize wildcard identifier '{}'",
+                identifier.value
+            )));
         }
 
-        let auth: Authorization = response.json().await.map_err(|e| {
+        let response = self
+            .requester()
+            .post(
+                &new_authz,
+                KeyId::Kid(&self.account_id),
+                &json!({ "identifier": identifier }),
+            )
+            .await?;
+
+        let auth_url = response
+            .location()
+            .ok_or_else(|| {
+                crate::error::AcmeError::order(
+                    "Missing Location header in authorization response".to_string(),
+                    "".to_string(),
+                )
+            })?
+            .to_string();
+
+        let auth: Authorization = response.json().map_err(|e| {
             crate::error::AcmeError::order(
                 "Failed to parse authorization".to_string(),
                 e.to_string(),
             )
         })?;
 
-        Ok(auth)
+        tracing::info!(
+            "Authorization created for {}: {}",
+            identifier.value,
+            auth_url
+        );
+        Ok((auth_url, auth))
     }
 
-    /// Respond to challenge (tell ACME server we're ready)
-    pub async fn respond_to_challenge(&self, challenge_url: &str) -> Result<Challenge> {
-        let nonce = self.nonce_manager.get_nonce().await?;
+    /// Deactivate an authorization (RFC 8555 Section 7.5.2)
+    pub async fn deactivate_authorization(&self, auth_url: &str) -> Result<Authorization> {
+        let response = self
+            .requester()
+            .post(
+                auth_url,
+                KeyId::Kid(&self.account_id),
+                &json!({ "status": "deactivated" }),
+            )
+            .await?;
 
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": &self.account_id,
-            "nonce": nonce,
-            "url": challenge_url,
-        });
+        let auth: Authorization = response.json().map_err(|e| {
+            crate::error::AcmeError::order(
+                "Failed to parse authorization".to_string(),
+                e.to_string(),
+            )
+        })?;
 
-        // Empty payload triggers validation
-        let payload = json!({});
-        let jws = self.account_manager.get_signer().sign(&header, &payload)?;
+        tracing::info!("Authorization deactivated: {}", auth_url);
+        Ok(auth)
+    }
 
+    /// Respond to challenge (tell ACME server we're ready)
+    pub async fn respond_to_challenge(&self, challenge_url: &str) -> Result<Challenge> {
+        // Empty JSON object payload triggers validation
         let response = self
-            .http_client
-            .post(challenge_url)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
-            .await
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to respond to challenge: {}", e))
-            })?;
-
-        // Cache nonce
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
+            .requester()
+            .post(challenge_url, KeyId::Kid(&self.account_id), &json!({}))
+            .await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            return Err(crate::error::AcmeError::challenge(
-                "unknown".to_string(),
-                format!("Failed to respond to challenge: HTTP {}", status),
-            ));
-        }
-
-        let challenge: Challenge = response.json().await.map_err(|e| {
+        let mut challenge: Challenge = response.json().map_err(|e| {
             crate::error::AcmeError::challenge(
                 "unknown".to_string(),
                 format!("Failed to parse challenge: {}", e),
             )
         })?;
+        challenge.retry_after = response.retry_after();
 
         tracing::info!("Challenge response submitted: {}", challenge_url);
         Ok(challenge)
     }
 
-    /// Poll order until it reaches a final state
+    /// Poll order until it leaves the "pending" and "processing" states or `timeout` elapses.
+    /// Waits as long as the server's `Retry-After` asks, or `interval` when it gives none.
     pub async fn poll_order(
         &self,
         order_url: &str,
-        max_attempts: u32,
+        timeout: Duration,
         interval: Duration,
     ) -> Result<Order> {
-        for attempt in 0..max_attempts {
+        let deadline = Instant::now() + timeout;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
             let order = self.get_order(order_url).await?;
 
             match order.status.as_str() {
                 "ready" | "valid" | "invalid" => {
-                    tracing::info!("Order status: {} (attempt {})", order.status, attempt + 1);
+                    tracing::info!("Order status: {} (attempt {})", order.status, attempt);
                     return Ok(order);
                 }
-                "pending" | "processing" => {
-                    tracing::debug!("Order still pending, waiting... (attempt {})", attempt + 1);
-                    tokio::time::sleep(interval).await;
-                }
+                "pending" | "processing" => {}
                 status => {
                     return Err(crate::error::AcmeError::order(
                         format!("Unexpected order status: {}", status),
@@ -282,25 +314,65 @@ impl<'a> OrderManager<'a> {
                     ));
                 }
             }
+
+            let Some(delay) = poll_delay(order.retry_after, interval, deadline) else {
+                return Err(crate::error::AcmeError::timeout(format!(
+                    "Order {} still {} after {:?}",
+                    order_url, order.status, timeout
+                )));
+            };
+            tracing::debug!(
+                "Order is {}, polling again in {:?} (attempt {})",
+                order.status,
+                delay,
+                attempt
+            );
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        Err(crate::error::AcmeError::order(
-            "Order polling timeout".to_string(),
-            format!("Exceeded {} attempts", max_attempts),
-        ))
+    /// Poll authorization until it leaves the "pending" state or `timeout` elapses.
+    /// Waits as long as the server's `Retry-After` asks, or `interval` when it gives none.
+    pub async fn poll_authorization(
+        &self,
+        auth_url: &str,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<Authorization> {
+        let deadline = Instant::now() + timeout;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let auth = self.get_authorization(auth_url).await?;
+
+            if auth.status != "pending" {
+                tracing::info!(
+                    "Authorization for {} is {} (attempt {})",
+                    auth.identifier.value,
+                    auth.status,
+                    attempt
+                );
+                return Ok(auth);
+            }
+
+            let Some(delay) = poll_delay(auth.retry_after, interval, deadline) else {
+                return Err(crate::error::AcmeError::timeout(format!(
+                    "Authorization {} still pending after {:?}",
+                    auth_url, timeout
+                )));
+            };
+            tracing::debug!(
+                "Authorization for {} still pending, polling again in {:?} (attempt {})",
+                auth.identifier.value,
+                delay,
+                attempt
+            );
+            tokio::time::sleep(delay).await;
+        }
     }
 
     /// Finalize order with CSR
     pub async fn finalize_order(&self, finalize_url: &str, csr_der: &[u8]) -> Result<Order> {
-        let nonce = self.nonce_manager.get_nonce().await?;
-
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": &self.account_id,
-            "nonce": nonce,
-            "url": finalize_url,
-        });
-
         // Encode CSR as base64url
         let csr_b64 = URL_SAFE_NO_PAD.encode(csr_der);
 
@@ -308,44 +380,18 @@ impl<'a> OrderManager<'a> {
             "csr": csr_b64
         });
 
-        let jws = self.account_manager.get_signer().sign(&header, &payload)?;
-
         let response = self
-            .http_client
-            .post(finalize_url)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
-            .await
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to finalize order: {}", e))
-            })?;
-
-        // Cache nonce
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(crate::error::AcmeError::order(
-                format!("Failed to finalize order: HTTP {}", status),
-                error_text,
-            ));
-        }
+            .requester()
+            .post(finalize_url, KeyId::Kid(&self.account_id), &payload)
+            .await?;
 
-        let order: Order = response.json().await.map_err(|e| {
+        let mut order: Order = response.json().map_err(|e| {
             crate::error::AcmeError::order(
                 "Failed to parse finalized order".to_string(),
                 e.to_string(),
             )
         })?;
+        order.retry_after = response.retry_after();
 
         tracing::info!("Order finalized successfully");
         Ok(order)
@@ -353,57 +399,72 @@ impl<'a> OrderManager<'a> {
 
     /// Download certificate
     pub async fn download_certificate(&self, certificate_url: &str) -> Result<String> {
-        let nonce = self.nonce_manager.get_nonce().await?;
+        let response = self
+            .requester()
+            .post_as_get(certificate_url, &self.account_id)
+            .await?;
 
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": &self.account_id,
-            "nonce": nonce,
-            "url": certificate_url,
-        });
+        let cert_pem = response.text().map_err(|e| {
+            crate::error::AcmeError::certificate(format!("Failed to read certificate: {}", e))
+        })?;
 
-        let payload = json!({});
-        let jws = self.account_manager.get_signer().sign(&header, &payload)?;
+        tracing::info!("Certificate downloaded successfully");
+        Ok(cert_pem)
+    }
 
+    /// Download the default certificate chain followed by any alternate chains
+    /// advertised with `Link: rel="alternate"` (RFC 8555 Section 7.4.2)
+    pub async fn download_certificate_chains(&self, certificate_url: &str) -> Result<Vec<String>> {
         let response = self
-            .http_client
-            .post(certificate_url)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
-            .await
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to download certificate: {}", e))
-            })?;
-
-        // Cache nonce
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
-        }
+            .requester()
+            .post_as_get(certificate_url, &self.account_id)
+            .await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            return Err(crate::error::AcmeError::certificate(format!(
-                "Failed to download certificate: HTTP {}",
-                status
-            )));
-        }
-
-        let cert_pem = response.text().await.map_err(|e| {
+        let default_pem = response.text().map_err(|e| {
             crate::error::AcmeError::certificate(format!("Failed to read certificate: {}", e))
         })?;
+        let mut chains = vec![default_pem];
+
+        for alternate_url in response.links("alternate") {
+            tracing::debug!("Downloading alternate chain from: {}", alternate_url);
+            match self.download_certificate(&alternate_url).await {
+                Ok(pem) => chains.push(pem),
+                Err(e) => tracing::warn!(
+                    "Skipping alternate chain {} that failed to download: {}",
+                    alternate_url,
+                    e
+                ),
+            }
+        }
 
-        tracing::info!("Certificate downloaded successfully");
-        Ok(cert_pem)
+        tracing::info!("Downloaded {} certificate chain(s)", chains.len());
+        Ok(chains)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_order_manager_creation() {
         // This is a compile test - actual tests require full ACME setup
     }
+
+    #[test]
+    fn test_poll_delay() {
+        let interval = Duration::from_secs(2);
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(poll_delay(None, interval, deadline), Some(interval));
+        assert_eq!(
+            poll_delay(Some(Duration::from_secs(10)), interval, deadline),
+            Some(Duration::from_secs(10))
+        );
+
+        // Retry-After is capped at the remaining time
+        let delay = poll_delay(Some(Duration::from_secs(3600)), interval, deadline).unwrap();
+        assert!(delay <= Duration::from_secs(60));
+
+        assert_eq!(poll_delay(None, interval, Instant::now()), None);
+    }
 }
@@ -4,7 +4,12 @@ pub mod manager;
 pub mod objects;
 pub mod revocation;
 
-pub use csr::{CsrGenerator, parse_certificate_chain, verify_certificate_domains};
+pub use csr::{
+    CertificateOptions, CertificateSubject, CsrGenerator, parse_certificate_chain,
+    verify_certificate_domains,
+};
 pub use manager::OrderManager;
-pub use objects::{Authorization, Challenge, FinalizationRequest, NewOrderRequest, Order};
+pub use objects::{
+    Authorization, AutoRenewal, Challenge, FinalizationRequest, NewOrderRequest, Order,
+};
 pub use revocation::CertificateRevocation;
@@ -2,7 +2,9 @@
 /// This module defines the structures for orders, authorizations, and challenges
 /// as specified in RFC 8555.
 use crate::types::{AuthorizationStatus, Identifier, OrderStatus};
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Represents an ACME authorization challenge.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +37,10 @@ pub struct Challenge {
     /// Error information if the challenge validation failed.
     #[serde(default)]
     pub error: Option<serde_json::Value>,
+
+    /// How long the server asked the client to wait before polling again (`Retry-After`).
+    #[serde(skip)]
+    pub retry_after: Option<Duration>,
 }
 
 /// Represents an authorization for a specific identifier (e.g., a domain).
@@ -59,6 +65,10 @@ pub struct Authorization {
     /// A convenience field for storing combined challenge data.
     #[serde(default)]
     pub combined_challenges: Option<Vec<Challenge>>,
+
+    /// How long the server asked the client to wait before polling again (`Retry-After`).
+    #[serde(skip)]
+    pub retry_after: Option<Duration>,
 }
 
 impl Authorization {
@@ -97,9 +107,33 @@ pub struct Order {
     #[serde(default)]
     pub certificate: Option<String>,
 
+    /// The certificate profile the order was created under, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// The auto-renewal parameters of a STAR order (RFC 8739).
+    #[serde(
+        rename = "auto-renewal",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub auto_renewal: Option<AutoRenewal>,
+
+    /// The rolling URL of the current short-term certificate of a valid STAR order.
+    #[serde(
+        rename = "star-certificate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub star_certificate: Option<String>,
+
     /// A convenience field for storing combined authorization data.
     #[serde(skip)]
     pub combined_authorizations: Option<Vec<Authorization>>,
+
+    /// How long the server asked the client to wait before polling again (`Retry-After`).
+    #[serde(skip)]
+    pub retry_after: Option<Duration>,
 }
 
 impl Order {
@@ -124,6 +158,61 @@ impl Order {
     }
 }
 
+/// Auto-renewal parameters of a Short-Term Automatically Renewed (STAR) order (RFC 8739).
+/// The CA keeps issuing certificates of `lifetime` seconds until `end_date`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AutoRenewal {
+    /// When the first certificate becomes valid; defaults to the order's issuance time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<Timestamp>,
+
+    /// When the last certificate expires.
+    pub end_date: Timestamp,
+
+    /// Validity period of each certificate, in seconds.
+    pub lifetime: u64,
+
+    /// Overlap between consecutive certificates, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifetime_adjust: Option<u64>,
+
+    /// Whether the certificates may be fetched with unauthenticated GET requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_certificate_get: Option<bool>,
+}
+
+impl AutoRenewal {
+    /// Creates auto-renewal parameters for certificates of `lifetime` renewed until `end_date`.
+    pub fn new(end_date: Timestamp, lifetime: Duration) -> Self {
+        Self {
+            start_date: None,
+            end_date,
+            lifetime: lifetime.as_secs(),
+            lifetime_adjust: None,
+            allow_certificate_get: None,
+        }
+    }
+
+    /// Sets when the first certificate becomes valid.
+    pub fn with_start_date(mut self, start_date: Timestamp) -> Self {
+        self.start_date = Some(start_date);
+        self
+    }
+
+    /// Sets the overlap between consecutive certificates.
+    pub fn with_lifetime_adjust(mut self, adjust: Duration) -> Self {
+        self.lifetime_adjust = Some(adjust.as_secs());
+        self
+    }
+
+    /// Asks the CA to allow unauthenticated GET requests for the certificates.
+    pub fn with_allow_certificate_get(mut self, allow: bool) -> Self {
+        self.allow_certificate_get = Some(allow);
+        self
+    }
+}
+
 /// A request to create a new certificate order.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewOrderRequest {
@@ -139,18 +228,38 @@ pub struct NewOrderRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "notAfter")]
     pub not_after: Option<String>,
+
+    /// ARI certificate identifier of the certificate this order replaces (RFC 9773).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+
+    /// Name of the certificate profile to issue under, as advertised in the directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Requests a STAR order with these auto-renewal parameters (RFC 8739).
+    #[serde(
+        rename = "auto-renewal",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub auto_renewal: Option<AutoRenewal>,
 }
 
 impl NewOrderRequest {
     /// Creates a new order request for the specified list of domains.
+    /// IP address literals are requested as IP identifiers (RFC 8738).
     pub fn new(domains: Vec<String>) -> Self {
         tracing::debug!("Creating NewOrderRequest for domains: {:?}", domains);
-        let identifiers = domains.into_iter().map(Identifier::dns).collect();
+        let identifiers = domains.into_iter().map(Identifier::from_name).collect();
 
         Self {
             identifiers,
             not_before: None,
             not_after: None,
+            replaces: None,
+            profile: None,
+            auto_renewal: None,
         }
     }
 
@@ -165,6 +274,24 @@ impl NewOrderRequest {
         self.not_after = Some(not_after);
         self
     }
+
+    /// Marks this order as a renewal of the certificate with the given ARI identifier.
+    pub fn with_replaces(mut self, cert_id: String) -> Self {
+        self.replaces = Some(cert_id);
+        self
+    }
+
+    /// Requests issuance under the named certificate profile.
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Requests a STAR order that the CA renews automatically (RFC 8739).
+    pub fn with_auto_renewal(mut self, auto_renewal: AutoRenewal) -> Self {
+        self.auto_renewal = Some(auto_renewal);
+        self
+    }
 }
 
 /// A request to finalize an order by submitting a CSR.
@@ -260,4 +387,50 @@ mod tests {
         assert_eq!(req.identifiers[0].value, "example.com");
         assert_eq!(req.identifiers[0].id_type, "dns");
     }
+
+    #[test]
+    fn test_new_order_request_with_ip() {
+        let req = NewOrderRequest::new(vec!["example.com".to_string(), "10.0.0.1".to_string()]);
+        let json = serde_json::to_value(&req).unwrap();
+
+        assert_eq!(json["identifiers"][0]["type"], "dns");
+        assert_eq!(json["identifiers"][1]["type"], "ip");
+        assert_eq!(json["identifiers"][1]["value"], "10.0.0.1");
+    }
+
+    #[test]
+    fn test_star_order_request() {
+        let end_date: Timestamp = "2026-12-01T00:00:00Z".parse().unwrap();
+        let req = NewOrderRequest::new(vec!["example.com".to_string()]).with_auto_renewal(
+            AutoRenewal::new(end_date, Duration::from_secs(345_600))
+                .with_lifetime_adjust(Duration::from_secs(86_400)),
+        );
+        let json = serde_json::to_value(&req).unwrap();
+
+        assert_eq!(json["auto-renewal"]["end-date"], "2026-12-01T00:00:00Z");
+        assert_eq!(json["auto-renewal"]["lifetime"], 345_600);
+        assert_eq!(json["auto-renewal"]["lifetime-adjust"], 86_400);
+        assert!(json["auto-renewal"].get("start-date").is_none());
+
+        let order: Order = serde_json::from_str(
+            r#"{
+            "status": "valid",
+            "expires": "2026-12-01T00:00:00Z",
+            "identifiers": [{"type": "dns", "value": "example.com"}],
+            "authorizations": ["https://example.com/acme/authz/1"],
+            "finalize": "https://example.com/acme/finalize/1",
+            "auto-renewal": {
+                "end-date": "2026-12-01T00:00:00Z",
+                "lifetime": 345600
+            },
+            "star-certificate": "https://example.com/acme/cert/mAt3xBGaobw"
+        }"#,
+        )
+        .expect("Failed to parse STAR order");
+        assert_eq!(order.auto_renewal.unwrap().end_date, end_date);
+        assert_eq!(
+            order.star_certificate.as_deref(),
+            Some("https://example.com/acme/cert/mAt3xBGaobw")
+        );
+    }
 }
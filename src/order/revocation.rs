@@ -1,21 +1,25 @@
 /// Certificate revocation implementation
 use crate::account::AccountManager;
-use crate::error::Result;
+use crate::error::{AcmeError, Result};
+use crate::protocol::KeyId;
 use crate::types::RevocationReason;
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use serde_json::json;
+use x509_parser::prelude::{FromDer, X509Certificate};
 
 /// Certificate revocation handler
 pub struct CertificateRevocation<'a> {
+    /// Signs the request: the account key, or the certificate key for `jwk` revocation.
     account_manager: &'a AccountManager<'a>,
-    account_id: String,
+    /// The account URL used as `kid`; `None` when signing with the certificate key.
+    account_id: Option<String>,
     certificate_der: Vec<u8>,
     reason: Option<RevocationReason>,
 }
 
 impl<'a> CertificateRevocation<'a> {
-    /// Create a new revocation request
+    /// Create a new revocation request signed by the account that holds the certificate
     pub fn new(
         account_manager: &'a AccountManager<'a>,
         account_id: impl Into<String>,
@@ -23,7 +27,22 @@ impl<'a> CertificateRevocation<'a> {
     ) -> Self {
         Self {
             account_manager,
-            account_id: account_id.into(),
+            account_id: Some(account_id.into()),
+            certificate_der,
+            reason: None,
+        }
+    }
+
+    /// Create a revocation request signed with the certificate's own private key (RFC 8555
+    /// Section 7.6), for when the issuing account is unknown or lost, e.g. after a key compromise.
+    /// `key_manager` must be built over the certificate key pair; its public key is sent as `jwk`.
+    pub fn with_certificate_key(
+        key_manager: &'a AccountManager<'a>,
+        certificate_der: Vec<u8>,
+    ) -> Self {
+        Self {
+            account_manager: key_manager,
+            account_id: None,
             certificate_der,
             reason: None,
         }
@@ -37,9 +56,12 @@ impl<'a> CertificateRevocation<'a> {
 
     /// Execute revocation
     pub async fn revoke(&self) -> Result<()> {
+        if self.account_id.is_none() {
+            self.check_certificate_key()?;
+        }
+
         let directory = self.account_manager.directory_manager.get().await?;
         let revoke_url = directory.revoke_cert;
-        let nonce = self.account_manager.nonce_manager.get_nonce().await?;
 
         let cert_b64 = URL_SAFE_NO_PAD.encode(&self.certificate_der);
 
@@ -51,50 +73,67 @@ impl<'a> CertificateRevocation<'a> {
             payload["reason"] = json!(reason.as_u8());
         }
 
-        let header = json!({
-            "alg": "EdDSA",
-            "kid": self.account_id,
-            "nonce": nonce,
-            "url": revoke_url,
-        });
+        let key_id = match &self.account_id {
+            Some(account_id) => KeyId::Kid(account_id),
+            None => KeyId::Jwk(self.account_manager.get_jwk()),
+        };
 
-        let jws = self.account_manager.signer.sign(&header, &payload)?;
-
-        let response = self
-            .account_manager
-            .http_client
-            .post(&revoke_url)
-            .header("Content-Type", "application/jose+json")
-            .body(jws)
-            .send()
+        self.account_manager
+            .requester()
+            .post(&revoke_url, key_id, &payload)
             .await
-            .map_err(|e| {
-                crate::error::AcmeError::transport(format!("Failed to revoke certificate: {}", e))
-            })?;
-
-        // Cache nonce
-        if let Some(nonce_header) = response.headers().get("replay-nonce")
-            && let Ok(nonce_str) = nonce_header.to_str()
-        {
-            self.account_manager
-                .nonce_manager
-                .cache_nonce(nonce_str.to_string())
-                .await;
-        }
+            .inspect_err(|e| tracing::error!("Certificate revocation failed: {}", e))?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(crate::error::AcmeError::order(
-                format!("Failed to revoke certificate: HTTP {}", status),
-                error_text,
+        tracing::info!("Certificate revoked successfully");
+        Ok(())
+    }
+
+    /// Fails early if the signing key is not the key of the certificate being revoked,
+    /// which the CA would otherwise reject as unauthorized.
+    fn check_certificate_key(&self) -> Result<()> {
+        let (_, cert) = X509Certificate::from_der(&self.certificate_der).map_err(|e| {
+            tracing::error!("Failed to parse certificate for revocation: {}", e);
+            AcmeError::certificate(format!("Failed to parse certificate: {}", e))
+        })?;
+
+        let cert_key = cert.public_key().subject_public_key.data.as_ref();
+        if cert_key != self.account_manager.key_pair.0.public_key_raw() {
+            return Err(AcmeError::invalid_input(
+                "The private key does not belong to the certificate being revoked",
             ));
         }
-
-        tracing::info!("Certificate revoked successfully");
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::KeyPair;
+    use crate::protocol::{DirectoryManager, NonceManager};
+
+    #[test]
+    fn test_certificate_key_must_match() {
+        let cert_key = KeyPair::generate().unwrap();
+        let cert = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+            .unwrap()
+            .self_signed(&cert_key.0)
+            .unwrap();
+
+        let http_client = reqwest::Client::new();
+        let directory = DirectoryManager::new("https://ca.example/directory", http_client.clone());
+        let nonces = NonceManager::new("https://ca.example/new-nonce", http_client.clone());
+
+        let manager = AccountManager::new(&cert_key, &nonces, &directory, &http_client).unwrap();
+        let revocation = CertificateRevocation::with_certificate_key(&manager, cert.der().to_vec());
+        assert!(revocation.check_certificate_key().is_ok());
+
+        let other_key = KeyPair::generate().unwrap();
+        let manager = AccountManager::new(&other_key, &nonces, &directory, &http_client).unwrap();
+        let revocation = CertificateRevocation::with_certificate_key(&manager, cert.der().to_vec());
+        assert!(matches!(
+            revocation.check_certificate_key(),
+            Err(AcmeError::InvalidInput(_))
+        ));
+    }
+}
@@ -2,6 +2,7 @@
 /// This module handles the discovery of ACME service endpoints from the directory URL.
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -20,6 +21,10 @@ pub struct Directory {
     #[serde(rename = "newOrder")]
     pub new_order: String,
 
+    /// Endpoint for pre-authorizing identifiers, if the server supports it (RFC 8555 Section 7.4.1).
+    #[serde(rename = "newAuthz", default, skip_serializing_if = "Option::is_none")]
+    pub new_authz: Option<String>,
+
     /// Endpoint for revoking a certificate.
     #[serde(rename = "revokeCert")]
     pub revoke_cert: String,
@@ -28,6 +33,14 @@ pub struct Directory {
     #[serde(rename = "keyChange")]
     pub key_change: String,
 
+    /// Endpoint for ACME Renewal Information (RFC 9773), if the server supports ARI.
+    #[serde(
+        rename = "renewalInfo",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub renewal_info: Option<String>,
+
     /// Optional metadata provided by the ACME server.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<DirectoryMeta>,
@@ -50,6 +63,61 @@ pub struct DirectoryMeta {
     /// Indicates whether the server requires an external account binding.
     #[serde(rename = "externalAccountRequired")]
     pub external_account_required: Option<bool>,
+
+    /// Certificate profiles offered by the server, mapping profile name to a
+    /// human-readable description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<HashMap<String, String>>,
+
+    /// STAR capabilities, present if the server supports auto-renewal orders (RFC 8739).
+    #[serde(
+        rename = "auto-renewal",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub auto_renewal: Option<AutoRenewalMeta>,
+}
+
+/// Limits the server places on STAR orders (RFC 8739 Section 3.1.1).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AutoRenewalMeta {
+    /// Minimum lifetime of a short-term certificate, in seconds.
+    pub min_lifetime: u64,
+
+    /// Maximum total duration of a STAR order, in seconds.
+    pub max_duration: u64,
+
+    /// Whether the server allows fetching STAR certificates with unauthenticated GET.
+    #[serde(default)]
+    pub allow_certificate_get: bool,
+}
+
+impl DirectoryMeta {
+    /// Returns the names of the certificate profiles offered by the server, sorted.
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .profiles
+            .iter()
+            .flat_map(|p| p.keys().map(String::as_str))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns true if the server advertises the given certificate profile.
+    pub fn supports_profile(&self, profile: &str) -> bool {
+        self.profiles
+            .as_ref()
+            .is_some_and(|p| p.contains_key(profile))
+    }
+}
+
+impl Directory {
+    /// Returns the server's STAR limits, if it supports auto-renewal orders.
+    pub fn auto_renewal(&self) -> Option<&AutoRenewalMeta> {
+        self.meta.as_ref().and_then(|m| m.auto_renewal.as_ref())
+    }
 }
 
 /// A thread-safe manager for the ACME directory with caching capabilities.
@@ -148,6 +216,26 @@ mod tests {
         let dir: Directory = serde_json::from_str(json).expect("Failed to parse directory");
         assert_eq!(dir.new_nonce, "https://example.com/acme/new-nonce");
         assert_eq!(dir.new_account, "https://example.com/acme/new-account");
+        assert!(dir.renewal_info.is_none());
+        assert!(dir.new_authz.is_none());
+    }
+
+    #[test]
+    fn test_directory_with_new_authz() {
+        let json = r#"{
+            "newNonce": "https://example.com/acme/new-nonce",
+            "newAccount": "https://example.com/acme/new-account",
+            "newOrder": "https://example.com/acme/new-order",
+            "newAuthz": "https://example.com/acme/new-authz",
+            "revokeCert": "https://example.com/acme/revoke-cert",
+            "keyChange": "https://example.com/acme/key-change"
+        }"#;
+
+        let dir: Directory = serde_json::from_str(json).expect("Failed to parse directory");
+        assert_eq!(
+            dir.new_authz.as_deref(),
+            Some("https://example.com/acme/new-authz")
+        );
     }
 
     #[test]
@@ -167,12 +255,58 @@ mod tests {
         }"#;
 
         let dir: Directory = serde_json::from_str(json).expect("Failed to parse directory");
-        assert!(dir.meta.is_some());
-        let meta = dir.meta.unwrap();
+        assert!(dir.auto_renewal().is_none());
+        let meta = dir.meta.expect("directory meta");
         assert_eq!(
             meta.terms_of_service,
             Some("https://example.com/tos".to_string())
         );
         assert_eq!(meta.external_account_required, Some(false));
+        assert!(meta.profiles.is_none());
+        assert!(!meta.supports_profile("classic"));
+    }
+
+    #[test]
+    fn test_directory_meta_auto_renewal() {
+        let json = r#"{
+            "newNonce": "https://example.com/acme/new-nonce",
+            "newAccount": "https://example.com/acme/new-account",
+            "newOrder": "https://example.com/acme/new-order",
+            "revokeCert": "https://example.com/acme/revoke-cert",
+            "keyChange": "https://example.com/acme/key-change",
+            "meta": {
+                "auto-renewal": {
+                    "min-lifetime": 86400,
+                    "max-duration": 31536000,
+                    "allow-certificate-get": true
+                }
+            }
+        }"#;
+
+        let dir: Directory = serde_json::from_str(json).expect("Failed to parse directory");
+        let star = dir.auto_renewal().expect("auto-renewal meta");
+        assert_eq!(star.min_lifetime, 86400);
+        assert_eq!(star.max_duration, 31_536_000);
+        assert!(star.allow_certificate_get);
+    }
+
+    #[test]
+    fn test_directory_meta_profiles() {
+        let json = r#"{
+            "termsOfService": "https://example.com/tos",
+            "profiles": {
+                "tlsserver": "https://example.com/docs/profiles#tlsserver",
+                "classic": "https://example.com/docs/profiles#classic",
+                "shortlived": "https://example.com/docs/profiles#shortlived"
+            }
+        }"#;
+
+        let meta: DirectoryMeta = serde_json::from_str(json).expect("Failed to parse meta");
+        assert!(meta.supports_profile("shortlived"));
+        assert!(!meta.supports_profile("unknown"));
+        assert_eq!(
+            meta.profile_names(),
+            vec!["classic", "shortlived", "tlsserver"]
+        );
     }
 }
@@ -1,5 +1,6 @@
 /// JSON Web Key (JWK) implementation for ACME
-use crate::error::Result;
+use crate::crypto::KeyType;
+use crate::error::{AcmeError, Result};
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use serde::{Deserialize, Serialize};
@@ -14,7 +15,7 @@ pub struct Jwk {
     pub kty: String,
 
     /// Use (typically "sig" for signing)
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "use", skip_serializing_if = "Option::is_none")]
     pub use_: Option<String>,
 
     /// Key operations
@@ -70,6 +71,51 @@ impl Jwk {
         }
     }
 
+    /// Build the public JWK for an rcgen key pair (Ed25519, ECDSA or RSA)
+    pub fn from_key_pair(key_pair: &rcgen::KeyPair) -> Result<Self> {
+        let key_type = KeyType::from_key_pair(key_pair)?;
+        let raw = key_pair.public_key_raw();
+
+        match key_type {
+            KeyType::Ed25519 => Ok(Self::new_ed25519(URL_SAFE_NO_PAD.encode(raw))),
+            KeyType::EcdsaP256 | KeyType::EcdsaP384 | KeyType::EcdsaP521 => {
+                // Uncompressed SEC1 point: 0x04 || x || y
+                if raw.first() != Some(&0x04) || raw.len() % 2 != 1 {
+                    return Err(AcmeError::crypto("Unexpected EC public key encoding"));
+                }
+                let coord_len = (raw.len() - 1) / 2;
+                let crv = match key_type {
+                    KeyType::EcdsaP256 => "P-256",
+                    KeyType::EcdsaP384 => "P-384",
+                    _ => "P-521",
+                };
+                Ok(Self::new_ec(
+                    crv,
+                    URL_SAFE_NO_PAD.encode(&raw[1..1 + coord_len]),
+                    URL_SAFE_NO_PAD.encode(&raw[1 + coord_len..]),
+                ))
+            }
+            KeyType::Rsa2048 | KeyType::Rsa3072 | KeyType::Rsa4096 => {
+                use rcgen::PublicKeyData;
+                use x509_parser::prelude::FromDer;
+                use x509_parser::public_key::PublicKey;
+                use x509_parser::x509::SubjectPublicKeyInfo;
+
+                let spki_der = key_pair.subject_public_key_info();
+                let (_, spki) = SubjectPublicKeyInfo::from_der(&spki_der).map_err(|e| {
+                    AcmeError::crypto(format!("Failed to parse RSA public key: {}", e))
+                })?;
+                match spki.parsed() {
+                    Ok(PublicKey::RSA(rsa)) => Ok(Self::new_rsa(
+                        URL_SAFE_NO_PAD.encode(strip_leading_zeros(rsa.modulus)),
+                        URL_SAFE_NO_PAD.encode(strip_leading_zeros(rsa.exponent)),
+                    )),
+                    _ => Err(AcmeError::crypto("Unexpected RSA public key encoding")),
+                }
+            }
+        }
+    }
+
     /// Generate JWK thumbprint according to RFC 7638
     /// Uses SHA-256 hash for the thumbprint
     pub fn thumbprint_sha256(&self) -> Result<String> {
@@ -182,6 +228,12 @@ impl Jwk {
     }
 }
 
+/// Strip the sign-padding zero bytes from a DER integer (JWK uses unsigned big-endian)
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,6 +292,30 @@ mod tests {
         assert!(!thumbprint.is_empty());
     }
 
+    #[test]
+    fn test_from_key_pair() {
+        let ec = rcgen::KeyPair::generate_for(&rcgen::PKCS_ECDSA_P384_SHA384).unwrap();
+        let jwk = Jwk::from_key_pair(&ec).unwrap();
+        assert_eq!(jwk.kty, "EC");
+        assert_eq!(jwk.params["crv"], "P-384");
+        let x = URL_SAFE_NO_PAD
+            .decode(jwk.params["x"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(x.len(), 48);
+
+        let rsa =
+            rcgen::KeyPair::generate_rsa_for(&rcgen::PKCS_RSA_SHA256, rcgen::RsaKeySize::_2048)
+                .unwrap();
+        let jwk = Jwk::from_key_pair(&rsa).unwrap();
+        assert_eq!(jwk.kty, "RSA");
+        assert_eq!(jwk.params["e"], "AQAB");
+        let n = URL_SAFE_NO_PAD
+            .decode(jwk.params["n"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(n.len(), 256);
+        assert!(jwk.thumbprint_sha256().is_ok());
+    }
+
     #[test]
     fn test_to_value() {
         let jwk = Jwk::new_ed25519("AAAA");
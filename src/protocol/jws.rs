@@ -1,9 +1,10 @@
 /// JWS (JSON Web Signature) signing for ACME
+use crate::crypto::KeyType;
 use crate::error::{AcmeError, Result};
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use rcgen::{KeyPair, SigningKey};
-use serde_json::Value;
+use serde_json::{Value, json};
 
 /// JWS Signer for signing ACME requests
 pub struct JwsSigner<'a> {
@@ -16,20 +17,40 @@ impl<'a> JwsSigner<'a> {
         Self { key_pair }
     }
 
-    /// Sign a JWS with the given header and payload
+    /// Get the key type of the signing key
+    pub fn key_type(&self) -> Result<KeyType> {
+        KeyType::from_key_pair(self.key_pair)
+    }
+
+    /// Get the JWA algorithm identifier ("ES256", "ES384", "RS256", ...) for the `alg` header
+    pub fn algorithm(&self) -> Result<&'static str> {
+        Ok(self.key_type()?.jwa_algorithm())
+    }
+
+    /// Sign a JWS with the given header and payload, returning the compact serialization
     pub fn sign(&self, header: &Value, payload: &Value) -> Result<String> {
-        let header_json = header.to_string();
-        let payload_json = if payload.is_null() {
-            String::new()
-        } else {
-            payload.to_string()
-        };
+        let (protected, payload, signature) = self.sign_parts(header, payload)?;
+        Ok(format!("{}.{}.{}", protected, payload, signature))
+    }
+
+    /// Sign a JWS and return the flattened JSON serialization required by ACME (RFC 8555 §6.2)
+    pub fn sign_flattened(&self, header: &Value, payload: &Value) -> Result<Value> {
+        let (protected, payload, signature) = self.sign_parts(header, payload)?;
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": signature,
+        }))
+    }
 
-        let header_encoded = URL_SAFE_NO_PAD.encode(header_json.as_bytes());
+    /// Produce the base64url-encoded protected header, payload and signature.
+    /// A `null` payload yields an empty payload, as used by POST-as-GET requests.
+    fn sign_parts(&self, header: &Value, payload: &Value) -> Result<(String, String, String)> {
+        let header_encoded = URL_SAFE_NO_PAD.encode(header.to_string().as_bytes());
         let payload_encoded = if payload.is_null() {
             String::new()
         } else {
-            URL_SAFE_NO_PAD.encode(payload_json.as_bytes())
+            URL_SAFE_NO_PAD.encode(payload.to_string().as_bytes())
         };
 
         let signing_input = format!("{}.{}", header_encoded, payload_encoded);
@@ -40,11 +61,18 @@ impl<'a> JwsSigner<'a> {
             .sign(signing_input.as_bytes())
             .map_err(|e| AcmeError::crypto(format!("JWS signing failed: {}", e)))?;
 
-        let signature_encoded = URL_SAFE_NO_PAD.encode(&signature);
+        // JWS requires ECDSA signatures as fixed-size R || S rather than ASN.1 DER (RFC 7518 §3.4)
+        let signature = match self.key_type()? {
+            KeyType::EcdsaP256 => ecdsa_der_to_raw(&signature, 32)?,
+            KeyType::EcdsaP384 => ecdsa_der_to_raw(&signature, 48)?,
+            KeyType::EcdsaP521 => ecdsa_der_to_raw(&signature, 66)?,
+            _ => signature,
+        };
 
-        Ok(format!(
-            "{}.{}.{}",
-            header_encoded, payload_encoded, signature_encoded
+        Ok((
+            header_encoded,
+            payload_encoded,
+            URL_SAFE_NO_PAD.encode(&signature),
         ))
     }
 
@@ -59,6 +87,35 @@ impl<'a> JwsSigner<'a> {
     }
 }
 
+/// Convert an ASN.1 DER `ECDSA-Sig-Value` into the fixed-width R || S form
+fn ecdsa_der_to_raw(der: &[u8], coord_len: usize) -> Result<Vec<u8>> {
+    use x509_parser::der_parser::ber::BerObjectContent;
+    use x509_parser::der_parser::der::parse_der_sequence;
+
+    let invalid = || AcmeError::crypto("Invalid ECDSA signature encoding");
+    let (_, seq) = parse_der_sequence(der).map_err(|_| invalid())?;
+    let items = match seq.content {
+        BerObjectContent::Sequence(items) if items.len() == 2 => items,
+        _ => return Err(invalid()),
+    };
+
+    let mut raw = vec![0u8; coord_len * 2];
+    for (i, item) in items.iter().enumerate() {
+        let bytes = match item.content {
+            BerObjectContent::Integer(bytes) => bytes,
+            _ => return Err(invalid()),
+        };
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        let bytes = &bytes[start..];
+        if bytes.len() > coord_len {
+            return Err(invalid());
+        }
+        let offset = (i + 1) * coord_len - bytes.len();
+        raw[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+    Ok(raw)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,4 +166,65 @@ mod tests {
         assert_eq!(parts.len(), 3, "JWS should have 3 parts");
         assert_eq!(parts[1], "", "Payload part should be empty");
     }
+
+    #[test]
+    fn test_jws_sign_flattened() {
+        let key_pair = KeyPair::generate().expect("Failed to generate key pair");
+        let signer = JwsSigner::new(&key_pair);
+
+        let header = serde_json::json!({ "alg": "ES256", "nonce": "n", "url": "u" });
+        let jws = signer
+            .sign_flattened(&header, &serde_json::json!({ "status": "deactivated" }))
+            .expect("Failed to sign JWS");
+
+        let protected = URL_SAFE_NO_PAD
+            .decode(jws["protected"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&protected).unwrap(), header);
+        let payload = URL_SAFE_NO_PAD
+            .decode(jws["payload"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&payload).unwrap()["status"],
+            "deactivated"
+        );
+        assert!(!jws["signature"].as_str().unwrap().is_empty());
+
+        let empty = signer.sign_flattened(&header, &Value::Null).unwrap();
+        assert_eq!(empty["payload"], "");
+    }
+
+    #[test]
+    fn test_jws_signature_sizes() {
+        let cases: [(KeyPair, &str, usize); 3] = [
+            (
+                KeyPair::generate_for(&rcgen::PKCS_ECDSA_P256_SHA256).unwrap(),
+                "ES256",
+                64,
+            ),
+            (
+                KeyPair::generate_for(&rcgen::PKCS_ECDSA_P384_SHA384).unwrap(),
+                "ES384",
+                96,
+            ),
+            (
+                KeyPair::generate_rsa_for(&rcgen::PKCS_RSA_SHA256, rcgen::RsaKeySize::_2048)
+                    .unwrap(),
+                "RS256",
+                256,
+            ),
+        ];
+
+        for (key_pair, alg, sig_len) in cases {
+            let signer = JwsSigner::new(&key_pair);
+            assert_eq!(signer.algorithm().unwrap(), alg);
+
+            let header = serde_json::json!({ "alg": alg, "nonce": "n", "url": "u" });
+            let jws = signer.sign(&header, &serde_json::json!({})).unwrap();
+            let signature = URL_SAFE_NO_PAD
+                .decode(jws.rsplit('.').next().unwrap())
+                .unwrap();
+            assert_eq!(signature.len(), sig_len, "{} signature length", alg);
+        }
+    }
 }
@@ -4,9 +4,13 @@ pub mod jwk;
 pub mod jws;
 pub mod nonce;
 pub mod nonce_pool;
+pub mod renewal_info;
+pub mod request;
 
 pub use directory::{Directory, DirectoryManager};
 pub use jwk::Jwk;
 pub use jws::JwsSigner;
 pub use nonce::NonceManager;
 pub use nonce_pool::NoncePool;
+pub use renewal_info::{RenewalInfo, SuggestedWindow, ari_cert_id};
+pub use request::{AcmeRequester, AcmeResponse, KeyId};
@@ -44,7 +44,7 @@ impl NonceManager {
     }
 
     /// Fetches a fresh anti-replay nonce from the ACME server using a HEAD request.
-    async fn fetch_nonce(&self) -> Result<String> {
+    pub(crate) async fn fetch_nonce(&self) -> Result<String> {
         tracing::info!("Fetching new nonce from: {}", self.nonce_url);
         let response = self
             .http_client
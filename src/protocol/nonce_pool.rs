@@ -1,14 +1,15 @@
 use crate::error::Result;
 use crate::protocol::nonce::NonceManager;
-use std::collections::VecDeque;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::{debug, info};
 
-/// Nonce pool for pre-fetching and caching nonces to improve performance
+/// Nonce pool for pre-fetching and caching nonces to improve performance.
+/// Nonces are fetched into the cache of the wrapped `NonceManager`, so every signed
+/// request made through that manager (or a clone of it) benefits from the pool.
 pub struct NoncePool {
     nonce_manager: NonceManager,
-    nonces: Arc<Mutex<VecDeque<String>>>,
+    refilling: Arc<AtomicBool>,
     min_size: usize,
     max_size: usize,
 }
@@ -18,61 +19,66 @@ impl NoncePool {
     pub fn new(nonce_manager: NonceManager, min_size: usize, max_size: usize) -> Self {
         Self {
             nonce_manager,
-            nonces: Arc::new(Mutex::new(VecDeque::with_capacity(max_size))),
-            min_size,
+            refilling: Arc::new(AtomicBool::new(false)),
+            min_size: min_size.min(max_size),
             max_size,
         }
     }
 
     /// Get a nonce from the pool, fetching more if necessary
     pub async fn get_nonce(&self) -> Result<String> {
-        let mut n = self.nonces.lock().await;
+        let nonce = self.nonce_manager.get_nonce().await?;
+        self.maintain().await;
+        Ok(nonce)
+    }
 
-        // If we have nonces, return one and trigger refill if below min_size
-        if let Some(nonce) = n.pop_front() {
-            if n.len() < self.min_size {
-                let pool_clone = self.clone_internal();
-                tokio::spawn(async move {
-                    if let Err(e) = pool_clone.refill().await {
-                        debug!("Failed to refill nonce pool: {}", e);
-                    }
-                });
-            }
-            return Ok(nonce);
+    /// Starts a background refill if the pool has dropped below its minimum size
+    pub async fn maintain(&self) {
+        if self.nonce_manager.pool_size().await >= self.min_size {
+            return;
+        }
+        // Only one refill runs at a time
+        if self.refilling.swap(true, Ordering::AcqRel) {
+            return;
         }
 
-        // No nonces available, fetch one immediately
-        drop(n);
-        info!("Nonce pool empty, fetching immediate nonce");
-        self.nonce_manager.get_nonce().await
+        let pool = self.clone_internal();
+        tokio::spawn(async move {
+            if let Err(e) = pool.refill().await {
+                debug!("Failed to refill nonce pool: {}", e);
+            }
+            pool.refilling.store(false, Ordering::Release);
+        });
     }
 
     /// Refill the pool to max_size
     pub async fn refill(&self) -> Result<()> {
-        let mut n = self.nonces.lock().await;
-        let to_fetch = self.max_size - n.len();
+        let to_fetch = self
+            .max_size
+            .saturating_sub(self.nonce_manager.pool_size().await);
 
         if to_fetch == 0 {
             return Ok(());
         }
 
-        debug!("Refilling nonce pool, fetching {} nonces", to_fetch);
+        info!("Refilling nonce pool, fetching {} nonces", to_fetch);
         for _ in 0..to_fetch {
-            match self.nonce_manager.get_nonce().await {
-                Ok(nonce) => n.push_back(nonce),
-                Err(e) => {
-                    return Err(e);
-                }
-            }
+            let nonce = self.nonce_manager.fetch_nonce().await?;
+            self.nonce_manager.cache_nonce(nonce).await;
         }
 
         Ok(())
     }
 
+    /// Returns the number of nonces currently available
+    pub async fn size(&self) -> usize {
+        self.nonce_manager.pool_size().await
+    }
+
     fn clone_internal(&self) -> Self {
         Self {
             nonce_manager: self.nonce_manager.clone(),
-            nonces: self.nonces.clone(),
+            refilling: self.refilling.clone(),
             min_size: self.min_size,
             max_size: self.max_size,
         }
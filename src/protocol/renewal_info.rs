@@ -0,0 +1,237 @@
+/// ACME Renewal Information (ARI, RFC 9773).
+/// This module computes ARI certificate identifiers and fetches the CA-suggested
+/// renewal window for an issued certificate.
+use crate::error::{AcmeError, Result};
+use crate::transport::parse_retry_after;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jiff::Timestamp;
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use x509_parser::extensions::ParsedExtension;
+use x509_parser::prelude::FromDer;
+
+/// How long to wait between polls when the server sends no `Retry-After`.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// Shortest wait between polls, whatever the server asks for.
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest wait between polls, whatever the server asks for.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// The time window during which the CA suggests renewing a certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuggestedWindow {
+    /// The earliest suggested renewal time.
+    pub start: Timestamp,
+    /// The latest suggested renewal time.
+    pub end: Timestamp,
+}
+
+/// Renewal information returned by the `renewalInfo` endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenewalInfo {
+    /// The suggested renewal window.
+    #[serde(rename = "suggestedWindow")]
+    pub suggested_window: SuggestedWindow,
+
+    /// A URL explaining why the window was set (e.g. an incident report).
+    #[serde(
+        rename = "explanationURL",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub explanation_url: Option<String>,
+
+    /// How long the client should wait before polling the renewal information again.
+    #[serde(skip)]
+    pub retry_after: Option<Duration>,
+
+    /// The renewal time chosen within the suggested window, kept while the window
+    /// is unchanged so repeated checks do not draw a new time each pass.
+    #[serde(skip)]
+    pub renewal_time: Option<Timestamp>,
+}
+
+impl RenewalInfo {
+    /// Selects a uniformly random renewal time within the suggested window,
+    /// as recommended by RFC 9773 Section 4.2 to spread load on the CA.
+    pub fn select_renewal_time(&self) -> Timestamp {
+        let start = self.suggested_window.start.as_second();
+        let end = self.suggested_window.end.as_second();
+        if end <= start {
+            return self.suggested_window.start;
+        }
+        let secs = rand::rng().random_range(start..end);
+        Timestamp::from_second(secs).unwrap_or(self.suggested_window.start)
+    }
+
+    /// Determines whether the certificate should be renewed at the given time.
+    /// Compares against the chosen `renewal_time`, or the window start if none was chosen.
+    pub fn should_renew_at(&self, now: Timestamp) -> bool {
+        if now >= self.suggested_window.end {
+            return true;
+        }
+        now >= self.renewal_time.unwrap_or(self.suggested_window.start)
+    }
+
+    /// Returns when the renewal information should next be polled, following the
+    /// server's `Retry-After` clamped to a sane range (RFC 9773 Section 4.3).
+    pub fn next_poll(&self, now: Timestamp) -> Timestamp {
+        let wait = self
+            .retry_after
+            .unwrap_or(DEFAULT_POLL_INTERVAL)
+            .clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL);
+        Timestamp::from_second(now.as_second() + wait.as_secs() as i64).unwrap_or(now)
+    }
+}
+
+/// Computes the ARI certificate identifier of a DER-encoded certificate:
+/// `base64url(AKI keyIdentifier) "." base64url(serial number)`.
+pub fn ari_cert_id(cert_der: &[u8]) -> Result<String> {
+    let (_, cert) = x509_parser::prelude::X509Certificate::from_der(cert_der).map_err(|e| {
+        tracing::error!("Failed to parse certificate for ARI: {}", e);
+        AcmeError::certificate(format!("Failed to parse certificate: {}", e))
+    })?;
+
+    let key_id = cert
+        .extensions()
+        .iter()
+        .find_map(|ext| match ext.parsed_extension() {
+            ParsedExtension::AuthorityKeyIdentifier(aki) => aki.key_identifier.as_ref(),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            AcmeError::certificate(
+                "Certificate has no Authority Key Identifier; cannot build ARI identifier",
+            )
+        })?;
+
+    Ok(format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(key_id.0),
+        URL_SAFE_NO_PAD.encode(cert.tbs_certificate.raw_serial())
+    ))
+}
+
+/// Fetches the renewal information for a certificate from the `renewalInfo` endpoint.
+pub async fn fetch_renewal_info(
+    http_client: &reqwest::Client,
+    renewal_info_url: &str,
+    cert_id: &str,
+) -> Result<RenewalInfo> {
+    let url = format!("{}/{}", renewal_info_url.trim_end_matches('/'), cert_id);
+    tracing::debug!("Fetching renewal information from: {}", url);
+
+    let response = http_client.get(&url).send().await.map_err(|e| {
+        tracing::error!("Network error while fetching renewal info: {}", e);
+        AcmeError::transport(format!("Failed to fetch renewal info: {}", e))
+    })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        tracing::warn!("Renewal info request failed with status {}", status);
+        return Err(AcmeError::protocol(format!(
+            "Failed to fetch renewal info: HTTP {}",
+            status
+        )));
+    }
+
+    let retry_after = response
+        .headers()
+        .get("retry-after")
+        .and_then(|h| h.to_str().ok())
+        .and_then(parse_retry_after);
+
+    let mut info: RenewalInfo = response.json().await.map_err(|e| {
+        tracing::error!("Failed to parse renewal info JSON: {}", e);
+        AcmeError::protocol(format!("Failed to parse renewal info: {}", e))
+    })?;
+    info.retry_after = retry_after;
+    info.renewal_time = Some(info.select_renewal_time());
+
+    tracing::debug!(
+        "Suggested renewal window: {} - {}",
+        info.suggested_window.start,
+        info.suggested_window.end
+    );
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renewal_info_parsing() {
+        let json = r#"{
+            "suggestedWindow": {
+                "start": "2025-01-02T04:00:00Z",
+                "end": "2025-01-03T04:00:00Z"
+            },
+            "explanationURL": "https://acme.example.com/docs/ari"
+        }"#;
+
+        let info: RenewalInfo = serde_json::from_str(json).expect("Failed to parse renewal info");
+        let selected = info.select_renewal_time();
+        assert!(selected >= info.suggested_window.start);
+        assert!(selected < info.suggested_window.end);
+
+        let before: Timestamp = "2025-01-01T00:00:00Z".parse().unwrap();
+        let after: Timestamp = "2025-01-04T00:00:00Z".parse().unwrap();
+        assert!(!info.should_renew_at(before));
+        assert!(info.should_renew_at(after));
+    }
+
+    #[test]
+    fn test_renewal_time_and_next_poll() {
+        let mut info: RenewalInfo = serde_json::from_str(
+            r#"{"suggestedWindow": {"start": "2025-01-02T04:00:00Z", "end": "2025-01-03T04:00:00Z"}}"#,
+        )
+        .unwrap();
+        let chosen: Timestamp = "2025-01-02T16:00:00Z".parse().unwrap();
+        info.renewal_time = Some(chosen);
+
+        // The decision follows the chosen time only
+        let between: Timestamp = "2025-01-02T10:00:00Z".parse().unwrap();
+        assert!(!info.should_renew_at(between));
+        assert!(info.should_renew_at(chosen));
+
+        let now: Timestamp = "2025-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(info.next_poll(now).as_second() - now.as_second(), 6 * 3600);
+        info.retry_after = Some(Duration::from_secs(5));
+        assert_eq!(info.next_poll(now).as_second() - now.as_second(), 60);
+        info.retry_after = Some(Duration::from_secs(7 * 24 * 3600));
+        assert_eq!(info.next_poll(now).as_second() - now.as_second(), 24 * 3600);
+    }
+
+    #[test]
+    fn test_ari_cert_id() {
+        let ca_key = rcgen::KeyPair::generate().unwrap();
+        let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let issuer = rcgen::Issuer::new(ca_params, ca_key);
+
+        let leaf_key = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(vec!["example.com".to_string()]).unwrap();
+        params.use_authority_key_identifier_extension = true;
+        params.serial_number = Some(rcgen::SerialNumber::from(vec![0x00, 0x87, 0x65, 0x43]));
+        let cert = params.signed_by(&leaf_key, &issuer).unwrap();
+
+        let id = ari_cert_id(cert.der()).unwrap();
+        let (aki, serial) = id.split_once('.').unwrap();
+        assert!(!aki.is_empty());
+        assert_eq!(
+            URL_SAFE_NO_PAD.decode(serial).unwrap(),
+            vec![0x00, 0x87, 0x65, 0x43]
+        );
+
+        let self_signed = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+            .unwrap()
+            .self_signed(&leaf_key)
+            .unwrap();
+        assert!(ari_cert_id(self_signed.der()).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+/// Signed ACME requests.
+/// This module implements the JWS-signed POST and POST-as-GET requests shared by all
+/// ACME resources (RFC 8555 Section 6.2-6.3), including nonce handling, automatic
+/// retries on `badNonce`, and mapping of problem documents onto `AcmeError`.
+use crate::error::{AcmeError, Result};
+use crate::protocol::{Jwk, JwsSigner, NonceManager};
+use crate::transport::parse_retry_after;
+use crate::types::AcmeErrorDetail;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Maximum number of times a request is re-sent after the server rejects its nonce.
+const MAX_BAD_NONCE_RETRIES: u32 = 3;
+
+/// Identifies the signing key in the JWS protected header.
+#[derive(Debug, Clone, Copy)]
+pub enum KeyId<'k> {
+    /// Embeds the public key itself, used for newAccount and certificate-key revocation.
+    Jwk(&'k Jwk),
+    /// References a registered account by its URL.
+    Kid(&'k str),
+}
+
+/// A successful response to a signed ACME request.
+#[derive(Debug, Clone)]
+pub struct AcmeResponse {
+    /// The HTTP status code.
+    pub status: StatusCode,
+    /// The response headers.
+    pub headers: HeaderMap,
+    /// The raw response body.
+    pub body: Vec<u8>,
+}
+
+impl AcmeResponse {
+    /// Returns the value of a response header, if present and valid UTF-8.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|h| h.to_str().ok())
+    }
+
+    /// Returns the `Location` header, which carries the URL of newly created resources.
+    pub fn location(&self) -> Option<&str> {
+        self.header("location")
+    }
+
+    /// Returns the targets of all `Link` headers with the given relation type (e.g. "alternate").
+    pub fn links(&self, rel: &str) -> Vec<String> {
+        self.headers
+            .get_all("link")
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|link| {
+                let (target, params) = link.trim().split_once(';')?;
+                let target = target.trim().strip_prefix('<')?.strip_suffix('>')?;
+                params
+                    .split(';')
+                    .filter_map(|param| param.trim().split_once('='))
+                    .any(|(key, value)| {
+                        key.trim() == "rel" && value.trim().trim_matches('"') == rel
+                    })
+                    .then(|| target.to_string())
+            })
+            .collect()
+    }
+
+    /// Returns the delay requested by the `Retry-After` header, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.header("retry-after").and_then(parse_retry_after)
+    }
+
+    /// Deserializes the response body from JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).map_err(|e| {
+            tracing::error!("Failed to parse ACME response JSON: {}", e);
+            AcmeError::protocol(format!("Failed to parse response: {}", e))
+        })
+    }
+
+    /// Returns the response body as a UTF-8 string.
+    pub fn text(&self) -> Result<String> {
+        String::from_utf8(self.body.clone()).map_err(|e| {
+            tracing::error!("ACME response body is not valid UTF-8: {}", e);
+            AcmeError::protocol(format!("Invalid UTF-8 in response: {}", e))
+        })
+    }
+}
+
+/// Sends JWS-signed requests to an ACME server.
+pub struct AcmeRequester<'a> {
+    /// The signer for the key authenticating the requests.
+    signer: &'a JwsSigner<'a>,
+    /// Manager supplying and caching anti-replay nonces.
+    nonce_manager: &'a NonceManager,
+    /// The HTTP client used for network requests.
+    http_client: &'a reqwest::Client,
+}
+
+impl<'a> AcmeRequester<'a> {
+    /// Creates a new requester signing with `signer`.
+    pub fn new(
+        signer: &'a JwsSigner<'a>,
+        nonce_manager: &'a NonceManager,
+        http_client: &'a reqwest::Client,
+    ) -> Self {
+        Self {
+            signer,
+            nonce_manager,
+            http_client,
+        }
+    }
+
+    /// Sends a signed POST with a JSON payload.
+    pub async fn post(
+        &self,
+        url: &str,
+        key_id: KeyId<'_>,
+        payload: &Value,
+    ) -> Result<AcmeResponse> {
+        self.send(url, key_id, payload).await
+    }
+
+    /// Fetches a resource with a POST-as-GET request (empty payload) on behalf of an account.
+    pub async fn post_as_get(&self, url: &str, account_url: &str) -> Result<AcmeResponse> {
+        self.send(url, KeyId::Kid(account_url), &Value::Null).await
+    }
+
+    /// Signs and sends the request, retrying with a fresh nonce when the server answers `badNonce`.
+    async fn send(&self, url: &str, key_id: KeyId<'_>, payload: &Value) -> Result<AcmeResponse> {
+        let mut attempt = 0;
+        loop {
+            let nonce = self.nonce_manager.get_nonce().await?;
+            let mut header = json!({
+                "alg": self.signer.algorithm()?,
+                "nonce": nonce,
+                "url": url,
+            });
+            match key_id {
+                KeyId::Jwk(jwk) => header["jwk"] = jwk.to_value(),
+                KeyId::Kid(kid) => header["kid"] = json!(kid),
+            }
+            let jws = self.signer.sign_flattened(&header, payload)?;
+
+            tracing::debug!("Sending signed ACME request to {}", url);
+            let response = self
+                .http_client
+                .post(url)
+                .header("Content-Type", "application/jose+json")
+                .body(jws.to_string())
+                .send()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Network error during ACME request to {}: {}", url, e);
+                    AcmeError::transport(format!("Request to {} failed: {}", url, e))
+                })?;
+
+            // Every response, including errors, carries a fresh nonce
+            if let Some(nonce_header) = response.headers().get("replay-nonce")
+                && let Ok(nonce_str) = nonce_header.to_str()
+            {
+                self.nonce_manager.cache_nonce(nonce_str.to_string()).await;
+            }
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to read ACME response body: {}", e);
+                    AcmeError::transport(format!("Failed to read response: {}", e))
+                })?
+                .to_vec();
+            let response = AcmeResponse {
+                status,
+                headers,
+                body,
+            };
+
+            if status.is_success() {
+                return Ok(response);
+            }
+
+            let Ok(problem) = serde_json::from_slice::<AcmeErrorDetail>(&response.body) else {
+                let text = String::from_utf8_lossy(&response.body);
+                tracing::error!("ACME request to {} failed with {}: {}", url, status, text);
+                return Err(AcmeError::protocol(format!("HTTP {}: {}", status, text)));
+            };
+
+            if problem.is_bad_nonce() && attempt < MAX_BAD_NONCE_RETRIES {
+                attempt += 1;
+                tracing::warn!(
+                    "Server rejected nonce for {}, retrying ({}/{})",
+                    url,
+                    attempt,
+                    MAX_BAD_NONCE_RETRIES
+                );
+                continue;
+            }
+
+            tracing::error!(
+                "ACME request to {} failed with {}: {}",
+                url,
+                status,
+                problem
+            );
+            return Err(AcmeError::from_problem(problem, response.retry_after()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(json: &str) -> AcmeErrorDetail {
+        serde_json::from_str(json).expect("Failed to parse problem document")
+    }
+
+    #[test]
+    fn test_link_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "link",
+            "<https://ca.example/acme/directory>;rel=\"index\""
+                .parse()
+                .unwrap(),
+        );
+        headers.append(
+            "link",
+            "<https://ca.example/cert/1/1>; rel=\"alternate\", <https://ca.example/cert/1/2>;rel=alternate"
+                .parse()
+                .unwrap(),
+        );
+        let response = AcmeResponse {
+            status: StatusCode::OK,
+            headers,
+            body: Vec::new(),
+        };
+
+        assert_eq!(
+            response.links("alternate"),
+            vec!["https://ca.example/cert/1/1", "https://ca.example/cert/1/2"]
+        );
+        assert_eq!(
+            response.links("index"),
+            vec!["https://ca.example/acme/directory"]
+        );
+        assert!(response.links("up").is_empty());
+    }
+
+    #[test]
+    fn test_problem_with_subproblems() {
+        let detail = problem(
+            r#"{
+                "type": "urn:ietf:params:acme:error:malformed",
+                "detail": "Some of the identifiers requested were rejected",
+                "subproblems": [
+                    {
+                        "type": "urn:ietf:params:acme:error:rejectedIdentifier",
+                        "detail": "This CA will not issue for \"example.net\"",
+                        "identifier": { "type": "dns", "value": "example.net" }
+                    }
+                ]
+            }"#,
+        );
+        assert_eq!(detail.kind(), "malformed");
+        assert_eq!(detail.subproblems.as_ref().unwrap().len(), 1);
+
+        let err = AcmeError::from_problem(detail, None);
+        let AcmeError::Rejected {
+            kind,
+            detail: message,
+        } = err
+        else {
+            panic!("Expected Rejected, got {:?}", err);
+        };
+        assert_eq!(kind, "malformed");
+        assert!(message.contains("example.net: This CA will not issue"));
+        assert!(message.contains("(rejectedIdentifier)"));
+    }
+
+    #[test]
+    fn test_problem_mapping() {
+        let rate_limited = problem(r#"{"type": "urn:ietf:params:acme:error:rateLimited"}"#);
+        assert!(matches!(
+            AcmeError::from_problem(rate_limited, Some(Duration::from_secs(60))),
+            AcmeError::RateLimited(Some(d)) if d == Duration::from_secs(60)
+        ));
+
+        let no_account = problem(r#"{"type": "urn:ietf:params:acme:error:accountDoesNotExist"}"#);
+        assert!(matches!(
+            AcmeError::from_problem(no_account, None),
+            AcmeError::Account(_)
+        ));
+
+        let not_ready = problem(r#"{"type": "urn:ietf:params:acme:error:orderNotReady"}"#);
+        assert!(matches!(
+            AcmeError::from_problem(not_ready, None),
+            AcmeError::Order { status, .. } if status == "orderNotReady"
+        ));
+
+        let bad_nonce = problem(r#"{"type": "urn:ietf:params:acme:error:badNonce"}"#);
+        assert!(bad_nonce.is_bad_nonce());
+        assert!(matches!(
+            AcmeError::from_problem(bad_nonce, None),
+            AcmeError::Protocol(_)
+        ));
+    }
+}
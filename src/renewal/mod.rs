@@ -122,6 +122,7 @@ impl<B: StorageBackend> SimpleRenewalScheduler<B> {
     }
 
     /// Determines if a certificate for the given domains needs renewal.
+    /// The CA-suggested ARI window is used when available, otherwise the renew-before threshold.
     pub async fn needs_renewal(&self, domains: &[String]) -> Result<bool> {
         let bundle = self.store.load(domains).await?;
         let Some(bundle) = bundle else {
@@ -132,35 +133,7 @@ impl<B: StorageBackend> SimpleRenewalScheduler<B> {
             return Ok(true);
         };
 
-        let expiry = certificate_expiry_timestamp(&bundle)?;
-        let now = now_timestamp()?;
-
-        // If expired or expiring soon
-        if now >= expiry {
-            tracing::warn!(
-                "Certificate for {:?} has already expired (Expiry: {})",
-                domains,
-                expiry
-            );
-            return Ok(true);
-        }
-
-        let renew_before_secs = self.renew_before.as_secs() as i64;
-        let threshold_secs = expiry.as_second() - renew_before_secs;
-        let threshold = Timestamp::from_second(threshold_secs)
-            .map_err(|e| AcmeError::certificate(format!("Invalid threshold timestamp: {}", e)))?;
-
-        let needs_renew = now >= threshold;
-        if needs_renew {
-            tracing::info!(
-                "Certificate for {:?} is within the renewal window (Threshold: {}, Expiry: {})",
-                domains,
-                threshold,
-                expiry
-            );
-        }
-
-        Ok(needs_renew)
+        renewal_due(Some(&self.client), &bundle, self.renew_before).await
     }
 
     /// Performs the actual certificate renewal by requesting a new one from the ACME server.
@@ -170,10 +143,18 @@ impl<B: StorageBackend> SimpleRenewalScheduler<B> {
         // Default to HTTP-01 for simple scheduler; advanced scheduler can be more flexible
         registry.register(crate::challenge::Http01Solver::default());
 
-        let bundle = self
-            .client
-            .issue_certificate(domains.clone(), &mut registry)
-            .await?;
+        let bundle = match self.store.load(&domains).await? {
+            // The CA renews STAR certificates itself; just pick up the current one
+            Some(previous) if previous.active_star(now_timestamp()?).is_some() => {
+                self.client.fetch_star_certificate(&previous).await?
+            }
+            Some(previous) => self.client.renew_certificate(&previous, &registry).await?,
+            None => {
+                self.client
+                    .issue_certificate(domains.clone(), &registry)
+                    .await?
+            }
+        };
 
         tracing::debug!("Saving renewed certificate bundle to storage");
         self.store.save(&bundle).await?;
@@ -181,8 +162,101 @@ impl<B: StorageBackend> SimpleRenewalScheduler<B> {
     }
 }
 
+/// Decides whether a stored certificate is due for renewal.
+///
+/// When a client is given and the CA supports ARI (RFC 9773), the CA-suggested renewal
+/// window is authoritative. Otherwise, or if fetching the window fails, the certificate
+/// is due once it is within `renew_before` of its expiry.
+///
+/// Certificates of an active STAR order (RFC 8739) are due once half of their validity
+/// has passed, by which time the CA has issued the next one.
+pub async fn renewal_due(
+    client: Option<&AcmeClient>,
+    bundle: &CertificateBundle,
+    renew_before: Duration,
+) -> Result<bool> {
+    let now = now_timestamp()?;
+
+    if bundle.active_star(now).is_some() {
+        let (not_before, not_after) = certificate_validity(bundle)?;
+        let midpoint =
+            not_before.as_second() + (not_after.as_second() - not_before.as_second()) / 2;
+        let due = now.as_second() >= midpoint;
+        if due {
+            tracing::info!(
+                "STAR certificate for {:?} is past half of its lifetime (Expiry: {})",
+                bundle.domains,
+                not_after
+            );
+        }
+        return Ok(due);
+    }
+
+    if let Some(client) = client {
+        match client.renewal_info(&bundle.certificate_pem).await {
+            Ok(Some(info)) => {
+                let due = info.should_renew_at(now);
+                if due {
+                    tracing::info!(
+                        "Certificate for {:?} is within the CA-suggested renewal window ({} - {})",
+                        bundle.domains,
+                        info.suggested_window.start,
+                        info.suggested_window.end
+                    );
+                    if let Some(ref url) = info.explanation_url {
+                        tracing::info!("CA renewal explanation: {}", url);
+                    }
+                }
+                return Ok(due);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to fetch renewal info for {:?}, falling back to expiry threshold: {}",
+                    bundle.domains,
+                    e
+                );
+            }
+        }
+    }
+
+    let expiry = certificate_expiry_timestamp(bundle)?;
+
+    // If expired or expiring soon
+    if now >= expiry {
+        tracing::warn!(
+            "Certificate for {:?} has already expired (Expiry: {})",
+            bundle.domains,
+            expiry
+        );
+        return Ok(true);
+    }
+
+    let renew_before_secs = renew_before.as_secs() as i64;
+    let threshold_secs = expiry.as_second() - renew_before_secs;
+    let threshold = Timestamp::from_second(threshold_secs)
+        .map_err(|e| AcmeError::certificate(format!("Invalid threshold timestamp: {}", e)))?;
+
+    let needs_renew = now >= threshold;
+    if needs_renew {
+        tracing::info!(
+            "Certificate for {:?} is within the renewal window (Threshold: {}, Expiry: {})",
+            bundle.domains,
+            threshold,
+            expiry
+        );
+    }
+
+    Ok(needs_renew)
+}
+
 /// Extracts the expiration timestamp from a `CertificateBundle`.
 pub fn certificate_expiry_timestamp(bundle: &CertificateBundle) -> Result<Timestamp> {
+    certificate_validity(bundle).map(|(_, not_after)| not_after)
+}
+
+/// Extracts the validity period (not before, not after) of the leaf certificate of a bundle.
+fn certificate_validity(bundle: &CertificateBundle) -> Result<(Timestamp, Timestamp)> {
     let chain = crate::order::parse_certificate_chain(&bundle.certificate_pem)?;
     let cert_der = chain.first().ok_or_else(|| {
         tracing::error!("Certificate bundle contains an empty chain");
@@ -194,11 +268,13 @@ pub fn certificate_expiry_timestamp(bundle: &CertificateBundle) -> Result<Timest
         AcmeError::certificate(format!("Failed to parse certificate: {}", e))
     })?;
 
-    let not_after = cert.validity().not_after.timestamp();
-    let ts = Timestamp::from_second(not_after)
+    let validity = cert.validity();
+    let not_before = Timestamp::from_second(validity.not_before.timestamp())
+        .map_err(|e| AcmeError::certificate(format!("Invalid issuance timestamp: {}", e)))?;
+    let not_after = Timestamp::from_second(validity.not_after.timestamp())
         .map_err(|e| AcmeError::certificate(format!("Invalid expiry timestamp: {}", e)))?;
 
-    Ok(ts)
+    Ok((not_before, not_after))
 }
 
 /// Returns the current system time as a `jiff::Timestamp`.
@@ -3,11 +3,13 @@
 /// of renewal tasks and executes them concurrently using a semaphore.
 use crate::challenge::{ChallengeSolverRegistry, Http01Solver};
 use crate::client::{AcmeClient, CertificateBundle};
+use crate::config::CertificateSettings;
 use crate::error::Result;
-use crate::renewal::RenewalHook;
+use crate::renewal::{RenewalHook, certificate_expiry_timestamp, now_timestamp, renewal_due};
 use crate::storage::{CertificateStore, StorageBackend};
 use std::collections::BinaryHeap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{Mutex, Notify, mpsc};
 
 /// Trait for renewal schedulers.
@@ -30,6 +32,19 @@ pub enum Priority {
     Urgent = 3,
 }
 
+impl Priority {
+    /// Derives the priority from the time remaining until the certificate expires.
+    pub fn from_remaining(remaining: Duration) -> Self {
+        const DAY: u64 = 24 * 3600;
+        match remaining.as_secs() {
+            s if s < DAY => Priority::Urgent,
+            s if s < 7 * DAY => Priority::High,
+            s if s < 15 * DAY => Priority::Normal,
+            _ => Priority::Low,
+        }
+    }
+}
+
 /// Represents a single certificate renewal task.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct RenewalTask {
@@ -65,6 +80,10 @@ pub struct AdvancedRenewalScheduler<B: StorageBackend> {
     hook: Option<Arc<dyn RenewalHook>>,
     /// Maximum number of concurrent renewal tasks.
     concurrency: usize,
+    /// Fallback renew-before window used when the CA does not provide ARI.
+    renew_before: Duration,
+    /// Certificate settings resolved per certificate, overriding the client's options.
+    certificate: Option<Arc<CertificateSettings>>,
     /// The priority queue of pending tasks.
     queue: Arc<Mutex<BinaryHeap<RenewalTask>>>,
     /// Notifier to wake up the scheduler when new tasks are added.
@@ -105,6 +124,8 @@ impl<B: StorageBackend + 'static> AdvancedRenewalScheduler<B> {
             store,
             hook: None,
             concurrency,
+            renew_before: Duration::from_secs(30 * 24 * 3600),
+            certificate: None,
             queue,
             notifier,
             task_tx: tx.clone(),
@@ -118,18 +139,54 @@ impl<B: StorageBackend + 'static> AdvancedRenewalScheduler<B> {
         self
     }
 
+    /// Sets the time window before expiry used when the CA does not provide ARI.
+    pub fn with_renew_before(mut self, renew_before: Duration) -> Self {
+        self.renew_before = renew_before;
+        self
+    }
+
+    /// Resolves certificate options for each renewal from these settings, so
+    /// per-certificate overrides apply instead of the client's fixed options.
+    pub fn with_certificate_settings(mut self, settings: CertificateSettings) -> Self {
+        self.certificate = Some(Arc::new(settings));
+        self
+    }
+
     /// Scans the storage and enqueues all certificates that require renewal.
+    /// The CA-suggested ARI window decides when available; priority follows the remaining lifetime.
     pub async fn run_once_internal(&self) -> Result<()> {
         tracing::info!("Scanning storage for certificates due for renewal");
         let certs = self.store.list_all().await?;
+        let now = now_timestamp()?;
         for cert in certs {
-            // In a real implementation, we would check the expiry here and set priority accordingly.
-            // For now, we enqueue all found certificates with Normal priority.
+            match renewal_due(Some(&self.client), &cert, self.renew_before).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::debug!("Certificate for {:?} is not due for renewal", cert.domains);
+                    continue;
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Error checking renewal status for {:?}: {}",
+                        cert.domains,
+                        e
+                    );
+                    continue;
+                }
+            }
+
+            let priority = certificate_expiry_timestamp(&cert)
+                .map(|expiry| {
+                    let remaining = (expiry.as_second() - now.as_second()).max(0) as u64;
+                    Priority::from_remaining(Duration::from_secs(remaining))
+                })
+                .unwrap_or(Priority::Urgent);
+
             let _ = self
                 .task_tx
                 .send(RenewalTask {
                     domains: cert.domains.clone(),
-                    priority: Priority::Normal,
+                    priority,
                     retry_count: 0,
                 })
                 .await;
@@ -182,7 +239,8 @@ impl<B: StorageBackend + 'static> AdvancedRenewalScheduler<B> {
 
                 // Clone the client for thread-safe concurrent access
                 let mut client = s.client.clone();
-                match Self::perform_renewal(&mut client, &s.store, &task.domains).await {
+                let settings = s.certificate.as_deref();
+                match Self::perform_renewal(&mut client, &s.store, settings, &task.domains).await {
                     Ok(bundle) => {
                         tracing::info!("Successfully renewed certificate for {:?}", task.domains);
                         if let Some(h) = &s.hook {
@@ -224,16 +282,33 @@ impl<B: StorageBackend + 'static> AdvancedRenewalScheduler<B> {
     /// Internal helper to perform the actual certificate issuance.
     async fn perform_renewal(
         client: &mut AcmeClient,
-        _store: &CertificateStore<B>,
+        store: &CertificateStore<B>,
+        certificate: Option<&CertificateSettings>,
         domains: &[String],
     ) -> Result<CertificateBundle> {
+        if let Some(settings) = certificate {
+            client.set_certificate_options(settings.options_for(domains)?);
+        }
+
         let mut registry = ChallengeSolverRegistry::new();
         // Default to HTTP-01; in a full implementation, this would be configurable per task
         registry.register(Http01Solver::default());
 
-        client
-            .issue_certificate(domains.to_vec(), &mut registry)
-            .await
+        let bundle = match store.load(domains).await? {
+            // The CA renews STAR certificates itself; just pick up the current one
+            Some(previous) if previous.active_star(now_timestamp()?).is_some() => {
+                client.fetch_star_certificate(&previous).await?
+            }
+            Some(previous) => client.renew_certificate(&previous, &registry).await?,
+            None => {
+                client
+                    .issue_certificate(domains.to_vec(), &registry)
+                    .await?
+            }
+        };
+
+        store.save(&bundle).await?;
+        Ok(bundle)
     }
 }
 
@@ -1,3 +1,5 @@
+use crate::account::ExternalAccountKey;
+use crate::error::AcmeError;
 use crate::metrics::AcmeEvent;
 use crate::metrics::events::EventAuditor;
 use crate::server::api::AppState;
@@ -5,7 +7,7 @@ use axum::{
     Json,
     extract::{Path, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use tracing::info;
@@ -14,6 +16,12 @@ use tracing::info;
 pub struct CreateAccountRequest {
     pub email: String,
     pub tos_agreed: bool,
+    /// External Account Binding key ID, for CAs that require EAB.
+    #[serde(default)]
+    pub eab_key_id: Option<String>,
+    /// External Account Binding HMAC key (base64url).
+    #[serde(default)]
+    pub eab_hmac_key: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,6 +37,21 @@ pub async fn create_account(
 ) -> impl IntoResponse {
     info!("Request to create account for email: {}", payload.email);
 
+    // EAB needs both the key ID and the HMAC key
+    match (&payload.eab_key_id, &payload.eab_hmac_key) {
+        (Some(_), None) => {
+            return problem_response(&AcmeError::invalid_input(
+                "'eab_hmac_key' is required when 'eab_key_id' is set",
+            ));
+        }
+        (None, Some(_)) => {
+            return problem_response(&AcmeError::invalid_input(
+                "'eab_key_id' is required when 'eab_hmac_key' is set",
+            ));
+        }
+        _ => {}
+    }
+
     // Track event
     EventAuditor::track_event(AcmeEvent::AccountCreated {
         email: payload.email.clone(),
@@ -37,6 +60,12 @@ pub async fn create_account(
     if let Some(client) = state.client {
         // Clone the client from Arc to get a mutable instance
         let mut client = (*client).clone();
+        if let (Some(key_id), Some(hmac_key)) = (&payload.eab_key_id, &payload.eab_hmac_key) {
+            match ExternalAccountKey::new(key_id.clone(), hmac_key) {
+                Ok(eab) => client.config_mut().external_account_binding = Some(eab),
+                Err(e) => return problem_response(&e),
+            }
+        }
         match client.register_account().await {
             Ok(account_id) => {
                 return (
@@ -50,11 +79,8 @@ pub async fn create_account(
                     .into_response();
             }
             Err(e) => {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Failed to register account: {}", e),
-                )
-                    .into_response();
+                tracing::error!("Failed to register account: {}", e);
+                return problem_response(&e);
             }
         }
     }
@@ -66,6 +92,13 @@ pub async fn create_account(
         .into_response()
 }
 
+/// Converts an error into an RFC 7807 problem response with the matching status code.
+pub(crate) fn problem_response(err: &AcmeError) -> Response {
+    let problem = err.to_problem_details();
+    let status = StatusCode::from_u16(problem.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(problem)).into_response()
+}
+
 pub async fn get_account(
     State(_state): State<AppState>,
     Path(id): Path<String>,
@@ -0,0 +1,459 @@
+/// Local certificate authority backing the built-in ACME server.
+/// The CA consists of a self-signed root and an intermediate that signs leaf
+/// certificates. Both are generated on first use and persisted through a
+/// `StorageBackend`, so every instance sharing the storage issues from the same CA.
+/// The root key is discarded once it has signed the intermediate.
+use super::jws::verify_raw;
+use super::objects::KEY_PREFIX;
+use crate::crypto::KeyPairGenerator;
+use crate::error::{AcmeError, Result};
+use crate::order::csr::{TLS_FEATURE_OID, TLS_FEATURE_STATUS_REQUEST};
+use crate::storage::StorageBackend;
+use jiff::Timestamp;
+use rcgen::{
+    BasicConstraints, CertificateParams, CustomExtension, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, Issuer, KeyPair, KeyUsagePurpose, PublicKeyData, SerialNumber,
+    SignatureAlgorithm,
+};
+use rustls::pki_types::{AlgorithmIdentifier, alg_id};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::Duration;
+use x509_parser::certification_request::X509CertificationRequest;
+use x509_parser::cri_attributes::ParsedCriAttribute;
+use x509_parser::extensions::{GeneralName, ParsedExtension};
+use x509_parser::prelude::FromDer;
+use x509_parser::x509::SubjectPublicKeyInfo;
+
+/// Validity of the generated root certificate.
+const ROOT_VALIDITY: Duration = Duration::from_secs(20 * 365 * 24 * 3600);
+
+/// Validity of the generated intermediate certificate.
+const INTERMEDIATE_VALIDITY: Duration = Duration::from_secs(10 * 365 * 24 * 3600);
+
+/// Backdating applied to issued certificates to tolerate clock skew on clients.
+const BACKDATE: Duration = Duration::from_secs(60);
+
+/// Certificates and keys of the CA as persisted in storage.
+/// Only the intermediate's key is kept; nothing is signed with the root after setup.
+#[derive(Serialize, Deserialize)]
+struct CaMaterial {
+    name: String,
+    root_cert_pem: String,
+    intermediate_cert_pem: String,
+    intermediate_key_pem: String,
+}
+
+/// A certificate signing request whose signature has been verified.
+pub struct CertificateRequest {
+    /// The DNS names and IP addresses requested in the subject alternative names
+    /// and common name, lower-cased and deduplicated.
+    pub names: Vec<String>,
+    /// Whether the request asks for the OCSP Must-Staple extension.
+    pub must_staple: bool,
+    /// The public key to certify.
+    public_key: RequestPublicKey,
+}
+
+/// The subject public key of a certificate request.
+struct RequestPublicKey {
+    raw: Vec<u8>,
+    algorithm: &'static SignatureAlgorithm,
+}
+
+impl PublicKeyData for RequestPublicKey {
+    fn der_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    fn algorithm(&self) -> &'static SignatureAlgorithm {
+        self.algorithm
+    }
+}
+
+impl CertificateRequest {
+    /// Parses a DER-encoded PKCS#10 request and verifies its self-signature.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let (rest, csr) = X509CertificationRequest::from_der(der)
+            .map_err(|e| AcmeError::certificate(format!("Failed to parse CSR: {}", e)))?;
+        if !rest.is_empty() {
+            return Err(AcmeError::certificate("Trailing data after CSR"));
+        }
+
+        let info = &csr.certification_request_info;
+        let (key_alg, algorithm) = request_key_algorithm(&info.subject_pki)?;
+        let signature_alg = match csr.signature_algorithm.algorithm.to_id_string().as_str() {
+            "1.2.840.10045.4.3.2" => alg_id::ECDSA_SHA256,
+            "1.2.840.10045.4.3.3" => alg_id::ECDSA_SHA384,
+            "1.2.840.10045.4.3.4" => alg_id::ECDSA_SHA512,
+            "1.2.840.113549.1.1.11" => alg_id::RSA_PKCS1_SHA256,
+            "1.2.840.113549.1.1.12" => alg_id::RSA_PKCS1_SHA384,
+            "1.2.840.113549.1.1.13" => alg_id::RSA_PKCS1_SHA512,
+            "1.3.101.112" => alg_id::ED25519,
+            other => {
+                return Err(AcmeError::certificate(format!(
+                    "Unsupported CSR signature algorithm {}",
+                    other
+                )));
+            }
+        };
+        let raw_key = info.subject_pki.subject_public_key.data.to_vec();
+        verify_raw(
+            key_alg,
+            signature_alg,
+            &raw_key,
+            info.raw,
+            &csr.signature_value.data,
+        )
+        .map_err(|_| AcmeError::certificate("CSR signature is invalid"))?;
+
+        let mut names: Vec<String> = Vec::new();
+        let mut push = |name: String| {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        };
+        for cn in info.subject.iter_common_name() {
+            let cn = cn
+                .as_str()
+                .map_err(|_| AcmeError::certificate("CSR common name is not a string"))?;
+            push(canonical_name(cn));
+        }
+
+        let mut must_staple = false;
+        for attribute in info.iter_attributes() {
+            let ParsedCriAttribute::ExtensionRequest(request) = attribute.parsed_attribute() else {
+                continue;
+            };
+            for extension in &request.extensions {
+                match extension.parsed_extension() {
+                    ParsedExtension::SubjectAlternativeName(san) => {
+                        for name in &san.general_names {
+                            match name {
+                                GeneralName::DNSName(dns) => push(canonical_name(dns)),
+                                GeneralName::IPAddress(bytes) => push(ip_name(bytes)?),
+                                other => {
+                                    return Err(AcmeError::certificate(format!(
+                                        "Unsupported subject alternative name {}",
+                                        other
+                                    )));
+                                }
+                            }
+                        }
+                    }
+                    _ if is_tls_feature(&extension.oid) => {
+                        must_staple = extension.value == TLS_FEATURE_STATUS_REQUEST;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if names.is_empty() {
+            return Err(AcmeError::certificate("CSR does not request any names"));
+        }
+
+        Ok(Self {
+            names,
+            must_staple,
+            public_key: RequestPublicKey {
+                raw: raw_key,
+                algorithm,
+            },
+        })
+    }
+}
+
+/// Maps the key algorithm of a SubjectPublicKeyInfo to its verification algorithm
+/// identifier and the rcgen algorithm used to re-encode it in the certificate.
+fn request_key_algorithm(
+    spki: &SubjectPublicKeyInfo<'_>,
+) -> Result<(AlgorithmIdentifier, &'static SignatureAlgorithm)> {
+    match spki.algorithm.algorithm.to_id_string().as_str() {
+        "1.2.840.10045.2.1" => {
+            let curve = spki
+                .algorithm
+                .parameters
+                .as_ref()
+                .and_then(|p| p.as_oid().ok())
+                .map(|oid| oid.to_id_string());
+            match curve.as_deref() {
+                Some("1.2.840.10045.3.1.7") => {
+                    Ok((alg_id::ECDSA_P256, &rcgen::PKCS_ECDSA_P256_SHA256))
+                }
+                Some("1.3.132.0.34") => Ok((alg_id::ECDSA_P384, &rcgen::PKCS_ECDSA_P384_SHA384)),
+                Some("1.3.132.0.35") => Ok((alg_id::ECDSA_P521, &rcgen::PKCS_ECDSA_P521_SHA512)),
+                _ => Err(AcmeError::certificate("Unsupported EC curve in CSR")),
+            }
+        }
+        "1.2.840.113549.1.1.1" => Ok((alg_id::RSA_ENCRYPTION, &rcgen::PKCS_RSA_SHA256)),
+        "1.3.101.112" => Ok((alg_id::ED25519, &rcgen::PKCS_ED25519)),
+        other => Err(AcmeError::certificate(format!(
+            "Unsupported CSR key algorithm {}",
+            other
+        ))),
+    }
+}
+
+fn is_tls_feature(oid: &x509_parser::oid_registry::Oid<'_>) -> bool {
+    oid.iter()
+        .is_some_and(|arcs| arcs.eq(TLS_FEATURE_OID.iter().copied()))
+}
+
+pub(crate) fn canonical_name(name: &str) -> String {
+    match name.parse::<IpAddr>() {
+        Ok(ip) => ip.to_string(),
+        Err(_) => name.to_lowercase(),
+    }
+}
+
+fn ip_name(bytes: &[u8]) -> Result<String> {
+    match bytes.len() {
+        4 => Ok(IpAddr::from(<[u8; 4]>::try_from(bytes).unwrap_or_default()).to_string()),
+        16 => Ok(IpAddr::from(<[u8; 16]>::try_from(bytes).unwrap_or_default()).to_string()),
+        _ => Err(AcmeError::certificate("Invalid IP address in CSR")),
+    }
+}
+
+/// A certificate issued by the local CA.
+#[derive(Debug, Clone)]
+pub struct IssuedCertificate {
+    /// The lower-case hex serial number.
+    pub serial: String,
+    /// The leaf certificate followed by the intermediate, PEM encoded.
+    pub chain_pem: String,
+    /// When the leaf certificate expires.
+    pub not_after: Timestamp,
+}
+
+/// A root and intermediate CA issuing certificates for the built-in ACME server.
+pub struct LocalCa {
+    name: String,
+    root_pem: String,
+    intermediate_pem: String,
+    issuer: Issuer<'static, KeyPair>,
+}
+
+impl LocalCa {
+    /// Storage key of the CA material.
+    fn storage_key() -> String {
+        format!("{}ca", KEY_PREFIX)
+    }
+
+    /// Loads the CA from storage, generating and storing a new one on first use.
+    /// `name` is only used when a new CA is generated.
+    pub async fn load_or_create(storage: &dyn StorageBackend, name: &str) -> Result<Self> {
+        let key = Self::storage_key();
+        if let Some(bytes) = storage.load(&key).await? {
+            let material: CaMaterial = serde_json::from_slice(&bytes)
+                .map_err(|e| AcmeError::storage(format!("Deserialize local CA failed: {}", e)))?;
+            tracing::info!("Loaded local CA \"{}\" from storage", material.name);
+            if serde_json::from_slice::<serde_json::Value>(&bytes)
+                .is_ok_and(|stored| stored.get("root_key_pem").is_some())
+            {
+                // Older versions also stored the root key
+                tracing::info!("Removing the stored root key of local CA");
+                let data = serde_json::to_vec(&material)
+                    .map_err(|e| AcmeError::storage(format!("Serialize local CA failed: {}", e)))?;
+                storage.store(&key, &data).await?;
+            }
+            return Self::from_material(material);
+        }
+
+        tracing::info!("Generating local CA \"{}\"", name);
+        let material = Self::generate(name)?;
+        let data = serde_json::to_vec(&material)
+            .map_err(|e| AcmeError::storage(format!("Serialize local CA failed: {}", e)))?;
+        storage.store(&key, &data).await?;
+        Self::from_material(material)
+    }
+
+    /// Generates a new root and intermediate.
+    fn generate(name: &str) -> Result<CaMaterial> {
+        let now = time::OffsetDateTime::now_utc();
+        let root_key = KeyPairGenerator::ecdsa_p384().generate()?;
+        let mut root_params = ca_params(&format!("{} Root CA", name), name, None);
+        root_params.not_before = now - BACKDATE;
+        root_params.not_after = now + ROOT_VALIDITY;
+        let root_cert = root_params
+            .self_signed(&root_key)
+            .map_err(|e| AcmeError::crypto(format!("Failed to sign root CA: {}", e)))?;
+        let root_issuer = Issuer::new(root_params, root_key);
+
+        let intermediate_key = KeyPairGenerator::ecdsa_p384().generate()?;
+        let mut intermediate_params = intermediate_params(name);
+        intermediate_params.not_before = now - BACKDATE;
+        intermediate_params.not_after = now + INTERMEDIATE_VALIDITY;
+        let intermediate_cert = intermediate_params
+            .signed_by(&intermediate_key, &root_issuer)
+            .map_err(|e| AcmeError::crypto(format!("Failed to sign intermediate CA: {}", e)))?;
+
+        Ok(CaMaterial {
+            name: name.to_string(),
+            root_cert_pem: root_cert.pem(),
+            intermediate_cert_pem: intermediate_cert.pem(),
+            intermediate_key_pem: intermediate_key.serialize_pem(),
+        })
+    }
+
+    fn from_material(material: CaMaterial) -> Result<Self> {
+        let key = KeyPair::from_pem(&material.intermediate_key_pem)
+            .map_err(|e| AcmeError::crypto(format!("Invalid intermediate CA key: {}", e)))?;
+        Ok(Self {
+            issuer: Issuer::new(intermediate_params(&material.name), key),
+            name: material.name,
+            root_pem: material.root_cert_pem,
+            intermediate_pem: material.intermediate_cert_pem,
+        })
+    }
+
+    /// Returns the CA name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the PEM root certificate that clients must trust.
+    pub fn root_pem(&self) -> &str {
+        &self.root_pem
+    }
+
+    /// Returns the PEM intermediate certificate.
+    pub fn intermediate_pem(&self) -> &str {
+        &self.intermediate_pem
+    }
+
+    /// Issues a certificate for the request's key and names, valid for `validity`.
+    pub fn issue(
+        &self,
+        request: &CertificateRequest,
+        validity: Duration,
+    ) -> Result<IssuedCertificate> {
+        let mut params = CertificateParams::new(request.names.clone())
+            .map_err(|e| AcmeError::certificate(format!("Invalid certificate names: {}", e)))?;
+
+        // The common name is informational; it must fit the 64 character X.520 limit
+        let mut dn = DistinguishedName::new();
+        if let Some(cn) = request.names.iter().find(|n| n.len() <= 64) {
+            dn.push(DnType::CommonName, cn.as_str());
+        }
+        params.distinguished_name = dn;
+
+        let mut serial: [u8; 16] = rand::random();
+        // Positive and without a leading zero byte, so the DER encoding keeps all 16 bytes
+        serial[0] = (serial[0] & 0x7f).max(1);
+        params.serial_number = Some(SerialNumber::from_slice(&serial));
+
+        let now = time::OffsetDateTime::now_utc();
+        params.not_before = now - BACKDATE;
+        params.not_after = now + validity;
+        params.is_ca = IsCa::ExplicitNoCa;
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        if request.public_key.algorithm == &rcgen::PKCS_RSA_SHA256 {
+            params.key_usages.push(KeyUsagePurpose::KeyEncipherment);
+        }
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.use_authority_key_identifier_extension = true;
+        if request.must_staple {
+            params
+                .custom_extensions
+                .push(CustomExtension::from_oid_content(
+                    TLS_FEATURE_OID,
+                    TLS_FEATURE_STATUS_REQUEST.to_vec(),
+                ));
+        }
+
+        let cert = params
+            .signed_by(&request.public_key, &self.issuer)
+            .map_err(|e| AcmeError::certificate(format!("Failed to sign certificate: {}", e)))?;
+        let not_after = Timestamp::from_second(params.not_after.unix_timestamp())
+            .map_err(|e| AcmeError::certificate(format!("Invalid certificate expiry: {}", e)))?;
+
+        Ok(IssuedCertificate {
+            serial: hex::encode(serial),
+            chain_pem: format!("{}{}", cert.pem(), self.intermediate_pem),
+            not_after,
+        })
+    }
+}
+
+/// Parameters shared by the root and intermediate certificates.
+fn ca_params(common_name: &str, organization: &str, path_len: Option<u8>) -> CertificateParams {
+    let mut params = CertificateParams::default();
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::OrganizationName, organization);
+    dn.push(DnType::CommonName, common_name);
+    params.distinguished_name = dn;
+    params.is_ca = match path_len {
+        Some(len) => IsCa::Ca(BasicConstraints::Constrained(len)),
+        None => IsCa::Ca(BasicConstraints::Unconstrained),
+    };
+    params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+    params.use_authority_key_identifier_extension = true;
+    params
+}
+
+/// Parameters of the intermediate, which also identify it as the issuer of leaf certificates.
+fn intermediate_params(name: &str) -> CertificateParams {
+    ca_params(&format!("{} Intermediate CA", name), name, Some(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::CsrGenerator;
+    use crate::storage::MemoryStorage;
+
+    #[tokio::test]
+    async fn test_local_ca_persists_and_issues() {
+        let storage = MemoryStorage::new();
+        let ca = LocalCa::load_or_create(&storage, "Test").await.unwrap();
+        let reloaded = LocalCa::load_or_create(&storage, "Other").await.unwrap();
+        assert_eq!(ca.root_pem(), reloaded.root_pem());
+        assert_eq!(reloaded.name(), "Test");
+
+        // Only the intermediate's private key is persisted
+        let stored = storage
+            .load(&LocalCa::storage_key())
+            .await
+            .unwrap()
+            .unwrap();
+        let stored = String::from_utf8(stored).unwrap();
+        assert_eq!(stored.matches("PRIVATE KEY-----").count(), 2);
+
+        let domains = vec!["example.com".to_string(), "www.example.com".to_string()];
+        let (csr_der, _key) = CsrGenerator::new(domains)
+            .with_must_staple(true)
+            .generate()
+            .unwrap();
+        let request = CertificateRequest::from_der(&csr_der).unwrap();
+        assert_eq!(request.names, vec!["example.com", "www.example.com"]);
+        assert!(request.must_staple);
+
+        // Certificates signed after a reload chain to the same intermediate
+        let issued = reloaded
+            .issue(&request, Duration::from_secs(7 * 24 * 3600))
+            .unwrap();
+        let chain = crate::order::parse_certificate_chain(&issued.chain_pem).unwrap();
+        assert_eq!(chain.len(), 2);
+        let (_, leaf) = x509_parser::parse_x509_certificate(&chain[0]).unwrap();
+        let (_, intermediate) = x509_parser::parse_x509_certificate(&chain[1]).unwrap();
+        assert_eq!(leaf.issuer(), intermediate.subject());
+        assert_eq!(hex::encode(leaf.raw_serial()), issued.serial);
+        let san = leaf.subject_alternative_name().unwrap().unwrap();
+        assert_eq!(san.value.general_names.len(), 2);
+    }
+
+    #[test]
+    fn test_reject_tampered_csr() {
+        let (mut csr_der, _key) = CsrGenerator::new(vec!["example.com".to_string()])
+            .generate()
+            .unwrap();
+        let last = csr_der.len() - 1;
+        csr_der[last] ^= 0x01;
+        assert!(CertificateRequest::from_der(&csr_der).is_err());
+    }
+}
@@ -0,0 +1,1340 @@
+/// HTTP handlers of the built-in ACME server.
+use super::AcmeServer;
+use super::ca::CertificateRequest;
+use super::jws::{FlattenedJws, jwk_public_key};
+use super::objects::{
+    AccountRecord, AuthorizationRecord, CertificateRecord, ChallengeRecord, OrderRecord,
+};
+use super::problem::Problem;
+use super::relay::Relay;
+use crate::error::AcmeError;
+use crate::protocol::directory::DirectoryMeta;
+use crate::protocol::{Directory, Jwk};
+use crate::types::{ChallengeType, Identifier};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jiff::{SignedDuration, Timestamp};
+use rand::RngExt;
+use rand::distr::Alphanumeric;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+type HandlerResult = std::result::Result<Response, Problem>;
+
+/// Maximum number of identifiers in one order.
+const MAX_IDENTIFIERS: usize = 100;
+
+/// Seconds a client is asked to wait before polling an order being issued upstream.
+const RELAY_RETRY_AFTER: u64 = 3;
+
+/// Revocation reasons a subscriber may request (RFC 5280 Section 5.3.1).
+const ALLOWED_REVOCATION_REASONS: [u8; 5] = [0, 1, 3, 4, 5];
+
+/// Which key reference a request must carry in its protected header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyRef {
+    /// An embedded `jwk`, for newAccount.
+    Jwk,
+    /// A `kid` naming a registered account.
+    Kid,
+    /// Either, for revokeCert.
+    Any,
+}
+
+/// A request whose JWS signature, nonce and URL have been verified.
+struct SignedRequest {
+    payload: Vec<u8>,
+    jwk: Jwk,
+    account: Option<AccountRecord>,
+}
+
+impl SignedRequest {
+    /// Whether this is a POST-as-GET request.
+    fn is_post_as_get(&self) -> bool {
+        self.payload.is_empty()
+    }
+
+    /// Deserializes the JSON payload.
+    fn json<T: DeserializeOwned>(&self) -> Result<T, Problem> {
+        serde_json::from_slice(&self.payload)
+            .map_err(|e| Problem::malformed(format!("Invalid request payload: {}", e)))
+    }
+
+    /// Returns the account a `kid` request was signed by.
+    fn account(&self) -> Result<&AccountRecord, Problem> {
+        self.account
+            .as_ref()
+            .ok_or_else(|| Problem::malformed("Request must be signed with an account key"))
+    }
+}
+
+/// Adds a fresh nonce and the directory link to every response.
+pub(super) async fn replay_nonce(
+    State(server): State<Arc<AcmeServer>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    if let Ok(nonce) = HeaderValue::from_str(&server.nonces.issue()) {
+        headers.insert("replay-nonce", nonce);
+    }
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>;rel=\"index\"", server.directory_url()))
+    {
+        headers.append(header::LINK, link);
+    }
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
+pub(super) async fn directory(State(server): State<Arc<AcmeServer>>) -> Json<Directory> {
+    let meta = server
+        .relay
+        .as_ref()
+        .filter(|relay| relay.policy().requires_eab())
+        .map(|_| DirectoryMeta {
+            terms_of_service: None,
+            website: None,
+            caa_identities: None,
+            external_account_required: Some(true),
+            profiles: None,
+            auto_renewal: None,
+        });
+    Json(Directory {
+        new_nonce: server.url("/new-nonce"),
+        new_account: server.url("/new-account"),
+        new_order: server.url("/new-order"),
+        new_authz: None,
+        revoke_cert: server.url("/revoke-cert"),
+        key_change: server.url("/key-change"),
+        renewal_info: None,
+        meta,
+    })
+}
+
+/// Serves the root certificate clients have to trust.
+pub(super) async fn root_certificate(State(server): State<Arc<AcmeServer>>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "application/pem-certificate-chain")],
+        server.ca.root_pem().to_string(),
+    )
+        .into_response()
+}
+
+/// HEAD answers 200 and GET 204 (RFC 8555 Section 7.2); the nonce is added by `replay_nonce`.
+pub(super) async fn new_nonce(method: Method) -> StatusCode {
+    if method == Method::HEAD {
+        StatusCode::OK
+    } else {
+        StatusCode::NO_CONTENT
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewAccountPayload {
+    #[serde(default)]
+    contact: Vec<String>,
+    #[serde(default)]
+    terms_of_service_agreed: bool,
+    #[serde(default)]
+    only_return_existing: bool,
+    #[serde(default)]
+    external_account_binding: Option<Value>,
+}
+
+pub(super) async fn new_account(
+    State(server): State<Arc<AcmeServer>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.url("/new-account");
+    let request = server.verify(&headers, &body, &url, KeyRef::Jwk).await?;
+    let payload: NewAccountPayload = request.json()?;
+    let thumbprint = request
+        .jwk
+        .thumbprint_sha256()
+        .map_err(|e| Problem::malformed(format!("Invalid account key: {}", e)))?;
+
+    let _guard = server.lock.lock().await;
+    if let Some(id) = server
+        .store
+        .load::<String>("account-key", &thumbprint)
+        .await?
+        && let Some(account) = server.store.load::<AccountRecord>("account", &id).await?
+    {
+        return Ok((
+            StatusCode::OK,
+            [(header::LOCATION, server.account_url(&account.id))],
+            Json(server.account_json(&account)),
+        )
+            .into_response());
+    }
+    if payload.only_return_existing {
+        return Err(Problem::account_does_not_exist(
+            "No account exists with the provided key",
+        ));
+    }
+    check_contacts(&payload.contact)?;
+    let external_account_id = match &server.relay {
+        Some(relay) => relay.policy().verify_binding(
+            payload.external_account_binding.as_ref(),
+            &request.jwk,
+            &url,
+        )?,
+        None => None,
+    };
+
+    let account = AccountRecord {
+        id: random_id(),
+        status: "valid".to_string(),
+        contact: payload.contact,
+        terms_of_service_agreed: payload.terms_of_service_agreed,
+        jwk: request.jwk,
+        thumbprint,
+        created_at: now(),
+        external_account_id,
+    };
+    server.store.save("account", &account.id, &account).await?;
+    server
+        .store
+        .save("account-key", &account.thumbprint, &account.id)
+        .await?;
+    tracing::info!("Registered ACME account {}", account.id);
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, server.account_url(&account.id))],
+        Json(server.account_json(&account)),
+    )
+        .into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateAccountPayload {
+    #[serde(default)]
+    contact: Option<Vec<String>>,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+pub(super) async fn update_account(
+    State(server): State<Arc<AcmeServer>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.account_url(&id);
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    let mut account = request.account()?.clone();
+    if account.id != id {
+        return Err(Problem::unauthorized(
+            "Account URL does not match the signing key",
+        ));
+    }
+
+    if !request.is_post_as_get() {
+        let payload: UpdateAccountPayload = request.json()?;
+        let _guard = server.lock.lock().await;
+        if let Some(contact) = payload.contact {
+            check_contacts(&contact)?;
+            account.contact = contact;
+        }
+        match payload.status.as_deref() {
+            None => {}
+            Some("deactivated") => {
+                tracing::info!("Deactivating ACME account {}", account.id);
+                account.status = "deactivated".to_string();
+            }
+            Some(other) => {
+                return Err(Problem::malformed(format!(
+                    "Cannot change account status to {}",
+                    other
+                )));
+            }
+        }
+        server.store.save("account", &account.id, &account).await?;
+    }
+
+    Ok(Json(server.account_json(&account)).into_response())
+}
+
+pub(super) async fn account_orders(
+    State(server): State<Arc<AcmeServer>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.url(&format!("/account/{}/orders", id));
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    if request.account()?.id != id {
+        return Err(Problem::unauthorized("Orders belong to another account"));
+    }
+
+    let mut orders: Vec<OrderRecord> = server.store.list("order").await?;
+    orders.retain(|order| order.account_id == id);
+    orders.sort_by_key(|order| order.expires);
+    let urls: Vec<String> = orders.iter().map(|o| server.order_url(&o.id)).collect();
+    Ok(Json(json!({ "orders": urls })).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyChangePayload {
+    account: String,
+    old_key: Jwk,
+}
+
+pub(super) async fn key_change(
+    State(server): State<Arc<AcmeServer>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.url("/key-change");
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    let mut account = request.account()?.clone();
+
+    // The payload is a JWS signed by the new key (RFC 8555 Section 7.3.5)
+    let inner = FlattenedJws::parse(&request.payload)?;
+    let inner_header = inner.header()?;
+    let (Some(new_jwk), None) = (inner_header.jwk, inner_header.kid) else {
+        return Err(Problem::malformed(
+            "Inner JWS must carry the new key as jwk",
+        ));
+    };
+    if inner_header.url != url {
+        return Err(Problem::malformed(
+            "Inner JWS url does not match the request",
+        ));
+    }
+    inner.verify(&inner_header.alg, &new_jwk)?;
+    let payload: KeyChangePayload = serde_json::from_slice(&inner.payload()?)
+        .map_err(|e| Problem::malformed(format!("Invalid key change payload: {}", e)))?;
+
+    if payload.account != server.account_url(&account.id) {
+        return Err(Problem::unauthorized(
+            "Key change names a different account",
+        ));
+    }
+    let old_thumbprint = payload
+        .old_key
+        .thumbprint_sha256()
+        .map_err(|e| Problem::malformed(format!("Invalid old key: {}", e)))?;
+    if old_thumbprint != account.thumbprint {
+        return Err(Problem::unauthorized(
+            "oldKey is not the current account key",
+        ));
+    }
+    let new_thumbprint = new_jwk
+        .thumbprint_sha256()
+        .map_err(|e| Problem::malformed(format!("Invalid new key: {}", e)))?;
+
+    let _guard = server.lock.lock().await;
+    if let Some(existing) = server
+        .store
+        .load::<String>("account-key", &new_thumbprint)
+        .await?
+    {
+        let mut problem = Problem::malformed("The new key is already in use by another account");
+        problem.status = StatusCode::CONFLICT;
+        let mut response = problem.into_response();
+        if let Ok(location) = HeaderValue::from_str(&server.account_url(&existing)) {
+            response.headers_mut().insert(header::LOCATION, location);
+        }
+        return Ok(response);
+    }
+
+    server
+        .store
+        .delete("account-key", &account.thumbprint)
+        .await?;
+    account.jwk = new_jwk;
+    account.thumbprint = new_thumbprint;
+    server.store.save("account", &account.id, &account).await?;
+    server
+        .store
+        .save("account-key", &account.thumbprint, &account.id)
+        .await?;
+    tracing::info!("Rolled over the key of ACME account {}", account.id);
+
+    Ok(Json(server.account_json(&account)).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewOrderPayload {
+    identifiers: Vec<Identifier>,
+    #[serde(default)]
+    not_before: Option<String>,
+    #[serde(default)]
+    not_after: Option<String>,
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+pub(super) async fn new_order(
+    State(server): State<Arc<AcmeServer>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.url("/new-order");
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    let account = request.account()?;
+    let payload: NewOrderPayload = request.json()?;
+
+    if payload.not_before.is_some() || payload.not_after.is_some() {
+        return Err(Problem::malformed(
+            "notBefore and notAfter are not supported by this server",
+        ));
+    }
+    if let Some(profile) = payload.profile {
+        return Err(Problem::new(
+            StatusCode::BAD_REQUEST,
+            "invalidProfile",
+            format!("Unknown certificate profile {}", profile),
+        ));
+    }
+    if payload.identifiers.is_empty() || payload.identifiers.len() > MAX_IDENTIFIERS {
+        return Err(Problem::malformed(format!(
+            "An order must contain between 1 and {} identifiers",
+            MAX_IDENTIFIERS
+        )));
+    }
+
+    let mut identifiers: Vec<Identifier> = Vec::new();
+    for identifier in &payload.identifiers {
+        let identifier = normalize_identifier(identifier)?;
+        if let Some(relay) = &server.relay
+            && !relay.policy().allows(&identifier)
+        {
+            return Err(Problem::rejected_identifier(format!(
+                "The relay policy does not allow {}",
+                identifier.value
+            )));
+        }
+        if !identifiers.iter().any(|i| i.value == identifier.value) {
+            identifiers.push(identifier);
+        }
+    }
+
+    let created = now();
+    let mut authorizations = Vec::new();
+    for identifier in &identifiers {
+        let (value, wildcard) = match identifier.value.strip_prefix("*.") {
+            Some(base) => (base.to_string(), true),
+            None => (identifier.value.clone(), false),
+        };
+        // The relay policy stands in for challenges; the upstream CA validates for real
+        let challenge_types: &[ChallengeType] = if server.relay.is_some() {
+            &[]
+        } else if wildcard {
+            &[ChallengeType::Dns01]
+        } else if identifier.is_ip() {
+            &[ChallengeType::Http01, ChallengeType::TlsAlpn01]
+        } else {
+            &[
+                ChallengeType::Http01,
+                ChallengeType::Dns01,
+                ChallengeType::TlsAlpn01,
+            ]
+        };
+        let authz = AuthorizationRecord {
+            id: random_id(),
+            account_id: account.id.clone(),
+            identifier: Identifier {
+                id_type: identifier.id_type.clone(),
+                value,
+            },
+            wildcard,
+            status: if server.relay.is_some() {
+                "valid".to_string()
+            } else {
+                "pending".to_string()
+            },
+            expires: after(created, server.config.authorization_validity),
+            challenges: challenge_types
+                .iter()
+                .map(|challenge_type| ChallengeRecord {
+                    id: random_id(),
+                    challenge_type: challenge_type.as_str().to_string(),
+                    token: URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>()),
+                    status: "pending".to_string(),
+                    validated: None,
+                    error: None,
+                })
+                .collect(),
+        };
+        server.store.save("authz", &authz.id, &authz).await?;
+        authorizations.push(authz.id);
+    }
+
+    let order = OrderRecord {
+        id: random_id(),
+        account_id: account.id.clone(),
+        status: "pending".to_string(),
+        expires: after(created, server.config.order_validity),
+        identifiers,
+        authorizations,
+        certificate_id: None,
+        error: None,
+    };
+    server.store.save("order", &order.id, &order).await?;
+    tracing::info!(
+        "Created order {} for account {}: {:?}",
+        order.id,
+        account.id,
+        order
+            .identifiers
+            .iter()
+            .map(|i| i.value.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, server.order_url(&order.id))],
+        Json(server.order_json(&order)),
+    )
+        .into_response())
+}
+
+pub(super) async fn get_order(
+    State(server): State<Arc<AcmeServer>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.order_url(&id);
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    let _guard = server.lock.lock().await;
+    let order = server.owned_order(&id, request.account()?).await?;
+    Ok(Json(server.order_json(&order)).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct FinalizePayload {
+    csr: String,
+}
+
+pub(super) async fn finalize_order(
+    State(server): State<Arc<AcmeServer>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.url(&format!("/order/{}/finalize", id));
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    let payload: FinalizePayload = request.json()?;
+
+    let _guard = server.lock.lock().await;
+    let mut order = server.owned_order(&id, request.account()?).await?;
+    if order.status != "ready" {
+        return Err(Problem::order_not_ready(format!(
+            "Order is {}, not ready",
+            order.status
+        )));
+    }
+
+    let csr_der = URL_SAFE_NO_PAD
+        .decode(payload.csr.trim_end_matches('='))
+        .map_err(|_| Problem::bad_csr("CSR is not valid base64url"))?;
+    let csr =
+        CertificateRequest::from_der(&csr_der).map_err(|e| Problem::bad_csr(e.to_string()))?;
+
+    let mut requested = csr.names.clone();
+    requested.sort();
+    let mut expected: Vec<String> = order.identifiers.iter().map(|i| i.value.clone()).collect();
+    expected.sort();
+    if requested != expected {
+        return Err(Problem::bad_csr(format!(
+            "CSR names {:?} do not match the order identifiers {:?}",
+            requested, expected
+        )));
+    }
+
+    if let Some(relay) = &server.relay {
+        order.status = "processing".to_string();
+        server.store.save("order", &order.id, &order).await?;
+        tokio::spawn(
+            server
+                .clone()
+                .relay_order(relay.clone(), order.id.clone(), csr_der),
+        );
+        return Ok((
+            [
+                (header::LOCATION, server.order_url(&order.id)),
+                (header::RETRY_AFTER, RELAY_RETRY_AFTER.to_string()),
+            ],
+            Json(server.order_json(&order)),
+        )
+            .into_response());
+    }
+
+    let issued = server
+        .ca
+        .issue(&csr, server.config.certificate_validity)
+        .map_err(Problem::from)?;
+    let certificate = CertificateRecord {
+        id: issued.serial,
+        account_id: order.account_id.clone(),
+        chain_pem: issued.chain_pem,
+        not_after: issued.not_after,
+        revocation_reason: None,
+        revoked_at: None,
+    };
+    server
+        .store
+        .save("cert", &certificate.id, &certificate)
+        .await?;
+
+    order.status = "valid".to_string();
+    order.certificate_id = Some(certificate.id.clone());
+    server.store.save("order", &order.id, &order).await?;
+    tracing::info!(
+        "Issued certificate {} for order {}",
+        certificate.id,
+        order.id
+    );
+
+    Ok((
+        [(header::LOCATION, server.order_url(&order.id))],
+        Json(server.order_json(&order)),
+    )
+        .into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateAuthorizationPayload {
+    #[serde(default)]
+    status: Option<String>,
+}
+
+pub(super) async fn authorization(
+    State(server): State<Arc<AcmeServer>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.authz_url(&id);
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    let _guard = server.lock.lock().await;
+    let mut authz = server.owned_authorization(&id, request.account()?).await?;
+
+    if !request.is_post_as_get() {
+        let payload: UpdateAuthorizationPayload = request.json()?;
+        match payload.status.as_deref() {
+            None => {}
+            Some("deactivated") if matches!(authz.status.as_str(), "pending" | "valid") => {
+                authz.status = "deactivated".to_string();
+                server.store.save("authz", &authz.id, &authz).await?;
+            }
+            Some(other) => {
+                return Err(Problem::malformed(format!(
+                    "Cannot change a {} authorization to {}",
+                    authz.status, other
+                )));
+            }
+        }
+    }
+
+    Ok(Json(server.authorization_json(&authz)).into_response())
+}
+
+pub(super) async fn challenge(
+    State(server): State<Arc<AcmeServer>>,
+    Path((authz_id, id)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.challenge_url(&authz_id, &id);
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    let _guard = server.lock.lock().await;
+    let mut authz = server
+        .owned_authorization(&authz_id, request.account()?)
+        .await?;
+    let index = authz
+        .challenges
+        .iter()
+        .position(|c| c.id == id)
+        .ok_or_else(|| Problem::not_found("Challenge not found"))?;
+
+    // Any JSON object (normally `{}`) asks the server to validate the challenge
+    let processing = authz.challenges.iter().any(|c| c.status == "processing");
+    if !request.is_post_as_get()
+        && authz.status == "pending"
+        && authz.challenges[index].status == "pending"
+        && !processing
+    {
+        authz.challenges[index].status = "processing".to_string();
+        server.store.save("authz", &authz.id, &authz).await?;
+        tokio::spawn(server.clone().validate(authz.id.clone(), id.clone()));
+    }
+
+    let challenge = &authz.challenges[index];
+    let up = format!("<{}>;rel=\"up\"", server.authz_url(&authz.id));
+    Ok((
+        [(header::LINK, up)],
+        Json(server.challenge_json(&authz, challenge)),
+    )
+        .into_response())
+}
+
+pub(super) async fn certificate(
+    State(server): State<Arc<AcmeServer>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.url(&format!("/cert/{}", id));
+    let request = server.verify(&headers, &body, &url, KeyRef::Kid).await?;
+    if !request.is_post_as_get() {
+        return Err(Problem::malformed(
+            "Certificates are fetched with POST-as-GET",
+        ));
+    }
+    let certificate = server
+        .store
+        .load::<CertificateRecord>("cert", &id)
+        .await?
+        .ok_or_else(|| Problem::not_found("Certificate not found"))?;
+    if certificate.account_id != request.account()?.id {
+        return Err(Problem::unauthorized(
+            "Certificate belongs to another account",
+        ));
+    }
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/pem-certificate-chain")],
+        certificate.chain_pem,
+    )
+        .into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct RevokePayload {
+    certificate: String,
+    #[serde(default)]
+    reason: Option<u8>,
+}
+
+pub(super) async fn revoke_certificate(
+    State(server): State<Arc<AcmeServer>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> HandlerResult {
+    let url = server.url("/revoke-cert");
+    let request = server.verify(&headers, &body, &url, KeyRef::Any).await?;
+    let payload: RevokePayload = request.json()?;
+
+    let reason = payload.reason.unwrap_or(0);
+    if !ALLOWED_REVOCATION_REASONS.contains(&reason) {
+        return Err(Problem::bad_revocation_reason(format!(
+            "Revocation reason {} is not allowed",
+            reason
+        )));
+    }
+    let der = URL_SAFE_NO_PAD
+        .decode(payload.certificate.trim_end_matches('='))
+        .map_err(|_| Problem::malformed("Certificate is not valid base64url"))?;
+    let (_, cert) = x509_parser::parse_x509_certificate(&der)
+        .map_err(|_| Problem::malformed("Certificate cannot be parsed"))?;
+    let serial = hex::encode(cert.raw_serial());
+
+    let _guard = server.lock.lock().await;
+    let mut record = server
+        .store
+        .load::<CertificateRecord>("cert", &serial)
+        .await?
+        .filter(|record| {
+            crate::order::parse_certificate_chain(&record.chain_pem)
+                .ok()
+                .and_then(|chain| chain.into_iter().next())
+                .is_some_and(|leaf| leaf == der)
+        })
+        .ok_or_else(|| Problem::not_found("Certificate was not issued by this server"))?;
+
+    match &request.account {
+        Some(account) if account.id == record.account_id => {}
+        Some(_) => {
+            return Err(Problem::unauthorized(
+                "Certificate was issued to another account",
+            ));
+        }
+        None => {
+            let (_, key) = jwk_public_key(&request.jwk)?;
+            if key != cert.public_key().subject_public_key.data.as_ref() {
+                return Err(Problem::unauthorized(
+                    "Request is not signed by the certificate key",
+                ));
+            }
+        }
+    }
+    if record.is_revoked() {
+        return Err(Problem::already_revoked("Certificate is already revoked"));
+    }
+    if let Some(relay) = &server.relay {
+        relay.revoke(&record.chain_pem, reason).await?;
+    }
+
+    record.revocation_reason = Some(reason);
+    record.revoked_at = Some(now());
+    server.store.save("cert", &record.id, &record).await?;
+    tracing::info!("Revoked certificate {} (reason {})", record.id, reason);
+
+    Ok(StatusCode::OK.into_response())
+}
+
+impl AcmeServer {
+    /// Verifies the JWS of a request to `url` and resolves its signing key.
+    async fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        url: &str,
+        key_ref: KeyRef,
+    ) -> Result<SignedRequest, Problem> {
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        if content_type != "application/jose+json" {
+            return Err(Problem::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "malformed",
+                "Requests must use the application/jose+json content type",
+            ));
+        }
+
+        let jws = FlattenedJws::parse(body)?;
+        let header = jws.header()?;
+        let nonce = header
+            .nonce
+            .as_deref()
+            .ok_or_else(|| Problem::bad_nonce("JWS header has no nonce"))?;
+        if !self.nonces.consume(nonce) {
+            return Err(Problem::bad_nonce("Nonce is unknown or was already used"));
+        }
+        if header.url != url {
+            return Err(Problem::unauthorized(format!(
+                "JWS url {} does not match the request URL {}",
+                header.url, url
+            )));
+        }
+
+        let (jwk, account) = match (header.jwk, header.kid) {
+            (Some(jwk), None) if key_ref != KeyRef::Kid => (jwk, None),
+            (None, Some(kid)) if key_ref != KeyRef::Jwk => {
+                let account = match kid.strip_prefix(&self.url("/account/")) {
+                    Some(id) => self.store.load::<AccountRecord>("account", id).await?,
+                    None => None,
+                }
+                .ok_or_else(|| {
+                    Problem::account_does_not_exist(format!("Unknown account {}", kid))
+                })?;
+                if account.status != "valid" {
+                    return Err(Problem::unauthorized(format!(
+                        "Account is {}",
+                        account.status
+                    )));
+                }
+                (account.jwk.clone(), Some(account))
+            }
+            (Some(_), Some(_)) | (None, None) => {
+                return Err(Problem::malformed(
+                    "JWS header must contain exactly one of jwk and kid",
+                ));
+            }
+            (Some(_), None) => {
+                return Err(Problem::malformed(
+                    "Request must be signed with an account key (kid)",
+                ));
+            }
+            (None, Some(_)) => {
+                return Err(Problem::malformed(
+                    "Request must carry the public key (jwk)",
+                ));
+            }
+        };
+
+        jws.verify(&header.alg, &jwk)?;
+        Ok(SignedRequest {
+            payload: jws.payload()?,
+            jwk,
+            account,
+        })
+    }
+
+    fn account_url(&self, id: &str) -> String {
+        self.url(&format!("/account/{}", id))
+    }
+
+    fn order_url(&self, id: &str) -> String {
+        self.url(&format!("/order/{}", id))
+    }
+
+    fn authz_url(&self, id: &str) -> String {
+        self.url(&format!("/authz/{}", id))
+    }
+
+    fn challenge_url(&self, authz_id: &str, id: &str) -> String {
+        self.url(&format!("/chall/{}/{}", authz_id, id))
+    }
+
+    fn account_json(&self, account: &AccountRecord) -> Value {
+        json!({
+            "status": account.status,
+            "contact": account.contact,
+            "termsOfServiceAgreed": account.terms_of_service_agreed,
+            "orders": self.url(&format!("/account/{}/orders", account.id)),
+            "createdAt": account.created_at,
+        })
+    }
+
+    fn order_json(&self, order: &OrderRecord) -> Value {
+        let mut value = json!({
+            "status": order.status,
+            "expires": order.expires,
+            "identifiers": order.identifiers,
+            "authorizations": order
+                .authorizations
+                .iter()
+                .map(|id| self.authz_url(id))
+                .collect::<Vec<_>>(),
+            "finalize": self.url(&format!("/order/{}/finalize", order.id)),
+        });
+        if let Some(certificate) = &order.certificate_id {
+            value["certificate"] = json!(self.url(&format!("/cert/{}", certificate)));
+        }
+        if let Some(error) = &order.error {
+            value["error"] = json!(error);
+        }
+        value
+    }
+
+    fn authorization_json(&self, authz: &AuthorizationRecord) -> Value {
+        let mut value = json!({
+            "identifier": authz.identifier,
+            "status": authz.status,
+            "expires": authz.expires,
+            "challenges": authz
+                .challenges
+                .iter()
+                .map(|c| self.challenge_json(authz, c))
+                .collect::<Vec<_>>(),
+        });
+        if authz.wildcard {
+            value["wildcard"] = json!(true);
+        }
+        value
+    }
+
+    fn challenge_json(&self, authz: &AuthorizationRecord, challenge: &ChallengeRecord) -> Value {
+        let mut value = json!({
+            "type": challenge.challenge_type,
+            "url": self.challenge_url(&authz.id, &challenge.id),
+            "status": challenge.status,
+            "token": challenge.token,
+        });
+        if let Some(validated) = challenge.validated {
+            value["validated"] = json!(validated);
+        }
+        if let Some(error) = &challenge.error {
+            value["error"] = json!(error);
+        }
+        value
+    }
+
+    /// Loads an authorization of the account, marking it expired if its lifetime has passed.
+    /// Callers hold the update lock.
+    async fn owned_authorization(
+        &self,
+        id: &str,
+        account: &AccountRecord,
+    ) -> Result<AuthorizationRecord, Problem> {
+        let mut authz = self
+            .store
+            .load::<AuthorizationRecord>("authz", id)
+            .await?
+            .ok_or_else(|| Problem::not_found("Authorization not found"))?;
+        if authz.account_id != account.id {
+            return Err(Problem::unauthorized(
+                "Authorization belongs to another account",
+            ));
+        }
+        if matches!(authz.status.as_str(), "pending" | "valid") && authz.expires < Timestamp::now()
+        {
+            authz.status = "expired".to_string();
+            self.store.save("authz", &authz.id, &authz).await?;
+        }
+        Ok(authz)
+    }
+
+    /// Loads an order of the account, bringing its status up to date with its
+    /// authorizations and expiry. Callers hold the update lock.
+    async fn owned_order(&self, id: &str, account: &AccountRecord) -> Result<OrderRecord, Problem> {
+        let mut order = self
+            .store
+            .load::<OrderRecord>("order", id)
+            .await?
+            .ok_or_else(|| Problem::not_found("Order not found"))?;
+        if order.account_id != account.id {
+            return Err(Problem::unauthorized("Order belongs to another account"));
+        }
+
+        let mut changed = false;
+        if order.status == "pending" {
+            let mut all_valid = true;
+            for authz_id in &order.authorizations {
+                let authz = self.owned_authorization(authz_id, account).await?;
+                match authz.status.as_str() {
+                    "valid" => {}
+                    "pending" => all_valid = false,
+                    status => {
+                        order.status = "invalid".to_string();
+                        order.error = Some(
+                            Problem::unauthorized(format!(
+                                "Authorization for {} is {}",
+                                authz.identifier.value, status
+                            ))
+                            .to_detail(),
+                        );
+                        all_valid = false;
+                        changed = true;
+                        break;
+                    }
+                }
+            }
+            if all_valid {
+                order.status = "ready".to_string();
+                changed = true;
+            }
+        }
+        if matches!(order.status.as_str(), "pending" | "ready") && order.expires < Timestamp::now()
+        {
+            order.status = "invalid".to_string();
+            order.error = Some(Problem::malformed("Order expired").to_detail());
+            changed = true;
+        }
+
+        if changed {
+            self.store.save("order", &order.id, &order).await?;
+        }
+        Ok(order)
+    }
+
+    /// Obtains the certificate of a finalized order from the upstream CA in the background
+    /// and records the outcome.
+    async fn relay_order(self: Arc<Self>, relay: Arc<Relay>, order_id: String, csr_der: Vec<u8>) {
+        if let Err(e) = self.run_relay_order(&relay, &order_id, &csr_der).await {
+            tracing::error!("Relaying order {} failed: {}", order_id, e);
+        }
+    }
+
+    async fn run_relay_order(
+        &self,
+        relay: &Relay,
+        order_id: &str,
+        csr_der: &[u8],
+    ) -> crate::error::Result<()> {
+        let not_found = || AcmeError::not_found(format!("Order {} vanished", order_id));
+        let order: OrderRecord = self
+            .store
+            .load("order", order_id)
+            .await?
+            .ok_or_else(not_found)?;
+        tracing::info!("Relaying order {} to the upstream CA", order.id);
+        let result = relay.issue(&order.identifiers, csr_der).await;
+
+        let _guard = self.lock.lock().await;
+        let mut order: OrderRecord = self
+            .store
+            .load("order", order_id)
+            .await?
+            .ok_or_else(not_found)?;
+        match result.and_then(|chain_pem| upstream_certificate(&order, chain_pem)) {
+            Ok(certificate) => {
+                self.store
+                    .save("cert", &certificate.id, &certificate)
+                    .await?;
+                tracing::info!(
+                    "Relayed certificate {} for order {}",
+                    certificate.id,
+                    order.id
+                );
+                order.status = "valid".to_string();
+                order.certificate_id = Some(certificate.id);
+            }
+            Err(e) => {
+                tracing::warn!("Upstream issuance for order {} failed: {}", order.id, e);
+                order.status = "invalid".to_string();
+                order.error = Some(
+                    Problem::server_internal(format!("Upstream CA did not issue: {}", e))
+                        .to_detail(),
+                );
+            }
+        }
+        self.store.save("order", &order.id, &order).await
+    }
+
+    /// Validates a challenge in the background and records the outcome.
+    async fn validate(self: Arc<Self>, authz_id: String, challenge_id: String) {
+        if let Err(e) = self.run_validation(&authz_id, &challenge_id).await {
+            tracing::error!(
+                "Validation of challenge {} of authorization {} failed: {}",
+                challenge_id,
+                authz_id,
+                e
+            );
+            // A challenge left processing would refuse every retry
+            if let Err(e) = self.abort_validation(&authz_id, &challenge_id, &e).await {
+                tracing::error!(
+                    "Cannot mark challenge {} of authorization {} invalid: {}",
+                    challenge_id,
+                    authz_id,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Marks a challenge still being processed, and its authorization, invalid
+    /// after validation stopped on an internal error.
+    async fn abort_validation(
+        &self,
+        authz_id: &str,
+        challenge_id: &str,
+        error: &AcmeError,
+    ) -> crate::error::Result<()> {
+        let _guard = self.lock.lock().await;
+        let Some(mut authz) = self
+            .store
+            .load::<AuthorizationRecord>("authz", authz_id)
+            .await?
+        else {
+            return Ok(());
+        };
+        let Some(challenge) = authz
+            .challenges
+            .iter_mut()
+            .find(|c| c.id == challenge_id && c.status == "processing")
+        else {
+            return Ok(());
+        };
+        challenge.status = "invalid".to_string();
+        challenge.error = Some(
+            Problem::server_internal(format!("Validation could not complete: {}", error))
+                .to_detail(),
+        );
+        if authz.status == "pending" {
+            authz.status = "invalid".to_string();
+        }
+        self.store.save("authz", &authz.id, &authz).await
+    }
+
+    async fn run_validation(&self, authz_id: &str, challenge_id: &str) -> crate::error::Result<()> {
+        let not_found = || AcmeError::not_found(format!("Authorization {} vanished", authz_id));
+        let authz: AuthorizationRecord = self
+            .store
+            .load("authz", authz_id)
+            .await?
+            .ok_or_else(not_found)?;
+        let account: AccountRecord = self
+            .store
+            .load("account", &authz.account_id)
+            .await?
+            .ok_or_else(not_found)?;
+        let challenge = authz
+            .challenges
+            .iter()
+            .find(|c| c.id == challenge_id)
+            .ok_or_else(not_found)?;
+        let challenge_type: ChallengeType = challenge
+            .challenge_type
+            .parse()
+            .map_err(AcmeError::protocol)?;
+        let key_authorization = format!("{}.{}", challenge.token, account.thumbprint);
+
+        tracing::info!(
+            "Validating {} challenge for {}",
+            challenge_type,
+            authz.identifier.value
+        );
+        let result = self
+            .verifier
+            .verify(
+                challenge_type,
+                &authz.identifier,
+                &challenge.token,
+                &key_authorization,
+            )
+            .await;
+
+        let _guard = self.lock.lock().await;
+        let mut authz: AuthorizationRecord = self
+            .store
+            .load("authz", authz_id)
+            .await?
+            .ok_or_else(not_found)?;
+        let Some(challenge) = authz.challenges.iter_mut().find(|c| c.id == challenge_id) else {
+            return Err(not_found());
+        };
+        match result {
+            Ok(()) => {
+                tracing::info!(
+                    "Validated {} for {}",
+                    challenge_type,
+                    authz.identifier.value
+                );
+                challenge.status = "valid".to_string();
+                challenge.validated = Some(now());
+                if authz.status == "pending" {
+                    authz.status = "valid".to_string();
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "{} validation for {} failed: {}",
+                    challenge_type,
+                    authz.identifier.value,
+                    e
+                );
+                challenge.status = "invalid".to_string();
+                challenge.error = Some(validation_problem(challenge_type, e).to_detail());
+                if authz.status == "pending" {
+                    authz.status = "invalid".to_string();
+                }
+            }
+        }
+        self.store.save("authz", &authz.id, &authz).await
+    }
+}
+
+/// Records a chain issued by the upstream CA under the serial number of its leaf.
+fn upstream_certificate(
+    order: &OrderRecord,
+    chain_pem: String,
+) -> crate::error::Result<CertificateRecord> {
+    let leaf = crate::order::parse_certificate_chain(&chain_pem)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| AcmeError::certificate("Upstream returned an empty chain"))?;
+    let (_, cert) = x509_parser::parse_x509_certificate(&leaf)
+        .map_err(|e| AcmeError::certificate(format!("Cannot parse upstream certificate: {}", e)))?;
+    let not_after = Timestamp::from_second(cert.validity().not_after.timestamp())
+        .map_err(|e| AcmeError::certificate(format!("Invalid certificate expiry: {}", e)))?;
+    Ok(CertificateRecord {
+        id: hex::encode(cert.raw_serial()),
+        account_id: order.account_id.clone(),
+        chain_pem,
+        not_after,
+        revocation_reason: None,
+        revoked_at: None,
+    })
+}
+
+/// Maps a failed validation onto the ACME error type reported in the challenge.
+fn validation_problem(challenge_type: ChallengeType, err: AcmeError) -> Problem {
+    match err {
+        AcmeError::Transport(detail) if challenge_type == ChallengeType::Dns01 => {
+            Problem::new(StatusCode::BAD_REQUEST, "dns", detail)
+        }
+        AcmeError::Transport(detail) => Problem::new(StatusCode::BAD_REQUEST, "connection", detail),
+        AcmeError::Challenge { error, .. } => {
+            Problem::new(StatusCode::FORBIDDEN, "incorrectResponse", error)
+        }
+        other => Problem::new(
+            StatusCode::FORBIDDEN,
+            "incorrectResponse",
+            other.to_string(),
+        ),
+    }
+}
+
+/// Validates and canonicalizes an identifier of a new order.
+fn normalize_identifier(identifier: &Identifier) -> Result<Identifier, Problem> {
+    match identifier.id_type.as_str() {
+        "dns" => {
+            let value = identifier.value.trim_end_matches('.').to_lowercase();
+            let base = value.strip_prefix("*.").unwrap_or(&value);
+            let valid = !base.is_empty()
+                && base.len() <= 253
+                && base.parse::<IpAddr>().is_err()
+                && base.split('.').all(|label| {
+                    !label.is_empty()
+                        && label.len() <= 63
+                        && !label.starts_with('-')
+                        && !label.ends_with('-')
+                        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                });
+            if !valid {
+                return Err(Problem::rejected_identifier(format!(
+                    "{} is not a valid DNS name",
+                    identifier.value
+                )));
+            }
+            Ok(Identifier::dns(value))
+        }
+        "ip" => identifier
+            .value
+            .parse::<IpAddr>()
+            .map(|ip| Identifier::ip(ip.to_string()))
+            .map_err(|_| {
+                Problem::rejected_identifier(format!(
+                    "{} is not a valid IP address",
+                    identifier.value
+                ))
+            }),
+        other => Err(Problem::unsupported_identifier(format!(
+            "Identifier type {} is not supported",
+            other
+        ))),
+    }
+}
+
+/// Only email contacts are accepted.
+fn check_contacts(contacts: &[String]) -> Result<(), Problem> {
+    for contact in contacts {
+        let valid = contact
+            .strip_prefix("mailto:")
+            .is_some_and(|address| address.contains('@') && !address.contains(','));
+        if !valid {
+            return Err(Problem::unsupported_contact(format!(
+                "Contact {} is not a single mailto: address",
+                contact
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn random_id() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect()
+}
+
+/// The current time, truncated to whole seconds for readable timestamps.
+fn now() -> Timestamp {
+    Timestamp::from_second(Timestamp::now().as_second()).unwrap_or_else(|_| Timestamp::now())
+}
+
+fn after(start: Timestamp, duration: Duration) -> Timestamp {
+    SignedDuration::try_from(duration)
+        .ok()
+        .and_then(|d| start.checked_add(d).ok())
+        .unwrap_or(Timestamp::MAX)
+}
@@ -0,0 +1,288 @@
+/// JWS verification for requests to the built-in ACME server (RFC 8555 Section 6.2).
+/// Signatures are checked with the signature verification algorithms of rustls' aws-lc-rs
+/// provider, which expect public keys and signatures in their X.509 encodings.
+use super::problem::Problem;
+use crate::protocol::Jwk;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rustls::pki_types::{AlgorithmIdentifier, alg_id};
+use serde::Deserialize;
+
+/// A JWS in flattened JSON serialization.
+#[derive(Debug, Deserialize)]
+pub(crate) struct FlattenedJws {
+    pub protected: String,
+    #[serde(default)]
+    pub payload: String,
+    pub signature: String,
+}
+
+/// The protected header of an ACME JWS.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ProtectedHeader {
+    pub alg: String,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub jwk: Option<Jwk>,
+    #[serde(default)]
+    pub kid: Option<String>,
+}
+
+impl FlattenedJws {
+    /// Parses a flattened JWS from a request body.
+    pub(crate) fn parse(body: &[u8]) -> Result<Self, Problem> {
+        serde_json::from_slice(body)
+            .map_err(|e| Problem::malformed(format!("Request body is not a flattened JWS: {}", e)))
+    }
+
+    /// Decodes the protected header.
+    pub(crate) fn header(&self) -> Result<ProtectedHeader, Problem> {
+        let bytes = decode(&self.protected, "protected header")?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| Problem::malformed(format!("Invalid JWS protected header: {}", e)))
+    }
+
+    /// Decodes the payload; POST-as-GET requests have an empty payload.
+    pub(crate) fn payload(&self) -> Result<Vec<u8>, Problem> {
+        decode(&self.payload, "payload")
+    }
+
+    /// Verifies the signature over the protected header and payload with the given key.
+    pub(crate) fn verify(&self, alg: &str, jwk: &Jwk) -> Result<(), Problem> {
+        let signature = decode(&self.signature, "signature")?;
+        let message = format!("{}.{}", self.protected, self.payload);
+        verify_signature(jwk, alg, message.as_bytes(), &signature)
+    }
+}
+
+fn decode(value: &str, what: &str) -> Result<Vec<u8>, Problem> {
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|e| Problem::malformed(format!("Invalid base64url in JWS {}: {}", what, e)))
+}
+
+/// Verifies a JWS signature made with `alg` by the key in `jwk`.
+pub(crate) fn verify_signature(
+    jwk: &Jwk,
+    alg: &str,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Problem> {
+    let (public_key_alg, public_key) = jwk_public_key(jwk)?;
+    let (expected_key_alg, signature_alg, signature) = match alg {
+        "ES256" => (
+            alg_id::ECDSA_P256,
+            alg_id::ECDSA_SHA256,
+            ecdsa_raw_to_der(signature, 32)?,
+        ),
+        "ES384" => (
+            alg_id::ECDSA_P384,
+            alg_id::ECDSA_SHA384,
+            ecdsa_raw_to_der(signature, 48)?,
+        ),
+        "ES512" => (
+            alg_id::ECDSA_P521,
+            alg_id::ECDSA_SHA512,
+            ecdsa_raw_to_der(signature, 66)?,
+        ),
+        "RS256" => (
+            alg_id::RSA_ENCRYPTION,
+            alg_id::RSA_PKCS1_SHA256,
+            signature.to_vec(),
+        ),
+        "EdDSA" => (alg_id::ED25519, alg_id::ED25519, signature.to_vec()),
+        other => {
+            return Err(Problem::bad_signature_algorithm(format!(
+                "Unsupported JWS algorithm {}; use ES256, ES384, ES512, RS256 or EdDSA",
+                other
+            )));
+        }
+    };
+    if public_key_alg != expected_key_alg {
+        return Err(Problem::bad_signature_algorithm(format!(
+            "JWS algorithm {} does not match the {} key",
+            alg, jwk.kty
+        )));
+    }
+
+    verify_raw(
+        public_key_alg,
+        signature_alg,
+        &public_key,
+        message,
+        &signature,
+    )
+    .map_err(|_| Problem::malformed("JWS signature is invalid"))
+}
+
+/// Verifies an X.509-style signature: `public_key` is the content of a SubjectPublicKeyInfo
+/// bit string and ECDSA signatures are DER encoded.
+pub(crate) fn verify_raw(
+    public_key_alg: AlgorithmIdentifier,
+    signature_alg: AlgorithmIdentifier,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), ()> {
+    let provider = rustls::crypto::aws_lc_rs::default_provider();
+    let algorithm = provider
+        .signature_verification_algorithms
+        .all
+        .iter()
+        .find(|a| a.public_key_alg_id() == public_key_alg && a.signature_alg_id() == signature_alg)
+        .ok_or(())?;
+    algorithm
+        .verify_signature(public_key, message, signature)
+        .map_err(|_| ())
+}
+
+/// Returns the algorithm and SubjectPublicKeyInfo bit string content of a JWK:
+/// an uncompressed point for EC keys, an `RSAPublicKey` for RSA and the raw key for Ed25519.
+pub(crate) fn jwk_public_key(jwk: &Jwk) -> Result<(AlgorithmIdentifier, Vec<u8>), Problem> {
+    let param = |name: &str| -> Result<Vec<u8>, Problem> {
+        let value = jwk
+            .params
+            .get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| Problem::malformed(format!("JWK is missing '{}'", name)))?;
+        URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|_| Problem::malformed(format!("Invalid base64url in JWK '{}'", name)))
+    };
+    let curve = jwk.params.get("crv").and_then(|v| v.as_str());
+
+    match (jwk.kty.as_str(), curve) {
+        ("EC", Some(crv)) => {
+            let (alg, coord_len) = match crv {
+                "P-256" => (alg_id::ECDSA_P256, 32),
+                "P-384" => (alg_id::ECDSA_P384, 48),
+                "P-521" => (alg_id::ECDSA_P521, 66),
+                other => {
+                    return Err(Problem::bad_signature_algorithm(format!(
+                        "Unsupported EC curve {}",
+                        other
+                    )));
+                }
+            };
+            let (x, y) = (param("x")?, param("y")?);
+            if x.len() != coord_len || y.len() != coord_len {
+                return Err(Problem::malformed(
+                    "EC JWK coordinates have the wrong length",
+                ));
+            }
+            let mut point = Vec::with_capacity(1 + 2 * coord_len);
+            point.push(0x04);
+            point.extend_from_slice(&x);
+            point.extend_from_slice(&y);
+            Ok((alg, point))
+        }
+        ("OKP", Some("Ed25519")) => Ok((alg_id::ED25519, param("x")?)),
+        ("RSA", _) => {
+            let mut key = der_integer(&param("n")?);
+            key.extend(der_integer(&param("e")?));
+            Ok((alg_id::RSA_ENCRYPTION, der_sequence(&key)))
+        }
+        (kty, _) => Err(Problem::bad_signature_algorithm(format!(
+            "Unsupported JWK key type {}",
+            kty
+        ))),
+    }
+}
+
+/// Converts a fixed-width JWS ECDSA signature (R || S) into an ASN.1 DER `ECDSA-Sig-Value`.
+fn ecdsa_raw_to_der(raw: &[u8], coord_len: usize) -> Result<Vec<u8>, Problem> {
+    if raw.len() != coord_len * 2 {
+        return Err(Problem::malformed("ECDSA signature has the wrong length"));
+    }
+    let (r, s) = raw.split_at(coord_len);
+    let mut content = der_integer(r);
+    content.extend(der_integer(s));
+    Ok(der_sequence(&content))
+}
+
+/// Encodes an unsigned big-endian integer as a DER INTEGER.
+fn der_integer(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    let mut value = bytes[start..].to_vec();
+    if value.first().is_none_or(|b| b & 0x80 != 0) {
+        value.insert(0, 0);
+    }
+    der_tlv(0x02, &value)
+}
+
+fn der_sequence(content: &[u8]) -> Vec<u8> {
+    der_tlv(0x30, content)
+}
+
+fn der_tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = value.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let len_bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect();
+        out.push(0x80 | len_bytes.len() as u8);
+        out.extend(len_bytes);
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPairGenerator;
+    use crate::protocol::JwsSigner;
+    use serde_json::json;
+
+    #[test]
+    fn test_verify_client_signatures() {
+        for generator in [
+            KeyPairGenerator::ecdsa_p256(),
+            KeyPairGenerator::ecdsa_p384(),
+            KeyPairGenerator::ed25519(),
+            KeyPairGenerator::new(crate::crypto::KeyType::Rsa2048),
+        ] {
+            let key_pair = generator.generate().expect("key pair");
+            let signer = JwsSigner::new(&key_pair);
+            let jwk = Jwk::from_key_pair(&key_pair).expect("jwk");
+            let header = json!({
+                "alg": signer.algorithm().unwrap(),
+                "nonce": "nonce",
+                "url": "https://ca.example/new-account",
+                "jwk": jwk.to_value(),
+            });
+            let signed = signer
+                .sign_flattened(&header, &json!({"termsOfServiceAgreed": true}))
+                .expect("signed JWS");
+
+            let jws = FlattenedJws::parse(signed.to_string().as_bytes()).expect("JWS");
+            let parsed = jws.header().expect("header");
+            assert_eq!(parsed.jwk.as_ref(), Some(&jwk));
+            jws.verify(&parsed.alg, &jwk).expect("valid signature");
+
+            // A signature over a different payload must not verify
+            let tampered = FlattenedJws {
+                payload: URL_SAFE_NO_PAD.encode(b"{}"),
+                ..jws
+            };
+            assert!(tampered.verify(&parsed.alg, &jwk).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reject_mismatched_algorithm() {
+        let key_pair = KeyPairGenerator::ecdsa_p256().generate().unwrap();
+        let jwk = Jwk::from_key_pair(&key_pair).unwrap();
+        let err = verify_signature(&jwk, "ES384", b"message", &[0u8; 96]).unwrap_err();
+        assert_eq!(err.kind, "badSignatureAlgorithm");
+        let err = verify_signature(&jwk, "HS256", b"message", &[0u8; 32]).unwrap_err();
+        assert_eq!(err.kind, "badSignatureAlgorithm");
+    }
+}
@@ -0,0 +1,271 @@
+/// Built-in ACME server (RFC 8555) backed by a local CA.
+/// This module lets acmex act as the certificate authority for internal networks:
+/// any ACME client can register an account, order certificates, prove control of
+/// its identifiers with http-01, dns-01 or tls-alpn-01, finalize, download and
+/// revoke certificates signed by the local intermediate. In relay mode the server
+/// instead fulfils authenticated orders at an upstream CA (see [`relay`]).
+pub mod ca;
+mod handlers;
+mod jws;
+pub mod objects;
+pub mod problem;
+pub mod relay;
+pub mod validation;
+
+pub use ca::{CertificateRequest, IssuedCertificate, LocalCa};
+pub use problem::Problem;
+pub use relay::{Relay, RelayPolicy};
+pub use validation::{ChallengeVerifier, NetworkVerifier};
+
+use crate::error::{AcmeError, Result};
+use crate::storage::StorageBackend;
+use axum::Router;
+use axum::routing::{get, post};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use objects::ServerStore;
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Name of the local CA generated when none is configured.
+pub const DEFAULT_CA_NAME: &str = "AcmeX Local CA";
+
+/// Default lifetime of issued certificates.
+pub const DEFAULT_CERTIFICATE_VALIDITY: Duration = Duration::from_secs(90 * 24 * 3600);
+
+/// Maximum number of outstanding nonces; the oldest are forgotten first.
+const MAX_NONCES: usize = 10_000;
+
+/// Configuration of the built-in ACME server.
+#[derive(Debug, Clone)]
+pub struct AcmeServerConfig {
+    /// The externally reachable URL the server's resources live under,
+    /// e.g. `https://acme.internal:14000/acme`. The directory is at `{base_url}/directory`.
+    pub base_url: String,
+    /// Name of the local CA, used in the subject of the root and intermediate.
+    pub ca_name: String,
+    /// Lifetime of issued certificates.
+    pub certificate_validity: Duration,
+    /// How long pending orders remain usable.
+    pub order_validity: Duration,
+    /// How long authorizations remain valid.
+    pub authorization_validity: Duration,
+}
+
+impl AcmeServerConfig {
+    /// Creates a configuration for a server reachable at `base_url`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            ca_name: DEFAULT_CA_NAME.to_string(),
+            certificate_validity: DEFAULT_CERTIFICATE_VALIDITY,
+            order_validity: Duration::from_secs(7 * 24 * 3600),
+            authorization_validity: Duration::from_secs(30 * 24 * 3600),
+        }
+    }
+
+    /// Sets the name of the local CA.
+    pub fn with_ca_name(mut self, name: impl Into<String>) -> Self {
+        self.ca_name = name.into();
+        self
+    }
+
+    /// Sets the lifetime of issued certificates.
+    pub fn with_certificate_validity(mut self, validity: Duration) -> Self {
+        self.certificate_validity = validity;
+        self
+    }
+
+    /// Returns the path component of the base URL, without a trailing slash.
+    fn base_path(&self) -> Result<String> {
+        let url = reqwest::Url::parse(&self.base_url).map_err(|e| {
+            AcmeError::configuration(format!("Invalid ACME server base URL: {}", e))
+        })?;
+        Ok(url.path().trim_end_matches('/').to_string())
+    }
+}
+
+/// Anti-replay nonces issued by the server (RFC 8555 Section 6.5).
+#[derive(Default)]
+pub(crate) struct NonceStore {
+    issued: Mutex<(HashSet<String>, VecDeque<String>)>,
+}
+
+impl NonceStore {
+    /// Issues a fresh nonce.
+    pub(crate) fn issue(&self) -> String {
+        let nonce = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 16]>());
+        let mut guard = self.issued.lock().unwrap_or_else(|e| e.into_inner());
+        let (set, order) = &mut *guard;
+        if order.len() >= MAX_NONCES
+            && let Some(oldest) = order.pop_front()
+        {
+            set.remove(&oldest);
+        }
+        set.insert(nonce.clone());
+        order.push_back(nonce.clone());
+        nonce
+    }
+
+    /// Consumes a nonce, returning false if it was never issued or already used.
+    fn consume(&self, nonce: &str) -> bool {
+        let mut guard = self.issued.lock().unwrap_or_else(|e| e.into_inner());
+        let (set, order) = &mut *guard;
+        if !set.remove(nonce) {
+            return false;
+        }
+        order.retain(|n| n != nonce);
+        true
+    }
+}
+
+/// An RFC 8555 ACME server issuing certificates from a [`LocalCa`].
+pub struct AcmeServer {
+    config: AcmeServerConfig,
+    store: ServerStore,
+    ca: LocalCa,
+    verifier: Arc<dyn ChallengeVerifier>,
+    /// Upstream fulfilment of orders, replacing challenge validation and the local CA.
+    relay: Option<Arc<Relay>>,
+    pub(crate) nonces: NonceStore,
+    /// Serializes read-modify-write updates of stored resources.
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl AcmeServer {
+    /// Creates a server persisting its CA and resources in `storage`.
+    /// The local CA is loaded from storage, or generated on first start.
+    pub async fn new(config: AcmeServerConfig, storage: Arc<dyn StorageBackend>) -> Result<Self> {
+        config.base_path()?;
+        let ca = LocalCa::load_or_create(storage.as_ref(), &config.ca_name).await?;
+        Ok(Self {
+            config,
+            store: ServerStore::new(storage),
+            ca,
+            verifier: Arc::new(NetworkVerifier::new()),
+            relay: None,
+            nonces: NonceStore::default(),
+            lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Replaces the challenge verifier, e.g. to validate on non-standard ports.
+    pub fn with_verifier(mut self, verifier: Arc<dyn ChallengeVerifier>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Runs the server in relay mode: clients are authenticated by the relay policy
+    /// instead of challenges, and certificates are obtained from the upstream CA.
+    pub fn with_relay(mut self, relay: Relay) -> Self {
+        self.relay = Some(Arc::new(relay));
+        self
+    }
+
+    /// Returns the relay, if the server runs in relay mode.
+    pub fn relay(&self) -> Option<&Relay> {
+        self.relay.as_deref()
+    }
+
+    /// Returns the server configuration.
+    pub fn config(&self) -> &AcmeServerConfig {
+        &self.config
+    }
+
+    /// Returns the local CA.
+    pub fn ca(&self) -> &LocalCa {
+        &self.ca
+    }
+
+    /// Returns the URL of the ACME directory.
+    pub fn directory_url(&self) -> String {
+        self.url("/directory")
+    }
+
+    /// Returns the absolute URL of a server resource path.
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.base_url, path)
+    }
+
+    /// Builds the router serving the ACME resources under the base URL's path.
+    pub fn router(self: Arc<Self>) -> Router {
+        let routes = Router::new()
+            .route("/directory", get(handlers::directory))
+            .route("/root", get(handlers::root_certificate))
+            .route("/new-nonce", get(handlers::new_nonce))
+            .route("/new-account", post(handlers::new_account))
+            .route("/account/{id}", post(handlers::update_account))
+            .route("/account/{id}/orders", post(handlers::account_orders))
+            .route("/key-change", post(handlers::key_change))
+            .route("/new-order", post(handlers::new_order))
+            .route("/order/{id}", post(handlers::get_order))
+            .route("/order/{id}/finalize", post(handlers::finalize_order))
+            .route("/authz/{id}", post(handlers::authorization))
+            .route("/chall/{authz}/{id}", post(handlers::challenge))
+            .route("/cert/{id}", post(handlers::certificate))
+            .route("/revoke-cert", post(handlers::revoke_certificate))
+            .layer(axum::middleware::from_fn_with_state(
+                self.clone(),
+                handlers::replay_nonce,
+            ))
+            .with_state(self.clone());
+
+        match self.config.base_path() {
+            Ok(path) if !path.is_empty() => Router::new().nest(&path, routes),
+            _ => routes,
+        }
+    }
+}
+
+/// Starts the ACME server on the specified address.
+pub async fn start_acme_server(addr: SocketAddr, server: AcmeServer) -> Result<()> {
+    let server = Arc::new(server);
+    let listener = TcpListener::bind(addr).await.map_err(|e| {
+        tracing::error!("Failed to bind to address {}: {}", addr, e);
+        AcmeError::transport(format!("Failed to bind ACME server: {}", e))
+    })?;
+
+    tracing::info!(
+        "AcmeX ACME server is now listening on {}, directory at {}",
+        addr,
+        server.directory_url()
+    );
+
+    axum::serve(listener, server.router()).await.map_err(|e| {
+        tracing::error!("ACME server error: {}", e);
+        AcmeError::transport(format!("Server error: {}", e))
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonces_are_single_use() {
+        let nonces = NonceStore::default();
+        let nonce = nonces.issue();
+        assert!(nonces.consume(&nonce));
+        assert!(!nonces.consume(&nonce));
+        assert!(!nonces.consume("never-issued"));
+    }
+
+    #[test]
+    fn test_base_path() {
+        let config = AcmeServerConfig::new("https://acme.internal:14000/acme/");
+        assert_eq!(config.base_url, "https://acme.internal:14000/acme");
+        assert_eq!(config.base_path().unwrap(), "/acme");
+        assert_eq!(
+            AcmeServerConfig::new("http://127.0.0.1:14000")
+                .base_path()
+                .unwrap(),
+            ""
+        );
+        assert!(AcmeServerConfig::new("not a url").base_path().is_err());
+    }
+}
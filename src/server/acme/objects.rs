@@ -0,0 +1,182 @@
+/// Resources kept by the built-in ACME server.
+/// Accounts, orders, authorizations and certificates are persisted through a
+/// `StorageBackend` under the `acme-server:` key prefix.
+use crate::error::{AcmeError, Result};
+use crate::protocol::Jwk;
+use crate::storage::StorageBackend;
+use crate::types::{AcmeErrorDetail, Identifier};
+use jiff::Timestamp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Prefix of every storage key written by the ACME server.
+pub(crate) const KEY_PREFIX: &str = "acme-server:";
+
+/// A registered ACME account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRecord {
+    /// The account ID, the last segment of the account URL.
+    pub id: String,
+    /// The account status ("valid" or "deactivated").
+    pub status: String,
+    /// The contact URLs of the account.
+    #[serde(default)]
+    pub contact: Vec<String>,
+    /// Whether the client agreed to the terms of service.
+    #[serde(default)]
+    pub terms_of_service_agreed: bool,
+    /// The account's public key.
+    pub jwk: Jwk,
+    /// The RFC 7638 thumbprint of the account key.
+    pub thumbprint: String,
+    /// When the account was created.
+    pub created_at: Timestamp,
+    /// The key ID of the External Account Binding the account was registered with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_account_id: Option<String>,
+}
+
+/// A certificate order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRecord {
+    /// The order ID.
+    pub id: String,
+    /// The ID of the account that created the order.
+    pub account_id: String,
+    /// The order status.
+    pub status: String,
+    /// When the order expires if it has not become valid.
+    pub expires: Timestamp,
+    /// The identifiers requested in the order.
+    pub identifiers: Vec<Identifier>,
+    /// The IDs of the order's authorizations, one per identifier.
+    pub authorizations: Vec<String>,
+    /// The ID of the issued certificate, once the order is valid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_id: Option<String>,
+    /// The error that made the order invalid, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<AcmeErrorDetail>,
+}
+
+/// An authorization of one identifier for one account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationRecord {
+    /// The authorization ID.
+    pub id: String,
+    /// The ID of the account the authorization belongs to.
+    pub account_id: String,
+    /// The identifier being authorized, without any wildcard prefix.
+    pub identifier: Identifier,
+    /// Whether the order asked for a wildcard of the identifier.
+    #[serde(default)]
+    pub wildcard: bool,
+    /// The authorization status.
+    pub status: String,
+    /// When the authorization expires.
+    pub expires: Timestamp,
+    /// The challenges offered to prove control of the identifier.
+    pub challenges: Vec<ChallengeRecord>,
+}
+
+/// A challenge offered within an authorization.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeRecord {
+    /// The challenge ID, unique within its authorization.
+    pub id: String,
+    /// The challenge type ("http-01", "dns-01" or "tls-alpn-01").
+    pub challenge_type: String,
+    /// The challenge token.
+    pub token: String,
+    /// The challenge status.
+    pub status: String,
+    /// When the challenge was validated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validated: Option<Timestamp>,
+    /// Why validation failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<AcmeErrorDetail>,
+}
+
+/// A certificate issued by the local CA, or by the upstream CA in relay mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateRecord {
+    /// The certificate ID, the lower-case hex serial number.
+    pub id: String,
+    /// The ID of the account the certificate was issued to.
+    pub account_id: String,
+    /// The PEM chain served to the client: the leaf followed by its issuers.
+    pub chain_pem: String,
+    /// The leaf certificate's expiry.
+    pub not_after: Timestamp,
+    /// The RFC 5280 reason code, if the certificate was revoked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revocation_reason: Option<u8>,
+    /// When the certificate was revoked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<Timestamp>,
+}
+
+impl CertificateRecord {
+    /// Whether the certificate has been revoked.
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// Typed access to the server's records in a storage backend.
+#[derive(Clone)]
+pub(crate) struct ServerStore {
+    backend: Arc<dyn StorageBackend>,
+}
+
+impl ServerStore {
+    pub(crate) fn new(backend: Arc<dyn StorageBackend>) -> Self {
+        Self { backend }
+    }
+
+    fn key(kind: &str, id: &str) -> String {
+        format!("{}{}:{}", KEY_PREFIX, kind, id)
+    }
+
+    /// Loads the record of the given kind and ID.
+    pub(crate) async fn load<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+        id: &str,
+    ) -> Result<Option<T>> {
+        match self.backend.load(&Self::key(kind, id)).await? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+                AcmeError::storage(format!("Deserialize {} {} failed: {}", kind, id, e))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Saves a record of the given kind under its ID.
+    pub(crate) async fn save<T: Serialize>(&self, kind: &str, id: &str, record: &T) -> Result<()> {
+        let data = serde_json::to_vec(record)
+            .map_err(|e| AcmeError::storage(format!("Serialize {} {} failed: {}", kind, id, e)))?;
+        self.backend.store(&Self::key(kind, id), &data).await
+    }
+
+    /// Deletes the record of the given kind and ID.
+    pub(crate) async fn delete(&self, kind: &str, id: &str) -> Result<()> {
+        self.backend.delete(&Self::key(kind, id)).await
+    }
+
+    /// Loads every record of the given kind.
+    pub(crate) async fn list<T: DeserializeOwned>(&self, kind: &str) -> Result<Vec<T>> {
+        let prefix = Self::key(kind, "");
+        let mut records = Vec::new();
+        for key in self.backend.list(&prefix).await? {
+            if let Some(bytes) = self.backend.load(&key).await?
+                && let Ok(record) = serde_json::from_slice(&bytes)
+            {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
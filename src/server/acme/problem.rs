@@ -0,0 +1,135 @@
+/// ACME problem documents returned by the built-in server (RFC 8555 Section 6.7).
+use crate::error::AcmeError;
+use crate::types::{ACME_ERROR_NAMESPACE, AcmeErrorDetail};
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+/// An error answered to an ACME client as an `application/problem+json` document.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// The HTTP status of the response.
+    pub status: StatusCode,
+    /// The ACME error type without the URN namespace (e.g. "badNonce").
+    pub kind: &'static str,
+    /// A human-readable explanation of the problem.
+    pub detail: String,
+}
+
+impl Problem {
+    /// Creates a problem with the given status, ACME error type and detail.
+    pub fn new(status: StatusCode, kind: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            kind,
+            detail: detail.into(),
+        }
+    }
+
+    /// The request message was malformed.
+    pub fn malformed(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "malformed", detail)
+    }
+
+    /// The requested resource does not exist.
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "malformed", detail)
+    }
+
+    /// The client lacks sufficient authorization.
+    pub fn unauthorized(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "unauthorized", detail)
+    }
+
+    /// The client sent an unacceptable anti-replay nonce.
+    pub fn bad_nonce(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "badNonce", detail)
+    }
+
+    /// The JWS was signed with an unsupported algorithm.
+    pub fn bad_signature_algorithm(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "badSignatureAlgorithm", detail)
+    }
+
+    /// The request specified an account that does not exist.
+    pub fn account_does_not_exist(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "accountDoesNotExist", detail)
+    }
+
+    /// The CSR is unacceptable.
+    pub fn bad_csr(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "badCSR", detail)
+    }
+
+    /// The order is not ready to be finalized.
+    pub fn order_not_ready(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "orderNotReady", detail)
+    }
+
+    /// The server will not issue certificates for the identifier.
+    pub fn rejected_identifier(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "rejectedIdentifier", detail)
+    }
+
+    /// The identifier is of an unsupported type.
+    pub fn unsupported_identifier(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "unsupportedIdentifier", detail)
+    }
+
+    /// The server does not support the given contact URL.
+    pub fn unsupported_contact(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "unsupportedContact", detail)
+    }
+
+    /// The revocation reason is not allowed.
+    pub fn bad_revocation_reason(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "badRevocationReason", detail)
+    }
+
+    /// The certificate has already been revoked.
+    pub fn already_revoked(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "alreadyRevoked", detail)
+    }
+
+    /// The server requires an external account binding for new accounts.
+    pub fn external_account_required(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "externalAccountRequired", detail)
+    }
+
+    /// The server experienced an internal error.
+    pub fn server_internal(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "serverInternal", detail)
+    }
+
+    /// Returns the problem as an ACME error document.
+    pub fn to_detail(&self) -> AcmeErrorDetail {
+        AcmeErrorDetail {
+            error_type: format!("{}{}", ACME_ERROR_NAMESPACE, self.kind),
+            detail: Some(self.detail.clone()),
+            status: Some(self.status.as_u16()),
+            title: None,
+            instance: None,
+            subproblems: None,
+        }
+    }
+}
+
+impl From<AcmeError> for Problem {
+    fn from(err: AcmeError) -> Self {
+        tracing::error!("ACME server internal error: {}", err);
+        Self::server_internal(err.to_string())
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(self.to_detail()),
+        )
+            .into_response()
+    }
+}
@@ -0,0 +1,243 @@
+/// Relay mode of the built-in ACME server.
+/// In relay mode the server fronts an upstream CA for hosts that can neither reach it
+/// nor answer its challenges: internal clients authenticate with External Account
+/// Binding and/or an allowed-domain policy instead of proving control of their
+/// identifiers, and each finalized order is fulfilled upstream through the relay's own
+/// account and DNS-01 solvers. The upstream certificate is handed back unchanged.
+use super::jws::FlattenedJws;
+use super::problem::Problem;
+use crate::account::ExternalAccountKey;
+use crate::challenge::ChallengeSolverRegistry;
+use crate::client::AcmeClient;
+use crate::error::{AcmeError, Result};
+use crate::protocol::Jwk;
+use crate::types::{Identifier, RevocationReason};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Who may use the relay and for which identifiers.
+#[derive(Debug, Clone, Default)]
+pub struct RelayPolicy {
+    /// EAB credentials handed out to internal clients, by key ID.
+    eab_keys: HashMap<String, ExternalAccountKey>,
+    /// Domains that may be ordered, each including its subdomains, and literal IP addresses.
+    allowed_domains: Vec<String>,
+}
+
+impl RelayPolicy {
+    /// Creates an empty policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts account registrations bound to `key`; once any key is added, newAccount
+    /// requests without a valid binding are rejected.
+    pub fn with_eab_key(mut self, key: ExternalAccountKey) -> Self {
+        self.eab_keys.insert(key.key_id().to_string(), key);
+        self
+    }
+
+    /// Allows orders for `domain` and all of its subdomains, or for an IP address.
+    pub fn with_allowed_domain(mut self, domain: impl Into<String>) -> Self {
+        let domain = domain.into();
+        self.allowed_domains.push(
+            domain
+                .trim_start_matches("*.")
+                .trim_end_matches('.')
+                .to_lowercase(),
+        );
+        self
+    }
+
+    /// Whether new accounts must carry an External Account Binding.
+    pub fn requires_eab(&self) -> bool {
+        !self.eab_keys.is_empty()
+    }
+
+    /// Whether the policy lets an order contain `identifier`.
+    /// Without allowed domains, every identifier is accepted.
+    pub fn allows(&self, identifier: &Identifier) -> bool {
+        if self.allowed_domains.is_empty() {
+            return true;
+        }
+        if identifier.is_ip() {
+            return self.allowed_domains.contains(&identifier.value);
+        }
+        let name = identifier.value.trim_start_matches("*.");
+        self.allowed_domains.iter().any(|allowed| {
+            name == allowed
+                || name
+                    .strip_suffix(allowed.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+
+    /// Verifies the `externalAccountBinding` of a newAccount request (RFC 8555 Section 7.3.4)
+    /// and returns the key ID the account is bound to.
+    pub(crate) fn verify_binding(
+        &self,
+        binding: Option<&Value>,
+        account_jwk: &Jwk,
+        new_account_url: &str,
+    ) -> std::result::Result<Option<String>, Problem> {
+        let Some(binding) = binding else {
+            if self.requires_eab() {
+                return Err(Problem::external_account_required(
+                    "This relay requires an external account binding",
+                ));
+            }
+            return Ok(None);
+        };
+
+        let jws: FlattenedJws = serde_json::from_value(binding.clone())
+            .map_err(|e| Problem::malformed(format!("Invalid external account binding: {}", e)))?;
+        let header = jws.header()?;
+        if header.alg != "HS256" {
+            return Err(Problem::bad_signature_algorithm(
+                "External account bindings must use HS256",
+            ));
+        }
+        if header.nonce.is_some() || header.url != new_account_url {
+            return Err(Problem::malformed(
+                "External account binding must target the newAccount URL without a nonce",
+            ));
+        }
+        let key = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.eab_keys.get(kid))
+            .ok_or_else(|| Problem::unauthorized("Unknown external account key ID"))?;
+
+        let mac = URL_SAFE_NO_PAD
+            .decode(&jws.signature)
+            .map_err(|_| Problem::malformed("External account binding MAC is not base64url"))?;
+        let signing_input = format!("{}.{}", jws.protected, jws.payload);
+        if !key.verify(signing_input.as_bytes(), &mac)? {
+            return Err(Problem::unauthorized(
+                "External account binding MAC is invalid",
+            ));
+        }
+
+        let bound_jwk: Jwk = serde_json::from_slice(&jws.payload()?).map_err(|e| {
+            Problem::malformed(format!(
+                "External account binding payload is not a JWK: {}",
+                e
+            ))
+        })?;
+        if bound_jwk != *account_jwk {
+            return Err(Problem::unauthorized(
+                "External account binding is for a different account key",
+            ));
+        }
+        Ok(Some(key.key_id().to_string()))
+    }
+}
+
+/// Fulfils orders of the built-in ACME server at an upstream CA.
+pub struct Relay {
+    client: AcmeClient,
+    /// Solvers for the upstream challenges, shared by concurrent upstream orders.
+    solvers: Arc<ChallengeSolverRegistry>,
+    policy: RelayPolicy,
+}
+
+impl Relay {
+    /// Creates a relay ordering upstream with `client` and answering the upstream
+    /// challenges with `solvers`, normally a `Dns01Solver`.
+    /// Fails if the policy neither requires EAB nor restricts the allowed domains,
+    /// which would let anyone on the network obtain certificates for any name.
+    pub fn new(
+        client: AcmeClient,
+        solvers: ChallengeSolverRegistry,
+        policy: RelayPolicy,
+    ) -> Result<Self> {
+        if !policy.requires_eab() && policy.allowed_domains.is_empty() {
+            return Err(AcmeError::configuration(
+                "A relay needs EAB keys or allowed domains to authenticate clients",
+            ));
+        }
+        Ok(Self {
+            client,
+            solvers: Arc::new(solvers),
+            policy,
+        })
+    }
+
+    /// Returns the relay policy.
+    pub fn policy(&self) -> &RelayPolicy {
+        &self.policy
+    }
+
+    /// Orders a certificate for `identifiers` upstream, finalizing with the client's CSR.
+    pub(crate) async fn issue(&self, identifiers: &[Identifier], csr_der: &[u8]) -> Result<String> {
+        let domains = identifiers.iter().map(|i| i.value.clone()).collect();
+        let mut client = self.client.clone();
+        client.issue_for_csr(domains, csr_der, &self.solvers).await
+    }
+
+    /// Revokes an upstream certificate with the relay's account.
+    pub(crate) async fn revoke(&self, chain_pem: &str, reason: u8) -> Result<()> {
+        let reason = match reason {
+            1 => RevocationReason::KeyCompromise,
+            3 => RevocationReason::AffiliationChanged,
+            4 => RevocationReason::Superseded,
+            5 => RevocationReason::CessationOfOperation,
+            _ => RevocationReason::Unspecified,
+        };
+        let mut client = self.client.clone();
+        client.revoke_certificate(chain_pem, Some(reason)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::KeyPair;
+
+    #[test]
+    fn test_allowed_domains() {
+        let policy = RelayPolicy::new()
+            .with_allowed_domain("Internal.Example.com")
+            .with_allowed_domain("10.0.0.1");
+        assert!(policy.allows(&Identifier::dns("internal.example.com")));
+        assert!(policy.allows(&Identifier::dns("a.b.internal.example.com")));
+        assert!(policy.allows(&Identifier::dns("*.internal.example.com")));
+        assert!(!policy.allows(&Identifier::dns("evilinternal.example.com")));
+        assert!(!policy.allows(&Identifier::dns("example.com")));
+        assert!(policy.allows(&Identifier::ip("10.0.0.1")));
+        assert!(!policy.allows(&Identifier::ip("10.0.0.2")));
+        assert!(RelayPolicy::new().allows(&Identifier::dns("anything.test")));
+    }
+
+    #[test]
+    fn test_verify_binding() {
+        let key = ExternalAccountKey::new("kid-1", "c2VjcmV0LWhtYWMta2V5").unwrap();
+        let policy = RelayPolicy::new().with_eab_key(key.clone());
+        let url = "https://relay.internal/new-account";
+        let jwk = KeyPair::generate().unwrap().jwk().unwrap();
+        let binding = key.binding(&jwk, url).unwrap();
+
+        assert_eq!(
+            policy.verify_binding(Some(&binding), &jwk, url).unwrap(),
+            Some("kid-1".to_string())
+        );
+        let err = policy.verify_binding(None, &jwk, url).unwrap_err();
+        assert_eq!(err.kind, "externalAccountRequired");
+
+        // A binding for another key or signed with another secret is refused
+        let other_jwk = KeyPair::generate().unwrap().jwk().unwrap();
+        assert!(
+            policy
+                .verify_binding(Some(&binding), &other_jwk, url)
+                .is_err()
+        );
+        let forged = ExternalAccountKey::new("kid-1", "b3RoZXItc2VjcmV0")
+            .unwrap()
+            .binding(&jwk, url)
+            .unwrap();
+        assert!(policy.verify_binding(Some(&forged), &jwk, url).is_err());
+    }
+}
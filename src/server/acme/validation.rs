@@ -0,0 +1,407 @@
+/// Challenge validation performed by the built-in ACME server.
+/// `NetworkVerifier` checks http-01 (RFC 8555 Section 8.3), dns-01 (Section 8.4) and
+/// tls-alpn-01 (RFC 8737) responses against the client's key authorization.
+use crate::error::{AcmeError, Result};
+use crate::types::{ChallengeType, Identifier};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hickory_resolver::config::ResolverConfig;
+use hickory_resolver::net::runtime::TokioRuntimeProvider;
+use hickory_resolver::proto::rr::RData;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_rustls::TlsConnector;
+use x509_parser::extensions::{GeneralName, ParsedExtension};
+
+/// ALPN protocol negotiated for tls-alpn-01 validation.
+const ACME_TLS_ALPN: &[u8] = b"acme-tls/1";
+
+/// An incorrect challenge response.
+fn incorrect(challenge_type: &str, detail: impl Into<String>) -> AcmeError {
+    AcmeError::challenge(challenge_type.to_string(), detail.into())
+}
+
+/// Checks that a client has fulfilled a challenge for an identifier.
+#[async_trait]
+pub trait ChallengeVerifier: Send + Sync {
+    /// Returns `Ok` if the challenge response for `identifier` proves the `key_authorization`.
+    /// Transport errors are reported as connection problems, anything else as an
+    /// incorrect response.
+    async fn verify(
+        &self,
+        challenge_type: ChallengeType,
+        identifier: &Identifier,
+        token: &str,
+        key_authorization: &str,
+    ) -> Result<()>;
+}
+
+/// Validates challenges by querying the identifier over the network.
+pub struct NetworkVerifier {
+    http_client: reqwest::Client,
+    http01_port: u16,
+    tls_alpn01_port: u16,
+    timeout: Duration,
+}
+
+impl NetworkVerifier {
+    /// Creates a verifier connecting to the standard ports (80 for http-01, 443 for tls-alpn-01).
+    pub fn new() -> Self {
+        let timeout = Duration::from_secs(10);
+        Self {
+            http_client: reqwest::Client::builder()
+                .timeout(timeout)
+                .redirect(reqwest::redirect::Policy::limited(10))
+                .build()
+                .unwrap_or_default(),
+            http01_port: 80,
+            tls_alpn01_port: 443,
+            timeout,
+        }
+    }
+
+    /// Sets the port queried for http-01 challenges.
+    pub fn with_http01_port(mut self, port: u16) -> Self {
+        self.http01_port = port;
+        self
+    }
+
+    /// Sets the port connected to for tls-alpn-01 challenges.
+    pub fn with_tls_alpn01_port(mut self, port: u16) -> Self {
+        self.tls_alpn01_port = port;
+        self
+    }
+
+    /// Host part of a URL or socket address for the identifier.
+    fn host(identifier: &Identifier) -> String {
+        match identifier.value.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+            _ => identifier.value.clone(),
+        }
+    }
+
+    async fn verify_http01(
+        &self,
+        identifier: &Identifier,
+        token: &str,
+        key_authorization: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "http://{}:{}/.well-known/acme-challenge/{}",
+            Self::host(identifier),
+            self.http01_port,
+            token
+        );
+        tracing::debug!("Validating http-01 challenge at {}", url);
+        let response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| AcmeError::transport(format!("Failed to fetch {}: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(incorrect(
+                "http-01",
+                format!("{} returned HTTP {}", url, response.status()),
+            ));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AcmeError::transport(format!("Failed to read {}: {}", url, e)))?;
+        if body.trim_end() != key_authorization {
+            return Err(incorrect(
+                "http-01",
+                format!("{} returned an unexpected key authorization", url),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn verify_dns01(&self, identifier: &Identifier, key_authorization: &str) -> Result<()> {
+        if identifier.is_ip() {
+            return Err(incorrect("dns-01", "dns-01 cannot validate IP identifiers"));
+        }
+        let name = format!(
+            "_acme-challenge.{}.",
+            identifier.value.trim_end_matches('.')
+        );
+        let expected = URL_SAFE_NO_PAD.encode(Sha256::digest(key_authorization.as_bytes()));
+        tracing::debug!("Validating dns-01 challenge at {}", name);
+
+        // A fresh resolver per validation, so cached answers never hide a new record
+        let resolver = hickory_resolver::TokioResolver::builder_with_config(
+            ResolverConfig::default(),
+            TokioRuntimeProvider::default(),
+        )
+        .build()
+        .map_err(|e| AcmeError::transport(format!("DNS resolver setup failed: {}", e)))?;
+        let response = resolver
+            .txt_lookup(name.as_str())
+            .await
+            .map_err(|e| AcmeError::transport(format!("TXT lookup for {} failed: {}", name, e)))?;
+
+        let found = response
+            .answers()
+            .iter()
+            .filter_map(|record| match &record.data {
+                RData::TXT(txt) => Some(txt.to_string()),
+                _ => None,
+            })
+            .any(|txt| txt == expected);
+        if !found {
+            return Err(incorrect(
+                "dns-01",
+                format!("No TXT record at {} matches the key authorization", name),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn verify_tls_alpn01(
+        &self,
+        identifier: &Identifier,
+        key_authorization: &str,
+    ) -> Result<()> {
+        let address = format!("{}:{}", Self::host(identifier), self.tls_alpn01_port);
+        tracing::debug!("Validating tls-alpn-01 challenge at {}", address);
+
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let mut config = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| AcmeError::crypto(format!("TLS configuration failed: {}", e)))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth();
+        config.alpn_protocols = vec![ACME_TLS_ALPN.to_vec()];
+
+        let server_name = ServerName::try_from(identifier.tls_server_name())
+            .map_err(|e| AcmeError::invalid_input(format!("Invalid server name: {}", e)))?;
+        let stream = tokio::net::TcpStream::connect(&address)
+            .await
+            .map_err(|e| {
+                AcmeError::transport(format!("Failed to connect to {}: {}", address, e))
+            })?;
+        let tls = TlsConnector::from(Arc::new(config))
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| {
+                AcmeError::transport(format!("TLS handshake with {} failed: {}", address, e))
+            })?;
+
+        let (_, connection) = tls.get_ref();
+        if connection.alpn_protocol() != Some(ACME_TLS_ALPN) {
+            return Err(incorrect(
+                "tls-alpn-01",
+                format!("{} did not negotiate the acme-tls/1 protocol", address),
+            ));
+        }
+        let certificate = connection
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .ok_or_else(|| incorrect("tls-alpn-01", "No certificate presented"))?;
+        check_validation_certificate(certificate, identifier, key_authorization)
+    }
+}
+
+impl Default for NetworkVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ChallengeVerifier for NetworkVerifier {
+    async fn verify(
+        &self,
+        challenge_type: ChallengeType,
+        identifier: &Identifier,
+        token: &str,
+        key_authorization: &str,
+    ) -> Result<()> {
+        let validation = async {
+            match challenge_type {
+                ChallengeType::Http01 => {
+                    self.verify_http01(identifier, token, key_authorization)
+                        .await
+                }
+                ChallengeType::Dns01 => self.verify_dns01(identifier, key_authorization).await,
+                ChallengeType::TlsAlpn01 => {
+                    self.verify_tls_alpn01(identifier, key_authorization).await
+                }
+            }
+        };
+        tokio::time::timeout(self.timeout * 3, validation)
+            .await
+            .map_err(|_| {
+                AcmeError::transport(format!(
+                    "{} validation of {} timed out",
+                    challenge_type, identifier.value
+                ))
+            })?
+    }
+}
+
+/// Checks the self-signed tls-alpn-01 certificate: it must name exactly the identifier and
+/// carry a critical acmeIdentifier extension with the key authorization digest (RFC 8737 Section 3).
+fn check_validation_certificate(
+    der: &CertificateDer<'_>,
+    identifier: &Identifier,
+    key_authorization: &str,
+) -> Result<()> {
+    let invalid = |msg: &str| AcmeError::challenge("tls-alpn-01", msg);
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|_| invalid("Validation certificate cannot be parsed"))?;
+
+    let names: Vec<String> = cert
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|san| {
+            san.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_lowercase()),
+                    GeneralName::IPAddress(bytes) => match bytes.len() {
+                        4 => <[u8; 4]>::try_from(*bytes)
+                            .ok()
+                            .map(|b| IpAddr::from(b).to_string()),
+                        16 => <[u8; 16]>::try_from(*bytes)
+                            .ok()
+                            .map(|b| IpAddr::from(b).to_string()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if names != [identifier.value.to_lowercase()] {
+        return Err(invalid(
+            "Validation certificate must name exactly the identifier",
+        ));
+    }
+
+    let digest = Sha256::digest(key_authorization.as_bytes());
+    let matches = cert.extensions().iter().any(|ext| {
+        ext.oid.to_id_string() == "1.3.6.1.5.5.7.1.31"
+            && ext.critical
+            && !matches!(ext.parsed_extension(), ParsedExtension::ParseError { .. })
+            && ext.value.len() == 34
+            && ext.value[..2] == [0x04, 0x20]
+            && ext.value[2..] == digest[..]
+    });
+    if !matches {
+        return Err(invalid(
+            "Validation certificate lacks a matching acmeIdentifier extension",
+        ));
+    }
+    Ok(())
+}
+
+/// Accepts any server certificate: tls-alpn-01 validation certificates are self-signed,
+/// and their content is checked separately after the handshake.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<rustls::crypto::CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::{ChallengeSolver, Http01Solver};
+    use crate::order::Challenge;
+
+    #[tokio::test]
+    async fn test_http01_validation() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let solver = Http01Solver::new(addr);
+        let challenge = Challenge {
+            challenge_type: "http-01".to_string(),
+            url: "https://ca.example/chall/1".to_string(),
+            status: "pending".to_string(),
+            token: "token-1".to_string(),
+            key_authorization: None,
+            validation: None,
+            updated: None,
+            error: None,
+            retry_after: None,
+        };
+        let identifier = Identifier::ip("127.0.0.1");
+        solver
+            .prepare(&challenge, &identifier, "token-1.thumbprint")
+            .await
+            .unwrap();
+
+        let verifier = NetworkVerifier::new().with_http01_port(addr.port());
+        verifier
+            .verify(
+                ChallengeType::Http01,
+                &identifier,
+                "token-1",
+                "token-1.thumbprint",
+            )
+            .await
+            .expect("http-01 validation");
+        assert!(
+            verifier
+                .verify(ChallengeType::Http01, &identifier, "token-1", "wrong")
+                .await
+                .is_err()
+        );
+        solver.cleanup().await.unwrap();
+    }
+}
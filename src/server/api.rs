@@ -14,13 +14,15 @@ use tokio::sync::RwLock;
 
 use super::account::{create_account, deactivate_account, get_account, update_account};
 use super::auth::api_key_auth;
+use super::authorization::{deactivate_authorization, pre_authorize};
 use super::certificate::{
     get_certificate, list_certificates, renew_certificate, revoke_certificate,
 };
 use super::health::{HealthCheck, health_handler};
-use super::order::{create_order, get_order, list_orders, trigger_full_renewal};
+use super::order::{cancel_order, create_order, get_order, list_orders, trigger_full_renewal};
 use super::webhook::{WebhookHandler, webhook_handler};
 use crate::AcmeClient;
+use crate::certificate::OcspVerifier;
 use crate::config::Config;
 use crate::error::Result;
 use crate::notifications::WebhookManager;
@@ -56,6 +58,8 @@ pub struct AppState {
     pub api_keys: Arc<Vec<String>>,
     /// The certificate renewal scheduler.
     pub scheduler: Option<Arc<dyn RenewalScheduler>>,
+    /// OCSP verifier caching responses for status checks and stapling.
+    pub ocsp: Arc<OcspVerifier>,
 }
 
 /// Starts the REST API server on the specified address.
@@ -96,6 +100,7 @@ pub async fn start_server(
         tasks,
         api_keys,
         scheduler,
+        ocsp: Arc::new(OcspVerifier::new()),
     };
 
     // Define API routes with authentication middleware
@@ -108,9 +113,13 @@ pub async fn start_server(
                 .patch(update_account)
                 .delete(deactivate_account),
         )
+        // Authorization endpoints
+        .route("/authorizations", post(pre_authorize))
+        .route("/authorizations/deactivate", post(deactivate_authorization))
         // Order and renewal endpoints
         .route("/orders", get(list_orders).post(create_order))
         .route("/orders/renew-all", post(trigger_full_renewal))
+        .route("/orders/cancel", post(cancel_order))
         .route("/orders/:id", get(get_order))
         // Certificate management endpoints
         .route("/certificates", get(list_certificates))
@@ -0,0 +1,152 @@
+use crate::error::ProblemDetails;
+use crate::orchestrator::OrchestrationStatus;
+use crate::orchestrator::provisioner::build_solver_registry;
+use crate::server::account::problem_response;
+use crate::server::api::{AppState, TaskInfo};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use rand::RngExt;
+use rand::distr::Alphanumeric;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+#[derive(Debug, Deserialize)]
+pub struct PreAuthorizeRequest {
+    pub domains: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeactivateAuthorizationRequest {
+    /// The URL of the authorization to deactivate.
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthorizationResponse {
+    pub url: String,
+    pub identifier: String,
+    pub status: String,
+}
+
+/// Pre-authorizes identifiers in the background (newAuthz); progress is tracked as a task.
+pub async fn pre_authorize(
+    State(state): State<AppState>,
+    Json(payload): Json<PreAuthorizeRequest>,
+) -> impl IntoResponse {
+    info!(
+        "Request to pre-authorize identifiers: {:?}",
+        payload.domains
+    );
+
+    let Some(client) = state.client.clone() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "ACME client not configured on server",
+        )
+            .into_response();
+    };
+
+    // Reject up front when the CA cannot pre-authorize, rather than failing the task later
+    match client.directory().await {
+        Ok(directory) if directory.new_authz.is_none() => {
+            return (
+                StatusCode::NOT_IMPLEMENTED,
+                Json(ProblemDetails {
+                    problem_type: "https://acmex.sh/errors/not-supported".into(),
+                    title: "Pre-authorization Not Supported".into(),
+                    status: 501,
+                    detail: "The ACME server does not offer a newAuthz endpoint".into(),
+                    instance: None,
+                }),
+            )
+                .into_response();
+        }
+        Ok(_) => {}
+        Err(e) => return problem_response(&e),
+    }
+
+    let solver_registry = match build_solver_registry(&state.config).await {
+        Ok(registry) => registry,
+        Err(e) => return problem_response(&e),
+    };
+
+    // Generate a task ID
+    let task_id: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+
+    {
+        let mut tasks = state.tasks.write().await;
+        tasks.insert(
+            task_id.clone(),
+            TaskInfo {
+                status: OrchestrationStatus::InProgress {
+                    progress: 0.0,
+                    message: "Starting pre-authorization".to_string(),
+                },
+                domains: payload.domains.clone(),
+            },
+        );
+    }
+
+    let tasks = state.tasks.clone();
+    let task_id_clone = task_id.clone();
+    let domains = payload.domains.clone();
+    tokio::spawn(async move {
+        let mut client = (*client).clone();
+        let status = match client.pre_authorize(domains, &solver_registry).await {
+            Ok(_) => {
+                info!("Pre-authorization task {} completed", task_id_clone);
+                OrchestrationStatus::Completed
+            }
+            Err(e) => {
+                error!("Pre-authorization task {} failed: {}", task_id_clone, e);
+                OrchestrationStatus::Failed(e.to_string())
+            }
+        };
+        if let Some(task) = tasks.write().await.get_mut(&task_id_clone) {
+            task.status = status;
+        }
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "id": task_id,
+            "status": "accepted",
+            "domains": payload.domains,
+        })),
+    )
+        .into_response()
+}
+
+/// Deactivates an authorization so the account can no longer issue for its identifier.
+pub async fn deactivate_authorization(
+    State(state): State<AppState>,
+    Json(payload): Json<DeactivateAuthorizationRequest>,
+) -> impl IntoResponse {
+    info!("Request to deactivate authorization: {}", payload.url);
+
+    let Some(client) = state.client else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "ACME client not configured on server",
+        )
+            .into_response();
+    };
+
+    let mut client = (*client).clone();
+    match client.deactivate_authorization(&payload.url).await {
+        Ok(auth) => Json(AuthorizationResponse {
+            url: payload.url,
+            identifier: auth.identifier.value,
+            status: auth.status,
+        })
+        .into_response(),
+        Err(e) => {
+            error!("Failed to deactivate authorization {}: {}", payload.url, e);
+            problem_response(&e)
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::certificate::OcspVerifier;
+use crate::certificate::CertificateChain;
+use crate::client::CertificateBundle;
 use crate::error::ProblemDetails;
 use crate::orchestrator::OrchestrationStatus;
 use crate::server::api::{AppState, TaskInfo};
@@ -41,10 +42,13 @@ pub async fn get_certificate(
 
     let mut ocsp_status = None;
     if let Some(storage) = &state.storage
-        && let Ok(Some(cert_data)) = storage.load(&format!("cert:{}", id)).await
-        && let Ok(status) = OcspVerifier::verify_status(&cert_data).await
+        && let Ok(Some(data)) = storage.load(&format!("cert:{}", id)).await
+        && let Ok(bundle) = serde_json::from_slice::<CertificateBundle>(&data)
+        && let Ok(chain) = CertificateChain::from_pem(bundle.certificate_pem.as_bytes())
+        && let Some(issuer) = chain.intermediates.first()
+        && let Ok(response) = state.ocsp.verify_status(&chain.leaf, issuer).await
     {
-        ocsp_status = Some(format!("{:?}", status));
+        ocsp_status = Some(format!("{:?}", response.status));
     }
 
     Json(CertificateResponse {
@@ -1,11 +1,14 @@
 pub mod account;
+pub mod acme;
 pub mod api;
 pub mod auth;
+pub mod authorization;
 pub mod certificate;
 pub mod health;
 pub mod order;
 pub mod webhook;
 
+pub use acme::{AcmeServer, AcmeServerConfig, start_acme_server};
 pub use api::start_server;
 pub use health::HealthCheck;
 pub use webhook::WebhookHandler;
@@ -1,8 +1,11 @@
-use crate::error::ProblemDetails;
+use crate::config::CertificateSettings;
+use crate::error::{AcmeError, ProblemDetails};
 use crate::metrics::AcmeEvent;
 use crate::metrics::events::EventAuditor;
 use crate::orchestrator::{CertificateProvisioner, OrchestrationStatus, Orchestrator};
+use crate::server::account::problem_response;
 use crate::server::api::{AppState, TaskInfo};
+use crate::storage::CertificateStore;
 use axum::{
     Json,
     extract::{Path, State},
@@ -17,6 +20,15 @@ use tracing::{error, info};
 #[derive(Debug, Deserialize)]
 pub struct CreateOrderRequest {
     pub domains: Vec<String>,
+    /// Certificate key and CSR settings for this order, replacing the configured ones.
+    #[serde(default)]
+    pub certificate: Option<CertificateSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelOrderRequest {
+    /// The URL of the STAR order to cancel.
+    pub order_url: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,6 +44,15 @@ pub async fn create_order(
 ) -> impl IntoResponse {
     info!("Request to create order for domains: {:?}", payload.domains);
 
+    let mut provisioner = CertificateProvisioner::new(payload.domains.clone());
+    if let Some(settings) = payload.certificate {
+        // Reject bad settings now rather than failing the background task
+        if let Err(e) = settings.options_for(&payload.domains) {
+            return problem_response(&AcmeError::invalid_input(e.to_string()));
+        }
+        provisioner = provisioner.with_certificate_settings(settings);
+    }
+
     // Track event
     EventAuditor::track_event(AcmeEvent::OrderCreated {
         domains: payload.domains.clone(),
@@ -44,7 +65,6 @@ pub async fn create_order(
         .map(char::from)
         .collect();
 
-    let provisioner = CertificateProvisioner::new(payload.domains.clone());
     let state_clone = state.clone();
     let task_id_clone = task_id.clone();
 
@@ -165,3 +185,59 @@ pub async fn get_order(State(state): State<AppState>, Path(id): Path<String>) ->
             .into_response()
     }
 }
+
+/// Cancels a STAR order so the CA stops renewing its certificate (RFC 8739 Section 2.3).
+/// Stored certificates of the order are kept but no longer fetched by the scheduler.
+pub async fn cancel_order(
+    State(state): State<AppState>,
+    Json(payload): Json<CancelOrderRequest>,
+) -> impl IntoResponse {
+    info!("Request to cancel STAR order: {}", payload.order_url);
+
+    let Some(client) = state.client else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "ACME client not configured on server",
+        )
+            .into_response();
+    };
+
+    let mut client = (*client).clone();
+    let order = match client.cancel_star_order(&payload.order_url).await {
+        Ok(order) => order,
+        Err(e) => {
+            error!("Failed to cancel order {}: {}", payload.order_url, e);
+            return problem_response(&e);
+        }
+    };
+
+    if let Some(storage) = state.storage {
+        let store = CertificateStore::new(storage);
+        match store.list_all().await {
+            Ok(bundles) => {
+                for mut bundle in bundles {
+                    if bundle
+                        .star
+                        .as_ref()
+                        .is_some_and(|star| star.order_url == payload.order_url)
+                    {
+                        bundle.star = None;
+                        if let Err(e) = store.save(&bundle).await {
+                            error!(
+                                "Failed to update stored certificate for {:?}: {}",
+                                bundle.domains, e
+                            );
+                        }
+                    }
+                }
+            }
+            Err(e) => error!("Failed to list stored certificates: {}", e),
+        }
+    }
+
+    Json(serde_json::json!({
+        "order_url": payload.order_url,
+        "status": order.status,
+    }))
+    .into_response()
+}
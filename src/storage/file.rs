@@ -2,6 +2,7 @@
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 
 use super::StorageBackend;
 use crate::error::{AcmeError, Result};
@@ -24,6 +25,25 @@ impl FileStorage {
     }
 }
 
+/// Writes `data` to `path`, restricting the file to its owner (mode 0600) on Unix since
+/// stored values include account and certificate private keys.
+async fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    // The mode only applies on creation, so files written by older versions are fixed too
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await?;
+    }
+    file.write_all(data).await?;
+    file.flush().await
+}
+
 #[async_trait]
 impl StorageBackend for FileStorage {
     async fn store(&self, key: &str, value: &[u8]) -> Result<()> {
@@ -32,7 +52,7 @@ impl StorageBackend for FileStorage {
             .map_err(|e| AcmeError::storage(format!("Failed to create storage dir: {}", e)))?;
 
         let path = self.key_path(key);
-        fs::write(path, value)
+        write_private(&path, value)
             .await
             .map_err(|e| AcmeError::storage(format!("Failed to write storage: {}", e)))?;
         Ok(())
@@ -84,3 +104,27 @@ impl StorageBackend for FileStorage {
         Ok(keys)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_store_restricts_permissions() {
+        let dir = std::env::temp_dir().join(format!("acmex-file-{}", rand::random::<u64>()));
+        let storage = FileStorage::new(&dir);
+        storage.store("account_key", b"secret").await.unwrap();
+        assert_eq!(
+            storage.load("account_key").await.unwrap().as_deref(),
+            Some(&b"secret"[..])
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = storage.key_path("account_key");
+            let mode = std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
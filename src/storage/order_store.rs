@@ -0,0 +1,177 @@
+/// In-flight order storage.
+/// This module persists the progress of an order so certificate issuance can resume
+/// after a restart instead of creating a new order against the CA's rate limits.
+use crate::error::{AcmeError, Result};
+use crate::storage::StorageBackend;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// Persisted state of an authorization within a pending order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationState {
+    /// The authorization URL.
+    pub url: String,
+    /// The identifier value being authorized.
+    pub identifier: String,
+    /// The last known authorization status.
+    pub status: String,
+    /// The challenge type chosen to validate the identifier, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge_type: Option<String>,
+    /// The URL of the chosen challenge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge_url: Option<String>,
+}
+
+/// Persisted state of an order that has not produced a certificate yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingOrder {
+    /// The names requested in the order.
+    pub domains: Vec<String>,
+    /// The directory URL of the CA that holds the order.
+    pub directory_url: String,
+    /// The URL of the account that owns the order.
+    pub account_url: String,
+    /// The order URL.
+    pub order_url: String,
+    /// The last known order status.
+    pub status: String,
+    /// The finalize URL of the order.
+    pub finalize_url: String,
+    /// The authorizations of the order.
+    #[serde(default)]
+    pub authorizations: Vec<AuthorizationState>,
+    /// The certificate private key (PEM) whose CSR finalized the order, if finalized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_key_pem: Option<String>,
+    /// When this state was last saved.
+    pub updated_at: Timestamp,
+}
+
+impl PendingOrder {
+    /// Whether this order was created by the given account at the given CA.
+    pub fn belongs_to(&self, directory_url: &str, account_url: &str) -> bool {
+        self.directory_url == directory_url && self.account_url == account_url
+    }
+}
+
+/// Pending order store using a storage backend
+#[derive(Clone)]
+pub struct OrderStore<B: StorageBackend> {
+    backend: B,
+}
+
+impl<B: StorageBackend> OrderStore<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// Get the storage backend
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    fn key_for_domains(domains: &[String]) -> String {
+        let mut domains = domains.to_vec();
+        domains.sort();
+        format!("order:{}", domains.join(","))
+    }
+
+    /// Save the state of a pending order
+    pub async fn save(&self, order: &PendingOrder) -> Result<()> {
+        let key = Self::key_for_domains(&order.domains);
+        let mut order = order.clone();
+        order.updated_at = Timestamp::now();
+        let data = serde_json::to_vec(&order)
+            .map_err(|e| AcmeError::storage(format!("Serialize pending order failed: {}", e)))?;
+        self.backend.store(&key, &data).await
+    }
+
+    /// Load the pending order for a set of domains
+    pub async fn load(&self, domains: &[String]) -> Result<Option<PendingOrder>> {
+        let key = Self::key_for_domains(domains);
+        let data = self.backend.load(&key).await?;
+        match data {
+            Some(bytes) => {
+                let order: PendingOrder = serde_json::from_slice(&bytes).map_err(|e| {
+                    AcmeError::storage(format!("Deserialize pending order failed: {}", e))
+                })?;
+                Ok(Some(order))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Delete the pending order for a set of domains
+    pub async fn delete(&self, domains: &[String]) -> Result<()> {
+        let key = Self::key_for_domains(domains);
+        self.backend.delete(&key).await
+    }
+
+    /// List all pending orders
+    pub async fn list_all(&self) -> Result<Vec<PendingOrder>> {
+        let keys = self.backend.list("order:").await?;
+        let mut orders = Vec::new();
+        for key in keys {
+            if let Some(bytes) = self.backend.load(&key).await?
+                && let Ok(order) = serde_json::from_slice(&bytes)
+            {
+                orders.push(order);
+            }
+        }
+        Ok(orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn pending_order(domains: &[&str]) -> PendingOrder {
+        PendingOrder {
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            directory_url: "https://ca.example/directory".to_string(),
+            account_url: "https://ca.example/acct/1".to_string(),
+            order_url: "https://ca.example/order/1".to_string(),
+            status: "pending".to_string(),
+            finalize_url: "https://ca.example/order/1/finalize".to_string(),
+            authorizations: vec![AuthorizationState {
+                url: "https://ca.example/authz/1".to_string(),
+                identifier: "example.com".to_string(),
+                status: "pending".to_string(),
+                challenge_type: Some("http-01".to_string()),
+                challenge_url: Some("https://ca.example/chall/1".to_string()),
+            }],
+            certificate_key_pem: None,
+            updated_at: Timestamp::UNIX_EPOCH,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_order_store_roundtrip() {
+        let store = OrderStore::new(MemoryStorage::new());
+        store
+            .save(&pending_order(&["www.example.com", "example.com"]))
+            .await
+            .unwrap();
+
+        // Lookup is independent of the domain order
+        let loaded = store
+            .load(&["example.com".to_string(), "www.example.com".to_string()])
+            .await
+            .unwrap()
+            .expect("pending order");
+        assert_eq!(
+            loaded.authorizations[0].challenge_type.as_deref(),
+            Some("http-01")
+        );
+        assert!(loaded.updated_at > Timestamp::UNIX_EPOCH);
+        assert!(loaded.belongs_to("https://ca.example/directory", "https://ca.example/acct/1"));
+        assert!(!loaded.belongs_to("https://ca.example/directory", "https://ca.example/acct/2"));
+        assert_eq!(store.list_all().await.unwrap().len(), 1);
+
+        store.delete(&loaded.domains).await.unwrap();
+        assert!(store.load(&loaded.domains).await.unwrap().is_none());
+    }
+}
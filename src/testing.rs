@@ -0,0 +1,292 @@
+/// In-process fake ACME CA for integration tests.
+/// `FakeCa` runs the built-in ACME server on a random local port with in-memory storage,
+/// so `AcmeClient` can be driven through complete issuance flows offline. Requests are
+/// checked like a real CA would (JWS signatures, nonces, URLs), while challenge validation
+/// is simulated and faults can be injected to exercise error handling.
+///
+/// ```no_run
+/// # async fn example() -> acmex::Result<()> {
+/// use acmex::challenge::{ChallengeSolverRegistry, Dns01Solver};
+/// use acmex::testing::FakeCa;
+///
+/// let ca = FakeCa::start().await?;
+/// let mut registry = ChallengeSolverRegistry::new();
+/// registry.register(Dns01Solver::with_mock("example.com".to_string()));
+///
+/// let mut client = acmex::AcmeClient::new(ca.client_config())?;
+/// let bundle = client
+///     .issue_certificate(vec!["example.com".to_string()], &registry)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+use crate::client::AcmeConfig;
+use crate::error::{AcmeError, Result};
+use crate::server::acme::{AcmeServer, AcmeServerConfig, ChallengeVerifier, Problem};
+use crate::storage::MemoryStorage;
+use crate::types::{ChallengeType, Identifier};
+use async_trait::async_trait;
+use axum::body::{Body, to_bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Faults injected into the fake CA's responses.
+#[derive(Default)]
+struct Faults {
+    /// Number of upcoming signed requests rejected with `badNonce`.
+    bad_nonces: AtomicUsize,
+    /// Number of upcoming newOrder requests rejected with `rateLimited`.
+    rate_limited_orders: AtomicUsize,
+    /// Retry-After sent with rate limit errors.
+    retry_after: Mutex<Duration>,
+    /// Identifiers whose challenges fail validation.
+    failing_identifiers: Mutex<HashSet<String>>,
+    /// How long orders stay processing after finalization.
+    finalization_delay: Mutex<Duration>,
+    /// When each order was finalized, by order path.
+    finalized: Mutex<HashMap<String, Instant>>,
+}
+
+impl Faults {
+    /// Takes one of the remaining injections counted by `counter`.
+    fn take(counter: &AtomicUsize) -> bool {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Accepts every challenge, except for identifiers configured to fail.
+struct SimulatedVerifier {
+    faults: Arc<Faults>,
+}
+
+#[async_trait]
+impl ChallengeVerifier for SimulatedVerifier {
+    async fn verify(
+        &self,
+        challenge_type: ChallengeType,
+        identifier: &Identifier,
+        _token: &str,
+        _key_authorization: &str,
+    ) -> Result<()> {
+        if Faults::lock(&self.faults.failing_identifiers).contains(&identifier.value) {
+            return Err(AcmeError::challenge(
+                challenge_type.as_str().to_string(),
+                format!("Simulated incorrect response for {}", identifier.value),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// State shared with the fault injection middleware.
+#[derive(Clone)]
+struct FaultLayer {
+    server: Arc<AcmeServer>,
+    faults: Arc<Faults>,
+}
+
+impl FaultLayer {
+    /// An injected problem response, carrying a fresh nonce like every ACME response.
+    fn problem(&self, problem: Problem) -> Response {
+        let mut response = problem.into_response();
+        if let Ok(nonce) = HeaderValue::from_str(&self.server.nonces.issue()) {
+            response.headers_mut().insert("replay-nonce", nonce);
+        }
+        response
+    }
+}
+
+/// Injects the configured faults around the ACME server's handlers.
+async fn inject_faults(State(layer): State<FaultLayer>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    if request.method() == Method::POST {
+        if Faults::take(&layer.faults.bad_nonces) {
+            return layer.problem(Problem::bad_nonce("Injected bad nonce"));
+        }
+        if path == "/new-order" && Faults::take(&layer.faults.rate_limited_orders) {
+            let retry_after = *Faults::lock(&layer.faults.retry_after);
+            let mut response = layer.problem(Problem::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rateLimited",
+                "Injected rate limit",
+            ));
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(retry_after.as_secs()),
+            );
+            return response;
+        }
+    }
+
+    let response = next.run(request).await;
+    let delay = *Faults::lock(&layer.faults.finalization_delay);
+    let Some(order) = path.strip_prefix("/order/") else {
+        return response;
+    };
+    if delay.is_zero() || !response.status().is_success() {
+        return response;
+    }
+
+    // Hide the issued certificate until the finalization delay has passed
+    let remaining = {
+        let mut finalized = Faults::lock(&layer.faults.finalized);
+        let finalized_at = match order.strip_suffix("/finalize") {
+            Some(order) => *finalized
+                .entry(order.to_string())
+                .or_insert_with(Instant::now),
+            None => match finalized.get(order) {
+                Some(at) => *at,
+                None => return response,
+            },
+        };
+        delay.saturating_sub(finalized_at.elapsed())
+    };
+    if remaining.is_zero() {
+        return response;
+    }
+    hold_processing(response, remaining).await
+}
+
+/// Rewrites a valid order into a processing one that asks the client to retry later.
+async fn hold_processing(response: Response, remaining: Duration) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let Ok(mut order) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if order["status"] != "valid" {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    order["status"] = "processing".into();
+    if let Some(object) = order.as_object_mut() {
+        object.remove("certificate");
+    }
+
+    let retry_after = remaining.as_secs_f64().ceil() as u64;
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after.max(1)));
+    Response::from_parts(parts, Body::from(order.to_string()))
+}
+
+/// A stateful in-memory ACME CA listening on a random local port.
+/// The server stops when the `FakeCa` is dropped.
+pub struct FakeCa {
+    server: Arc<AcmeServer>,
+    faults: Arc<Faults>,
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl FakeCa {
+    /// Starts a fake CA on `127.0.0.1` with a freshly generated root and intermediate.
+    pub async fn start() -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| AcmeError::transport(format!("Failed to bind fake CA: {}", e)))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| AcmeError::transport(format!("Failed to bind fake CA: {}", e)))?;
+
+        let faults = Arc::new(Faults::default());
+        let config =
+            AcmeServerConfig::new(format!("http://{}", addr)).with_ca_name("AcmeX Fake CA");
+        let server = Arc::new(
+            AcmeServer::new(config, Arc::new(MemoryStorage::new()))
+                .await?
+                .with_verifier(Arc::new(SimulatedVerifier {
+                    faults: faults.clone(),
+                })),
+        );
+
+        let layer = FaultLayer {
+            server: server.clone(),
+            faults: faults.clone(),
+        };
+        let router = server
+            .clone()
+            .router()
+            .layer(axum::middleware::from_fn_with_state(layer, inject_faults));
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                tracing::error!("Fake CA stopped: {}", e);
+            }
+        });
+        tracing::debug!("Fake CA listening on {}", addr);
+
+        Ok(Self {
+            server,
+            faults,
+            addr,
+            task,
+        })
+    }
+
+    /// Returns the address the fake CA listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the URL of the ACME directory.
+    pub fn directory_url(&self) -> String {
+        self.server.directory_url()
+    }
+
+    /// Returns the PEM root certificate that issued certificates chain up to.
+    pub fn root_certificate_pem(&self) -> &str {
+        self.server.ca().root_pem()
+    }
+
+    /// Returns a client configuration for the fake CA that polls without delay.
+    pub fn client_config(&self) -> AcmeConfig {
+        AcmeConfig::new(self.directory_url())
+            .with_tos_agreed(true)
+            .with_poll_interval(Duration::from_millis(50))
+    }
+
+    /// Rejects the next `count` signed requests with `badNonce`.
+    pub fn inject_bad_nonces(&self, count: usize) {
+        self.faults.bad_nonces.store(count, Ordering::SeqCst);
+    }
+
+    /// Rejects the next `count` newOrder requests with `rateLimited` and a Retry-After header.
+    pub fn rate_limit_orders(&self, count: usize, retry_after: Duration) {
+        *Faults::lock(&self.faults.retry_after) = retry_after;
+        self.faults
+            .rate_limited_orders
+            .store(count, Ordering::SeqCst);
+    }
+
+    /// Fails challenge validation for `identifier` with `incorrectResponse`.
+    pub fn fail_challenges_for(&self, identifier: impl Into<String>) {
+        Faults::lock(&self.faults.failing_identifiers).insert(identifier.into());
+    }
+
+    /// Keeps finalized orders processing for `delay` before exposing their certificate.
+    pub fn set_finalization_delay(&self, delay: Duration) {
+        *Faults::lock(&self.faults.finalization_delay) = delay;
+    }
+}
+
+impl Drop for FakeCa {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -10,4 +10,4 @@ pub mod retry;
 pub use http_client::HttpClient;
 pub use middleware::{Middleware, MiddlewareChain};
 pub use rate_limit::RateLimiter;
-pub use retry::{RetryPolicy, RetryStrategy};
+pub use retry::{RetryPolicy, RetryStrategy, parse_retry_after};
@@ -125,10 +125,35 @@ impl RetryPolicy {
     }
 }
 
+/// Parses an HTTP `Retry-After` header value (delay-seconds or HTTP-date).
+/// Dates in the past yield a zero delay.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let at = jiff::fmt::rfc2822::DateTimeParser::new()
+        .parse_timestamp(value)
+        .ok()?;
+    let delta = at.as_second() - jiff::Timestamp::now().as_second();
+    Some(Duration::from_secs(delta.max(0) as u64))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
     #[test]
     fn test_exponential_backoff() {
         let strategy = RetryStrategy::ExponentialBackoff {
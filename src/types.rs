@@ -3,6 +3,7 @@
 /// including JWS headers, identifiers, and status enumerations.
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::IpAddr;
 
 /// Represents the header of a JSON Web Signature (JWS).
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +75,52 @@ pub struct AcmeSubproblem {
     pub identifier: Option<Identifier>,
 }
 
+/// The URN namespace shared by all ACME error types (RFC 8555 Section 6.7).
+pub const ACME_ERROR_NAMESPACE: &str = "urn:ietf:params:acme:error:";
+
+impl AcmeErrorDetail {
+    /// Returns the ACME error name without the URN namespace (e.g. "badNonce").
+    pub fn kind(&self) -> &str {
+        self.error_type
+            .strip_prefix(ACME_ERROR_NAMESPACE)
+            .unwrap_or(&self.error_type)
+    }
+
+    /// Returns true if the server rejected the request's anti-replay nonce.
+    pub fn is_bad_nonce(&self) -> bool {
+        self.kind() == "badNonce"
+    }
+}
+
+impl std::fmt::Display for AcmeErrorDetail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let detail = self
+            .detail
+            .as_deref()
+            .or(self.title.as_deref())
+            .unwrap_or("no detail provided");
+        write!(f, "{} ({})", detail, self.kind())?;
+
+        for sub in self.subproblems.iter().flatten() {
+            write!(f, "; ")?;
+            if let Some(identifier) = &sub.identifier {
+                write!(f, "{}: ", identifier.value)?;
+            }
+            let kind = sub
+                .error_type
+                .strip_prefix(ACME_ERROR_NAMESPACE)
+                .unwrap_or(&sub.error_type);
+            write!(
+                f,
+                "{} ({})",
+                sub.detail.as_deref().unwrap_or("no detail provided"),
+                kind
+            )?;
+        }
+        Ok(())
+    }
+}
+
 /// An identifier used in ACME authorizations (e.g., a DNS domain name).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Identifier {
@@ -100,10 +147,48 @@ impl Identifier {
             value: ip.into(),
         }
     }
+
+    /// Creates an identifier from a name as given on the command line or API:
+    /// IP address literals become IP identifiers (RFC 8738), everything else a DNS identifier.
+    pub fn from_name(name: impl Into<String>) -> Self {
+        let name = name.into();
+        match name.parse::<IpAddr>() {
+            // Use the canonical textual form (RFC 5952 for IPv6)
+            Ok(ip) => Self::ip(ip.to_string()),
+            Err(_) => Self::dns(name),
+        }
+    }
+
+    /// Returns true if this is an IP address identifier.
+    pub fn is_ip(&self) -> bool {
+        self.id_type == "ip"
+    }
+
+    /// Returns the TLS server name a validation server sends for this identifier:
+    /// the domain itself, or the reverse-DNS name for IP addresses (RFC 8738 Section 6).
+    pub fn tls_server_name(&self) -> String {
+        match self.value.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) if self.is_ip() => {
+                let [a, b, c, d] = ip.octets();
+                format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+            }
+            Ok(IpAddr::V6(ip)) if self.is_ip() => {
+                let nibbles: Vec<String> = ip
+                    .octets()
+                    .iter()
+                    .rev()
+                    .flat_map(|byte| [byte & 0x0f, byte >> 4])
+                    .map(|nibble| format!("{:x}", nibble))
+                    .collect();
+                format!("{}.ip6.arpa", nibbles.join("."))
+            }
+            _ => self.value.to_lowercase(),
+        }
+    }
 }
 
 /// Reasons for revoking a certificate (RFC 5280).
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum RevocationReason {
     /// No specific reason given.
@@ -347,6 +432,25 @@ mod tests {
         assert_eq!(id.value, "example.com");
     }
 
+    #[test]
+    fn test_identifier_from_name() {
+        let dns = Identifier::from_name("Example.com");
+        assert!(!dns.is_ip());
+        assert_eq!(dns.tls_server_name(), "example.com");
+
+        let v4 = Identifier::from_name("192.0.2.10");
+        assert!(v4.is_ip());
+        assert_eq!(v4.tls_server_name(), "10.2.0.192.in-addr.arpa");
+
+        let v6 = Identifier::from_name("2001:0db8:0000:0000:0000:0000:0000:0001");
+        assert_eq!(v6.id_type, "ip");
+        assert_eq!(v6.value, "2001:db8::1");
+        assert_eq!(
+            v6.tls_server_name(),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa"
+        );
+    }
+
     #[test]
     fn test_contact_email() {
         let contact = Contact::email("test@example.com");
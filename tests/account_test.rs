@@ -1,7 +1,9 @@
 mod common;
 
 use acmex::prelude::*;
-use common::MockAcmeServer;
+use common::{MockAcmeServer, jws_payload, jws_protected};
+use serde_json::json;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_full_account_lifecycle() -> Result<()> {
@@ -26,3 +28,243 @@ async fn test_full_account_lifecycle() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_register_requires_eab_when_directory_demands_it() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let _m_dir = mock_server
+        .mock_directory_with_meta(json!({ "externalAccountRequired": true }))
+        .await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+
+    let config = AcmeConfig::new(format!("{}/directory", mock_server.url())).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+
+    let err = client.register_account().await.unwrap_err();
+    assert!(matches!(err, AcmeError::Configuration(_)));
+    assert!(err.to_string().contains("External Account Binding"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_with_eab() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server
+        .mock_directory_with_meta(json!({ "externalAccountRequired": true }))
+        .await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let m_account = mock_server
+        .server
+        .mock("POST", "/new-account")
+        .match_request(|req| {
+            let payload = jws_payload(req.body().unwrap());
+            payload["externalAccountBinding"]["protected"].is_string()
+                && payload["externalAccountBinding"]["signature"].is_string()
+        })
+        .with_status(201)
+        .with_header("location", &format!("{}/account/1", url))
+        .with_body(json!({ "status": "valid", "contact": [] }).to_string())
+        .create_async()
+        .await;
+
+    let eab = ExternalAccountKey::new("kid-123", "c2VjcmV0LWhtYWMta2V5")?;
+    let config = AcmeConfig::new(format!("{}/directory", url))
+        .with_tos_agreed(true)
+        .with_external_account_binding(eab);
+    let mut client = AcmeClient::new(config)?;
+
+    client.register_account().await?;
+    m_account.assert_async().await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bad_nonce_is_retried() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+
+    let m_rejected = mock_server
+        .server
+        .mock("POST", "/new-account")
+        .match_request(|req| jws_protected(req.body().unwrap())["nonce"] == "test-nonce-123")
+        .with_status(400)
+        .with_header("content-type", "application/problem+json")
+        .with_header("replay-nonce", "fresh-nonce")
+        .with_body(
+            json!({
+                "type": "urn:ietf:params:acme:error:badNonce",
+                "detail": "JWS has an invalid anti-replay nonce"
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let m_accepted = mock_server
+        .server
+        .mock("POST", "/new-account")
+        .match_request(|req| jws_protected(req.body().unwrap())["nonce"] == "fresh-nonce")
+        .with_status(201)
+        .with_header("location", &format!("{}/account/1", url))
+        .with_body(json!({ "status": "valid", "contact": [] }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url)).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+    client.register_account().await?;
+
+    m_rejected.assert_async().await;
+    m_accepted.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_problem_document_maps_to_error_variant() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+
+    let _m_account = mock_server
+        .server
+        .mock("POST", "/new-account")
+        .with_status(429)
+        .with_header("content-type", "application/problem+json")
+        .with_header("retry-after", "120")
+        .with_body(
+            json!({
+                "type": "urn:ietf:params:acme:error:rateLimited",
+                "detail": "too many new registrations from this IP"
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", mock_server.url())).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+
+    let err = client.register_account().await.unwrap_err();
+    assert!(matches!(
+        err,
+        AcmeError::RateLimited(Some(d)) if d == Duration::from_secs(120)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_find_account_and_list_orders() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let m_lookup = mock_server
+        .server
+        .mock("POST", "/new-account")
+        .match_request(|req| {
+            jws_payload(req.body().unwrap()) == json!({"onlyReturnExisting": true})
+        })
+        .with_status(200)
+        .with_header("location", &format!("{}/account/1", url))
+        .with_body(
+            json!({
+                "status": "valid",
+                "orders": format!("{}/account/1/orders", url)
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let _m_get_account = mock_server
+        .server
+        .mock("POST", "/account/1")
+        .with_status(200)
+        .with_body(
+            json!({
+                "status": "valid",
+                "contact": ["mailto:admin@example.com"],
+                "orders": format!("{}/account/1/orders", url)
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let _m_page1 = mock_server
+        .server
+        .mock("POST", "/account/1/orders")
+        .with_status(200)
+        .with_header(
+            "link",
+            &format!("<{}/account/1/orders?cursor=2>;rel=\"next\"", url),
+        )
+        .with_body(
+            json!({ "orders": [format!("{}/order/1", url), format!("{}/order/2", url)] })
+                .to_string(),
+        )
+        .create_async()
+        .await;
+    let _m_page2 = mock_server
+        .server
+        .mock("POST", "/account/1/orders?cursor=2")
+        .with_status(200)
+        .with_body(json!({ "orders": [format!("{}/order/3", url)] }).to_string())
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url));
+    let mut client = AcmeClient::new(config)?;
+
+    let account = client.find_account().await?;
+    assert_eq!(account.id, format!("{}/account/1", url));
+    assert!(account.contact.is_empty());
+    assert_eq!(client.account_id(), Some(account.id.as_str()));
+
+    let orders = client.list_orders().await?;
+    assert_eq!(
+        orders,
+        vec![
+            format!("{}/order/1", url),
+            format!("{}/order/2", url),
+            format!("{}/order/3", url)
+        ]
+    );
+
+    m_lookup.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_find_account_for_unknown_key() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_lookup = mock_server
+        .server
+        .mock("POST", "/new-account")
+        .with_status(400)
+        .with_header("content-type", "application/problem+json")
+        .with_body(
+            json!({
+                "type": "urn:ietf:params:acme:error:accountDoesNotExist",
+                "detail": "No account exists with the provided key"
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", mock_server.url()));
+    let mut client = AcmeClient::new(config)?;
+
+    let err = client.list_orders().await.unwrap_err();
+    assert!(matches!(err, AcmeError::Account(_)));
+    assert!(client.account_id().is_none());
+    Ok(())
+}
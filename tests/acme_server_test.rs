@@ -0,0 +1,244 @@
+use acmex::account::ExternalAccountKey;
+use acmex::challenge::{ChallengeSolverRegistry, Http01Solver};
+use acmex::prelude::*;
+use acmex::server::acme::{NetworkVerifier, Relay, RelayPolicy};
+use acmex::server::{AcmeServer, AcmeServerConfig};
+use acmex::storage::{MemoryStorage, StorageBackend};
+use acmex::types::RevocationReason;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Returns a free local port.
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+/// Starts an ACME server on a random port, validating http-01 on `http01_port`.
+async fn start_server(storage: Arc<dyn StorageBackend>, http01_port: u16) -> Arc<AcmeServer> {
+    start_with(storage, http01_port, None).await
+}
+
+/// Starts an ACME server, in relay mode if `relay` is given.
+async fn start_with(
+    storage: Arc<dyn StorageBackend>,
+    http01_port: u16,
+    relay: Option<Relay>,
+) -> Arc<AcmeServer> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let config = AcmeServerConfig::new(format!("http://{}/acme", addr))
+        .with_certificate_validity(Duration::from_secs(7 * 24 * 3600));
+    let mut server = AcmeServer::new(config, storage)
+        .await
+        .expect("ACME server")
+        .with_verifier(Arc::new(
+            NetworkVerifier::new().with_http01_port(http01_port),
+        ));
+    if let Some(relay) = relay {
+        server = server.with_relay(relay);
+    }
+    let server = Arc::new(server);
+    let router = server.clone().router();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    server
+}
+
+fn client_config(server: &AcmeServer) -> AcmeConfig {
+    AcmeConfig::new(server.directory_url())
+        .with_contact(Contact::email("admin@example.com"))
+        .with_tos_agreed(true)
+        .with_poll_interval(Duration::from_millis(100))
+}
+
+fn http01_registry(port: u16) -> ChallengeSolverRegistry {
+    let mut registry = ChallengeSolverRegistry::new();
+    registry.register(Http01Solver::new(SocketAddr::from(([127, 0, 0, 1], port))));
+    registry
+}
+
+#[tokio::test]
+async fn test_issue_and_revoke_with_local_ca() -> Result<()> {
+    let http01_port = free_port();
+    let server = start_server(Arc::new(MemoryStorage::new()), http01_port).await;
+
+    let mut client = AcmeClient::new(client_config(&server))?;
+    let bundle = client
+        .issue_certificate(vec!["127.0.0.1".to_string()], &http01_registry(http01_port))
+        .await?;
+
+    // The chain is the leaf followed by the intermediate, which the root issued
+    let chain = acmex::order::parse_certificate_chain(&bundle.certificate_pem)?;
+    assert_eq!(chain.len(), 2);
+    let (_, leaf) = x509_parser::parse_x509_certificate(&chain[0]).unwrap();
+    let (_, intermediate) = x509_parser::parse_x509_certificate(&chain[1]).unwrap();
+    let root_der = acmex::order::parse_certificate_chain(server.ca().root_pem())?;
+    let (_, root) = x509_parser::parse_x509_certificate(&root_der[0]).unwrap();
+    assert_eq!(leaf.issuer(), intermediate.subject());
+    assert_eq!(intermediate.issuer(), root.subject());
+    assert!(!leaf.is_ca());
+    let san = leaf.subject_alternative_name().unwrap().unwrap();
+    assert_eq!(san.value.general_names.len(), 1);
+
+    // The order shows up in the account's order list
+    assert_eq!(client.list_orders().await?.len(), 1);
+
+    client
+        .revoke_certificate(&bundle.certificate_pem, Some(RevocationReason::Superseded))
+        .await?;
+    let err = client
+        .revoke_certificate(&bundle.certificate_pem, None)
+        .await
+        .expect_err("Revoking twice must fail");
+    assert!(err.to_string().contains("already revoked"), "{}", err);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revoke_with_certificate_key() -> Result<()> {
+    let http01_port = free_port();
+    let server = start_server(Arc::new(MemoryStorage::new()), http01_port).await;
+
+    let mut client = AcmeClient::new(client_config(&server))?;
+    let bundle = client
+        .issue_certificate(vec!["127.0.0.1".to_string()], &http01_registry(http01_port))
+        .await?;
+
+    // Another account may not revoke the certificate with its own key
+    let mut other = AcmeClient::new(client_config(&server))?;
+    other.register_account().await?;
+    assert!(
+        other
+            .revoke_certificate(&bundle.certificate_pem, None)
+            .await
+            .is_err()
+    );
+
+    // Anyone holding the certificate key may
+    let certificate_key = KeyPair::from_pem(&bundle.private_key_pem)?;
+    other
+        .revoke_with_certificate_key(
+            &bundle.certificate_pem,
+            &certificate_key,
+            Some(RevocationReason::KeyCompromise),
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_local_ca_survives_restart() -> Result<()> {
+    let storage: Arc<dyn StorageBackend> = Arc::new(MemoryStorage::new());
+    let first = start_server(storage.clone(), free_port()).await;
+    let second = start_server(storage, free_port()).await;
+    assert_eq!(first.ca().root_pem(), second.ca().root_pem());
+    assert_eq!(
+        first.ca().intermediate_pem(),
+        second.ca().intermediate_pem()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relay_issues_through_upstream() -> Result<()> {
+    // The upstream CA validates the relay's own http-01 challenges
+    let http01_port = free_port();
+    let upstream = start_server(Arc::new(MemoryStorage::new()), http01_port).await;
+    let eab = ExternalAccountKey::new("team-a", "c2VjcmV0LWhtYWMta2V5")?;
+    let relay = Relay::new(
+        AcmeClient::new(client_config(&upstream))?,
+        http01_registry(http01_port),
+        RelayPolicy::new()
+            .with_eab_key(eab.clone())
+            .with_allowed_domain("127.0.0.1"),
+    )?;
+    let relay_server = start_with(Arc::new(MemoryStorage::new()), free_port(), Some(relay)).await;
+
+    // Internal clients must be bound to an external account
+    let mut unbound = AcmeClient::new(client_config(&relay_server))?;
+    let err = unbound
+        .register_account()
+        .await
+        .expect_err("Registration without EAB must fail");
+    assert!(
+        err.to_string().contains("externalAccountRequired"),
+        "{}",
+        err
+    );
+
+    // Bound clients need no challenge solvers of their own
+    let mut client =
+        AcmeClient::new(client_config(&relay_server).with_external_account_binding(eab))?;
+    let bundle = client
+        .issue_certificate(
+            vec!["127.0.0.1".to_string()],
+            &ChallengeSolverRegistry::new(),
+        )
+        .await?;
+    let chain = acmex::order::parse_certificate_chain(&bundle.certificate_pem)?;
+    let (_, leaf) = x509_parser::parse_x509_certificate(&chain[0]).unwrap();
+    let upstream_root = acmex::order::parse_certificate_chain(upstream.ca().root_pem())?;
+    let (_, root) = x509_parser::parse_x509_certificate(&upstream_root[0]).unwrap();
+    let (_, intermediate) = x509_parser::parse_x509_certificate(&chain[1]).unwrap();
+    assert_eq!(intermediate.issuer(), root.subject());
+    assert_eq!(leaf.issuer(), intermediate.subject());
+
+    // Identifiers outside the policy are rejected
+    let err = client
+        .create_order(vec!["10.0.0.1".to_string()])
+        .await
+        .expect_err("Order outside the policy must fail");
+    assert!(err.to_string().contains("rejectedIdentifier"), "{}", err);
+
+    // Revocation is forwarded to the upstream CA
+    client
+        .revoke_certificate(&bundle.certificate_pem, None)
+        .await?;
+    Ok(())
+}
+
+/// Storage refusing to record a validated challenge, as if the backend failed mid-validation.
+struct FailingValidationStorage(MemoryStorage);
+
+#[async_trait::async_trait]
+impl StorageBackend for FailingValidationStorage {
+    async fn store(&self, key: &str, value: &[u8]) -> Result<()> {
+        if key.contains(":authz:") && String::from_utf8_lossy(value).contains("\"validated\"") {
+            return Err(AcmeError::storage("disk full"));
+        }
+        self.0.store(key, value).await
+    }
+
+    async fn load(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.0.load(key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.0.delete(key).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.0.list(prefix).await
+    }
+}
+
+#[tokio::test]
+async fn test_validation_storage_error_invalidates_challenge() -> Result<()> {
+    let http01_port = free_port();
+    let storage = Arc::new(FailingValidationStorage(MemoryStorage::new()));
+    let server = start_server(storage, http01_port).await;
+
+    // The challenge ends invalid instead of staying processing forever
+    let mut client = AcmeClient::new(
+        client_config(&server).with_authorization_timeout(Duration::from_secs(10)),
+    )?;
+    let err = client
+        .issue_certificate(vec!["127.0.0.1".to_string()], &http01_registry(http01_port))
+        .await
+        .expect_err("Issuance must fail when validation cannot be recorded");
+    assert!(err.to_string().contains("serverInternal"), "{}", err);
+    Ok(())
+}
@@ -7,6 +7,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower::ServiceExt;
 
+use acmex::certificate::OcspVerifier;
 use acmex::config::Config;
 use acmex::notifications::WebhookManager;
 use acmex::orchestrator::OrchestrationStatus;
@@ -30,6 +31,7 @@ async fn test_api_renew_all() {
         tasks,
         api_keys,
         scheduler: None,
+        ocsp: Arc::new(OcspVerifier::new()),
     };
 
     let app = axum::Router::new()
@@ -81,6 +83,7 @@ async fn test_api_list_orders() {
         tasks,
         api_keys: Arc::new(vec!["test-key".to_string()]),
         scheduler: None,
+        ocsp: Arc::new(OcspVerifier::new()),
     };
 
     let app = axum::Router::new()
@@ -111,3 +114,147 @@ async fn test_api_list_orders() {
     assert!(orders.is_array());
     assert_eq!(orders[0]["id"], "task-1");
 }
+
+#[tokio::test]
+async fn test_api_pre_authorize_unsupported_by_ca() {
+    let mut server = mockito::Server::new_async().await;
+    let url = server.url();
+    let _m_dir = server
+        .mock("GET", "/directory")
+        .with_status(200)
+        .with_body(
+            serde_json::json!({
+                "newNonce": format!("{}/new-nonce", url),
+                "newAccount": format!("{}/new-account", url),
+                "newOrder": format!("{}/new-order", url),
+                "revokeCert": format!("{}/revoke-cert", url),
+                "keyChange": format!("{}/key-change", url)
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let client =
+        acmex::AcmeClient::new(acmex::AcmeConfig::new(format!("{}/directory", url))).unwrap();
+    let state = AppState {
+        config: Arc::new(Config::default()),
+        client: Some(Arc::new(client)),
+        storage: None,
+        health: Arc::new(acmex::server::HealthCheck::new()),
+        webhook: Arc::new(acmex::server::WebhookHandler::new(Arc::new(
+            WebhookManager::new(vec![]),
+        ))),
+        tasks: Arc::new(RwLock::new(HashMap::new())),
+        api_keys: Arc::new(vec!["test-key".to_string()]),
+        scheduler: None,
+        ocsp: Arc::new(OcspVerifier::new()),
+    };
+
+    let app = axum::Router::new()
+        .route(
+            "/api/authorizations",
+            axum::routing::post(acmex::server::authorization::pre_authorize),
+        )
+        .with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/authorizations")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"domains": ["example.com"]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+}
+
+#[tokio::test]
+async fn test_api_create_order_rejects_invalid_certificate_settings() {
+    let tasks = Arc::new(RwLock::new(HashMap::new()));
+    let state = AppState {
+        config: Arc::new(Config::default()),
+        client: None,
+        storage: None,
+        health: Arc::new(acmex::server::HealthCheck::new()),
+        webhook: Arc::new(acmex::server::WebhookHandler::new(Arc::new(
+            WebhookManager::new(vec![]),
+        ))),
+        tasks: tasks.clone(),
+        api_keys: Arc::new(vec!["test-key".to_string()]),
+        scheduler: None,
+        ocsp: Arc::new(OcspVerifier::new()),
+    };
+
+    let app = axum::Router::new()
+        .route(
+            "/api/orders",
+            axum::routing::post(acmex::server::order::create_order),
+        )
+        .with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/orders")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"domains": ["example.com"], "certificate": {"key_type": "dsa-1024"}}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(tasks.read().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_api_create_account_rejects_partial_eab() {
+    let state = AppState {
+        config: Arc::new(Config::default()),
+        client: None,
+        storage: None,
+        health: Arc::new(acmex::server::HealthCheck::new()),
+        webhook: Arc::new(acmex::server::WebhookHandler::new(Arc::new(
+            WebhookManager::new(vec![]),
+        ))),
+        tasks: Arc::new(RwLock::new(HashMap::new())),
+        api_keys: Arc::new(vec!["test-key".to_string()]),
+        scheduler: None,
+        ocsp: Arc::new(OcspVerifier::new()),
+    };
+
+    let app = axum::Router::new()
+        .route(
+            "/api/accounts",
+            axum::routing::post(acmex::server::account::create_account),
+        )
+        .with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/accounts")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"email": "admin@example.com", "tos_agreed": true, "eab_key_id": "kid-1"}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("eab_hmac_key"));
+}
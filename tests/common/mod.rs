@@ -1,6 +1,27 @@
+#![allow(dead_code)]
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use mockito::Server;
 use serde_json::json;
 
+/// Decodes the JSON payload of a flattened JWS request body.
+pub fn jws_payload(body: &[u8]) -> serde_json::Value {
+    jws_part(body, "payload")
+}
+
+/// Decodes the protected header of a flattened JWS request body.
+pub fn jws_protected(body: &[u8]) -> serde_json::Value {
+    jws_part(body, "protected")
+}
+
+fn jws_part(body: &[u8], part: &str) -> serde_json::Value {
+    let jws: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+    let encoded = jws[part].as_str().unwrap_or_default();
+    let decoded = URL_SAFE_NO_PAD.decode(encoded).unwrap_or_default();
+    serde_json::from_slice(&decoded).unwrap_or(serde_json::Value::Null)
+}
+
 pub struct MockAcmeServer {
     pub server: mockito::ServerGuard,
 }
@@ -16,24 +37,37 @@ impl MockAcmeServer {
     }
 
     pub async fn mock_directory(&mut self) -> mockito::Mock {
+        self.mock_directory_with_meta(json!({
+            "termsOfService": "https://example.com/tos"
+        }))
+        .await
+    }
+
+    pub async fn mock_directory_with_meta(&mut self, meta: serde_json::Value) -> mockito::Mock {
+        self.mock_directory_with(json!({ "meta": meta })).await
+    }
+
+    /// Mocks the directory, merging `extra` fields (e.g. "renewalInfo") into the default endpoints.
+    pub async fn mock_directory_with(&mut self, extra: serde_json::Value) -> mockito::Mock {
         let url = self.url();
+        let mut directory = json!({
+            "newNonce": format!("{}/new-nonce", url),
+            "newAccount": format!("{}/new-account", url),
+            "newOrder": format!("{}/new-order", url),
+            "revokeCert": format!("{}/revoke-cert", url),
+            "keyChange": format!("{}/key-change", url),
+        });
+        if let (Some(target), Some(fields)) = (directory.as_object_mut(), extra.as_object()) {
+            for (key, value) in fields {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+
         self.server
             .mock("GET", "/directory")
             .with_status(200)
             .with_header("content-type", "application/json")
-            .with_body(
-                json!({
-                    "newNonce": format!("{}/new-nonce", url),
-                    "newAccount": format!("{}/new-account", url),
-                    "newOrder": format!("{}/new-order", url),
-                    "revokeCert": format!("{}/revoke-cert", url),
-                    "keyChange": format!("{}/key-change", url),
-                    "meta": {
-                        "termsOfService": "https://example.com/tos"
-                    }
-                })
-                .to_string(),
-            )
+            .with_body(directory.to_string())
             .create_async()
             .await
     }
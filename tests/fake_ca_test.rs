@@ -0,0 +1,225 @@
+#![cfg(feature = "testing")]
+
+use acmex::challenge::{ChallengeSolverRegistry, Dns01Solver};
+use acmex::prelude::*;
+use acmex::testing::FakeCa;
+use acmex::{parse_certificate_chain, verify_certificate_domains};
+use std::time::{Duration, Instant};
+
+fn dns01_registry() -> ChallengeSolverRegistry {
+    let mut registry = ChallengeSolverRegistry::new();
+    registry.register(Dns01Solver::with_mock("example.com".to_string()));
+    registry
+}
+
+#[tokio::test]
+async fn test_full_issuance_flow() -> Result<()> {
+    let ca = FakeCa::start().await?;
+    let mut client = AcmeClient::new(ca.client_config())?;
+    let bundle = client
+        .issue_certificate(
+            vec!["example.com".to_string(), "*.example.com".to_string()],
+            &dns01_registry(),
+        )
+        .await?;
+
+    let chain = parse_certificate_chain(&bundle.certificate_pem)?;
+    assert_eq!(chain.len(), 2);
+    assert!(verify_certificate_domains(&chain[0], &bundle.domains)?);
+    assert!(!ca.root_certificate_pem().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bad_nonces_are_retried() -> Result<()> {
+    let ca = FakeCa::start().await?;
+    ca.inject_bad_nonces(1);
+    let mut client = AcmeClient::new(ca.client_config())?;
+    client.register_account().await?;
+
+    // More rejections in a row than the client retries surface as an error
+    ca.inject_bad_nonces(10);
+    let err = client
+        .create_order(vec!["example.com".to_string()])
+        .await
+        .expect_err("Repeated badNonce must fail");
+    assert!(err.to_string().contains("badNonce"), "{}", err);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limited_order() -> Result<()> {
+    let ca = FakeCa::start().await?;
+    ca.rate_limit_orders(1, Duration::from_secs(30));
+    let mut client = AcmeClient::new(ca.client_config())?;
+    let err = client
+        .issue_certificate(vec!["example.com".to_string()], &dns01_registry())
+        .await
+        .expect_err("Rate limited order must fail");
+    assert!(
+        matches!(err, AcmeError::RateLimited(Some(d)) if d == Duration::from_secs(30)),
+        "{:?}",
+        err
+    );
+
+    // The limit only applied to one request
+    client
+        .issue_certificate(vec!["example.com".to_string()], &dns01_registry())
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_challenge() -> Result<()> {
+    let ca = FakeCa::start().await?;
+    ca.fail_challenges_for("b.example.com");
+    let mut client = AcmeClient::new(ca.client_config())?;
+    let err = client
+        .issue_certificate(
+            vec!["a.example.com".to_string(), "b.example.com".to_string()],
+            &dns01_registry(),
+        )
+        .await
+        .expect_err("Issuance must fail when a challenge is invalid");
+
+    let AcmeError::Challenge { error, .. } = err else {
+        panic!("Expected a challenge error, got {:?}", err);
+    };
+    assert!(error.contains("b.example.com"), "{}", error);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_slow_finalization() -> Result<()> {
+    let ca = FakeCa::start().await?;
+    ca.set_finalization_delay(Duration::from_secs(1));
+
+    let mut client = AcmeClient::new(ca.client_config())?;
+    let started = Instant::now();
+    client
+        .issue_certificate(vec!["example.com".to_string()], &dns01_registry())
+        .await?;
+    assert!(started.elapsed() >= Duration::from_secs(1));
+
+    // A client that gives up sooner reports the stuck order
+    ca.set_finalization_delay(Duration::from_secs(30));
+    let mut impatient = AcmeClient::new(
+        ca.client_config()
+            .with_finalization_timeout(Duration::from_millis(300)),
+    )?;
+    assert!(
+        impatient
+            .issue_certificate(vec!["example.com".to_string()], &dns01_registry())
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_renewal_applies_certificate_overrides() -> Result<()> {
+    use acmex::account::KeyPair;
+    use acmex::config::Config;
+    use acmex::crypto::KeyType;
+    use acmex::orchestrator::{CertificateRenewer, Orchestrator};
+    use acmex::storage::{CertificateStore, MemoryStorage};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    let ca = FakeCa::start().await?;
+    let store = CertificateStore::new(Arc::new(MemoryStorage::new()));
+    let mut client = AcmeClient::new(ca.client_config())?;
+    let issued = client
+        .issue_certificate(vec!["example.com".to_string()], &dns01_registry())
+        .await?;
+    assert_eq!(
+        KeyPair::from_pem(&issued.private_key_pem)?.key_type()?,
+        KeyType::EcdsaP256
+    );
+    store.save(&issued).await?;
+
+    let config = Config::from_str(
+        r#"
+[acme]
+ca = "letsencrypt"
+
+[challenge]
+challenge_type = "http-01"
+
+[challenge.http01]
+listen_addr = "127.0.0.1:0"
+
+[certificate.overrides."example.com"]
+key_type = "ecdsa-p384"
+"#,
+    )?;
+
+    // Every certificate is due well before a ten-year threshold
+    CertificateRenewer::new(store.clone(), 3650)
+        .with_client(client)
+        .execute(&config)
+        .await?;
+
+    let renewed = store
+        .load(&["example.com".to_string()])
+        .await?
+        .expect("renewed certificate stored");
+    assert_ne!(renewed.certificate_pem, issued.certificate_pem);
+    assert_eq!(
+        KeyPair::from_pem(&renewed.private_key_pem)?.key_type()?,
+        KeyType::EcdsaP384
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_renewal_failure_does_not_stop_other_certificates() -> Result<()> {
+    use acmex::config::Config;
+    use acmex::orchestrator::{CertificateRenewer, Orchestrator};
+    use acmex::storage::{CertificateStore, MemoryStorage};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    let ca = FakeCa::start().await?;
+    let store = CertificateStore::new(Arc::new(MemoryStorage::new()));
+    let mut client = AcmeClient::new(ca.client_config())?;
+    let mut issued = Vec::new();
+    for domain in ["a.example.com", "b.example.com", "c.example.com"] {
+        let bundle = client
+            .issue_certificate(vec![domain.to_string()], &dns01_registry())
+            .await?;
+        store.save(&bundle).await?;
+        issued.push(bundle);
+    }
+    ca.fail_challenges_for("b.example.com");
+
+    let config = Config::from_str(
+        r#"
+[acme]
+ca = "letsencrypt"
+
+[challenge]
+challenge_type = "http-01"
+
+[challenge.http01]
+listen_addr = "127.0.0.1:0"
+"#,
+    )?;
+
+    let err = CertificateRenewer::new(store.clone(), 3650)
+        .with_client(client)
+        .execute(&config)
+        .await
+        .expect_err("A failed renewal must be reported");
+    assert!(err.to_string().contains("b.example.com"), "{}", err);
+
+    for bundle in &issued {
+        let stored = store
+            .load(&bundle.domains)
+            .await?
+            .expect("certificate stays stored");
+        let renewed = stored.certificate_pem != bundle.certificate_pem;
+        assert_eq!(renewed, bundle.domains[0] != "b.example.com");
+    }
+    Ok(())
+}
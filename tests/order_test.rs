@@ -1,8 +1,12 @@
 mod common;
 
+use acmex::challenge::{ChallengeSolver, ChallengeSolverRegistry};
 use acmex::prelude::*;
-use common::MockAcmeServer;
+use common::{MockAcmeServer, jws_payload, jws_protected};
 use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_certificate_order_flow() -> Result<()> {
@@ -47,3 +51,995 @@ async fn test_certificate_order_flow() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_order_with_certificate_profile() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+
+    let _m_dir = mock_server
+        .mock_directory_with_meta(json!({
+            "profiles": {
+                "classic": "https://example.com/docs/classic",
+                "shortlived": "https://example.com/docs/shortlived"
+            }
+        }))
+        .await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+
+    let m_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .match_request(|req| {
+            let payload = jws_payload(req.body().unwrap());
+            payload["profile"] == "shortlived"
+        })
+        .with_status(201)
+        .with_header("location", &format!("{}/order/1", url))
+        .with_body(
+            json!({
+                "status": "pending",
+                "expires": "2026-02-10T00:00:00Z",
+                "identifiers": [{"type": "dns", "value": "example.com"}],
+                "authorizations": [format!("{}/authz/1", url)],
+                "finalize": format!("{}/order/1/finalize", url),
+                "profile": "shortlived"
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url))
+        .with_contact(Contact::email("admin@example.com"))
+        .with_tos_agreed(true)
+        .with_profile("shortlived");
+
+    let mut client = AcmeClient::new(config)?;
+    client.register_account().await?;
+
+    let order = client.create_order(vec!["example.com".to_string()]).await?;
+    assert_eq!(order.profile.as_deref(), Some("shortlived"));
+    m_order.assert_async().await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_profile_rejected_before_order() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+
+    let _m_dir = mock_server
+        .mock_directory_with_meta(json!({
+            "profiles": { "classic": "https://example.com/docs/classic" }
+        }))
+        .await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+
+    let m_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .with_status(201)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url))
+        .with_contact(Contact::email("admin@example.com"))
+        .with_tos_agreed(true)
+        .with_profile("tlsserver");
+
+    let mut client = AcmeClient::new(config)?;
+    client.register_account().await?;
+
+    let err = client
+        .create_order(vec!["example.com".to_string()])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AcmeError::InvalidInput(_)));
+    assert!(err.to_string().contains("classic"));
+    m_order.assert_async().await;
+
+    Ok(())
+}
+
+/// Builds a PEM chain (leaf + intermediate) whose intermediate is issued by `root_cn`.
+fn chain_to_root(root_cn: &str) -> String {
+    let ca = |cn: &str| {
+        let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, cn);
+        params
+    };
+    let root = rcgen::Issuer::new(ca(root_cn), rcgen::KeyPair::generate().unwrap());
+    let intermediate_key = rcgen::KeyPair::generate().unwrap();
+    let intermediate = ca("Test Intermediate")
+        .signed_by(&intermediate_key, &root)
+        .unwrap();
+    let intermediate_issuer = rcgen::Issuer::new(ca("Test Intermediate"), intermediate_key);
+    let leaf = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+        .unwrap()
+        .signed_by(&rcgen::KeyPair::generate().unwrap(), &intermediate_issuer)
+        .unwrap();
+    format!("{}{}", leaf.pem(), intermediate.pem())
+}
+
+#[tokio::test]
+async fn test_download_alternate_chains() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+
+    let default_chain = chain_to_root("Test Root X2");
+    let alternate_chain = chain_to_root("Test Root X1");
+
+    let _m_cert = mock_server
+        .server
+        .mock("POST", "/cert/1")
+        .with_status(200)
+        .with_header("content-type", "application/pem-certificate-chain")
+        .with_header("link", &format!("<{}/cert/1/1>;rel=\"alternate\"", url))
+        .with_body(&default_chain)
+        .create_async()
+        .await;
+    let _m_alternate = mock_server
+        .server
+        .mock("POST", "/cert/1/1")
+        .with_status(200)
+        .with_header("content-type", "application/pem-certificate-chain")
+        .with_body(&alternate_chain)
+        .create_async()
+        .await;
+
+    let http_client = reqwest::Client::new();
+    let key_pair = KeyPair::generate()?;
+    let dir_mgr = DirectoryManager::new(format!("{}/directory", url), http_client.clone());
+    let nonce_mgr = NonceManager::new(format!("{}/new-nonce", url), http_client.clone());
+    let account_mgr = AccountManager::new(&key_pair, &nonce_mgr, &dir_mgr, &http_client)?;
+    let order_mgr = acmex::order::OrderManager::new(
+        &account_mgr,
+        &dir_mgr,
+        &nonce_mgr,
+        &http_client,
+        format!("{}/account/1", url),
+    );
+
+    let chains = order_mgr
+        .download_certificate_chains(&format!("{}/cert/1", url))
+        .await?;
+    assert_eq!(chains, vec![default_chain, alternate_chain]);
+
+    assert_eq!(
+        acmex::certificate::select_preferred_chain(&chains, "Test Root X1"),
+        1
+    );
+    assert_eq!(
+        acmex::certificate::select_preferred_chain(&chains, "Unknown Root"),
+        0
+    );
+
+    Ok(())
+}
+
+/// Challenge solver recording what the client asked it to do.
+#[derive(Clone, Default)]
+struct RecordingSolver {
+    prepared: Arc<Mutex<Vec<String>>>,
+    cleanups: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl ChallengeSolver for RecordingSolver {
+    fn challenge_type(&self) -> ChallengeType {
+        ChallengeType::Http01
+    }
+
+    async fn prepare(
+        &self,
+        _challenge: &Challenge,
+        identifier: &Identifier,
+        _key_authorization: &str,
+    ) -> Result<()> {
+        self.prepared.lock().unwrap().push(identifier.value.clone());
+        Ok(())
+    }
+
+    async fn present(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn verify(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn cleanup(&self) -> Result<()> {
+        self.cleanups.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Mocks an authorization that is pending on the first fetch and `final_status` afterwards.
+async fn mock_authorization(
+    mock_server: &mut MockAcmeServer,
+    id: u32,
+    domain: &str,
+    final_status: &'static str,
+) -> (mockito::Mock, mockito::Mock) {
+    let url = mock_server.url();
+    let challenge_url = format!("{}/chall/{}", url, id);
+    let fetches = AtomicUsize::new(0);
+    let domain = domain.to_string();
+    let challenge = challenge_url.clone();
+    let m_authz = mock_server
+        .server
+        .mock("POST", format!("/authz/{}", id).as_str())
+        .with_status(200)
+        .with_body_from_request(move |_| {
+            let first = fetches.fetch_add(1, Ordering::SeqCst) == 0;
+            let status = if first { "pending" } else { final_status };
+            let mut challenge = json!({
+                "type": "http-01",
+                "url": challenge,
+                "status": status,
+                "token": format!("token-{}", id)
+            });
+            if status == "invalid" {
+                challenge["error"] = json!({
+                    "type": "urn:ietf:params:acme:error:unauthorized",
+                    "detail": "Invalid response from http://example/"
+                });
+            }
+            json!({
+                "identifier": {"type": "dns", "value": domain},
+                "status": status,
+                "expires": "2026-02-10T00:00:00Z",
+                "challenges": [challenge]
+            })
+            .to_string()
+            .into_bytes()
+        })
+        .create_async()
+        .await;
+    let m_challenge = mock_server
+        .server
+        .mock("POST", format!("/chall/{}", id).as_str())
+        .with_status(200)
+        .with_body(
+            json!({
+                "type": "http-01",
+                "url": challenge_url,
+                "status": "processing",
+                "token": format!("token-{}", id)
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    (m_authz, m_challenge)
+}
+
+#[tokio::test]
+async fn test_failed_authorization_cleans_up_challenges() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+    let _m_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .with_status(201)
+        .with_header("location", &format!("{}/order/1", url))
+        .with_body(
+            json!({
+                "status": "pending",
+                "expires": "2026-02-10T00:00:00Z",
+                "identifiers": [
+                    {"type": "dns", "value": "a.example.com"},
+                    {"type": "dns", "value": "b.example.com"}
+                ],
+                "authorizations": [format!("{}/authz/1", url), format!("{}/authz/2", url)],
+                "finalize": format!("{}/order/1/finalize", url)
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let (_m_authz1, m_chall1) =
+        mock_authorization(&mut mock_server, 1, "a.example.com", "valid").await;
+    let (_m_authz2, m_chall2) =
+        mock_authorization(&mut mock_server, 2, "b.example.com", "invalid").await;
+    let m_finalize = mock_server
+        .server
+        .mock("POST", "/order/1/finalize")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let solver = RecordingSolver::default();
+    let mut registry = ChallengeSolverRegistry::new();
+    registry.register(solver.clone());
+
+    let config = AcmeConfig::new(format!("{}/directory", url))
+        .with_tos_agreed(true)
+        .with_authorization_concurrency(2);
+    let mut client = AcmeClient::new(config)?;
+    let err = client
+        .issue_certificate(
+            vec!["a.example.com".to_string(), "b.example.com".to_string()],
+            &registry,
+        )
+        .await
+        .expect_err("Issuance must fail when an authorization is invalid");
+
+    let AcmeError::Challenge { error, .. } = err else {
+        panic!("Expected a challenge error, got {:?}", err);
+    };
+    assert!(error.contains("b.example.com"));
+    assert!(error.contains("Invalid response"));
+
+    let mut prepared = solver.prepared.lock().unwrap().clone();
+    prepared.sort();
+    assert_eq!(prepared, vec!["a.example.com", "b.example.com"]);
+    assert_eq!(solver.cleanups.load(Ordering::SeqCst), 1);
+
+    m_chall1.assert_async().await;
+    m_chall2.assert_async().await;
+    m_finalize.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_session_is_reused_across_calls() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let m_dir = mock_server.mock_directory().await.expect(1);
+    let m_nonce = mock_server.mock_new_nonce().await.expect(1);
+    let m_account = mock_server
+        .server
+        .mock("POST", "/new-account")
+        .with_status(201)
+        .with_header("location", &format!("{}/account/1", url))
+        .with_header("replay-nonce", "nonce-from-account")
+        .with_body(json!({ "status": "valid", "contact": [] }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let m_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .match_request(|req| {
+            common::jws_protected(req.body().unwrap())["kid"]
+                .as_str()
+                .is_some_and(|kid| kid.ends_with("/account/1"))
+        })
+        .with_status(201)
+        .with_header("location", &format!("{}/order/1", url))
+        .with_header("replay-nonce", "nonce-from-order")
+        .with_body(
+            json!({
+                "status": "pending",
+                "expires": "2026-02-10T00:00:00Z",
+                "identifiers": [{"type": "dns", "value": "example.com"}],
+                "authorizations": [format!("{}/authz/1", url)],
+                "finalize": format!("{}/order/1/finalize", url)
+            })
+            .to_string(),
+        )
+        .expect(3)
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url)).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+    client.create_order(vec!["example.com".to_string()]).await?;
+    client.create_order(vec!["example.com".to_string()]).await?;
+
+    // Clones share the session, including the registered account
+    let mut clone = client.clone();
+    clone.create_order(vec!["example.com".to_string()]).await?;
+    assert_eq!(clone.account_id(), client.account_id());
+
+    // One directory fetch and one HEAD request serve the whole session
+    m_dir.assert_async().await;
+    m_nonce.assert_async().await;
+    m_account.assert_async().await;
+    m_order.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_issuance_resumes_stored_order() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+    let m_new_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .expect(0)
+        .create_async()
+        .await;
+
+    // The previous run finalized the order, then died before downloading the certificate
+    let key = rcgen::KeyPair::generate().unwrap();
+    let cert = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+        .unwrap()
+        .self_signed(&key)
+        .unwrap();
+    let _m_order = mock_server
+        .server
+        .mock("POST", "/order/1")
+        .with_status(200)
+        .with_body(
+            json!({
+                "status": "valid",
+                "expires": "2026-02-10T00:00:00Z",
+                "identifiers": [{"type": "dns", "value": "example.com"}],
+                "authorizations": [format!("{}/authz/1", url)],
+                "finalize": format!("{}/order/1/finalize", url),
+                "certificate": format!("{}/cert/1", url)
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let m_cert = mock_server
+        .server
+        .mock("POST", "/cert/1")
+        .with_status(200)
+        .with_header("content-type", "application/pem-certificate-chain")
+        .with_body(cert.pem())
+        .create_async()
+        .await;
+
+    let storage = Arc::new(acmex::storage::MemoryStorage::new());
+    let orders = acmex::storage::OrderStore::new(storage.clone());
+    orders
+        .save(&acmex::storage::PendingOrder {
+            domains: vec!["example.com".to_string()],
+            directory_url: format!("{}/directory", url),
+            account_url: format!("{}/account/1", url),
+            order_url: format!("{}/order/1", url),
+            status: "processing".to_string(),
+            finalize_url: format!("{}/order/1/finalize", url),
+            authorizations: Vec::new(),
+            certificate_key_pem: Some(key.serialize_pem()),
+            updated_at: jiff::Timestamp::now(),
+        })
+        .await?;
+
+    let config = AcmeConfig::new(format!("{}/directory", url)).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?.with_order_storage(storage);
+    let bundle = client
+        .issue_certificate(
+            vec!["example.com".to_string()],
+            &ChallengeSolverRegistry::new(),
+        )
+        .await?;
+
+    assert_eq!(bundle.private_key_pem, key.serialize_pem());
+    assert_eq!(bundle.certificate_pem, cert.pem());
+    assert!(orders.load(&bundle.domains).await?.is_none());
+    m_new_order.assert_async().await;
+    m_cert.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pre_authorization_and_deactivation() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server
+        .mock_directory_with(json!({ "newAuthz": format!("{}/new-authz", url) }))
+        .await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+    let m_new_authz = mock_server
+        .server
+        .mock("POST", "/new-authz")
+        .match_request(|req| {
+            let payload = jws_payload(req.body().unwrap());
+            payload["identifier"] == json!({"type": "dns", "value": "a.example.com"})
+        })
+        .with_status(201)
+        .with_header("location", &format!("{}/authz/1", url))
+        .with_body(
+            json!({
+                "identifier": {"type": "dns", "value": "a.example.com"},
+                "status": "pending",
+                "expires": "2026-02-10T00:00:00Z",
+                "challenges": []
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let (_m_authz, m_chall) =
+        mock_authorization(&mut mock_server, 1, "a.example.com", "valid").await;
+    let m_deactivate = mock_server
+        .server
+        .mock("POST", "/authz/2")
+        .match_request(|req| jws_payload(req.body().unwrap())["status"] == "deactivated")
+        .with_status(200)
+        .with_body(
+            json!({
+                "identifier": {"type": "dns", "value": "b.example.com"},
+                "status": "deactivated",
+                "expires": "2026-02-10T00:00:00Z",
+                "challenges": []
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let solver = RecordingSolver::default();
+    let mut registry = ChallengeSolverRegistry::new();
+    registry.register(solver.clone());
+
+    let config = AcmeConfig::new(format!("{}/directory", url)).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+    let authorizations = client
+        .pre_authorize(vec!["a.example.com".to_string()], &registry)
+        .await?
+        .expect("CA offers newAuthz");
+    assert_eq!(authorizations.len(), 1);
+    assert_eq!(authorizations[0].url, format!("{}/authz/1", url));
+    assert_eq!(authorizations[0].status, "valid");
+    assert_eq!(solver.cleanups.load(Ordering::SeqCst), 1);
+
+    let wildcard = client
+        .pre_authorize(vec!["*.example.com".to_string()], &registry)
+        .await;
+    assert!(matches!(wildcard, Err(AcmeError::InvalidInput(_))));
+
+    let auth = client
+        .deactivate_authorization(&format!("{}/authz/2", url))
+        .await?;
+    assert_eq!(auth.status, "deactivated");
+
+    m_new_authz.assert_async().await;
+    m_chall.assert_async().await;
+    m_deactivate.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pre_authorization_unsupported_by_ca() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let m_account = mock_server.mock_new_account().await.expect(0);
+
+    let mut registry = ChallengeSolverRegistry::new();
+    registry.register(RecordingSolver::default());
+
+    let config = AcmeConfig::new(format!("{}/directory", url)).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+    let authorizations = client
+        .pre_authorize(vec!["example.com".to_string()], &registry)
+        .await?;
+    assert!(authorizations.is_none());
+
+    m_account.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revoke_with_certificate_key() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let m_account = mock_server.mock_new_account().await.expect(0);
+
+    let cert_key = KeyPair::generate()?;
+    let cert = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+        .unwrap()
+        .self_signed(&cert_key.0)
+        .unwrap();
+    let expected_jwk = cert_key.jwk()?.to_value();
+    let expected_cert = base64::Engine::encode(
+        &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+        cert.der(),
+    );
+    let m_revoke = mock_server
+        .server
+        .mock("POST", "/revoke-cert")
+        .match_request(move |req| {
+            let body = req.body().unwrap();
+            let protected = jws_protected(body);
+            let payload = jws_payload(body);
+            protected["jwk"] == expected_jwk
+                && protected.get("kid").is_none()
+                && payload["certificate"] == expected_cert.as_str()
+                && payload["reason"] == 1
+        })
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = AcmeClient::new(AcmeConfig::new(format!("{}/directory", url)))?;
+    client
+        .revoke_with_certificate_key(
+            &cert.pem(),
+            &cert_key,
+            Some(RevocationReason::KeyCompromise),
+        )
+        .await?;
+
+    // A key that does not match the certificate is rejected before contacting the CA
+    let err = client
+        .revoke_with_certificate_key(&cert.pem(), &KeyPair::generate()?, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AcmeError::InvalidInput(_)));
+
+    m_revoke.assert_async().await;
+    m_account.assert_async().await;
+    Ok(())
+}
+
+/// Mocks an order that is "ready" when created and is finalized asynchronously:
+/// it reports "processing" (with `Retry-After: 1`) for `processing_polls` polls, then "valid".
+async fn mock_async_finalization(
+    mock_server: &mut MockAcmeServer,
+    processing_polls: usize,
+) -> Vec<mockito::Mock> {
+    let url = mock_server.url();
+    let order = move |status: &str| {
+        json!({
+            "status": status,
+            "expires": "2026-02-10T00:00:00Z",
+            "identifiers": [{"type": "dns", "value": "example.com"}],
+            "authorizations": [],
+            "finalize": format!("{}/order/1/finalize", url),
+            "certificate": format!("{}/cert/1", url)
+        })
+        .to_string()
+    };
+
+    let key = rcgen::KeyPair::generate().unwrap();
+    let cert = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+        .unwrap()
+        .self_signed(&key)
+        .unwrap();
+    let polls = AtomicUsize::new(0);
+    let poll_order = order.clone();
+    vec![
+        mock_server
+            .server
+            .mock("POST", "/new-order")
+            .with_status(201)
+            .with_header("location", &format!("{}/order/1", mock_server.url()))
+            .with_body(order("ready"))
+            .create_async()
+            .await,
+        mock_server
+            .server
+            .mock("POST", "/order/1/finalize")
+            .with_status(200)
+            .with_header("retry-after", "1")
+            .with_body(order("processing"))
+            .expect(1)
+            .create_async()
+            .await,
+        mock_server
+            .server
+            .mock("POST", "/order/1")
+            .with_status(200)
+            .with_header("retry-after", "1")
+            .with_body_from_request(move |_| {
+                let poll = polls.fetch_add(1, Ordering::SeqCst);
+                let status = if poll < processing_polls {
+                    "processing"
+                } else {
+                    "valid"
+                };
+                poll_order(status).into_bytes()
+            })
+            .create_async()
+            .await,
+        mock_server
+            .server
+            .mock("POST", "/cert/1")
+            .with_status(200)
+            .with_body(cert.pem())
+            .create_async()
+            .await,
+    ]
+}
+
+#[tokio::test]
+async fn test_asynchronous_finalization_honours_retry_after() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+    let _mocks = mock_async_finalization(&mut mock_server, 1).await;
+
+    // Without Retry-After the client would wait the whole (long) poll interval
+    let config = AcmeConfig::new(format!("{}/directory", url))
+        .with_tos_agreed(true)
+        .with_poll_interval(Duration::from_secs(30))
+        .with_finalization_timeout(Duration::from_secs(20));
+    let mut client = AcmeClient::new(config)?;
+
+    let started = std::time::Instant::now();
+    let bundle = client
+        .issue_certificate(
+            vec!["example.com".to_string()],
+            &ChallengeSolverRegistry::new(),
+        )
+        .await?;
+    assert!(bundle.certificate_pem.contains("BEGIN CERTIFICATE"));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_finalization_deadline() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+    let _mocks = mock_async_finalization(&mut mock_server, usize::MAX).await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url))
+        .with_tos_agreed(true)
+        .with_finalization_timeout(Duration::from_secs(2));
+    let mut client = AcmeClient::new(config)?;
+
+    let err = client
+        .issue_certificate(
+            vec!["example.com".to_string()],
+            &ChallengeSolverRegistry::new(),
+        )
+        .await
+        .expect_err("Issuance must give up at the finalization deadline");
+    assert!(matches!(err, AcmeError::Timeout(_)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_star_order_issuance_fetch_and_cancel() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server
+        .mock_directory_with_meta(json!({
+            "auto-renewal": {
+                "min-lifetime": 86400,
+                "max-duration": 31536000
+            }
+        }))
+        .await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+
+    let end_date = jiff::Timestamp::now() + jiff::SignedDuration::from_hours(24 * 30);
+    let order = {
+        let url = url.clone();
+        move |status: &str| {
+            json!({
+                "status": status,
+                "expires": "2026-02-10T00:00:00Z",
+                "identifiers": [{"type": "dns", "value": "example.com"}],
+                "authorizations": [],
+                "finalize": format!("{}/order/1/finalize", url),
+                "auto-renewal": {
+                    "end-date": end_date.to_string(),
+                    "lifetime": 345600
+                },
+                "star-certificate": format!("{}/star/1", url)
+            })
+            .to_string()
+        }
+    };
+
+    let m_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .match_request(|req| {
+            let payload = jws_payload(req.body().unwrap());
+            payload["auto-renewal"]["lifetime"] == 345600
+        })
+        .with_status(201)
+        .with_header("location", &format!("{}/order/1", url))
+        .with_body(order("ready"))
+        .expect(1)
+        .create_async()
+        .await;
+    let _m_finalize = mock_server
+        .server
+        .mock("POST", "/order/1/finalize")
+        .with_status(200)
+        .with_body(order("valid"))
+        .create_async()
+        .await;
+
+    let key = rcgen::KeyPair::generate().unwrap();
+    let cert = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+        .unwrap()
+        .self_signed(&key)
+        .unwrap();
+    // The CA serves the current short-term certificate at the rolling URL
+    let m_star = mock_server
+        .server
+        .mock("POST", "/star/1")
+        .with_status(200)
+        .with_body(cert.pem())
+        .expect(2)
+        .create_async()
+        .await;
+    let m_cancel = mock_server
+        .server
+        .mock("POST", "/order/1")
+        .match_request(|req| jws_payload(req.body().unwrap())["status"] == "canceled")
+        .with_status(200)
+        .with_body(order("canceled"))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url)).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+
+    let bundle = client
+        .issue_star_certificate(
+            vec!["example.com".to_string()],
+            AutoRenewal::new(end_date, Duration::from_secs(345_600)),
+            &ChallengeSolverRegistry::new(),
+        )
+        .await?;
+    let star = bundle.star.clone().expect("STAR details");
+    assert_eq!(star.order_url, format!("{}/order/1", url));
+    assert_eq!(star.end_date, end_date);
+    assert!(bundle.active_star(jiff::Timestamp::now()).is_some());
+    assert!(bundle.active_star(end_date).is_none());
+
+    let next = client.fetch_star_certificate(&bundle).await?;
+    assert_eq!(next.private_key_pem, bundle.private_key_pem);
+    assert_eq!(next.star, bundle.star);
+
+    let canceled = client.cancel_star_order(&star.order_url).await?;
+    assert_eq!(canceled.status, "canceled");
+
+    m_order.assert_async().await;
+    m_star.assert_async().await;
+    m_cancel.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_star_order_unsupported_by_ca() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+    let m_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .with_status(201)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url)).with_tos_agreed(true);
+    let mut client = AcmeClient::new(config)?;
+
+    let end_date = jiff::Timestamp::now() + jiff::SignedDuration::from_hours(24 * 30);
+    let err = client
+        .issue_star_certificate(
+            vec!["example.com".to_string()],
+            AutoRenewal::new(end_date, Duration::from_secs(345_600)),
+            &ChallengeSolverRegistry::new(),
+        )
+        .await
+        .expect_err("STAR orders need CA support");
+    assert!(matches!(err, AcmeError::InvalidInput(_)));
+    m_order.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_renewal_reuses_certificate_key() -> Result<()> {
+    use base64::Engine;
+    use x509_parser::prelude::*;
+
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server.mock_directory().await;
+    let _m_nonce = mock_server.mock_new_nonce().await;
+    let _m_account = mock_server.mock_new_account().await;
+
+    let previous_key = rcgen::KeyPair::generate_for(&rcgen::PKCS_ECDSA_P384_SHA384).unwrap();
+    let previous_cert = rcgen::CertificateParams::new(vec!["example.com".to_string()])
+        .unwrap()
+        .self_signed(&previous_key)
+        .unwrap();
+    let expected_spki = rcgen::PublicKeyData::subject_public_key_info(&previous_key);
+
+    let order = {
+        let url = url.clone();
+        move |status: &str| {
+            json!({
+                "status": status,
+                "expires": "2026-02-10T00:00:00Z",
+                "identifiers": [{"type": "dns", "value": "example.com"}],
+                "authorizations": [],
+                "finalize": format!("{}/order/1/finalize", url),
+                "certificate": format!("{}/cert/1", url)
+            })
+            .to_string()
+        }
+    };
+    let _m_order = mock_server
+        .server
+        .mock("POST", "/new-order")
+        .with_status(201)
+        .with_header("location", &format!("{}/order/1", url))
+        .with_body(order("ready"))
+        .create_async()
+        .await;
+    // The CSR must carry the public key of the certificate being renewed
+    let m_finalize = mock_server
+        .server
+        .mock("POST", "/order/1/finalize")
+        .match_request(move |req| {
+            let payload = jws_payload(req.body().unwrap());
+            let csr_der = base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(payload["csr"].as_str().unwrap())
+                .unwrap();
+            let (_, csr) = X509CertificationRequest::from_der(&csr_der).unwrap();
+            csr.certification_request_info.subject_pki.raw == expected_spki.as_slice()
+        })
+        .with_status(200)
+        .with_body(order("valid"))
+        .expect(1)
+        .create_async()
+        .await;
+    let _m_cert = mock_server
+        .server
+        .mock("POST", "/cert/1")
+        .with_status(200)
+        .with_body(previous_cert.pem())
+        .create_async()
+        .await;
+
+    let config = AcmeConfig::new(format!("{}/directory", url))
+        .with_tos_agreed(true)
+        .with_certificate_options(CertificateOptions::default().with_reuse_key(true));
+    let mut client = AcmeClient::new(config)?;
+
+    let previous = CertificateBundle {
+        certificate_pem: previous_cert.pem(),
+        private_key_pem: previous_key.serialize_pem(),
+        domains: vec!["example.com".to_string()],
+        chain_issuer: None,
+        star: None,
+    };
+    let renewed = client
+        .renew_certificate(&previous, &ChallengeSolverRegistry::new())
+        .await?;
+    assert_eq!(renewed.private_key_pem, previous.private_key_pem);
+
+    m_finalize.assert_async().await;
+    Ok(())
+}
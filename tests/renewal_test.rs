@@ -0,0 +1,137 @@
+mod common;
+
+use acmex::prelude::*;
+use acmex::protocol::ari_cert_id;
+use acmex::renewal::renewal_due;
+use common::MockAcmeServer;
+use serde_json::json;
+use std::time::Duration;
+
+/// Issues a leaf certificate (with an Authority Key Identifier) from a throwaway CA.
+fn issue_test_certificate() -> (String, Vec<u8>) {
+    let ca_key = rcgen::KeyPair::generate().unwrap();
+    let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    let issuer = rcgen::Issuer::new(ca_params, ca_key);
+
+    let leaf_key = rcgen::KeyPair::generate().unwrap();
+    let mut params = rcgen::CertificateParams::new(vec!["example.com".to_string()]).unwrap();
+    params.use_authority_key_identifier_extension = true;
+    let cert = params.signed_by(&leaf_key, &issuer).unwrap();
+    (cert.pem(), cert.der().to_vec())
+}
+
+#[tokio::test]
+async fn test_renewal_info_drives_renewal_decision() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server
+        .mock_directory_with(json!({ "renewalInfo": format!("{}/renewal-info", url) }))
+        .await;
+
+    let (cert_pem, cert_der) = issue_test_certificate();
+    let cert_id = ari_cert_id(&cert_der)?;
+
+    // The CA asks for renewal right now, although the certificate is far from expiry
+    let m_ari = mock_server
+        .server
+        .mock("GET", format!("/renewal-info/{}", cert_id).as_str())
+        .with_status(200)
+        .with_header("retry-after", "21600")
+        .with_body(
+            json!({
+                "suggestedWindow": {
+                    "start": "2020-01-01T00:00:00Z",
+                    "end": "2020-01-02T00:00:00Z"
+                }
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let client = AcmeClient::new(AcmeConfig::new(format!("{}/directory", url)))?;
+
+    let info = client
+        .renewal_info(&cert_pem)
+        .await?
+        .expect("ARI supported");
+    assert_eq!(info.retry_after, Some(Duration::from_secs(21600)));
+
+    let bundle = acmex::client::CertificateBundle {
+        certificate_pem: cert_pem,
+        private_key_pem: String::new(),
+        domains: vec!["example.com".to_string()],
+        chain_issuer: None,
+        star: None,
+    };
+    let renew_before = Duration::from_secs(30 * 24 * 3600);
+
+    assert!(renewal_due(Some(&client), &bundle, renew_before).await?);
+    // Without ARI the freshly issued certificate is not yet due
+    assert!(!renewal_due(None, &bundle, renew_before).await?);
+    m_ari.expect_at_least(1).assert_async().await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_renewal_info_unsupported() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let _m_dir = mock_server.mock_directory().await;
+
+    let (cert_pem, _) = issue_test_certificate();
+    let client = AcmeClient::new(AcmeConfig::new(format!("{}/directory", mock_server.url())))?;
+
+    assert!(client.renewal_info(&cert_pem).await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_renewal_time_is_kept_until_retry_after() -> Result<()> {
+    let mut mock_server = MockAcmeServer::new().await;
+    let url = mock_server.url();
+    let _m_dir = mock_server
+        .mock_directory_with(json!({ "renewalInfo": format!("{}/renewal-info", url) }))
+        .await;
+
+    let (cert_pem, cert_der) = issue_test_certificate();
+    let cert_id = ari_cert_id(&cert_der)?;
+    let m_ari = mock_server
+        .server
+        .mock("GET", format!("/renewal-info/{}", cert_id).as_str())
+        .with_status(200)
+        .with_header("retry-after", "21600")
+        .with_body(
+            json!({
+                "suggestedWindow": {
+                    "start": "2099-01-01T00:00:00Z",
+                    "end": "2099-02-01T00:00:00Z"
+                }
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = AcmeClient::new(AcmeConfig::new(format!("{}/directory", url)))?;
+
+    // Later checks reuse the chosen time and do not poll before Retry-After has passed
+    let first = client
+        .renewal_info(&cert_pem)
+        .await?
+        .expect("ARI supported");
+    let renewal_time = first.renewal_time.expect("renewal time chosen");
+    assert!(renewal_time >= first.suggested_window.start);
+    for _ in 0..3 {
+        let info = client
+            .renewal_info(&cert_pem)
+            .await?
+            .expect("ARI supported");
+        assert_eq!(info.renewal_time, Some(renewal_time));
+    }
+    m_ari.assert_async().await;
+
+    Ok(())
+}